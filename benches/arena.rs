@@ -0,0 +1,165 @@
+//! Benchmark of parsing and dropping long operator expressions.
+//!
+//! Allocated memory is compared with the same trees of a box per node, the way they were stored
+//! before expressions were allocated in an arena.
+//!
+//! Run with `cargo bench --bench arena`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use compiler::{
+    ast::expression::{ExprArena, ExprId, Expression, ExpressionKind},
+    context::Context,
+    input_stream::InputStream,
+    lexer::{
+        operator::{BinaryOp, UnaryOp},
+        Lexer,
+    },
+    parser::FileParser,
+    path::AbsolutePath,
+    util::Span,
+};
+
+const TERMS: [usize; 3] = [1_000, 10_000, 50_000];
+
+/// Allocator that counts allocations and tracks the peak of allocated memory.
+struct Tracking;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Tracking = Tracking;
+
+/// Duration, number of allocations and memory allocated on top of the already allocated.
+struct Measurement {
+    duration: Duration,
+    allocations: usize,
+    /// Bytes that were allocated at most at once.
+    peak: usize,
+    /// Bytes that are still allocated afterwards.
+    retained: isize,
+}
+
+fn measure<T>(f: impl FnOnce() -> T) -> (T, Measurement) {
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(allocated, Ordering::Relaxed);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let output = f();
+    let measurement = Measurement {
+        duration: start.elapsed(),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        peak: PEAK.load(Ordering::Relaxed) - allocated,
+        retained: ALLOCATED.load(Ordering::Relaxed) as isize - allocated as isize,
+    };
+    (output, measurement)
+}
+
+fn report(name: &str, measurement: Measurement) {
+    println!(
+        "{name:<16} {:>12.3?} {:>8} allocations {:>10} bytes at peak {:>10} bytes retained",
+        measurement.duration, measurement.allocations, measurement.peak, measurement.retained
+    );
+}
+
+/// Expression of a box per node.
+#[allow(dead_code)]
+struct Boxed {
+    kind: BoxedKind,
+    span: Span,
+}
+
+#[allow(dead_code)]
+enum BoxedKind {
+    Operand(ExpressionKind),
+    Unary {
+        op: UnaryOp,
+        value: Box<Boxed>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Boxed>,
+        right: Box<Boxed>,
+    },
+}
+
+/// Copies operator expression with the `root` out of the arena.
+fn to_boxed(exprs: &ExprArena, root: ExprId) -> Box<Boxed> {
+    let mut nodes = HashMap::<ExprId, Box<Boxed>>::new();
+    // Children are allocated before their parents, so they are always copied already.
+    for (id, Expression { kind, span }) in exprs.iter() {
+        let kind = match kind {
+            ExpressionKind::Unary { op, value } => BoxedKind::Unary {
+                op: *op,
+                value: nodes.remove(value).unwrap(),
+            },
+            ExpressionKind::Binary { op, left, right } => BoxedKind::Binary {
+                op: *op,
+                left: nodes.remove(left).unwrap(),
+                right: nodes.remove(right).unwrap(),
+            },
+            kind => BoxedKind::Operand(kind.clone()),
+        };
+        nodes.insert(id, Box::new(Boxed { kind, span: *span }));
+    }
+    nodes.remove(&root).unwrap()
+}
+
+fn bench(terms: usize) {
+    let src = (0..terms)
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(" + ");
+    let context = Context::builder().build_str("");
+    let scope = "main".parse::<AbsolutePath>().unwrap();
+    let lexer = Lexer::new(InputStream::new(src, None), context.clone());
+    let parser = FileParser::new(lexer, scope, context);
+
+    println!("{terms} terms");
+    let ((parser, root), parse) = measure(|| {
+        let mut parser = parser;
+        let root = parser.parse_expr().expect("expression should be valid");
+        (parser, root)
+    });
+    report("parse to arena", parse);
+    let exprs = parser.item_table.exprs();
+    let (boxed, copy) = measure(|| to_boxed(exprs, root));
+    report("copy to boxes", copy);
+
+    let (_, drop_boxed) = measure(|| drop(boxed));
+    report("drop boxes", drop_boxed);
+    let (_, drop_arena) = measure(|| drop(parser));
+    report("drop arena", drop_arena);
+}
+
+fn main() {
+    // Boxes are dropped recursively, so deep trees need a large stack.
+    thread::Builder::new()
+        .stack_size(1 << 30)
+        .spawn(|| TERMS.into_iter().for_each(bench))
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -0,0 +1,133 @@
+//! Benchmark of merging item tables and populating HIR from a table of generated items.
+//!
+//! Allocations are counted to compare borrowing the table with cloning it.
+//!
+//! Run with `cargo bench --bench item_table`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use compiler::{
+    context::Context, hir::HirBuilder, input_stream::InputStream, item_table::ItemTable,
+    lexer::Lexer, parser::FileParser, path::AbsolutePath,
+};
+
+const MODULES: usize = 10;
+const ITEMS: usize = 100;
+const ITERATIONS: u32 = 10;
+
+/// Allocator that counts allocations.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Generates tables of [MODULES] modules of [ITEMS] items each.
+fn generate() -> Vec<ItemTable> {
+    let context = Context::builder().build_str("");
+    (0..MODULES)
+        .map(|i| {
+            let items: String = (0..ITEMS)
+                .map(|j| match j % 4 {
+                    0 => format!("struct Struct{j} {{ x: i32, y: bool }}\n"),
+                    1 => format!("const CONST{j}: i32 = {j};\n"),
+                    _ => format!(
+                        "fn function{j}(a: i32, b: i32) -> i32 {{
+                            let c: i32 = a * {j} + b;
+                            if c > {i} {{ return c - 1; }}
+                            c
+                        }}\n"
+                    ),
+                })
+                .collect();
+            let scope = format!("main::module{i}").parse::<AbsolutePath>().unwrap();
+            let lexer = Lexer::new(InputStream::new(items, None), context.clone());
+            let parsed = FileParser::new(lexer, scope, context.clone()).parse();
+            assert!(parsed.errors.is_empty(), "generated items should be valid");
+            parsed.item_table
+        })
+        .collect()
+}
+
+/// Runs `f` [ITERATIONS] times, returning average duration and number of allocations.
+fn measure<T>(mut setup: impl FnMut() -> T, mut f: impl FnMut(T)) -> (Duration, usize) {
+    let mut duration = Duration::ZERO;
+    let mut allocations = 0;
+    for _ in 0..ITERATIONS {
+        let input = setup();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        f(input);
+        duration += start.elapsed();
+        allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+    }
+    (duration / ITERATIONS, allocations / ITERATIONS as usize)
+}
+
+fn report(name: &str, (duration, allocations): (Duration, usize)) {
+    println!("{name:<28} {duration:>12.3?} {allocations:>10} allocations");
+}
+
+fn main() {
+    let tables = generate();
+    let mut table = ItemTable::new();
+    for module in tables.iter().cloned() {
+        table.extend(module);
+    }
+    println!("{} items", table.iter().count());
+
+    report(
+        "extend",
+        measure(
+            || tables.clone(),
+            |tables| {
+                let mut table = ItemTable::new();
+                for module in tables {
+                    table.extend(module);
+                }
+            },
+        ),
+    );
+    report(
+        "extend from clones",
+        measure(
+            || (),
+            |_| {
+                let mut table = ItemTable::new();
+                for module in tables.iter() {
+                    table.extend(module.clone());
+                }
+            },
+        ),
+    );
+    report(
+        "populate from borrowed table",
+        measure(
+            || HirBuilder::new(Context::builder().build_str("")),
+            |mut builder| builder.populate(&table),
+        ),
+    );
+    report(
+        "populate from cloned table",
+        measure(
+            || HirBuilder::new(Context::builder().build_str("")),
+            |mut builder| builder.populate(&table.clone()),
+        ),
+    );
+}
@@ -0,0 +1,57 @@
+//! Benchmark of parsing a crate of generated modules sequentially and in parallel.
+//!
+//! Run with `cargo bench --bench parse`.
+
+use std::time::{Duration, Instant};
+
+use compiler::{context::Context, parser::Parser};
+
+const MODULES: usize = 50;
+const FUNCTIONS: usize = 200;
+const ITERATIONS: u32 = 10;
+
+/// Generates crate with the main file that declares [MODULES] modules of [FUNCTIONS] functions.
+fn generate() -> Vec<(String, String)> {
+    let main = (0..MODULES).map(|i| format!("mod module{i};\n")).collect();
+    let mut files = vec![(String::from("/bench/main.sun"), main)];
+    for i in 0..MODULES {
+        let src = (0..FUNCTIONS)
+            .map(|j| {
+                format!(
+                    "fn function{j}(a: i32, b: i32) -> i32 {{
+                        let c: i32 = a * {j} + b;
+                        if c > {i} {{ return c - 1; }}
+                        while c < 100 {{ c += a; }}
+                        c
+                    }}\n"
+                )
+            })
+            .collect();
+        files.push((format!("/bench/module{i}.sun"), src));
+    }
+    files
+}
+
+fn parse(files: &[(String, String)], jobs: usize) -> Duration {
+    let mut builder = Context::builder().main(&files[0].0).jobs(jobs);
+    for (path, contents) in files {
+        builder = builder.virtual_file(path, contents);
+    }
+    let mut parser = Parser::new(builder.build().expect("main file should be virtual"));
+    let start = Instant::now();
+    let (_, errors) = parser.parse_partial();
+    let elapsed = start.elapsed();
+    assert!(errors.is_empty(), "generated crate should be valid");
+    elapsed
+}
+
+fn main() {
+    let files = generate();
+    for jobs in [1, 2, 4, 8] {
+        let total: Duration = (0..ITERATIONS).map(|_| parse(&files, jobs)).sum();
+        println!(
+            "{MODULES} modules, {jobs} job(s): {:?} per iteration",
+            total / ITERATIONS
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Checks of the fuzz targets, shared with the test that replays their corpus.
+//!
+//! Input is considered to be handled correctly if the compiler doesn't panic and every span it
+//! produces is located within the input.
+
+use std::{panic, thread};
+
+use compiler::{
+    context::Context, input_stream::InputStream, lexer::Lexer, parser::FileParser,
+    path::AbsolutePath, util::Span, Identifier,
+};
+
+/// Reads all tokens of `data`, replacing invalid UTF-8 sequences.
+#[allow(dead_code)]
+pub fn lex(data: &[u8]) {
+    let src = String::from_utf8_lossy(data);
+    let context = Context::builder().build_str("");
+    let mut lexer = Lexer::new(InputStream::new(&src, None), context.clone());
+    for (_, span) in lexer.tokenize() {
+        check_span(span, &src);
+    }
+    check_diagnostics(&context, &src);
+}
+
+/// Parses `data` as a file, ignoring input that isn't valid UTF-8.
+#[allow(dead_code)]
+pub fn parse(data: &[u8]) {
+    let Ok(src) = std::str::from_utf8(data) else {
+        return;
+    };
+    let context = Context::builder().build_str("");
+    let lexer = Lexer::new(InputStream::new(src, None), context.clone());
+    let scope = AbsolutePath::new(Identifier(String::from("fuzz")));
+    let parser = FileParser::new(lexer, scope, context.clone());
+    // Parser is given as much stack as the compiler provides for the allowed nesting.
+    thread::Builder::new()
+        .stack_size(context.metadata.stack_size())
+        .spawn(|| parser.parse())
+        .expect("parser thread should be spawned")
+        .join()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload));
+    check_diagnostics(&context, src);
+}
+
+fn check_diagnostics(context: &Context, src: &str) {
+    for diagnostic in context.error_reporter.diagnostics() {
+        let suggestion = diagnostic.suggestion.map(|suggestion| suggestion.span);
+        for span in diagnostic.span.into_iter().chain(suggestion) {
+            check_span(span, src);
+        }
+    }
+}
+
+fn check_span(span: Span, src: &str) {
+    assert!(
+        span.start.pos <= span.end.pos && span.end.pos <= src.len(),
+        "span {}..{} is out of bounds of {} bytes of input",
+        span.start.pos,
+        span.end.pos,
+        src.len(),
+    );
+}
@@ -1,6 +1,9 @@
 //! Abstract syntax tree
 
+pub mod dot;
 pub mod expression;
 pub mod item;
 pub mod pretty_print;
 pub mod statement;
+pub mod to_source;
+pub mod types;
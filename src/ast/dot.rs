@@ -0,0 +1,370 @@
+//! Graphviz representation of the AST.
+//!
+//! Every node of the tree becomes a labeled node of the digraph, functions are grouped into
+//! clusters named by their path.
+
+use std::{
+    fmt::Display,
+    io::{Result, Write},
+};
+
+use crate::{item_table::ItemTable, path::AbsolutePath};
+
+use super::{
+    expression::{Block, ExprArena, ExprId, ExpressionKind, Literal},
+    item::{Item, ItemKind, Module},
+    statement::{LetStatement, Place, Projection, Statement, StatementKind},
+};
+
+pub fn write_dot(w: impl Write, table: &ItemTable) -> Result<()> {
+    let mut writer = DotWriter {
+        writer: w,
+        exprs: table.exprs(),
+        next_id: 0,
+        indent: 1,
+    };
+    let mut items: Vec<_> = table.iter().collect();
+    items.sort_by_cached_key(|(path, _)| path.to_string());
+
+    writeln!(writer.writer, "digraph ast {{")?;
+    for (path, item) in items {
+        writer.write_item(path, item)?;
+    }
+    for (module, item) in table.impls() {
+        writer.write_item(module, item)?;
+    }
+    writeln!(writer.writer, "}}")
+}
+
+struct DotWriter<'a, W: Write> {
+    writer: W,
+    exprs: &'a ExprArena,
+    /// Id of the next node to be written.
+    next_id: usize,
+    indent: usize,
+}
+
+impl<W: Write> DotWriter<'_, W> {
+    /// Width of a single indentation.
+    const IDENT_WIDTH: usize = 4;
+
+    fn write_item(&mut self, path: &AbsolutePath, item: &Item) -> Result<usize> {
+        match &item.kind {
+            ItemKind::Module(Module::Inline(name) | Module::Loadable(name)) => {
+                self.node(format!("mod {name}"))
+            }
+            ItemKind::Struct(s) => {
+                let id = self.node(format!("struct {}", s.name))?;
+                for field in s.fields.iter() {
+                    let field = self.node(format!("{}: {}", field.name, field.type_))?;
+                    self.edge(id, field)?;
+                }
+                Ok(id)
+            }
+            ItemKind::Function(func) => {
+                let path = path.to_string();
+                self.println(format!("subgraph \"cluster_{}\" {{", escape(&path)))?;
+                self.indent += 1;
+                self.println(format!("label=\"{}\";", escape(&path)))?;
+                let id = self.node(format!("fn {}", func.name))?;
+                if func.receiver.is_some() {
+                    let receiver = self.node("self")?;
+                    self.edge(id, receiver)?;
+                }
+                for param in func.params.iter() {
+                    let param = self.node(format!("{}: {}", param.name, param.type_))?;
+                    self.edge(id, param)?;
+                }
+                if let Some(return_type) = &func.return_type {
+                    let return_type = self.node(format!("-> {return_type}"))?;
+                    self.edge(id, return_type)?;
+                }
+                let body = self.write_block(&func.body)?;
+                self.edge(id, body)?;
+                self.indent -= 1;
+                self.println("}")?;
+                Ok(id)
+            }
+            ItemKind::Const(constant) => {
+                let id = self.node(format!("const {}: {}", constant.name, constant.type_))?;
+                let value = self.write_expr(constant.value)?;
+                self.edge(id, value)?;
+                Ok(id)
+            }
+            ItemKind::Use(import) => match &import.alias {
+                Some(alias) => self.node(format!("use {} as {alias}", import.path)),
+                None => self.node(format!("use {}", import.path)),
+            },
+            ItemKind::Impl(implementation) => {
+                self.node(format!("impl {}", implementation.type_name))
+            }
+        }
+    }
+
+    fn write_stmt(&mut self, stmt: &Statement) -> Result<usize> {
+        match &stmt.kind {
+            StatementKind::ExprStmt(expr) => self.write_expr(*expr),
+            StatementKind::LetStmt(LetStatement {
+                name, type_, value, ..
+            }) => {
+                let id = match type_ {
+                    Some(type_) => self.node(format!("let {name}: {type_}"))?,
+                    None => self.node(format!("let {name}"))?,
+                };
+                if let Some(value) = value {
+                    let value = self.write_expr(*value)?;
+                    self.edge(id, value)?;
+                }
+                Ok(id)
+            }
+            StatementKind::Assignment {
+                assignee,
+                operator,
+                expression,
+            } => {
+                let id = self.write_place(assignee, operator)?;
+                let value = self.write_expr(*expression)?;
+                self.edge(id, value)?;
+                Ok(id)
+            }
+            StatementKind::Return(expr) => {
+                let id = self.node("return")?;
+                let value = self.write_expr(*expr)?;
+                self.edge(id, value)?;
+                Ok(id)
+            }
+            StatementKind::Break => self.node("break"),
+        }
+    }
+
+    fn write_expr(&mut self, expr: ExprId) -> Result<usize> {
+        let exprs = self.exprs;
+        match &exprs[expr].kind {
+            ExpressionKind::Block(block) => self.write_block(block),
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                let id = self.node("if")?;
+                let condition = self.write_expr(*condition)?;
+                self.edge(id, condition)?;
+                let body = self.write_block(body)?;
+                self.edge(id, body)?;
+                if let Some(else_body) = else_body {
+                    let else_body = self.write_block(else_body)?;
+                    self.edge(id, else_body)?;
+                }
+                Ok(id)
+            }
+            ExpressionKind::While { condition, body } => {
+                let id = self.node("while")?;
+                let condition = self.write_expr(*condition)?;
+                self.edge(id, condition)?;
+                let body = self.write_block(body)?;
+                self.edge(id, body)?;
+                Ok(id)
+            }
+            ExpressionKind::For { var, expr, body } => {
+                let id = self.node(format!("for {var}"))?;
+                let expr = self.write_expr(*expr)?;
+                self.edge(id, expr)?;
+                let body = self.write_block(body)?;
+                self.edge(id, body)?;
+                Ok(id)
+            }
+            ExpressionKind::Unary { op, value } => {
+                let id = self.node(op)?;
+                let value = self.write_expr(*value)?;
+                self.edge(id, value)?;
+                Ok(id)
+            }
+            ExpressionKind::Binary { op, left, right } => {
+                let id = self.node(op)?;
+                let left = self.write_expr(*left)?;
+                self.edge(id, left)?;
+                let right = self.write_expr(*right)?;
+                self.edge(id, right)?;
+                Ok(id)
+            }
+            ExpressionKind::FnCall { path, params } => {
+                let id = self.node(format!("{path}()"))?;
+                for param in params {
+                    let param = self.write_expr(*param)?;
+                    self.edge(id, param)?;
+                }
+                Ok(id)
+            }
+            ExpressionKind::Cast { value, type_ } => {
+                let id = self.node(format!("as {type_}"))?;
+                let value = self.write_expr(*value)?;
+                self.edge(id, value)?;
+                Ok(id)
+            }
+            ExpressionKind::MethodCall {
+                value,
+                method,
+                params,
+            } => {
+                let id = self.node(format!(".{method}()"))?;
+                let value = self.write_expr(*value)?;
+                self.edge(id, value)?;
+                for param in params {
+                    let param = self.write_expr(*param)?;
+                    self.edge(id, param)?;
+                }
+                Ok(id)
+            }
+            ExpressionKind::FieldAccess { value, field } => {
+                let id = self.node(format!(".{field}"))?;
+                let value = self.write_expr(*value)?;
+                self.edge(id, value)?;
+                Ok(id)
+            }
+            ExpressionKind::Index { value, index } => {
+                let id = self.node("[]")?;
+                let value = self.write_expr(*value)?;
+                self.edge(id, value)?;
+                let index = self.write_expr(*index)?;
+                self.edge(id, index)?;
+                Ok(id)
+            }
+            ExpressionKind::Var(var) => self.node(var),
+            ExpressionKind::Path(path) => self.node(path),
+            ExpressionKind::Literal(Literal::Number(num)) => self.node(num),
+            ExpressionKind::Literal(Literal::String(s)) => self.node(format!("\"{s}\"")),
+            ExpressionKind::Literal(Literal::Boolean(value)) => self.node(value),
+        }
+    }
+
+    /// Writes assignee of the assignment, with indices of the place as its children.
+    fn write_place(&mut self, place: &Place, operator: impl Display) -> Result<usize> {
+        let mut label = place.var.to_string();
+        for projection in place.projections.iter() {
+            match projection {
+                Projection::Field(field) => label.push_str(&format!(".{field}")),
+                Projection::Index(_) => label.push_str("[]"),
+            }
+        }
+        let id = self.node(format!("{label} {operator}"))?;
+        for projection in place.projections.iter() {
+            if let Projection::Index(index) = projection {
+                let index = self.write_expr(*index)?;
+                self.edge(id, index)?;
+            }
+        }
+        Ok(id)
+    }
+
+    fn write_block(&mut self, block: &Block) -> Result<usize> {
+        let id = self.node("block")?;
+        for stmt in block.statements.iter() {
+            let stmt = self.write_stmt(stmt)?;
+            self.edge(id, stmt)?;
+        }
+        if let Some(expr) = block.expression {
+            let expr = self.write_expr(expr)?;
+            self.edge(id, expr)?;
+        }
+        Ok(id)
+    }
+
+    /// Writes a new node and returns its id.
+    fn node(&mut self, label: impl Display) -> Result<usize> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.println(format!("n{id} [label=\"{}\"];", escape(&label.to_string())))?;
+        Ok(id)
+    }
+
+    fn edge(&mut self, from: usize, to: usize) -> Result<()> {
+        self.println(format!("n{from} -> n{to};"))
+    }
+
+    fn println(&mut self, line: impl Display) -> Result<()> {
+        writeln!(
+            self.writer,
+            "{}{line}",
+            " ".repeat(self.indent * Self::IDENT_WIDTH)
+        )
+    }
+}
+
+/// Escapes string to be used inside of quoted DOT identifier.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::FileParser;
+
+    use super::{escape, write_dot};
+
+    #[test]
+    fn golden() {
+        let parsed = FileParser::new_test(
+            "fn greet(name: str) -> str { let x: i32 = 1 + 2 * 3; if true { \"hi\" } else { name } }",
+        )
+        .parse();
+        assert!(parsed.errors.is_empty(), "parsing failed");
+        let mut output = Vec::new();
+        write_dot(&mut output, &parsed.item_table).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"digraph ast {
+    n0 [label="mod crate"];
+    subgraph "cluster_crate::greet" {
+        label="crate::greet";
+        n1 [label="fn greet"];
+        n2 [label="name: str"];
+        n1 -> n2;
+        n3 [label="-> str"];
+        n1 -> n3;
+        n4 [label="block"];
+        n5 [label="let x: i32"];
+        n6 [label="+"];
+        n7 [label="1"];
+        n6 -> n7;
+        n8 [label="*"];
+        n9 [label="2"];
+        n8 -> n9;
+        n10 [label="3"];
+        n8 -> n10;
+        n6 -> n8;
+        n5 -> n6;
+        n4 -> n5;
+        n11 [label="if"];
+        n12 [label="true"];
+        n11 -> n12;
+        n13 [label="block"];
+        n14 [label="\"hi\""];
+        n13 -> n14;
+        n11 -> n13;
+        n15 [label="block"];
+        n16 [label="name"];
+        n15 -> n16;
+        n11 -> n15;
+        n4 -> n11;
+        n1 -> n4;
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("say \"hi\"\n\\"), r#"say \"hi\"\n\\"#);
+    }
+}
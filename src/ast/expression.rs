@@ -1,68 +1,116 @@
+use std::ops::Index;
+
 use crate::{
-    ast::statement::Statement,
+    ast::{statement::Statement, types::TypeExpr},
     lexer::{
         number::Number,
         operator::{BinaryOp, UnaryOp},
     },
     path::RelativePath,
+    util::Span,
     Identifier,
 };
 
+/// An expression with its location in code.
+///
+/// Spans are ignored when comparing expressions, as only their structure is significant.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Check if that expression is block expression.
+    ///
+    /// Block expressions end with a right brace and don't require to be followed by a semicolon to
+    /// be accounted as expression statement.
+    pub fn is_block_expression(&self) -> bool {
+        matches!(
+            self.kind,
+            ExpressionKind::Block(_)
+                | ExpressionKind::If { .. }
+                | ExpressionKind::While { .. }
+                | ExpressionKind::For { .. }
+        )
+    }
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Expression {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExpressionKind {
     /// Block is a set of statements surrounded by opening and closing brace.
     Block(Block),
 
     If {
-        condition: Box<Expression>,
+        condition: ExprId,
         body: Block,
         else_body: Option<Block>,
     },
     While {
-        condition: Box<Expression>,
+        condition: ExprId,
         body: Block,
     },
     For {
         var: Identifier,
-        expr: Box<Expression>,
+        expr: ExprId,
         body: Block,
     },
 
     Unary {
         op: UnaryOp,
-        value: Box<Expression>,
+        value: ExprId,
     },
     Binary {
         op: BinaryOp,
-        left: Box<Expression>,
-        right: Box<Expression>,
+        left: ExprId,
+        right: ExprId,
+    },
+    /// `VALUE as TYPE`
+    Cast {
+        value: ExprId,
+        type_: TypeExpr,
     },
 
     FnCall {
         path: RelativePath,
-        params: Vec<Expression>,
+        params: Vec<ExprId>,
+    },
+    /// Call of the method with `self` receiver, such as `point.scale(2.0)`.
+    MethodCall {
+        value: ExprId,
+        method: Identifier,
+        params: Vec<ExprId>,
+    },
+    /// Access to the field of a struct, such as `point.x`.
+    FieldAccess {
+        value: ExprId,
+        field: Identifier,
+    },
+    /// Access to the element of an array, such as `values[i]`.
+    Index {
+        value: ExprId,
+        index: ExprId,
     },
     Var(Identifier),
+    /// Path to a value that consists of multiple segments, such as `crate::MAX`.
+    Path(RelativePath),
     Literal(Literal),
 }
 
-impl Expression {
-    /// Check if that expression is block expression.
-    ///
-    /// Block expressions end with a right brace and don't require to be followed by a semicolon to
-    /// be accounted as expression statement.
-    pub fn is_block_expression(&self) -> bool {
-        matches!(
-            self,
-            Expression::Block(_)
-                | Expression::If { .. }
-                | Expression::While { .. }
-                | Expression::For { .. }
-        )
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Literal {
     Number(Number),
     String(String),
@@ -71,7 +119,150 @@ pub enum Literal {
 
 /// Block is an expression that consists of a number of statements and an optional final expression.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     pub statements: Vec<Statement>,
-    pub expression: Option<Box<Expression>>,
+    pub expression: Option<ExprId>,
+}
+
+impl ExpressionKind {
+    /// Shifts ids of nested expressions by `offset`.
+    pub(crate) fn shift(&mut self, offset: u32) {
+        match self {
+            ExpressionKind::Block(block) => block.shift(offset),
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                condition.shift(offset);
+                body.shift(offset);
+                if let Some(else_body) = else_body {
+                    else_body.shift(offset);
+                }
+            }
+            ExpressionKind::While { condition, body } => {
+                condition.shift(offset);
+                body.shift(offset);
+            }
+            ExpressionKind::For { expr, body, .. } => {
+                expr.shift(offset);
+                body.shift(offset);
+            }
+            ExpressionKind::Unary { value, .. } | ExpressionKind::Cast { value, .. } => {
+                value.shift(offset)
+            }
+            ExpressionKind::Binary { left, right, .. } => {
+                left.shift(offset);
+                right.shift(offset);
+            }
+            ExpressionKind::FnCall { params, .. } => {
+                params.iter_mut().for_each(|param| param.shift(offset));
+            }
+            ExpressionKind::MethodCall { value, params, .. } => {
+                value.shift(offset);
+                params.iter_mut().for_each(|param| param.shift(offset));
+            }
+            ExpressionKind::FieldAccess { value, .. } => value.shift(offset),
+            ExpressionKind::Index { value, index } => {
+                value.shift(offset);
+                index.shift(offset);
+            }
+            ExpressionKind::Var(_) | ExpressionKind::Path(_) | ExpressionKind::Literal(_) => {}
+        }
+    }
+}
+
+impl Block {
+    /// Shifts ids of expressions of the block by `offset`.
+    pub(crate) fn shift(&mut self, offset: u32) {
+        for statement in self.statements.iter_mut() {
+            statement.kind.shift(offset);
+        }
+        if let Some(expression) = &mut self.expression {
+            expression.shift(offset);
+        }
+    }
+}
+
+/// Handle of the expression stored in [ExprArena].
+///
+/// Handles are only meaningful for the arena that produced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExprId(u32);
+
+impl ExprId {
+    pub(crate) fn shift(&mut self, offset: u32) {
+        self.0 += offset;
+    }
+}
+
+/// Storage of expressions, that refer to each other by [ExprId].
+///
+/// Nodes are allocated in a single buffer instead of a box per node, and dropping the tree
+/// doesn't recurse however deep it is. Children are always allocated before their parents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExprArena {
+    exprs: Vec<Expression>,
+}
+
+impl ExprArena {
+    pub fn new() -> Self {
+        Self { exprs: Vec::new() }
+    }
+
+    /// Stores `expr` in the arena, returning its handle.
+    pub fn alloc(&mut self, expr: Expression) -> ExprId {
+        let id = u32::try_from(self.exprs.len()).expect("arena should fit into u32 ids");
+        self.exprs.push(expr);
+        ExprId(id)
+    }
+
+    pub fn get(&self, id: ExprId) -> &Expression {
+        &self.exprs[id.0 as usize]
+    }
+
+    /// Number of stored expressions.
+    pub fn len(&self) -> usize {
+        self.exprs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exprs.is_empty()
+    }
+
+    /// Iterates over expressions in the order they were allocated in.
+    pub fn iter(&self) -> impl Iterator<Item = (ExprId, &Expression)> {
+        self.exprs
+            .iter()
+            .enumerate()
+            .map(|(id, expr)| (ExprId(id as u32), expr))
+    }
+
+    /// Moves expressions of `other` to the end of the arena.
+    ///
+    /// Returns an offset that ids of `other` have to be [shifted](ExpressionKind::shift) by to
+    /// refer to the moved expressions. Ids stored in the moved expressions are shifted already.
+    pub(crate) fn append(&mut self, mut other: ExprArena) -> u32 {
+        if self.exprs.is_empty() {
+            *self = other;
+            return 0;
+        }
+        let offset = u32::try_from(self.exprs.len()).expect("arena should fit into u32 ids");
+        for expr in other.exprs.iter_mut() {
+            expr.kind.shift(offset);
+        }
+        self.exprs.append(&mut other.exprs);
+        offset
+    }
+}
+
+impl Index<ExprId> for ExprArena {
+    type Output = Expression;
+
+    fn index(&self, id: ExprId) -> &Expression {
+        self.get(id)
+    }
 }
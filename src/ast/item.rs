@@ -1,18 +1,34 @@
-use crate::{ast::expression::Block, util::Span, Identifier};
+use std::fmt::Display;
+
+use crate::{
+    ast::{
+        expression::{Block, ExprId},
+        types::TypeExpr,
+    },
+    path::{RelativePath, RelativePathStart},
+    util::Span,
+    Identifier,
+};
 
 /// An Item is a static component of the package.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     pub kind: ItemKind,
     pub span: Span,
     pub visibility: Visibility,
+    pub attributes: Vec<Attribute>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ItemKind {
     Module(Module),
     Struct(Struct),
     Function(Function),
+    Const(Const),
+    Use(Use),
+    Impl(Impl),
 }
 
 impl Item {
@@ -22,6 +38,18 @@ impl Item {
             ItemKind::Module(Module::Loadable(ident)) => ident,
             ItemKind::Struct(s) => &s.name,
             ItemKind::Function(f) => &f.name,
+            ItemKind::Const(c) => &c.name,
+            ItemKind::Use(u) => u.name(),
+            ItemKind::Impl(i) => &i.type_name,
+        }
+    }
+
+    /// Shifts ids of expressions of the item by `offset`.
+    pub(crate) fn shift_exprs(&mut self, offset: u32) {
+        match &mut self.kind {
+            ItemKind::Function(function) => function.body.shift(offset),
+            ItemKind::Const(constant) => constant.value.shift(offset),
+            _ => {}
         }
     }
 
@@ -30,14 +58,62 @@ impl Item {
             kind: item.into(),
             span,
             visibility,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Finds attributes with single-segment path `name`.
+    pub fn attributes_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Attribute> {
+        self.attributes
+            .iter()
+            .filter(move |attribute| attribute.name().is_some_and(|ident| ident.as_str() == name))
+    }
+
+    /// Functions to generate for the item, listed in its `#[derive(..)]` attributes.
+    pub fn derives(&self) -> impl Iterator<Item = &Identifier> {
+        self.attributes_named("derive")
+            .flat_map(|attribute| attribute.arguments.iter().flatten())
+    }
+}
+
+/// Attribute that precedes an item.
+///
+/// `#[PATH]` or `#[PATH(ARGUMENT, ...)]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attribute {
+    pub path: RelativePath,
+    /// Arguments in parentheses, `None` if there are no parentheses.
+    pub arguments: Option<Vec<Identifier>>,
+    pub span: Span,
+}
+
+impl Attribute {
+    /// Name of the attribute, `None` if its path consists of multiple segments.
+    pub fn name(&self) -> Option<&Identifier> {
+        match &self.path.start {
+            RelativePathStart::Identifier(ident) if self.path.other.is_empty() => Some(ident),
+            _ => None,
         }
     }
 }
 
+impl Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some(arguments) = &self.arguments {
+            let arguments: Vec<_> = arguments.iter().map(Identifier::as_str).collect();
+            write!(f, "({})", arguments.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 /// Module is a container for zero or more [items](Item).
 ///
 /// Module may be either inline or loadable from separate file.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Module {
     Inline(Identifier),
     Loadable(Identifier),
@@ -51,6 +127,7 @@ impl From<Module> for ItemKind {
 
 /// A type that is composed of other types.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Struct {
     pub name: Identifier,
     pub fields: Vec<Field>,
@@ -63,39 +140,152 @@ impl From<Struct> for ItemKind {
 }
 
 /// Field
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Spans are ignored when comparing fields, as only their structure is significant.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Field {
     pub name: Identifier,
-    pub type_: Identifier,
+    pub type_: TypeExpr,
+    /// Location of the whole field declaration, including its type.
+    pub span: Span,
+}
+
+impl PartialEq for Field {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.type_ == other.type_
+    }
 }
 
 /// A function is a set of statements to perform a specific task.
 ///
 /// `fn NAME(NAME: TYPE, ...) -> RETURN_TYPE`
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Functions of implementation blocks may take `self` as the first parameter, which makes them
+/// methods: `fn NAME(self, NAME: TYPE, ...) -> RETURN_TYPE`.
+///
+/// Spans are ignored when comparing functions, as only their structure is significant.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Function {
     pub name: Identifier,
+    /// Location of `self` receiver, if the function is a method.
+    pub receiver: Option<Span>,
     pub params: Vec<Parameter>,
-    pub return_type: Option<Identifier>,
+    pub return_type: Option<TypeExpr>,
+    /// Location of the return type, if it is declared.
+    pub return_type_span: Option<Span>,
     pub body: Block,
 }
 
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.receiver.is_some() == other.receiver.is_some()
+            && self.params == other.params
+            && self.return_type == other.return_type
+            && self.body == other.body
+    }
+}
+
 impl From<Function> for ItemKind {
     fn from(val: Function) -> Self {
         ItemKind::Function(val)
     }
 }
 
+/// A constant is a named value that is computed at compile time.
+///
+/// `const NAME: TYPE = VALUE;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Const {
+    pub name: Identifier,
+    pub type_: TypeExpr,
+    pub value: ExprId,
+}
+
+impl From<Const> for ItemKind {
+    fn from(val: Const) -> Self {
+        ItemKind::Const(val)
+    }
+}
+
+/// An import of the item into the module.
+///
+/// `use PATH;` or `use PATH as ALIAS;`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Use {
+    pub path: RelativePath,
+    pub alias: Option<Identifier>,
+}
+
+impl Use {
+    /// Name under which item is imported.
+    pub fn name(&self) -> &Identifier {
+        self.alias
+            .as_ref()
+            .or_else(|| self.path.last())
+            .expect("import should either end with identifier or have an alias")
+    }
+}
+
+impl From<Use> for ItemKind {
+    fn from(val: Use) -> Self {
+        ItemKind::Use(val)
+    }
+}
+
+/// An implementation block that groups associated functions of the type.
+///
+/// Functions themselves are declared as separate items with path `TYPE::FUNCTION`.
+///
+/// `impl TYPE { FUNCTION... }`
+///
+/// Span of the type name is ignored when comparing implementations.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Impl {
+    pub type_name: Identifier,
+    /// Location of the type name.
+    pub type_span: Span,
+    pub functions: Vec<Identifier>,
+}
+
+impl PartialEq for Impl {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_name == other.type_name && self.functions == other.functions
+    }
+}
+
+impl From<Impl> for ItemKind {
+    fn from(val: Impl) -> Self {
+        ItemKind::Impl(val)
+    }
+}
+
 /// A parameter represents a value that the function expects you to pass when you call it.
 ///
 /// `NAME: TYPE`
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Spans are ignored when comparing parameters, as only their structure is significant.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parameter {
     pub name: Identifier,
-    pub type_: Identifier,
+    pub type_: TypeExpr,
+    pub span: Span,
+}
+
+impl PartialEq for Parameter {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.type_ == other.type_
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Visibility {
     Public,
     #[default]
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     fmt::Display,
     io::{Result, Write},
 };
@@ -6,33 +7,67 @@ use std::{
 use crate::{item_table::ItemTable, path::AbsolutePath};
 
 use super::{
-    expression::{Block, Expression, Literal},
+    expression::{Block, ExprArena, ExprId, ExpressionKind, Literal},
     item::{Item, ItemKind, Module, Visibility},
-    statement::{LetStatement, Statement},
+    statement::{LetStatement, Place, Projection, Statement, StatementKind},
 };
 
 pub fn print_table(w: impl Write + 'static, table: &ItemTable) -> Result<()> {
     let mut printer = Printer {
         writer: Box::new(w),
+        exprs: table.exprs(),
         indent: 0,
     };
-    for (path, item) in table.declared.iter() {
-        printer.print_item(path, item)?;
+    let mut associated = HashSet::new();
+    for (module, item) in table.impls() {
+        if let ItemKind::Impl(implementation) = &item.kind {
+            for function in implementation.functions.iter() {
+                let mut path = module.clone();
+                path.push(implementation.type_name.clone());
+                path.push(function.clone());
+                associated.insert(path);
+            }
+        }
+    }
+
+    for (path, item) in table.iter() {
+        if !associated.contains(path) {
+            printer.print_item(path, item)?;
+        }
+    }
+    for (module, item) in table.impls() {
+        printer.print_item(module, item)?;
+        let ItemKind::Impl(implementation) = &item.kind else { continue; };
+        printer.with_indent(|printer| {
+            for function in implementation.functions.iter() {
+                let mut path = module.clone();
+                path.push(implementation.type_name.clone());
+                path.push(function.clone());
+                if let Some(function) = table.get(&path) {
+                    printer.print_item(&path, function)?;
+                }
+            }
+            Ok(())
+        })?;
     }
     Ok(())
 }
 
-struct Printer {
+struct Printer<'a> {
     writer: Box<dyn Write>,
+    exprs: &'a ExprArena,
     indent: usize,
 }
 
-impl Printer {
+impl Printer<'_> {
     /// Width of a single indentation.
     const IDENT_WIDTH: usize = 4;
 
     fn print_item(&mut self, path: &AbsolutePath, item: &Item) -> Result<()> {
         self.println(format!("[{path}]"))?;
+        for attribute in item.attributes.iter() {
+            self.println(format!("#[{attribute}]"))?;
+        }
         if let Visibility::Public = item.visibility {
             write!(self.writer, "PUB ")?
         }
@@ -53,6 +88,9 @@ impl Printer {
             ItemKind::Function(func) => {
                 self.println(format!("FN `{}` {span}", func.name))?;
                 self.with_indent(|printer| {
+                    if func.receiver.is_some() {
+                        printer.println("SELF")?;
+                    }
                     if !func.params.is_empty() {
                         printer.println("PARAMS")?;
                         printer.with_indent(|printer| {
@@ -70,59 +108,76 @@ impl Printer {
                     Ok(())
                 })?;
             }
+            ItemKind::Const(constant) => {
+                self.println(format!(
+                    "CONST `{}`: `{}` {span}",
+                    constant.name, constant.type_
+                ))?;
+                self.with_indent(|printer| printer.print_expr(constant.value))?;
+            }
+            ItemKind::Use(import) => match &import.alias {
+                Some(alias) => writeln!(self.writer, "USE `{}` AS `{alias}` {span}", import.path)?,
+                None => writeln!(self.writer, "USE `{}` {span}", import.path)?,
+            },
+            ItemKind::Impl(implementation) => {
+                writeln!(self.writer, "IMPL `{}` {span}", implementation.type_name)?
+            }
         }
         self.newline()?;
         Ok(())
     }
 
     fn print_stmt(&mut self, stmt: &Statement) -> Result<()> {
-        match stmt {
-            Statement::ExprStmt(expr) => {
-                self.print_expr(expr)?;
+        match &stmt.kind {
+            StatementKind::ExprStmt(expr) => {
+                self.print_expr(*expr)?;
             }
-            Statement::LetStmt(LetStatement { name, type_, value }) => {
+            StatementKind::LetStmt(LetStatement {
+                name, type_, value, ..
+            }) => {
                 if let Some(type_) = type_ {
                     self.println(format!("LET `{name}`: `{type_}`"))?;
                 } else {
                     self.println(format!("LET `{name}`"))?;
                 }
                 if let Some(value) = value {
-                    self.with_indent(|printer| printer.print_expr(value))?;
+                    self.with_indent(|printer| printer.print_expr(*value))?;
                 }
             }
-            Statement::Assignment {
+            StatementKind::Assignment {
                 assignee,
                 operator,
                 expression,
             } => {
                 self.println("ASSIGN")?;
                 self.with_indent(|printer| {
-                    printer.println(format!("ASSIGNEE `{assignee}`"))?;
+                    printer.print_place(assignee)?;
                     printer.println(format!("OPERATOR `{operator}`"))?;
                     Ok(())
                 })?;
                 self.println("VALUE".to_string())?;
-                self.with_indent(|printer| printer.print_expr(expression))?;
+                self.with_indent(|printer| printer.print_expr(*expression))?;
             }
-            Statement::Return(expr) => {
+            StatementKind::Return(expr) => {
                 self.println("RETURN")?;
-                self.with_indent(|printer| printer.print_expr(expr))?;
+                self.with_indent(|printer| printer.print_expr(*expr))?;
             }
-            Statement::Break => self.println("BREAK")?,
+            StatementKind::Break => self.println("BREAK")?,
         }
         Ok(())
     }
 
-    fn print_expr(&mut self, expr: &Expression) -> Result<()> {
-        match expr {
-            Expression::Block(block) => self.print_block(block)?,
-            Expression::If {
+    fn print_expr(&mut self, expr: ExprId) -> Result<()> {
+        let exprs = self.exprs;
+        match &exprs[expr].kind {
+            ExpressionKind::Block(block) => self.print_block(block)?,
+            ExpressionKind::If {
                 condition,
                 body,
                 else_body,
             } => {
                 self.println("IF")?;
-                self.with_indent(|printer| printer.print_expr(condition))?;
+                self.with_indent(|printer| printer.print_expr(*condition))?;
 
                 self.println("THEN")?;
                 self.print_block(body)?;
@@ -132,43 +187,83 @@ impl Printer {
                     self.print_block(else_body)?;
                 }
             }
-            Expression::While { condition, body } => {
+            ExpressionKind::While { condition, body } => {
                 self.println("WHILE")?;
-                self.with_indent(|printer| printer.print_expr(condition))?;
+                self.with_indent(|printer| printer.print_expr(*condition))?;
                 self.println("BODY")?;
                 self.print_block(body)?;
             }
-            Expression::For { var, expr, body } => {
+            ExpressionKind::For { var, expr, body } => {
                 self.println(format!("FOR `{var}`"))?;
                 self.println("IN")?;
-                self.with_indent(|printer| printer.print_expr(expr))?;
+                self.with_indent(|printer| printer.print_expr(*expr))?;
                 self.println("BODY")?;
                 self.print_block(body)?;
             }
-            Expression::Literal(Literal::Number(num)) => self.println(format!("`{num}`"))?,
-            Expression::Literal(Literal::String(s)) => self.println(format!("`\"{s}\"`"))?,
-            Expression::Literal(Literal::Boolean(true)) => self.println("`true`")?,
-            Expression::Literal(Literal::Boolean(false)) => self.println("`false`")?,
-            Expression::Var(var) => self.println(var)?,
-            Expression::Unary { op, value } => {
+            ExpressionKind::Literal(Literal::Number(num)) => self.println(format!("`{num}`"))?,
+            ExpressionKind::Literal(Literal::String(s)) => self.println(format!("`\"{s}\"`"))?,
+            ExpressionKind::Literal(Literal::Boolean(true)) => self.println("`true`")?,
+            ExpressionKind::Literal(Literal::Boolean(false)) => self.println("`false`")?,
+            ExpressionKind::Var(var) => self.println(var)?,
+            ExpressionKind::Path(path) => self.println(path)?,
+            ExpressionKind::Cast { value, type_ } => {
+                self.println(format!("CAST `{type_}`"))?;
+                self.with_indent(|printer| printer.print_expr(*value))?;
+            }
+            ExpressionKind::FieldAccess { value, field } => {
+                self.println(format!("FIELD `{field}`"))?;
+                self.with_indent(|printer| printer.print_expr(*value))?;
+            }
+            ExpressionKind::Index { value, index } => {
+                self.println("INDEX")?;
+                self.with_indent(|printer| {
+                    printer.println("VALUE")?;
+                    printer.with_indent(|printer| printer.print_expr(*value))?;
+                    printer.println("POSITION")?;
+                    printer.with_indent(|printer| printer.print_expr(*index))?;
+                    Ok(())
+                })?;
+            }
+            ExpressionKind::Unary { op, value } => {
                 self.println(format!("UNARY `{op}`"))?;
-                self.with_indent(|printer| printer.print_expr(value))?;
+                self.with_indent(|printer| printer.print_expr(*value))?;
             }
-            Expression::Binary { op, left, right } => {
+            ExpressionKind::Binary { op, left, right } => {
                 self.println(format!("BINARY `{op}`"))?;
                 self.with_indent(|printer| {
                     printer.println("LEFT")?;
-                    printer.with_indent(|printer| printer.print_expr(left))?;
+                    printer.with_indent(|printer| printer.print_expr(*left))?;
                     printer.println("RIGHT")?;
-                    printer.with_indent(|printer| printer.print_expr(right))?;
+                    printer.with_indent(|printer| printer.print_expr(*right))?;
                     Ok(())
                 })?;
             }
-            Expression::FnCall { path, params } => {
+            ExpressionKind::FnCall { path, params } => {
                 self.println(format!("FNCALL `{path}`"))?;
                 self.with_indent(|printer| {
                     for param in params {
-                        printer.print_expr(param)?;
+                        printer.print_expr(*param)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            ExpressionKind::MethodCall {
+                value,
+                method,
+                params,
+            } => {
+                self.println(format!("METHODCALL `{method}`"))?;
+                self.with_indent(|printer| {
+                    printer.println("RECEIVER")?;
+                    printer.with_indent(|printer| printer.print_expr(*value))?;
+                    if !params.is_empty() {
+                        printer.println("ARGS")?;
+                        printer.with_indent(|printer| {
+                            for param in params {
+                                printer.print_expr(*param)?;
+                            }
+                            Ok(())
+                        })?;
                     }
                     Ok(())
                 })?;
@@ -177,12 +272,28 @@ impl Printer {
         Ok(())
     }
 
+    fn print_place(&mut self, place: &Place) -> Result<()> {
+        self.println(format!("ASSIGNEE `{}`", place.var))?;
+        self.with_indent(|printer| {
+            for projection in place.projections.iter() {
+                match projection {
+                    Projection::Field(field) => printer.println(format!("FIELD `{field}`"))?,
+                    Projection::Index(index) => {
+                        printer.println("INDEX")?;
+                        printer.with_indent(|printer| printer.print_expr(*index))?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
     fn print_block(&mut self, block: &Block) -> Result<()> {
         self.with_indent(|printer| {
             for stmt in block.statements.iter() {
                 printer.print_stmt(stmt)?;
             }
-            if let Some(expr) = &block.expression {
+            if let Some(expr) = block.expression {
                 printer.print_expr(expr)?;
             }
             Ok(())
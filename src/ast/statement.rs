@@ -1,22 +1,139 @@
-use crate::{ast::expression::Expression, lexer::operator::AssignOp, Identifier};
+use crate::{
+    ast::{
+        expression::{ExprArena, ExprId, Expression, ExpressionKind},
+        types::TypeExpr,
+    },
+    lexer::operator::AssignOp,
+    util::Span,
+    Identifier,
+};
+
+/// A statement with its location in code.
+///
+/// Spans are ignored when comparing statements, as only their structure is significant.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Statement {
-    ExprStmt(Expression),
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StatementKind {
+    ExprStmt(ExprId),
     LetStmt(LetStatement),
     Assignment {
-        assignee: Identifier,
+        assignee: Place,
         operator: AssignOp,
-        expression: Expression,
+        expression: ExprId,
     },
-    Return(Expression),
+    Return(ExprId),
     Break,
 }
 
-/// let VAR: TYPE = VALUE;
+impl StatementKind {
+    /// Shifts ids of expressions of the statement by `offset`.
+    pub(crate) fn shift(&mut self, offset: u32) {
+        match self {
+            StatementKind::ExprStmt(expr) | StatementKind::Return(expr) => expr.shift(offset),
+            StatementKind::Assignment {
+                assignee,
+                expression,
+                ..
+            } => {
+                assignee.shift(offset);
+                expression.shift(offset);
+            }
+            StatementKind::LetStmt(LetStatement { value, .. }) => {
+                if let Some(value) = value {
+                    value.shift(offset);
+                }
+            }
+            StatementKind::Break => {}
+        }
+    }
+}
+
+/// Place that value may be assigned to: a variable followed by any number of field and element
+/// accesses, such as `x`, `point.x` or `lines[i].start`.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Place {
+    pub var: Identifier,
+    /// Accesses applied to the variable, from the innermost to the outermost one.
+    pub projections: Vec<Projection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Projection {
+    Field(Identifier),
+    Index(ExprId),
+}
+
+impl Place {
+    /// Converts expression to a place, if it is a variable or an access to one.
+    ///
+    /// Nested expressions are looked up in `exprs`.
+    pub fn from_expr(expr: &Expression, exprs: &ExprArena) -> Option<Self> {
+        let mut projections = Vec::new();
+        let mut expr = expr;
+        let var = loop {
+            match &expr.kind {
+                ExpressionKind::Var(var) => break var.clone(),
+                ExpressionKind::FieldAccess { value, field } => {
+                    projections.push(Projection::Field(field.clone()));
+                    expr = &exprs[*value];
+                }
+                ExpressionKind::Index { value, index } => {
+                    projections.push(Projection::Index(*index));
+                    expr = &exprs[*value];
+                }
+                _ => return None,
+            }
+        };
+        projections.reverse();
+        Some(Self { var, projections })
+    }
+
+    /// Shifts ids of index expressions by `offset`.
+    pub(crate) fn shift(&mut self, offset: u32) {
+        for projection in self.projections.iter_mut() {
+            if let Projection::Index(index) = projection {
+                index.shift(offset);
+            }
+        }
+    }
+}
+
+/// let VAR: TYPE = VALUE;
+///
+/// Spans are ignored when comparing statements, as only their structure is significant.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LetStatement {
     pub name: Identifier,
-    pub type_: Option<Identifier>,
-    pub value: Option<Box<Expression>>,
+    pub type_: Option<TypeExpr>,
+    /// Location of the type annotation, if any.
+    pub type_span: Option<Span>,
+    pub value: Option<ExprId>,
+}
+
+impl PartialEq for LetStatement {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.type_ == other.type_ && self.value == other.value
+    }
 }
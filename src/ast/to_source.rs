@@ -0,0 +1,565 @@
+//! Conversion of the AST back to Sunshine source code.
+//!
+//! Output is formatted consistently regardless of the original formatting, and parsing it again
+//! yields an equal AST.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{Result, Write},
+};
+
+use crate::{item_table::ItemTable, lexer::operator::BinaryOp, path::AbsolutePath};
+
+use super::{
+    expression::{Block, ExprId, ExpressionKind, Literal},
+    item::{Item, ItemKind, Module, Visibility},
+    statement::{LetStatement, Place, Projection, Statement, StatementKind},
+};
+
+/// Writes source code of every top-level module of the table.
+///
+/// Contents of loadable modules (`mod NAME;`) are not written, as they belong to separate files.
+pub fn write_source(w: impl Write, table: &ItemTable) -> Result<()> {
+    let mut children = HashMap::<AbsolutePath, Vec<(&AbsolutePath, &Item)>>::new();
+    let mut roots = Vec::new();
+    for (path, item) in table.iter() {
+        let mut parent = path.clone();
+        match parent.pop() {
+            Some(_) => children.entry(parent).or_default().push((path, item)),
+            None => roots.push(path),
+        }
+    }
+    for items in children.values_mut() {
+        items.sort_by_cached_key(|(path, _)| path.to_string());
+    }
+    roots.sort_by_cached_key(|path| path.to_string());
+
+    let mut writer = SourceWriter {
+        writer: w,
+        table,
+        children,
+        indent: 0,
+        line_start: true,
+    };
+    for root in roots {
+        writer.write_module_items(root)?;
+    }
+    Ok(())
+}
+
+struct SourceWriter<'a, W: Write> {
+    writer: W,
+    table: &'a ItemTable,
+    /// Items declared in the module, sorted by path.
+    children: HashMap<AbsolutePath, Vec<(&'a AbsolutePath, &'a Item)>>,
+    indent: usize,
+    /// Whether nothing was written on the current line yet.
+    line_start: bool,
+}
+
+impl<W: Write> SourceWriter<'_, W> {
+    /// Width of a single indentation.
+    const IDENT_WIDTH: usize = 4;
+
+    /// Writes items of the module, including implementation blocks declared in it.
+    fn write_module_items(&mut self, module: &AbsolutePath) -> Result<()> {
+        let mut first = true;
+        let children = self.children.get(module).cloned().unwrap_or_default();
+        for (path, item) in children {
+            if !first {
+                self.newline()?;
+            }
+            first = false;
+            self.write_item(path, item)?;
+        }
+
+        let impls = self
+            .table
+            .impls()
+            .iter()
+            .filter(|(scope, _)| scope == module);
+        for (_, item) in impls {
+            let ItemKind::Impl(implementation) = &item.kind else { continue; };
+            if !first {
+                self.newline()?;
+            }
+            first = false;
+            self.write_visibility(item.visibility)?;
+            self.write(format!("impl {} {{", implementation.type_name))?;
+            if !implementation.functions.is_empty() {
+                self.newline()?;
+                self.with_indent(|writer| {
+                    for (i, function) in implementation.functions.iter().enumerate() {
+                        let mut path = module.clone();
+                        path.push(implementation.type_name.clone());
+                        path.push(function.clone());
+                        let Some(item) = writer.table.get(&path) else { continue; };
+                        if i != 0 {
+                            writer.newline()?;
+                        }
+                        writer.write_item(&path, item)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            self.write("}")?;
+            self.newline()?;
+        }
+        Ok(())
+    }
+
+    fn write_item(&mut self, path: &AbsolutePath, item: &Item) -> Result<()> {
+        for attribute in item.attributes.iter() {
+            self.write(format!("#[{attribute}]"))?;
+            self.newline()?;
+        }
+        self.write_visibility(item.visibility)?;
+        match &item.kind {
+            ItemKind::Module(Module::Inline(name)) => {
+                self.write(format!("mod {name} {{"))?;
+                if self.children.contains_key(path)
+                    || self.table.impls().iter().any(|(scope, _)| scope == path)
+                {
+                    self.newline()?;
+                    self.with_indent(|writer| writer.write_module_items(path))?;
+                }
+                self.write("}")?;
+            }
+            ItemKind::Module(Module::Loadable(name)) => self.write(format!("mod {name};"))?,
+            ItemKind::Struct(s) => {
+                self.write(format!("struct {} {{", s.name))?;
+                if !s.fields.is_empty() {
+                    self.newline()?;
+                    self.with_indent(|writer| {
+                        for field in s.fields.iter() {
+                            writer.write(format!("{}: {},", field.name, field.type_))?;
+                            writer.newline()?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                self.write("}")?;
+            }
+            ItemKind::Function(func) => {
+                self.write(format!("fn {}(", func.name))?;
+                if func.receiver.is_some() {
+                    self.write("self")?;
+                }
+                for (i, param) in func.params.iter().enumerate() {
+                    if i != 0 || func.receiver.is_some() {
+                        self.write(", ")?;
+                    }
+                    self.write(format!("{}: {}", param.name, param.type_))?;
+                }
+                self.write(")")?;
+                if let Some(return_type) = &func.return_type {
+                    self.write(format!(" -> {return_type}"))?;
+                }
+                self.write(" ")?;
+                self.write_block(&func.body)?;
+            }
+            ItemKind::Const(constant) => {
+                self.write(format!("const {}: {} = ", constant.name, constant.type_))?;
+                self.write_expr(constant.value)?;
+                self.write(";")?;
+            }
+            ItemKind::Use(import) => match &import.alias {
+                Some(alias) => self.write(format!("use {} as {alias};", import.path))?,
+                None => self.write(format!("use {};", import.path))?,
+            },
+            ItemKind::Impl(_) => unreachable!("implementation blocks aren't stored as items"),
+        }
+        self.newline()
+    }
+
+    fn write_visibility(&mut self, visibility: Visibility) -> Result<()> {
+        match visibility {
+            Visibility::Public => self.write("pub "),
+            Visibility::Private => Ok(()),
+        }
+    }
+
+    /// Writes statement at the current line.
+    ///
+    /// `is_last` defines if a statement is the last one of a block without tail expression, so
+    /// block expression statement needs a semicolon to not become the tail.
+    fn write_stmt(&mut self, stmt: &Statement, is_last: bool) -> Result<()> {
+        match &stmt.kind {
+            StatementKind::ExprStmt(expr) => {
+                self.write_expr(*expr)?;
+                if !self.table.expr(*expr).is_block_expression() || is_last {
+                    self.write(";")?;
+                }
+            }
+            StatementKind::LetStmt(LetStatement {
+                name, type_, value, ..
+            }) => {
+                self.write(format!("let {name}"))?;
+                if let Some(type_) = type_ {
+                    self.write(format!(": {type_}"))?;
+                }
+                if let Some(value) = value {
+                    self.write(" = ")?;
+                    self.write_expr(*value)?;
+                }
+                self.write(";")?;
+            }
+            StatementKind::Assignment {
+                assignee,
+                operator,
+                expression,
+            } => {
+                self.write_place(assignee)?;
+                self.write(format!(" {operator} "))?;
+                self.write_expr(*expression)?;
+                self.write(";")?;
+            }
+            StatementKind::Return(expr) => {
+                self.write("return ")?;
+                self.write_expr(*expr)?;
+                self.write(";")?;
+            }
+            StatementKind::Break => self.write("break;")?,
+        }
+        Ok(())
+    }
+
+    fn write_expr(&mut self, expr: ExprId) -> Result<()> {
+        let table = self.table;
+        match &table.expr(expr).kind {
+            ExpressionKind::Block(block) => self.write_block(block)?,
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                self.write("if ")?;
+                self.write_expr(*condition)?;
+                self.write(" ")?;
+                self.write_block(body)?;
+                if let Some(else_body) = else_body {
+                    self.write(" else ")?;
+                    self.write_block(else_body)?;
+                }
+            }
+            ExpressionKind::While { condition, body } => {
+                self.write("while ")?;
+                self.write_expr(*condition)?;
+                self.write(" ")?;
+                self.write_block(body)?;
+            }
+            ExpressionKind::For { var, expr, body } => {
+                self.write(format!("for {var} in "))?;
+                self.write_expr(*expr)?;
+                self.write(" ")?;
+                self.write_block(body)?;
+            }
+            ExpressionKind::Unary { op, value } => {
+                self.write(op)?;
+                let is_atom = matches!(
+                    table.expr(*value).kind,
+                    ExpressionKind::Var(_)
+                        | ExpressionKind::Path(_)
+                        | ExpressionKind::Literal(_)
+                        | ExpressionKind::FnCall { .. }
+                        | ExpressionKind::MethodCall { .. }
+                        | ExpressionKind::FieldAccess { .. }
+                        | ExpressionKind::Index { .. }
+                );
+                self.write_parenthesized(*value, !is_atom)?;
+            }
+            ExpressionKind::Binary { op, left, right } => {
+                self.write_operand(*left, *op, false)?;
+                self.write(format!(" {op} "))?;
+                self.write_operand(*right, *op, true)?;
+            }
+            ExpressionKind::FnCall { path, params } => {
+                self.write(format!("{path}("))?;
+                for (i, param) in params.iter().enumerate() {
+                    if i != 0 {
+                        self.write(", ")?;
+                    }
+                    self.write_expr(*param)?;
+                }
+                self.write(")")?;
+            }
+            ExpressionKind::Cast { value, type_ } => {
+                // Cast binds tighter than any binary operator, but looser than unary ones.
+                let value_expr = table.expr(*value);
+                let parenthesize = matches!(value_expr.kind, ExpressionKind::Binary { .. })
+                    || value_expr.is_block_expression();
+                self.write_parenthesized(*value, parenthesize)?;
+                self.write(format!(" as {type_}"))?;
+            }
+            ExpressionKind::MethodCall {
+                value,
+                method,
+                params,
+            } => {
+                self.write_expr(*value)?;
+                self.write(format!(".{method}("))?;
+                for (i, param) in params.iter().enumerate() {
+                    if i != 0 {
+                        self.write(", ")?;
+                    }
+                    self.write_expr(*param)?;
+                }
+                self.write(")")?;
+            }
+            ExpressionKind::FieldAccess { value, field } => {
+                self.write_expr(*value)?;
+                self.write(format!(".{field}"))?;
+            }
+            ExpressionKind::Index { value, index } => {
+                self.write_expr(*value)?;
+                self.write("[")?;
+                self.write_expr(*index)?;
+                self.write("]")?;
+            }
+            ExpressionKind::Var(var) => self.write(var)?,
+            ExpressionKind::Path(path) => self.write(path)?,
+            ExpressionKind::Literal(Literal::Number(num)) => self.write(num)?,
+            ExpressionKind::Literal(Literal::String(s)) => {
+                self.write(format!("\"{}\"", escape(s)))?
+            }
+            ExpressionKind::Literal(Literal::Boolean(value)) => self.write(value)?,
+        }
+        Ok(())
+    }
+
+    /// Writes operand of the binary operator, parenthesizing it if required by operator priority.
+    ///
+    /// All binary operators are left-associative, so right operand of the same priority is
+    /// parenthesized too. Unary operators and block expressions are always parenthesized.
+    fn write_operand(&mut self, operand: ExprId, op: BinaryOp, is_right: bool) -> Result<()> {
+        let expr = self.table.expr(operand);
+        let parenthesize = match &expr.kind {
+            ExpressionKind::Binary { op: inner, .. } => {
+                inner.priority() < op.priority() || is_right && inner.priority() == op.priority()
+            }
+            ExpressionKind::Unary { .. } => true,
+            _ => expr.is_block_expression(),
+        };
+        self.write_parenthesized(operand, parenthesize)
+    }
+
+    fn write_place(&mut self, place: &Place) -> Result<()> {
+        self.write(&place.var)?;
+        for projection in place.projections.iter() {
+            match projection {
+                Projection::Field(field) => self.write(format!(".{field}"))?,
+                Projection::Index(index) => {
+                    self.write("[")?;
+                    self.write_expr(*index)?;
+                    self.write("]")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_parenthesized(&mut self, expr: ExprId, parenthesize: bool) -> Result<()> {
+        if parenthesize {
+            self.write("(")?;
+            self.write_expr(expr)?;
+            self.write(")")
+        } else {
+            self.write_expr(expr)
+        }
+    }
+
+    /// Writes block, starting from the current position and ending right after closing brace.
+    fn write_block(&mut self, block: &Block) -> Result<()> {
+        if block.statements.is_empty() && block.expression.is_none() {
+            return self.write("{}");
+        }
+        self.write("{")?;
+        self.with_indent(|writer| {
+            for (i, stmt) in block.statements.iter().enumerate() {
+                writer.newline()?;
+                let is_last = i + 1 == block.statements.len() && block.expression.is_none();
+                writer.write_stmt(stmt, is_last)?;
+            }
+            if let Some(expr) = block.expression {
+                writer.newline()?;
+                writer.write_expr(expr)?;
+            }
+            Ok(())
+        })?;
+        self.newline()?;
+        self.write("}")
+    }
+
+    fn with_indent(&mut self, f: impl Fn(&mut Self) -> Result<()>) -> Result<()> {
+        self.indent += 1;
+        f(self)?;
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn newline(&mut self) -> Result<()> {
+        self.line_start = true;
+        writeln!(self.writer)
+    }
+
+    /// Writes text on the current line, indenting it if nothing was written on it yet.
+    fn write(&mut self, text: impl Display) -> Result<()> {
+        if self.line_start {
+            self.line_start = false;
+            write!(
+                self.writer,
+                "{}",
+                " ".repeat(self.indent * Self::IDENT_WIDTH)
+            )?;
+        }
+        write!(self.writer, "{text}")
+    }
+}
+
+/// Escapes string to be used inside of string literal.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\0' => escaped.push_str("\\0"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::{
+            dot::write_dot,
+            item::{Item, ItemKind, Visibility},
+        },
+        item_table::ItemTable,
+        parser::FileParser,
+        path::AbsolutePath,
+    };
+
+    use super::write_source;
+
+    /// Path, visibility and kind of the item, kind is omitted for items with expressions.
+    type Entry = (String, Visibility, Option<ItemKind>);
+
+    fn parse(src: &str) -> ItemTable {
+        let parsed = FileParser::new_test(src).parse();
+        assert!(parsed.errors.is_empty(), "failed to parse:\n{src}");
+        parsed.item_table
+    }
+
+    fn to_source(table: &ItemTable) -> String {
+        let mut output = Vec::new();
+        write_source(&mut output, table).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    /// Items of the table in a stable order along with their graph, ignoring spans.
+    ///
+    /// Ids of expressions depend on the order items were parsed in, so expressions are only
+    /// compared by the graph.
+    fn items(table: &ItemTable) -> (Vec<Entry>, Vec<Entry>, String) {
+        let entry = |(path, item): (&AbsolutePath, &Item)| {
+            let kind = match &item.kind {
+                ItemKind::Function(_) | ItemKind::Const(_) => None,
+                kind => Some(kind.clone()),
+            };
+            (path.to_string(), item.visibility, kind)
+        };
+        let mut declared: Vec<_> = table.iter().map(entry).collect();
+        declared.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut impls: Vec<_> = table
+            .impls()
+            .iter()
+            .map(|(path, item)| entry((path, item)))
+            .collect();
+        impls.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut graph = Vec::new();
+        write_dot(&mut graph, table).unwrap();
+        (declared, impls, String::from_utf8(graph).unwrap())
+    }
+
+    #[test]
+    fn formatting() {
+        let table = parse(
+            "pub struct Point { x: i32, y: i32 } impl Point { fn new() {} pub fn mix() {} }
+            fn run(a: i32) -> i32 { let b: i32 = a * (2 + 3);
+            if b > 0 { return b; } else { b -= 1; } while true { break; } b }",
+        );
+        assert_eq!(
+            to_source(&table),
+            "\
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn run(a: i32) -> i32 {
+    let b: i32 = a * (2 + 3);
+    if b > 0 {
+        return b;
+    } else {
+        b -= 1;
+    }
+    while true {
+        break;
+    }
+    b
+}
+
+impl Point {
+    fn new() {}
+
+    pub fn mix() {}
+}
+"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let corpus = [
+            "struct name {}",
+            "struct name { field1: type1, field2: type2, }",
+            "struct name { field1: type1, field2: type2 }",
+            "const LIMIT: i32 = 2 * 8;",
+            "use super::thing as alias;",
+            "use crate::a::item; pub use crate::b::other;",
+            "struct Color {} impl Color { fn new() {} pub fn mix() {} }",
+            "impl Point { fn norm(self) -> f32 { self.dot(self).sqrt() } fn scale(self, k: f32) {} }",
+            "mod geometry { pub struct Point { x: i32 } mod inner; } fn geometry() {}",
+            "mod empty {} mod outer { mod inner { fn deep() {} } }",
+            "fn run(point: crate::geometry::Point, pair: (i32, bool), one: (i32,), unit: ()) {}",
+            "fn run(buffer: [(u8, u8); 0x10]) -> [i32; 4] {}",
+            "fn run() { -x }",
+            "fn run() { 4 >= x }",
+            "fn run() { 1 + -2 }",
+            "fn run() { 1 + -2 - (3 * 4) / -5 }",
+            "fn run() { (1 - 2) - 3; 1 - (2 - 3); !(a && b) || c; -(-x) }",
+            "fn run() { f(1, g(2), super::super::module::item()) }",
+            "fn run() { let s: str = \"quoted \\\"text\\\"\\n\\ttab\"; let f = 1.5; 0b101 + 0o17 + 0xFF }",
+            "fn run() { if a { b } else { c } }",
+            "fn run() { if a { b; } while x < 10 { x += 1; } }",
+            "fn run() { for i in range(0, 10) { print(i); } { inner(); } }",
+            "fn run() { { 1 } + 2 }",
+            "fn run() { p.x = 1; lines[i + 1].start += p.values[0]; -f().y * a[b[0]] }",
+            "fn run() -> bool { return true && false; }",
+        ];
+        for src in corpus {
+            let table = parse(src);
+            let source = to_source(&table);
+            let reparsed = parse(&source);
+            assert_eq!(items(&table), items(&reparsed), "source:\n{source}");
+            assert_eq!(source, to_source(&reparsed));
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt::Display;
+
+use crate::{
+    path::{RelativePath, RelativePathStart},
+    Identifier,
+};
+
+/// A type as it is written in the source code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TypeExpr {
+    /// Named type, either primitive or user-defined.
+    ///
+    /// `PATH`
+    Path(RelativePath),
+    /// `(TYPE, TYPE, ...)`
+    Tuple(Vec<TypeExpr>),
+    /// `[TYPE; LENGTH]`
+    Array {
+        element: Box<TypeExpr>,
+        length: usize,
+    },
+    /// `()`
+    Unit,
+}
+
+impl From<Identifier> for TypeExpr {
+    fn from(val: Identifier) -> Self {
+        TypeExpr::Path(RelativePath::new(RelativePathStart::Identifier(val)))
+    }
+}
+
+impl Display for TypeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeExpr::Path(path) => write!(f, "{path}"),
+            TypeExpr::Tuple(types) => {
+                write!(f, "(")?;
+                for (i, type_) in types.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{type_}")?;
+                }
+                if types.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            TypeExpr::Array { element, length } => write!(f, "[{element}; {length}]"),
+            TypeExpr::Unit => write!(f, "()"),
+        }
+    }
+}
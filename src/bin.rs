@@ -1,17 +1,92 @@
-use clap::Parser as ArgParser;
+use clap::{Args as ArgGroup, Parser as ArgParser, Subcommand, ValueEnum};
 use compiler::{
     ast::pretty_print::print_table,
-    context::{Context, Emit, Metadata},
-    hir::HirBuilder,
-    parser::Parser,
-    Identifier,
+    compile, compile_str,
+    context::{Context, Emit, ErrorFormat},
+    error::{library::explain, LintLevel},
+    hir::{pretty_print::print_hir, test_runner::run_tests},
+    input_stream::InputStream,
+    lexer::{
+        dump::{write_tokens, write_tokens_json},
+        Lexer,
+    },
+    source::STDIN_PATH,
+    watch::{WatchSession, Watcher},
+    CompileOptions, CompileResult, Identifier,
 };
-use std::{io::stdout, path::PathBuf, str::FromStr};
+use std::{
+    io::{self, stderr, stdin, stdout, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+};
+
+/// Exit code for errors in the compiled code.
+const COMPILATION_FAILED: u8 = 1;
+/// Exit code for I/O and usage errors.
+const USAGE_ERROR: u8 = 2;
+/// Names of the main file that is looked up if INPUT is a directory, in order of preference.
+const MAIN_FILES: [&str; 2] = ["main.sun", "bin.sun"];
 
 #[derive(ArgParser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[arg(help = "Path to the root file of the crate", value_name = "INPUT")]
+    #[command(subcommand)]
+    command: Command,
+    #[arg(long, global = true, help = "Highlight diagnostics with ANSI colors")]
+    color: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Parse the crate and print its items.
+    Parse(CompileArgs),
+    /// Check the crate for errors without printing anything else.
+    Check(CompileArgs),
+    /// Check the crate, then check it again every time its files change.
+    Watch(CompileArgs),
+    /// Translate the crate to HIR and print it.
+    Hir(CompileArgs),
+    /// Run functions annotated with `#[test]` with the interpreter.
+    Test {
+        #[command(flatten)]
+        compile_args: CompileArgs,
+        #[arg(
+            long,
+            value_name = "SUBSTRING",
+            help = "Only run tests which paths contain SUBSTRING"
+        )]
+        filter: Option<String>,
+    },
+    /// Translate the crate to C and print it.
+    EmitC(CompileArgs),
+    /// Translate the crate to WebAssembly text format and print it.
+    #[cfg(feature = "wasm")]
+    EmitWat(CompileArgs),
+    /// Print tokens of a file along with their locations.
+    Lex {
+        #[arg(
+            value_name = "INPUT",
+            help = "Path to the file, `-` to read it from stdin"
+        )]
+        path: PathBuf,
+        #[arg(long, help = "Print tokens as JSON array")]
+        json: bool,
+    },
+    /// Print detailed explanation of an error code.
+    Explain {
+        #[arg(value_name = "CODE")]
+        code: String,
+    },
+}
+
+/// Options shared by subcommands that compile a crate.
+#[derive(ArgGroup, Debug)]
+struct CompileArgs {
+    #[arg(
+        help = "Path to the root file of the crate or to the directory that contains `main.sun` or `bin.sun`, `-` to read it from stdin",
+        value_name = "INPUT"
+    )]
     path: PathBuf,
     #[arg(
         long,
@@ -19,57 +94,336 @@ struct Args {
         help = "Specify the name of the crate being built"
     )]
     crate_name: Option<Identifier>,
-    #[arg(long, default_value = "binary")]
-    emit: Emit,
+    #[arg(long, help = "Compute constant expressions at compile time")]
+    fold_constants: bool,
+    #[arg(long, default_value = "human", help = "Format of the diagnostics")]
+    error_format: ErrorFormat,
+    #[arg(long, value_name = "CODE", help = "Silence errors with specified code")]
+    allow: Vec<String>,
+    #[arg(
+        long,
+        value_name = "CODE",
+        help = "Report errors with specified code as warnings"
+    )]
+    warn: Vec<String>,
+    #[arg(
+        long,
+        value_name = "CODE",
+        help = "Fail compilation on errors with specified code"
+    )]
+    deny: Vec<String>,
+    #[arg(long, value_name = "N", help = "Abort compilation after N errors")]
+    max_errors: Option<usize>,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory to look up modules in [default: directory of INPUT]"
+    )]
+    root: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 4,
+        help = "Number of columns to render tabs with in diagnostics"
+    )]
+    tab_width: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 2000,
+        help = "Maximal depth of nested expressions and modules"
+    )]
+    max_nesting: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 65536,
+        help = "Maximal number of operators and operands in a single expression"
+    )]
+    max_expression_tokens: usize,
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        help = "Number of threads to parse files with [default: number of CPUs]"
+    )]
+    jobs: Option<usize>,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "table",
+        help = "Print duration and size of compilation phases"
+    )]
+    timings: Option<TimingsFormat>,
+    #[cfg(feature = "serde")]
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory to cache parsed files in between runs"
+    )]
+    cache_dir: Option<PathBuf>,
+    #[cfg(feature = "serde")]
+    #[arg(long, help = "Parse every file even if --cache-dir is given")]
+    no_cache: bool,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let crate_name = match args.crate_name {
-        Some(crate_name) => crate_name,
-        None => {
-            let x = args.path.file_stem().unwrap().to_string_lossy().to_string();
-            Identifier::from_str(&x)?
+/// Format of the compilation statistics.
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum TimingsFormat {
+    Table,
+    Json,
+}
+
+impl CompileArgs {
+    /// Compiles the crate up to the `emit` stage and reports diagnostics to stderr.
+    fn compile(self, emit: Emit, color: bool) -> anyhow::Result<CompileResult> {
+        let options = self.options(emit)?;
+        let result = if is_stdin(&self.path) {
+            compile_str(&read_stdin()?, options.main(STDIN_PATH))
+        } else {
+            compile(self.main_file()?, options)?
+        };
+        if !result.diagnostics.is_empty() {
+            result.context.error_reporter.emit(stderr(), color)?;
         }
-    };
-    let context = Context::new(
-        args.path.clone(),
-        Metadata {
-            crate_name,
-            emit_type: args.emit,
-        },
-    )?;
-    let mut parser = Parser::new(args.path, context)?;
+        if let Some(stats) = &result.stats {
+            match self.timings {
+                Some(TimingsFormat::Json) => stats.write_json(stderr())?,
+                _ => stats.write_table(stderr())?,
+            }
+        }
+        Ok(result)
+    }
 
-    let item_table = parser.parse();
+    /// Options of the compilation up to the `emit` stage, except for the main file.
+    fn options(&self, emit: Emit) -> anyhow::Result<CompileOptions> {
+        let stdin = is_stdin(&self.path);
+        let crate_name = match self.crate_name.clone() {
+            Some(crate_name) => Some(crate_name),
+            // Crate read from stdin keeps the default name.
+            None if stdin => None,
+            None => {
+                let main = self.main_file()?;
+                let x = main.file_stem().unwrap().to_string_lossy().to_string();
+                Some(Identifier::from_str(&x)?)
+            }
+        };
+        let mut options = Context::builder()
+            .emit(emit)
+            .error_format(self.error_format)
+            .fold_constants(self.fold_constants)
+            .max_errors(self.max_errors)
+            .tab_width(self.tab_width)
+            .max_nesting(self.max_nesting)
+            .max_expression_tokens(self.max_expression_tokens)
+            .timings(self.timings.is_some());
+        if let Some(root) = &self.root {
+            options = options.root(root);
+        }
+        if let Some(jobs) = self.jobs {
+            options = options.jobs(jobs);
+        }
+        #[cfg(feature = "serde")]
+        if !self.no_cache {
+            options = options.cache_dir(self.cache_dir.clone());
+        }
+        for (codes, level) in [
+            (&self.allow, LintLevel::Allow),
+            (&self.warn, LintLevel::Warn),
+            (&self.deny, LintLevel::Deny),
+        ] {
+            for code in codes {
+                options = options.lint_level(code, level);
+            }
+        }
+        if let Some(crate_name) = crate_name {
+            options = options.crate_name(crate_name);
+        }
+        Ok(options)
+    }
 
-    match parser.context.metadata.emit_type {
-        Emit::Ast => match &item_table {
-            Ok(table) => print_table(stdout(), table)?,
-            Err(_) => {
-                println!("{}", parser.context.error_reporter);
+    /// Path to the main file of the crate, which is looked up in INPUT if it's a directory.
+    fn main_file(&self) -> anyhow::Result<PathBuf> {
+        if !self.path.is_dir() {
+            return Ok(self.path.clone());
+        }
+        let file = MAIN_FILES
+            .iter()
+            .map(|name| self.path.join(name))
+            .find(|file| file.is_file());
+        file.ok_or_else(|| {
+            anyhow::anyhow!(
+                "directory `{}` contains neither `main.sun` nor `bin.sun`",
+                self.path.display()
+            )
+        })
+    }
+}
+
+fn main() -> ExitCode {
+    #[cfg(feature = "trace")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(stderr)
+        .init();
+    let args = Args::parse();
+    match run(args) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            ExitCode::from(USAGE_ERROR)
+        }
+    }
+}
+
+fn run(args: Args) -> anyhow::Result<ExitCode> {
+    let result = match args.command {
+        Command::Parse(compile_args) => {
+            let result = compile_args.compile(Emit::Ast, args.color)?;
+            if !result.failed() {
+                print_table(stdout(), &result.item_table)?;
+            }
+            result
+        }
+        Command::Check(compile_args) => compile_args.compile(Emit::Hir, args.color)?,
+        Command::Watch(compile_args) => return watch(compile_args, args.color),
+        Command::Hir(compile_args) => {
+            let result = compile_args.compile(Emit::Hir, args.color)?;
+            if let Some(hir) = &result.hir {
+                print_hir(stdout(), hir)?;
+            }
+            result
+        }
+        Command::Test {
+            compile_args,
+            filter,
+        } => {
+            let result = compile_args.compile(Emit::Hir, args.color)?;
+            let Some(hir) = &result.hir else {
+                return Ok(exit_code(true));
+            };
+            let report = run_tests(hir, &result.context, filter.as_deref(), |outcome| {
+                println!("{outcome}")
+            });
+            println!("{report}");
+            return Ok(exit_code(report.failed() > 0));
+        }
+        Command::EmitC(compile_args) => {
+            let result = compile_args.compile(Emit::Hir, args.color)?;
+            if let Some(hir) = &result.hir {
+                print!("{}", compiler::codegen::c::emit_c(hir, hir.type_table()));
             }
-        },
-        Emit::Hir => match item_table {
-            Ok(item_table) => {
-                let mut builder = HirBuilder::new();
-                builder.populate(item_table);
-                match builder.build() {
-                    Ok(hir) => println!("{:#?}", hir),
+            result
+        }
+        #[cfg(feature = "wasm")]
+        Command::EmitWat(compile_args) => {
+            let result = compile_args.compile(Emit::Hir, args.color)?;
+            if let Some(hir) = &result.hir {
+                match compiler::codegen::wasm::emit_wat(hir) {
+                    Ok(wat) => print!("{wat}"),
                     Err(errors) => {
-                        for err in errors {
-                            println!("{}", err);
+                        for error in errors {
+                            eprintln!("error: {error}");
                         }
+                        return Ok(exit_code(true));
                     }
-                };
+                }
             }
-            Err(_) => {
-                println!("{}", parser.context.error_reporter);
+            result
+        }
+        Command::Lex { path, json } => return lex(path, json, args.color),
+        Command::Explain { code } => {
+            let Some(info) = explain(&code) else {
+                anyhow::bail!("no explanation is available for error code `{code}`");
+            };
+            println!("{}: {}\n\n{}", info.code, info.name, info.explanation());
+            return Ok(ExitCode::SUCCESS);
+        }
+    };
+    Ok(exit_code(result.failed()))
+}
+
+/// Checks the crate, then checks it again every time files in its root directory change.
+fn watch(args: CompileArgs, color: bool) -> anyhow::Result<ExitCode> {
+    anyhow::ensure!(
+        !is_stdin(&args.path),
+        "crate read from stdin can't be watched"
+    );
+    // Paths are made absolute, so the changed files can be matched with the loaded ones.
+    let main = args.main_file()?.canonicalize()?;
+    let root = match &args.root {
+        Some(root) => root.canonicalize()?,
+        None => main.parent().map(Path::to_owned).unwrap_or_default(),
+    };
+    let options = args.options(Emit::Hir)?.root(&root);
+    let mut session = WatchSession::new(main, options);
+    let mut watcher = Watcher::new(root);
+    let mut changes = Vec::new();
+    loop {
+        if stderr().is_terminal() {
+            // Clear the screen and move the cursor to its top left corner.
+            eprint!("\x1b[2J\x1b[H");
+        }
+        match session.update(&changes) {
+            Ok(report) => {
+                if report.errors + report.warnings > 0 {
+                    let context = session.context().expect("crate should be compiled");
+                    context.error_reporter.emit(stderr(), color)?;
+                }
+                eprintln!("{report}");
             }
-        },
-        Emit::LlvmIr => todo!(),
-        Emit::Binary => todo!(),
+            Err(err) => eprintln!("error: {err}"),
+        }
+        changes = watcher.wait();
+    }
+}
+
+/// Prints tokens of the file at `path`.
+fn lex(path: PathBuf, json: bool, color: bool) -> anyhow::Result<ExitCode> {
+    let (context, path) = if is_stdin(&path) {
+        let path = PathBuf::from(STDIN_PATH);
+        (
+            Context::builder().main(&path).build_str(&read_stdin()?),
+            path,
+        )
+    } else {
+        (Context::builder().main(&path).build()?, path)
     };
+    let (id, src) = {
+        let mut source_map = context.source.lock().unwrap();
+        let id = source_map.insert_path(path)?;
+        (id, source_map.get(id).read()?.to_owned())
+    };
+    let mut lexer = Lexer::new(InputStream::new(src, Some(id)), context.clone());
+    let tokens = lexer.tokenize();
+    if json {
+        write_tokens_json(stdout(), &tokens)?;
+    } else {
+        write_tokens(stdout(), &tokens)?;
+    }
+    let failed = context.error_reporter.compilation_failed();
+    if failed {
+        context.error_reporter.emit(stderr(), color)?;
+    }
+    Ok(exit_code(failed))
+}
+
+/// Check if `path` requests to read the file from stdin.
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+fn read_stdin() -> io::Result<String> {
+    let mut src = String::new();
+    stdin().read_to_string(&mut src)?;
+    Ok(src)
+}
 
-    Ok(())
+fn exit_code(failed: bool) -> ExitCode {
+    if failed {
+        ExitCode::from(COMPILATION_FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
 }
@@ -0,0 +1,5 @@
+//! Backends that generate code from [HIR](crate::hir).
+
+pub mod c;
+#[cfg(feature = "wasm")]
+pub mod wasm;
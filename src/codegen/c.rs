@@ -0,0 +1,764 @@
+//! C backend that emits a single translation unit.
+//!
+//! Generated code targets C99. Items are named after their paths with [mangle], structs, arrays and
+//! tuples become structs, and expressions that contain statements, such as `if` with a value,
+//! are computed into temporary variables beforehand.
+
+use std::{
+    collections::HashSet,
+    fmt::{Display, Write},
+};
+
+use crate::{
+    ast::expression::Literal,
+    hir::{
+        intrinsic::Intrinsic,
+        types::{PrimitiveType, TypeId, TypeTable},
+        Block, ConstValue, Expression, ExpressionKind, Function, Hir, Statement,
+    },
+    lexer::operator::{BinaryOp, UnaryOp},
+    path::AbsolutePath,
+    Identifier,
+};
+
+/// Headers and helpers included into every translation unit.
+pub const PRELUDE: &str = r#"#include <inttypes.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static const char* _sun_to_string(int64_t value) {
+    char* buffer = malloc(21);
+    snprintf(buffer, 21, "%" PRId64, value);
+    return buffer;
+}
+
+static const char* _sun_concat(const char* a, const char* b) {
+    size_t length = strlen(a);
+    char* buffer = malloc(length + strlen(b) + 1);
+    strcpy(buffer, a);
+    strcpy(buffer + length, b);
+    return buffer;
+}
+
+static void _sun_panic(const char* message, int line) {
+    fprintf(stderr, "panicked at line %d: %s\n", line, message);
+    exit(101);
+}
+
+static void _sun_assert(bool holds, int line) {
+    if (!holds) {
+        fprintf(stderr, "assertion failed at line %d\n", line);
+        exit(101);
+    }
+}
+"#;
+
+/// Translates the `hir` into C source code.
+pub fn emit_c(hir: &Hir, type_table: &TypeTable) -> String {
+    let mut functions: Vec<_> = hir.function_paths().collect();
+    functions.sort_by_key(|(path, _)| path.to_string());
+    let functions: Vec<(&AbsolutePath, &Function)> = functions
+        .into_iter()
+        .map(|(path, id)| {
+            let function = hir
+                .get_function(id)
+                .expect("HIR should only contain translated functions");
+            (path, function)
+        })
+        .collect();
+
+    let mut writer = Writer {
+        hir,
+        types: type_table,
+        output: String::from(PRELUDE),
+        indent: 0,
+        temps: 0,
+    };
+    writer.declare_types(&functions);
+    writer.newline();
+    for (path, function) in functions.iter() {
+        let signature = writer.signature(path, function);
+        writer.line(format!("{signature};"));
+    }
+    for (path, function) in functions.iter() {
+        writer.newline();
+        writer.function(path, function);
+    }
+    writer.output
+}
+
+/// Name of the item at `path` in the generated code.
+///
+/// Segments of the path are prefixed with their length, so `crate::a_b` and `crate::a::b` don't
+/// collide. For example, `crate::math::add` becomes `_S5crate4math3add`.
+pub fn mangle(path: &AbsolutePath) -> String {
+    let mut mangled = String::from("_S");
+    for segment in std::iter::once(&path.krate).chain(path.other.iter()) {
+        write!(mangled, "{}{segment}", segment.as_str().len()).unwrap();
+    }
+    mangled
+}
+
+struct Writer<'a> {
+    hir: &'a Hir,
+    types: &'a TypeTable,
+    output: String,
+    indent: usize,
+    /// Number of temporary variables declared in the current function.
+    temps: usize,
+}
+
+impl Writer<'_> {
+    const INDENT_WIDTH: usize = 4;
+
+    /// Declares aggregate types used by the program, placing every type after types it contains.
+    fn declare_types(&mut self, functions: &[(&AbsolutePath, &Function)]) {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        let mut structs: Vec<_> = self.types.iter().collect();
+        structs.sort_by_key(|(_, path)| path.to_string());
+        for (id, _) in structs {
+            self.visit_type(id, &mut visited, &mut order);
+        }
+        for (_, function) in functions {
+            let locals = function.body.locals().into_iter().map(|(_, type_)| type_);
+            for type_ in function.params.iter().copied().chain(locals) {
+                self.visit_type(type_, &mut visited, &mut order);
+            }
+            self.visit_type(function.return_type, &mut visited, &mut order);
+        }
+        if order.is_empty() {
+            return;
+        }
+
+        self.newline();
+        for id in order.iter() {
+            let name = self.c_type(*id);
+            self.line(format!("typedef struct {name} {name};"));
+        }
+        for id in order {
+            self.newline();
+            if let Some(layout) = self.types.layout_of(id) {
+                self.line(format!("/* {layout} */"));
+            }
+            self.line(format!("struct {} {{", self.c_type(id)));
+            self.indent += 1;
+            let fields: Vec<(String, TypeId)> = match id {
+                TypeId::Array(_) => {
+                    let (element, length) = self.types.array_element(id).unwrap();
+                    vec![(format!("elements[{length}]"), element)]
+                }
+                TypeId::Tuple(_) => self
+                    .types
+                    .tuple_elements(id)
+                    .unwrap()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(n, type_)| (format!("_{n}"), type_))
+                    .collect(),
+                _ => self
+                    .types
+                    .fields_of(id)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(name, type_)| (field_name(name), *type_))
+                    .collect(),
+            };
+            let fields: Vec<_> = fields
+                .into_iter()
+                .filter(|(_, type_)| *type_ != TypeId::UNIT)
+                .collect();
+            // Structs without members are not allowed by the standard.
+            if fields.is_empty() {
+                self.line("char _empty;");
+            }
+            for (name, type_) in fields {
+                self.line(format!("{} {name};", self.c_type(type_)));
+            }
+            self.indent -= 1;
+            self.line("};");
+        }
+    }
+
+    fn visit_type(&self, id: TypeId, visited: &mut HashSet<TypeId>, order: &mut Vec<TypeId>) {
+        if matches!(id, TypeId::Primitive(_)) || id == TypeId::UNIT || !visited.insert(id) {
+            return;
+        }
+        let contained = match id {
+            TypeId::Array(_) => vec![self.types.array_element(id).unwrap().0],
+            TypeId::Tuple(_) => self.types.tuple_elements(id).unwrap(),
+            _ => self
+                .types
+                .fields_of(id)
+                .unwrap_or_default()
+                .iter()
+                .map(|(_, type_)| *type_)
+                .collect(),
+        };
+        for type_ in contained {
+            self.visit_type(type_, visited, order);
+        }
+        order.push(id);
+    }
+
+    fn signature(&self, path: &AbsolutePath, function: &Function) -> String {
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .enumerate()
+            .filter(|(_, type_)| **type_ != TypeId::UNIT)
+            .map(|(n, type_)| format!("{} v{n}", self.c_type(*type_)))
+            .collect();
+        let params = match params.is_empty() {
+            true => String::from("void"),
+            false => params.join(", "),
+        };
+        format!(
+            "{} {}({params})",
+            self.c_type(function.return_type),
+            mangle(path)
+        )
+    }
+
+    fn function(&mut self, path: &AbsolutePath, function: &Function) {
+        let signature = self.signature(path, function);
+        self.line(format!("{signature} {{"));
+        self.indent += 1;
+        self.temps = 0;
+        for (var, type_) in function.body.locals() {
+            if type_ != TypeId::UNIT {
+                self.line(format!("{} v{};", self.c_type(type_), var.index()));
+            }
+        }
+        self.statements(&function.body);
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    /// Writes the `block`, discarding value of its tail.
+    fn statements(&mut self, block: &Block) {
+        for stmt in block.statements.iter() {
+            self.statement(stmt);
+        }
+        if let Some(tail) = &block.tail {
+            self.discard(tail);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::ExprStmt(expr) => self.discard(expr),
+            Statement::LetStmt {
+                var,
+                value: Some(value),
+                ..
+            } => {
+                if let Some(value) = self.expr(value) {
+                    self.line(format!("v{} = {};", var.index(), unwrap_parens(&value)));
+                }
+            }
+            Statement::LetStmt { value: None, .. } => {}
+            Statement::Assignment { assignee, value } => {
+                let value = self.expr(value);
+                let assignee = self.expr(assignee);
+                if let (Some(assignee), Some(value)) = (assignee, value) {
+                    self.line(format!("{assignee} = {};", unwrap_parens(&value)));
+                }
+            }
+            Statement::Return(expr) => match self.expr(expr) {
+                Some(value) => self.line(format!("return {};", unwrap_parens(&value))),
+                None => self.line("return;"),
+            },
+            Statement::Break => self.line("break;"),
+        }
+    }
+
+    fn discard(&mut self, expr: &Expression) {
+        if let Some(value) = self.expr(expr) {
+            self.line(format!("{};", unwrap_parens(&value)));
+        }
+    }
+
+    /// Writes statements that compute the `expr`, returning C expression of its value or `None`
+    /// if the value is a unit.
+    fn expr(&mut self, expr: &Expression) -> Option<String> {
+        let value = match &expr.kind {
+            ExpressionKind::Block(block) => {
+                for stmt in block.statements.iter() {
+                    self.statement(stmt);
+                }
+                return block.tail.as_ref().and_then(|tail| self.expr(tail));
+            }
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                let condition = self.expr(condition).expect("condition should be a bool");
+                let temp = (expr.type_ != TypeId::UNIT).then(|| self.temp(expr.type_));
+                self.line(format!("if ({}) {{", unwrap_parens(&condition)));
+                self.branch(body, temp.as_deref());
+                if let Some(else_body) = else_body {
+                    self.line("} else {");
+                    self.branch(else_body, temp.as_deref());
+                }
+                self.line("}");
+                return temp;
+            }
+            ExpressionKind::Loop(body) => {
+                self.line("while (1) {");
+                self.indent += 1;
+                self.statements(body);
+                self.indent -= 1;
+                self.line("}");
+                return None;
+            }
+            ExpressionKind::Literal(Literal::String(string)) => string_literal(string),
+            ExpressionKind::Literal(Literal::Boolean(value)) => value.to_string(),
+            ExpressionKind::Literal(Literal::Number(number)) => {
+                let target = match expr.type_ {
+                    TypeId::F32 | TypeId::F64 => PrimitiveType::F32,
+                    _ => PrimitiveType::I64,
+                };
+                let value = number
+                    .to_value(target)
+                    .expect("literals are checked by translation");
+                self.constant(value, expr.type_)
+            }
+            ExpressionKind::Const(value) => self.constant(*value, expr.type_),
+            ExpressionKind::FnCall(function, args) => {
+                let path = self
+                    .hir
+                    .function_path(*function)
+                    .expect("called function should be declared");
+                let args = self.operands(args.iter().collect());
+                format!("{}({})", mangle(path), join_args(args))
+            }
+            ExpressionKind::Intrinsic(intrinsic, args, span) => {
+                let args = join_args(self.operands(args.iter().collect()));
+                let line = span.start.line + 1;
+                match intrinsic {
+                    Intrinsic::Print => format!("fputs({args}, stdout)"),
+                    Intrinsic::Println => format!("puts({args})"),
+                    Intrinsic::ToString => format!("_sun_to_string({args})"),
+                    Intrinsic::Concat => format!("_sun_concat({args})"),
+                    Intrinsic::Assert | Intrinsic::AssertEq => {
+                        format!("_sun_assert({args}, {line})")
+                    }
+                    Intrinsic::Panic => format!("_sun_panic({args}, {line})"),
+                }
+            }
+            ExpressionKind::Var(_) | ExpressionKind::Field { .. } if expr.type_ == TypeId::UNIT => {
+                return None
+            }
+            ExpressionKind::Var(var) => format!("v{}", var.index()),
+            ExpressionKind::Field { value, field } => {
+                let value = self.expr(value).expect("struct should have fields");
+                format!("{value}.{}", field_name(field))
+            }
+            ExpressionKind::Index { value, index } => {
+                let [value, index] = self.operand_pair(value, index);
+                format!("{value}.elements[{}]", unwrap_parens(&index))
+            }
+            ExpressionKind::UnaryOp {
+                operator, value, ..
+            } => {
+                let value = self.expr(value).expect("operand should be a number");
+                match (operator, expr.type_) {
+                    (UnaryOp::Add, _) => value,
+                    (UnaryOp::Sub, _) => format!("(-{value})"),
+                    (UnaryOp::Not, TypeId::BOOL) => format!("(!{value})"),
+                    (UnaryOp::Not, _) => format!("(~{value})"),
+                }
+            }
+            ExpressionKind::Cast(value) => {
+                let value = self.expr(value).expect("operand should be a number");
+                format!("(({}){value})", self.c_type(expr.type_))
+            }
+            ExpressionKind::BinaryOp {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                let operand_type = left.type_;
+                let [left, right] = self.operand_pair(left, right);
+                match (operator, operand_type) {
+                    (BinaryOp::Mod, TypeId::F32) => format!("fmodf({left}, {right})"),
+                    (BinaryOp::Mod, TypeId::F64) => format!("fmod({left}, {right})"),
+                    (operator, _) => format!("({left} {operator} {right})"),
+                }
+            }
+        };
+        if expr.type_ == TypeId::UNIT {
+            self.line(format!("{};", unwrap_parens(&value)));
+            None
+        } else {
+            Some(value)
+        }
+    }
+
+    /// Writes branch of `if`, storing value of its tail into `temp`.
+    fn branch(&mut self, block: &Block, temp: Option<&str>) {
+        self.indent += 1;
+        for stmt in block.statements.iter() {
+            self.statement(stmt);
+        }
+        if let Some(tail) = &block.tail {
+            match (self.expr(tail), temp) {
+                (Some(value), Some(temp)) => {
+                    self.line(format!("{temp} = {};", unwrap_parens(&value)))
+                }
+                (Some(value), None) => self.line(format!("{};", unwrap_parens(&value))),
+                (None, _) => {}
+            }
+        }
+        self.indent -= 1;
+    }
+
+    /// Computes values of the `exprs` in order.
+    ///
+    /// Values are stored into temporary variables if statements of later operands could change
+    /// them.
+    fn operands(&mut self, exprs: Vec<&Expression>) -> Vec<Option<String>> {
+        let mut values = Vec::with_capacity(exprs.len());
+        for (n, expr) in exprs.iter().enumerate() {
+            let value = self.expr(expr);
+            let spill = exprs[n + 1..].iter().any(|expr| needs_statements(expr));
+            values.push(match value {
+                Some(value) if spill => {
+                    let temp = self.temp(expr.type_);
+                    self.line(format!("{temp} = {};", unwrap_parens(&value)));
+                    Some(temp)
+                }
+                value => value,
+            });
+        }
+        values
+    }
+
+    fn operand_pair(&mut self, left: &Expression, right: &Expression) -> [String; 2] {
+        let mut values = self
+            .operands(vec![left, right])
+            .into_iter()
+            .map(|value| value.expect("operand should have a value"));
+        [values.next().unwrap(), values.next().unwrap()]
+    }
+
+    /// Declares temporary variable of `type_`.
+    fn temp(&mut self, type_: TypeId) -> String {
+        let name = format!("_t{}", self.temps);
+        self.temps += 1;
+        self.line(format!("{} {name};", self.c_type(type_)));
+        name
+    }
+
+    fn constant(&self, value: ConstValue, type_: TypeId) -> String {
+        let constant = match value {
+            ConstValue::Integer(value) if value == i64::MIN.into() => {
+                return format!("(({})(-9223372036854775807 - 1))", self.c_type(type_))
+            }
+            // Decimal literal that doesn't fit into `long long` must be unsigned.
+            ConstValue::Integer(value) if value > i64::MAX.into() => {
+                return format!("(({}){value}u)", self.c_type(type_))
+            }
+            ConstValue::Integer(value) if type_ == TypeId::I32 => value.to_string(),
+            ConstValue::Integer(value) => return format!("(({}){value})", self.c_type(type_)),
+            ConstValue::Float(value) if value.is_nan() => String::from("NAN"),
+            ConstValue::Float(value) if value.is_infinite() => {
+                format!("{value}").replace("inf", "INFINITY")
+            }
+            ConstValue::Float(value) if type_ == TypeId::F64 => format!("{value:?}"),
+            ConstValue::Float(value) => format!("{value:?}f"),
+            ConstValue::Boolean(value) => value.to_string(),
+        };
+        // Negative constants are parenthesized, so negating them doesn't produce `--`.
+        match constant.starts_with('-') {
+            true => format!("({constant})"),
+            false => constant,
+        }
+    }
+
+    fn c_type(&self, type_: TypeId) -> String {
+        use PrimitiveType::*;
+        String::from(match type_ {
+            TypeId::UNIT => "void",
+            TypeId::Primitive(primitive) => match primitive {
+                Bool => "bool",
+                I8 => "int8_t",
+                I16 => "int16_t",
+                I32 => "int32_t",
+                I64 => "int64_t",
+                Isize => "ptrdiff_t",
+                U8 => "uint8_t",
+                U16 => "uint16_t",
+                U32 => "uint32_t",
+                U64 => "uint64_t",
+                Usize => "size_t",
+                F32 => "float",
+                F64 => "double",
+                Str => "const char*",
+            },
+            TypeId::Compound(_) => {
+                return mangle(
+                    self.types
+                        .path_of(type_)
+                        .expect("struct should be declared"),
+                )
+            }
+            TypeId::Tuple(index) => return format!("_T{index}"),
+            TypeId::Array(index) => return format!("_A{index}"),
+        })
+    }
+
+    fn newline(&mut self) {
+        self.output.push('\n');
+    }
+
+    fn line(&mut self, line: impl Display) {
+        let indent = " ".repeat(self.indent * Self::INDENT_WIDTH);
+        writeln!(self.output, "{indent}{line}").unwrap();
+    }
+}
+
+/// Checks if statements are written to compute the `expr`.
+fn needs_statements(expr: &Expression) -> bool {
+    match &expr.kind {
+        ExpressionKind::Block(_) | ExpressionKind::If { .. } | ExpressionKind::Loop(_) => true,
+        ExpressionKind::FnCall(_, args) | ExpressionKind::Intrinsic(_, args, _) => {
+            expr.type_ == TypeId::UNIT || args.iter().any(needs_statements)
+        }
+        ExpressionKind::Field { value, .. }
+        | ExpressionKind::Cast(value)
+        | ExpressionKind::UnaryOp { value, .. } => needs_statements(value),
+        ExpressionKind::Index { value, index } => {
+            needs_statements(value) || needs_statements(index)
+        }
+        ExpressionKind::BinaryOp { left, right, .. } => {
+            needs_statements(left) || needs_statements(right)
+        }
+        ExpressionKind::Literal(_) | ExpressionKind::Const(_) | ExpressionKind::Var(_) => false,
+    }
+}
+
+fn join_args(args: Vec<Option<String>>) -> String {
+    args.into_iter()
+        .flatten()
+        .map(|arg| unwrap_parens(&arg).to_owned())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Removes parentheses that surround the whole `expr`.
+fn unwrap_parens(expr: &str) -> &str {
+    let Some(inner) = expr
+        .strip_prefix('(')
+        .and_then(|expr| expr.strip_suffix(')'))
+    else {
+        return expr;
+    };
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for char in inner.chars() {
+        match char {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => {
+                if depth == 0 {
+                    // Opening parenthesis is closed before the end.
+                    return expr;
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    inner
+}
+
+fn string_literal(string: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in string.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            b'\n' => literal.push_str("\\n"),
+            b'\t' => literal.push_str("\\t"),
+            // Octal escapes are used as they have at most three digits, unlike hexadecimal ones.
+            0x20..=0x7e => literal.push(byte as char),
+            byte => write!(literal, "\\{byte:03o}").unwrap(),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Name of the struct field, renamed if it is a keyword in C.
+fn field_name(name: &Identifier) -> String {
+    const KEYWORDS: [&str; 34] = [
+        "auto", "bool", "break", "case", "char", "const", "continue", "default", "do", "double",
+        "else", "enum", "extern", "float", "for", "goto", "if", "inline", "int", "long",
+        "register", "restrict", "return", "short", "signed", "sizeof", "static", "struct",
+        "switch", "typedef", "union", "unsigned", "void", "while",
+    ];
+    match KEYWORDS.contains(&name.as_str()) {
+        true => format!("{name}_"),
+        false => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hir::Hir;
+
+    use super::{emit_c, PRELUDE};
+
+    fn emit(src: &str) -> String {
+        let hir = Hir::new_test(src);
+        let code = emit_c(&hir, hir.type_table());
+        code.strip_prefix(PRELUDE)
+            .expect("code should start with prelude")
+            .to_owned()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let code = emit(
+            "
+            fn add(a: i32, b: i32) -> i32 { a + b }
+            fn count(n: i32) -> i32 {
+                let total: i32 = 0;
+                while total < n { total = add(total, 1); }
+                if total > 10 { return 10; }
+                total
+            }
+            ",
+        );
+        let expected = "
+int32_t _S5crate3add(int32_t v0, int32_t v1);
+int32_t _S5crate5count(int32_t v0);
+
+int32_t _S5crate3add(int32_t v0, int32_t v1) {
+    return v0 + v1;
+}
+
+int32_t _S5crate5count(int32_t v0) {
+    int32_t v1;
+    v1 = 0;
+    while (1) {
+        if (!(v1 < v0)) {
+            break;
+        }
+        v1 = _S5crate3add(v1, 1);
+    }
+    if (v1 > 10) {
+        return 10;
+    }
+    return v1;
+}
+";
+        assert_eq!(code, expected);
+    }
+
+    #[test]
+    fn aggregates_and_temporaries() {
+        let code = emit(
+            r#"
+            struct Point { x: i32, y: i32 }
+            struct Segment { start: Point, end: Point, weights: [f32; 2] }
+            fn length(segment: Segment) -> i32 {
+                let dx: i32 = segment.end.x - segment.start.x;
+                if dx < 0 { -dx } else { dx }
+            }
+            fn weight(segment: Segment, i: usize) -> f32 { segment.weights[i] * 2.0 }
+            fn greet(name: str) { print("Hello, "); println(name); }
+            fn pick(flag: bool) -> i64 { let x: i64 = if flag { 1 } else { 2 }; x }
+            "#,
+        );
+        let expected = r#"
+typedef struct _S5crate5Point _S5crate5Point;
+typedef struct _A0 _A0;
+typedef struct _S5crate7Segment _S5crate7Segment;
+
+/* SIZE 8, ALIGN 4 */
+struct _S5crate5Point {
+    int32_t x;
+    int32_t y;
+};
+
+/* SIZE 8, ALIGN 4 */
+struct _A0 {
+    float elements[2];
+};
+
+/* SIZE 24, ALIGN 4 */
+struct _S5crate7Segment {
+    _S5crate5Point start;
+    _S5crate5Point end;
+    _A0 weights;
+};
+
+void _S5crate5greet(const char* v0);
+int32_t _S5crate6length(_S5crate7Segment v0);
+int64_t _S5crate4pick(bool v0);
+float _S5crate6weight(_S5crate7Segment v0, size_t v1);
+
+void _S5crate5greet(const char* v0) {
+    fputs("Hello, ", stdout);
+    puts(v0);
+}
+
+int32_t _S5crate6length(_S5crate7Segment v0) {
+    int32_t v1;
+    v1 = v0.end.x - v0.start.x;
+    if (v1 < 0) {
+        return -v1;
+    } else {
+        return v1;
+    }
+}
+
+int64_t _S5crate4pick(bool v0) {
+    int64_t v1;
+    int64_t _t0;
+    if (v0) {
+        _t0 = (int64_t)1;
+    } else {
+        _t0 = (int64_t)2;
+    }
+    v1 = _t0;
+    return v1;
+}
+
+float _S5crate6weight(_S5crate7Segment v0, size_t v1) {
+    return v0.weights.elements[v1] * 2.0f;
+}
+"#;
+        assert_eq!(code, expected);
+    }
+
+    #[test]
+    fn operands_are_computed_in_order() {
+        let code = emit(
+            "
+            fn next(x: i32) -> i32 { x + 1 }
+            fn both(x: i32) -> i32 { next(x) + { x = 5; x } }
+            ",
+        );
+        assert!(code.contains(
+            "    int32_t _t0;
+    _t0 = _S5crate4next(v0);
+    v0 = 5;
+    return _t0 + v0;
+"
+        ));
+    }
+}
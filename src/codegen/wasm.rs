@@ -0,0 +1,524 @@
+//! WebAssembly backend that emits modules in text format.
+//!
+//! Only numeric and boolean values are supported for now: `i32`, `u32` and `bool` are
+//! represented as `i32`, 64-bit integers as `i64`, and floats as `f32` and `f64`. Arithmetic
+//! wraps on overflow instead of trapping.
+
+use std::fmt::{Display, Write};
+
+use thiserror::Error;
+
+use crate::{
+    ast::expression::Literal,
+    hir::{
+        scope::VarId,
+        types::{PrimitiveType, TypeId},
+        Block, ConstValue, Expression, ExpressionKind, Function, Hir, Statement,
+    },
+    lexer::operator::{BinaryOp, UnaryOp},
+    path::AbsolutePath,
+};
+
+/// Translates the `hir` into WebAssembly text format.
+///
+/// Every function is emitted under its path, public functions declared at the root of the crate
+/// are exported under their names.
+///
+/// # Errors
+///
+/// Error is returned for every function that uses features not supported by the backend yet.
+pub fn emit_wat(hir: &Hir) -> Result<String, Vec<WasmError>> {
+    let mut functions: Vec<_> = hir.function_paths().collect();
+    functions.sort_by_key(|(path, _)| path.to_string());
+
+    let mut wat = String::from("(module\n");
+    let mut errors = Vec::new();
+    for (path, id) in functions {
+        let function = hir
+            .get_function(id)
+            .expect("HIR should only contain translated functions");
+        let export = hir.exported().contains(&id).then(|| path.last().as_str());
+        let mut writer = FunctionWriter {
+            hir,
+            path,
+            output: String::new(),
+            indent: 1,
+            loops: 0,
+        };
+        match writer.function(function, export) {
+            Ok(()) => wat.push_str(&writer.output),
+            Err(error) => errors.push(error),
+        }
+    }
+    wat.push_str(")\n");
+    if errors.is_empty() {
+        Ok(wat)
+    } else {
+        Err(errors)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WasmError {
+    #[error("type `{type_}` used in function `{function}` is not yet supported by wasm backend")]
+    UnsupportedType { function: String, type_: String },
+    #[error("{feature} used in function `{function}` is not yet supported by wasm backend")]
+    UnsupportedFeature { function: String, feature: String },
+}
+
+type WasmResult<T = ()> = Result<T, WasmError>;
+
+struct FunctionWriter<'a> {
+    hir: &'a Hir,
+    path: &'a AbsolutePath,
+    output: String,
+    indent: usize,
+    /// Number of loops the current instruction is nested in.
+    loops: usize,
+}
+
+impl FunctionWriter<'_> {
+    const INDENT_WIDTH: usize = 2;
+
+    fn function(&mut self, function: &Function, export: Option<&str>) -> WasmResult {
+        let mut header = format!("(func ${}", self.path);
+        if let Some(export) = export {
+            write!(header, " (export \"{export}\")").unwrap();
+        }
+        for (n, type_) in function.params.iter().enumerate() {
+            let type_ = self.local_type(*type_)?;
+            write!(header, " (param ${} {type_})", VarId::param(n as u32)).unwrap();
+        }
+        if let Some(type_) = self.value_type(function.return_type)? {
+            write!(header, " (result {type_})").unwrap();
+        }
+        self.line(header);
+
+        self.indent += 1;
+        for (var, type_) in function.body.locals() {
+            let type_ = self.local_type(type_)?;
+            self.line(format!("(local ${var} {type_})"));
+        }
+        self.block(&function.body)?;
+        // Branches that end with `return` don't leave a value, which must be made explicit.
+        let returns = matches!(function.body.statements.last(), Some(Statement::Return(_)));
+        if function.return_type != TypeId::UNIT && function.body.tail.is_none() && !returns {
+            self.line("unreachable");
+        }
+        self.indent -= 1;
+        self.line(")");
+        Ok(())
+    }
+
+    fn block(&mut self, block: &Block) -> WasmResult {
+        for stmt in block.statements.iter() {
+            self.statement(stmt)?;
+        }
+        if let Some(tail) = &block.tail {
+            self.expr(tail)?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Statement) -> WasmResult {
+        match stmt {
+            Statement::ExprStmt(expr) => {
+                self.expr(expr)?;
+                if self.value_type(expr.type_)?.is_some() {
+                    self.line("drop");
+                }
+            }
+            Statement::LetStmt {
+                var,
+                value: Some(value),
+                ..
+            } => {
+                self.expr(value)?;
+                self.line(format!("local.set ${var}"));
+            }
+            Statement::LetStmt { value: None, .. } => {}
+            Statement::Assignment { assignee, value } => {
+                let ExpressionKind::Var(var) = &assignee.kind else {
+                    return Err(self.unsupported("assignment to field or element"));
+                };
+                self.expr(value)?;
+                self.line(format!("local.set ${var}"));
+            }
+            Statement::Return(expr) => {
+                self.expr(expr)?;
+                self.line("return");
+            }
+            Statement::Break => self.line(format!("br $break{}", self.loops - 1)),
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &Expression) -> WasmResult {
+        match &expr.kind {
+            ExpressionKind::Block(block) => self.block(block)?,
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                self.expr(condition)?;
+                match self.value_type(expr.type_)? {
+                    Some(type_) => self.line(format!("if (result {type_})")),
+                    None => self.line("if"),
+                }
+                self.indent += 1;
+                self.block(body)?;
+                self.indent -= 1;
+                if let Some(else_body) = else_body {
+                    self.line("else");
+                    self.indent += 1;
+                    self.block(else_body)?;
+                    self.indent -= 1;
+                }
+                self.line("end");
+            }
+            ExpressionKind::Loop(body) => {
+                let depth = self.loops;
+                self.line(format!("block $break{depth}"));
+                self.indent += 1;
+                self.line(format!("loop $continue{depth}"));
+                self.indent += 1;
+                self.loops += 1;
+                self.block(body)?;
+                self.loops -= 1;
+                self.line(format!("br $continue{depth}"));
+                self.indent -= 1;
+                self.line("end");
+                self.indent -= 1;
+                self.line("end");
+            }
+            ExpressionKind::Literal(Literal::Number(number)) => {
+                let target = match expr.type_ {
+                    TypeId::F32 | TypeId::F64 => PrimitiveType::F32,
+                    _ => PrimitiveType::I64,
+                };
+                let value = number
+                    .to_value(target)
+                    .expect("literals are checked by translation");
+                self.constant(value, expr.type_)?;
+            }
+            ExpressionKind::Literal(Literal::Boolean(value)) => {
+                self.constant(ConstValue::Boolean(*value), expr.type_)?
+            }
+            ExpressionKind::Literal(Literal::String(_)) => {
+                return Err(self.unsupported("string literal"))
+            }
+            ExpressionKind::Const(value) => self.constant(*value, expr.type_)?,
+            ExpressionKind::FnCall(function, args) => {
+                for arg in args.iter() {
+                    self.expr(arg)?;
+                }
+                let path = self
+                    .hir
+                    .function_path(*function)
+                    .expect("called function should be declared");
+                self.line(format!("call ${path}"));
+            }
+            ExpressionKind::Intrinsic(intrinsic, ..) => {
+                return Err(self.unsupported(format!("intrinsic `{intrinsic}`")))
+            }
+            ExpressionKind::Var(var) => self.line(format!("local.get ${var}")),
+            ExpressionKind::Field { .. } => return Err(self.unsupported("field access")),
+            ExpressionKind::Index { .. } => return Err(self.unsupported("indexing")),
+            ExpressionKind::UnaryOp {
+                operator, value, ..
+            } => {
+                let type_ = self.primitive(expr.type_)?;
+                let value_type = value_type(type_);
+                match (operator, type_) {
+                    (UnaryOp::Add, _) => self.expr(value)?,
+                    (UnaryOp::Sub, PrimitiveType::F32 | PrimitiveType::F64) => {
+                        self.expr(value)?;
+                        self.line(format!("{value_type}.neg"));
+                    }
+                    (UnaryOp::Sub, _) => {
+                        self.line(format!("{value_type}.const 0"));
+                        self.expr(value)?;
+                        self.line(format!("{value_type}.sub"));
+                    }
+                    (UnaryOp::Not, PrimitiveType::Bool) => {
+                        self.expr(value)?;
+                        self.line("i32.eqz");
+                    }
+                    (UnaryOp::Not, _) => {
+                        self.expr(value)?;
+                        self.line(format!("{value_type}.const -1"));
+                        self.line(format!("{value_type}.xor"));
+                    }
+                }
+            }
+            ExpressionKind::Cast(value) => {
+                let source = self.primitive(value.type_)?;
+                let target = self.primitive(expr.type_)?;
+                self.expr(value)?;
+                if let Some(instruction) = cast_instruction(source, target) {
+                    self.line(instruction);
+                }
+            }
+            ExpressionKind::BinaryOp {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                let type_ = self.primitive(left.type_)?;
+                let Some(instruction) = binary_instruction(*operator, type_) else {
+                    return Err(self.unsupported(format!(
+                        "operator `{operator}` on `{}`",
+                        type_.name()
+                    )));
+                };
+                self.expr(left)?;
+                self.expr(right)?;
+                self.line(instruction);
+            }
+        }
+        Ok(())
+    }
+
+    fn constant(&mut self, value: ConstValue, type_: TypeId) -> WasmResult {
+        let value_type = value_type(self.primitive(type_)?);
+        let value = match value {
+            ConstValue::Integer(value) => value.to_string(),
+            ConstValue::Boolean(value) => u8::from(value).to_string(),
+            ConstValue::Float(value) if value.is_nan() => String::from("nan"),
+            ConstValue::Float(value) if value.is_infinite() => {
+                String::from(if value > 0.0 { "inf" } else { "-inf" })
+            }
+            ConstValue::Float(value) => format!("{value:?}"),
+        };
+        self.line(format!("{value_type}.const {value}"));
+        Ok(())
+    }
+
+    /// Type of the value of `type_` on the stack, `None` for unit.
+    fn value_type(&self, type_: TypeId) -> WasmResult<Option<&'static str>> {
+        match type_ {
+            TypeId::UNIT => Ok(None),
+            type_ => self.primitive(type_).map(value_type).map(Some),
+        }
+    }
+
+    /// Type of local variable or parameter, which can't be a unit.
+    fn local_type(&self, type_: TypeId) -> WasmResult<&'static str> {
+        self.primitive(type_).map(value_type)
+    }
+
+    /// Checks that `type_` is a primitive supported by the backend.
+    fn primitive(&self, type_: TypeId) -> WasmResult<PrimitiveType> {
+        use PrimitiveType::*;
+        match type_ {
+            TypeId::Primitive(
+                primitive @ (Bool | I32 | I64 | Isize | U32 | U64 | Usize | F32 | F64),
+            ) => Ok(primitive),
+            _ => Err(WasmError::UnsupportedType {
+                function: self.path.to_string(),
+                type_: self.hir.type_table().type_name(type_),
+            }),
+        }
+    }
+
+    fn unsupported(&self, feature: impl Into<String>) -> WasmError {
+        WasmError::UnsupportedFeature {
+            function: self.path.to_string(),
+            feature: feature.into(),
+        }
+    }
+
+    fn line(&mut self, line: impl Display) {
+        let indent = " ".repeat(self.indent * Self::INDENT_WIDTH);
+        writeln!(self.output, "{indent}{line}").unwrap();
+    }
+}
+
+fn value_type(type_: PrimitiveType) -> &'static str {
+    use PrimitiveType::*;
+    match type_ {
+        I64 | Isize | U64 | Usize => "i64",
+        F32 => "f32",
+        F64 => "f64",
+        _ => "i32",
+    }
+}
+
+fn is_signed(type_: PrimitiveType) -> bool {
+    use PrimitiveType::*;
+    matches!(type_, I8 | I16 | I32 | I64 | Isize)
+}
+
+fn is_float(type_: PrimitiveType) -> bool {
+    matches!(type_, PrimitiveType::F32 | PrimitiveType::F64)
+}
+
+/// Instruction that applies `op` to operands of `type_`, `None` if there is no such instruction.
+fn binary_instruction(op: BinaryOp, type_: PrimitiveType) -> Option<String> {
+    use BinaryOp::*;
+
+    let float = is_float(type_);
+    // Whether instruction of integer type depends on signedness of its operands.
+    let (name, signed) = match op {
+        Add => ("add", false),
+        Sub => ("sub", false),
+        Mul => ("mul", false),
+        Div => ("div", !float),
+        Mod if float => return None,
+        Mod => ("rem", true),
+        Rsh => ("shr", true),
+        Lsh => ("shl", false),
+        And | BinAnd => ("and", false),
+        Or | BinOr => ("or", false),
+        BinXor => ("xor", false),
+        Eq => ("eq", false),
+        Neq => ("ne", false),
+        More => ("gt", !float),
+        Less => ("lt", !float),
+        MoreEq => ("ge", !float),
+        LessEq => ("le", !float),
+        Range => return None,
+    };
+    let suffix = match (signed, is_signed(type_)) {
+        (false, _) => "",
+        (true, true) => "_s",
+        (true, false) => "_u",
+    };
+    Some(format!("{}.{name}{suffix}", value_type(type_)))
+}
+
+/// Instruction that converts value of `source` type to `target` type, `None` if no conversion
+/// is needed.
+///
+/// Floats are converted to integers with saturation, as `as` casts in Rust do.
+fn cast_instruction(source: PrimitiveType, target: PrimitiveType) -> Option<String> {
+    let (from, to) = (value_type(source), value_type(target));
+    let sign = |type_| if is_signed(type_) { "s" } else { "u" };
+    Some(match (from, to) {
+        _ if from == to => return None,
+        ("i32", "i64") => format!("i64.extend_i32_{}", sign(source)),
+        ("i64", "i32") => String::from("i32.wrap_i64"),
+        ("f32", "f64") => String::from("f64.promote_f32"),
+        ("f64", "f32") => String::from("f32.demote_f64"),
+        _ if is_float(target) => format!("{to}.convert_{from}_{}", sign(source)),
+        _ => format!("{to}.trunc_sat_{from}_{}", sign(target)),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hir::Hir;
+
+    use super::{emit_wat, WasmError};
+
+    #[test]
+    fn arithmetic() {
+        let hir = Hir::new_test(
+            "
+            pub fn add(a: i32, b: i32) -> i32 { a + b }
+            fn count(n: i32) -> i32 {
+                let total: i32 = 0;
+                while total < n { total = add(total, 1); }
+                if total > 10 { return 10; }
+                total
+            }
+            fn half(x: f32) -> f32 { x / 2.0 }
+            fn sign(x: u32) -> i64 { if x > 0 { 1 } else { x as i64 - 1 } }
+            ",
+        );
+        let expected = "\
+(module
+  (func $crate::add (export \"add\") (param $%0 i32) (param $%1 i32) (result i32)
+    local.get $%0
+    local.get $%1
+    i32.add
+    return
+  )
+  (func $crate::count (param $%0 i32) (result i32)
+    (local $%1 i32)
+    i32.const 0
+    local.set $%1
+    block $break0
+      loop $continue0
+        local.get $%1
+        local.get $%0
+        i32.lt_s
+        i32.eqz
+        if
+          br $break0
+        end
+        local.get $%1
+        i32.const 1
+        call $crate::add
+        local.set $%1
+        br $continue0
+      end
+    end
+    local.get $%1
+    i32.const 10
+    i32.gt_s
+    if
+      i32.const 10
+      return
+    end
+    local.get $%1
+    return
+  )
+  (func $crate::half (param $%0 f32) (result f32)
+    local.get $%0
+    f32.const 2.0
+    f32.div
+    return
+  )
+  (func $crate::sign (param $%0 i32) (result i64)
+    local.get $%0
+    i32.const 0
+    i32.gt_u
+    if
+      i64.const 1
+      return
+    else
+      local.get $%0
+      i64.extend_i32_u
+      i64.const 1
+      i64.sub
+      return
+    end
+    unreachable
+  )
+)
+";
+        assert_eq!(emit_wat(&hir).unwrap(), expected);
+    }
+
+    #[test]
+    fn public_entry_point() {
+        let hir = Hir::new_test("pub fn main() -> i32 { helper() } fn helper() -> i32 { 1 }");
+        let wat = emit_wat(&hir).unwrap();
+        assert!(wat.contains("(func $crate::main (export \"main\") (result i32)"));
+        assert!(wat.contains("(func $crate::helper (result i32)"));
+
+        let hir = Hir::new_test("fn main() {}");
+        assert!(emit_wat(&hir).unwrap().contains("(func $crate::main\n"));
+    }
+
+    #[test]
+    fn structs_are_rejected() {
+        let hir = Hir::new_test(
+            "
+            struct Point { x: i32, y: i32 }
+            fn x(point: Point) -> i32 { point.x }
+            fn zero() -> i32 { 0 }
+            ",
+        );
+        assert_eq!(
+            emit_wat(&hir),
+            Err(vec![WasmError::UnsupportedType {
+                function: String::from("crate::x"),
+                type_: String::from("crate::Point"),
+            }])
+        );
+    }
+}
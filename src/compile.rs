@@ -0,0 +1,349 @@
+//! Entry point of the compiler.
+//!
+//! [compile] runs every stage of the compilation on a crate, [compile_str] does the same for
+//! a crate that consists of a single in-memory file.
+
+use std::{panic, path::PathBuf, thread, time::Instant};
+
+use crate::{
+    context::{Context, ContextBuilder, Emit},
+    error::{Diagnostic, Severity},
+    hir::{Hir, HirBuilder},
+    item_table::ItemTable,
+    parser::Parser,
+    source::SourceError,
+    stats::{CompileStats, HirStats},
+};
+
+/// Options of the compilation.
+///
+/// Main file set in the options is ignored, as it is provided to [compile] explicitly.
+pub type CompileOptions = ContextBuilder;
+
+/// Outcome of the compilation.
+#[derive(Debug)]
+pub struct CompileResult {
+    /// Translated program, `None` if any errors occurred or HIR wasn't requested.
+    pub hir: Option<Hir>,
+    /// Items that were parsed successfully.
+    pub item_table: ItemTable,
+    /// Errors and warnings reported during compilation.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Context of the compilation, that may be used to render the diagnostics.
+    pub context: Context,
+    /// Duration and size of compilation phases, `None` unless
+    /// [timings](ContextBuilder::timings) are enabled.
+    pub stats: Option<CompileStats>,
+}
+
+impl CompileResult {
+    /// Check if any fatal error occurred.
+    pub fn failed(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Deny)
+    }
+}
+
+/// Compiles crate with the main file at `main`.
+///
+/// Translation to HIR is skipped if only AST is requested to be emitted.
+///
+/// # Errors
+///
+/// Error is returned if the main file couldn't be opened. Errors in the code are reported as
+/// diagnostics instead.
+pub fn compile(main: PathBuf, options: CompileOptions) -> Result<CompileResult, SourceError> {
+    let context = options.main(main).build()?;
+    Ok(run(context))
+}
+
+/// Compiles crate with the main file given by its content.
+///
+/// See [compile] for details.
+pub fn compile_str(source: &str, options: CompileOptions) -> CompileResult {
+    run(options.build_str(source))
+}
+
+/// Runs the compilation on a thread with stack that is large enough for the allowed nesting of
+/// the code.
+fn run(context: Context) -> CompileResult {
+    let stack_size = context.metadata.stack_size();
+    on_large_stack(stack_size, move || run_on_current_thread(context))
+}
+
+/// Runs `f` on a thread with `stack_size`, see [Metadata::stack_size].
+///
+/// [Metadata::stack_size]: crate::context::Metadata::stack_size
+pub(crate) fn on_large_stack<R: Send>(stack_size: usize, f: impl FnOnce() -> R + Send) -> R {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn_scoped(scope, f)
+            .expect("compilation thread should be spawned")
+            .join()
+            .unwrap_or_else(|payload| panic::resume_unwind(payload))
+    })
+}
+
+fn run_on_current_thread(context: Context) -> CompileResult {
+    let start = Instant::now();
+    let mut parser = Parser::new(context.clone());
+    let (item_table, errors) = parser.parse_partial();
+    let parsed = errors.is_empty() && !context.error_reporter.compilation_failed();
+    let (hir, hir_stats) = if parsed {
+        translate(&context, &item_table)
+    } else {
+        (None, None)
+    };
+    let stats = context.metadata.timings.then(|| CompileStats {
+        files: parser.file_stats().to_vec(),
+        hir: hir_stats,
+        total: start.elapsed(),
+    });
+    CompileResult {
+        hir,
+        item_table,
+        diagnostics: context.error_reporter.diagnostics(),
+        context,
+        stats,
+    }
+}
+
+/// Translates successfully parsed `item_table` to HIR, unless only AST is requested.
+pub(crate) fn translate(
+    context: &Context,
+    item_table: &ItemTable,
+) -> (Option<Hir>, Option<HirStats>) {
+    if context.metadata.emit_type == Emit::Ast {
+        return (None, None);
+    }
+    let start = Instant::now();
+    let mut builder = HirBuilder::new(context.clone());
+    builder.populate(item_table);
+    let hir = builder.build();
+    let stats = HirStats {
+        duration: start.elapsed(),
+        functions: item_table.functions().count(),
+        errors: hir.as_ref().err().copied().unwrap_or_default(),
+    };
+    (hir.ok(), Some(stats))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, str::FromStr};
+
+    use crate::{
+        context::{Context, Emit},
+        error::{
+            library::{
+                hir::UnreachableCode,
+                parser::{ExpressionTooComplex, ModuleWithoutRoot, NestingTooDeep},
+            },
+            Severity,
+        },
+        path::AbsolutePath,
+        source::{SourceError, STDIN_PATH},
+        Identifier,
+    };
+
+    use super::{compile, compile_str, CompileResult};
+
+    fn declares(result: &CompileResult, path: &str) -> bool {
+        let path = AbsolutePath::from_str(path).unwrap();
+        result.item_table.get(&path).is_some()
+    }
+
+    #[test]
+    fn success() {
+        let result = compile_str(
+            "fn main() -> i32 { 1 + 2 }",
+            Context::builder().emit(Emit::Hir),
+        );
+        assert!(!result.failed());
+        assert!(result.diagnostics.is_empty());
+        assert!(result.hir.is_some());
+        assert!(declares(&result, "main::main"));
+    }
+
+    #[test]
+    fn warnings() {
+        let result = compile_str(
+            "fn main() -> i32 { return 1; 2 }",
+            Context::builder().emit(Emit::Hir),
+        );
+        assert!(!result.failed());
+        assert!(result.hir.is_some());
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, UnreachableCode::CODE);
+    }
+
+    #[test]
+    fn syntax_error() {
+        let result = compile_str(
+            "fn first() {} fnn second() {} fn third() {}",
+            Context::builder().emit(Emit::Hir),
+        );
+        assert!(result.failed());
+        assert!(result.hir.is_none());
+        assert!(declares(&result, "main::first"));
+        assert!(declares(&result, "main::third"));
+    }
+
+    #[test]
+    fn translation_error() {
+        let result = compile_str(
+            "fn main() -> i32 { true }",
+            Context::builder().emit(Emit::Hir),
+        );
+        assert!(result.failed());
+        assert!(result.hir.is_none());
+        assert!(declares(&result, "main::main"));
+    }
+
+    #[test]
+    fn ast_only() {
+        let result = compile_str(
+            "fn main() -> i32 { true }",
+            Context::builder().emit(Emit::Ast),
+        );
+        assert!(!result.failed());
+        assert!(result.hir.is_none());
+    }
+
+    #[test]
+    fn stdin() {
+        let result = compile_str(
+            "mod shapes;\nfn main() -> i32 { 1 }",
+            Context::builder().main(STDIN_PATH).emit(Emit::Hir),
+        );
+        assert!(result.failed());
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, ModuleWithoutRoot::CODE);
+        assert!(declares(&result, "main::main"));
+
+        let mut output = Vec::new();
+        result
+            .context
+            .error_reporter
+            .emit(&mut output, false)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("<stdin>:1:5"), "{output}");
+    }
+
+    #[test]
+    fn timings() {
+        let main = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/valid.sun");
+        let result = compile(main.clone(), Context::builder().emit(Emit::Hir)).unwrap();
+        assert!(result.stats.is_none());
+
+        let options = Context::builder().emit(Emit::Hir).timings(true);
+        let result = compile(main.clone(), options).unwrap();
+        let stats = result.stats.unwrap();
+        assert_eq!(stats.files.len(), 1);
+        assert_eq!(stats.files[0].path, main);
+        assert!(stats.files[0].tokens > 0);
+        assert_eq!(stats.files[0].items, 3);
+        let hir = stats.hir.unwrap();
+        assert_eq!(hir.functions, 2);
+        assert_eq!(hir.errors, 0);
+        assert!(stats.total >= stats.files[0].duration + hir.duration);
+    }
+
+    #[test]
+    fn file() {
+        let main = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diagnostics.sun");
+        let options = Context::builder()
+            .crate_name(Identifier(String::from("diagnostics")))
+            .emit(Emit::Hir);
+        let result = compile(main, options).unwrap();
+        assert!(result.failed());
+        let severities: Vec<_> = result
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .collect();
+        assert_eq!(severities, [Severity::Warn, Severity::Deny, Severity::Deny]);
+    }
+
+    /// Wraps `inner` into `open` and `close` `depth` times.
+    fn nest(open: &str, inner: &str, close: &str, depth: usize) -> String {
+        format!("{}{inner}{}", open.repeat(depth), close.repeat(depth))
+    }
+
+    /// Sources that nest expressions and modules `depth` levels deep.
+    fn nested_sources(depth: usize) -> Vec<String> {
+        let exprs = [
+            ("(", ")"),
+            ("- ", ""),
+            ("{ ", " }"),
+            ("if true { ", " } else { 0 }"),
+            ("id(", ")"),
+        ]
+        .map(|(open, close)| {
+            let body = nest(open, "1", close, depth);
+            format!("fn id(x: i32) -> i32 {{ x }} fn main() -> i32 {{ {body} }}")
+        });
+        let modules = nest("mod inner { ", "fn main() {}", " }", depth);
+        exprs.into_iter().chain([modules]).collect()
+    }
+
+    #[test]
+    fn nesting() {
+        for src in nested_sources(1000) {
+            let result = compile_str(&src, Context::builder().emit(Emit::Hir));
+            // Functions of nested modules are never called, so they are warned about.
+            assert!(!result.failed(), "{}", &src[..60]);
+        }
+    }
+
+    #[test]
+    fn nesting_too_deep() {
+        for src in nested_sources(100_000) {
+            let result = compile_str(&src, Context::builder().emit(Emit::Hir));
+            assert!(result.failed());
+            // Recovery may report that the rest of the construct is malformed as well.
+            assert_eq!(
+                result.diagnostics[0].code,
+                NestingTooDeep::CODE,
+                "{}",
+                &src[..60]
+            );
+        }
+
+        let src = "fn main() -> i32 { ((((1)))) }";
+        let result = compile_str(src, Context::builder().emit(Emit::Hir).max_nesting(4));
+        assert!(result.failed());
+        let result = compile_str(src, Context::builder().emit(Emit::Hir).max_nesting(5));
+        assert!(!result.failed());
+    }
+
+    #[test]
+    fn expression_too_complex() {
+        // A million tokens, which are rejected before the tree is built.
+        let src = format!("fn main() -> i32 {{ {} }}", vec!["1"; 500_000].join(" + "));
+        let result = compile_str(&src, Context::builder().emit(Emit::Hir));
+        assert!(result.failed());
+        assert_eq!(result.diagnostics[0].code, ExpressionTooComplex::CODE);
+
+        let src = "fn main() -> i32 { -(1 + 2) * 3 }";
+        let options = || Context::builder().emit(Emit::Hir);
+        let result = compile_str(src, options().max_expression_tokens(7));
+        assert!(result.failed());
+        assert_eq!(result.diagnostics[0].code, ExpressionTooComplex::CODE);
+        assert_eq!(result.diagnostics[0].span.unwrap().start.column, 19);
+        let result = compile_str(src, options().max_expression_tokens(8));
+        assert!(!result.failed());
+    }
+
+    #[test]
+    fn missing_file() {
+        let main = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/missing.sun");
+        assert!(matches!(
+            compile(main, Context::builder()),
+            Err(SourceError::NotFound(_))
+        ));
+    }
+}
@@ -1,15 +1,18 @@
 //! Compiler context.
 
 use std::{
-    path::PathBuf,
+    collections::HashMap,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    thread,
 };
 
 use clap::ValueEnum;
 
 use crate::{
-    error::ErrorReporter,
-    source::{SourceError, SourceMap},
+    error::{ErrorReporter, LintLevel},
+    source::{SourceError, SourceMap, STDIN_PATH},
     Identifier,
 };
 
@@ -24,33 +27,281 @@ pub struct Context {
 }
 
 impl Context {
-    pub fn new(main: PathBuf, metadata: Metadata) -> Result<Context, SourceError> {
-        let source = Arc::new(Mutex::new(SourceMap::new(main)?));
-        Ok(Context {
-            metadata: Arc::new(metadata),
-            error_reporter: Arc::new(ErrorReporter::new(Arc::clone(&source))),
-            source,
-        })
+    /// Creates [ContextBuilder] with default settings.
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::new()
     }
 
     #[cfg(test)]
     pub fn new_test() -> Self {
+        Self::new_test_with(Metadata::new(Identifier(String::from("_TEST"))))
+    }
+
+    #[cfg(test)]
+    pub fn new_test_with(metadata: Metadata) -> Self {
         let source = Arc::new(Mutex::new(SourceMap::new_test().unwrap()));
+        Self::with_source(source, metadata)
+    }
+
+    fn with_source(source: Arc<Mutex<SourceMap>>, metadata: Metadata) -> Self {
+        let metadata = Arc::new(metadata);
         Self {
-            metadata: Arc::new(Metadata {
-                crate_name: Identifier(String::from("_TEST")),
-                emit_type: Emit::default(),
-            }),
-            error_reporter: Arc::new(ErrorReporter::new(Arc::clone(&source))),
+            error_reporter: Arc::new(ErrorReporter::new(
+                Arc::clone(&source),
+                Arc::clone(&metadata),
+            )),
+            metadata,
             source,
         }
     }
 }
 
+/// Builder of [Context].
+///
+/// Main file of the crate is the only required setting, defaults of the others are documented
+/// on their setters.
+#[derive(Debug, Clone)]
+pub struct ContextBuilder {
+    main: Option<PathBuf>,
+    root: Option<PathBuf>,
+    virtual_files: Vec<(PathBuf, String)>,
+    metadata: Metadata,
+}
+
+impl ContextBuilder {
+    pub fn new() -> Self {
+        Self {
+            main: None,
+            root: None,
+            virtual_files: Vec::new(),
+            metadata: Metadata::new(Identifier(String::from("main"))),
+        }
+    }
+
+    /// Name of the crate, `main` by default.
+    pub fn crate_name(mut self, crate_name: Identifier) -> Self {
+        self.metadata.crate_name = crate_name;
+        self
+    }
+
+    /// Path to the main file of the crate.
+    pub fn main(mut self, main: impl Into<PathBuf>) -> Self {
+        self.main = Some(main.into());
+        self
+    }
+
+    /// Directory that modules are looked up in, the directory of the main file by default.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Adds in-memory source file at `path`, that is used instead of the file on disk.
+    ///
+    /// Main file may be in-memory as well.
+    pub fn virtual_file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.virtual_files.push((path.into(), contents.into()));
+        self
+    }
+
+    /// Kind of the output, [Emit::Binary] by default.
+    pub fn emit(mut self, emit_type: Emit) -> Self {
+        self.metadata.emit_type = emit_type;
+        self
+    }
+
+    /// Format of the diagnostics, [ErrorFormat::Human] by default.
+    pub fn error_format(mut self, error_format: ErrorFormat) -> Self {
+        self.metadata.error_format = error_format;
+        self
+    }
+
+    /// Overrides level of errors with specified `code`. No levels are overridden by default.
+    pub fn lint_level(mut self, code: &str, level: LintLevel) -> Self {
+        self.metadata
+            .lint_levels
+            .insert(code.to_ascii_uppercase(), level);
+        self
+    }
+
+    /// Maximal number of errors after which compilation is aborted, unlimited by default.
+    pub fn max_errors(mut self, max_errors: Option<usize>) -> Self {
+        self.metadata.max_errors = max_errors;
+        self
+    }
+
+    /// Number of columns a tab is rendered with in snippets of the code, 4 by default.
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.metadata.tab_width = tab_width;
+        self
+    }
+
+    /// Maximal number of threads used to parse files, the number of available CPUs by default.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.metadata.jobs = jobs;
+        self
+    }
+
+    /// Whether constant expressions should be computed at compile time, disabled by default.
+    pub fn fold_constants(mut self, fold_constants: bool) -> Self {
+        self.metadata.fold_constants = fold_constants;
+        self
+    }
+
+    /// Whether [statistics](crate::stats::CompileStats) of the compilation should be collected,
+    /// disabled by default.
+    pub fn timings(mut self, timings: bool) -> Self {
+        self.metadata.timings = timings;
+        self
+    }
+
+    /// Name of the function at the root of the crate that execution starts from, `main` by
+    /// default.
+    pub fn entry_point(mut self, entry_point: Identifier) -> Self {
+        self.metadata.entry_point = entry_point;
+        self
+    }
+
+    /// Maximal depth of nested expressions and modules, 2000 by default.
+    pub fn max_nesting(mut self, max_nesting: usize) -> Self {
+        self.metadata.max_nesting = max_nesting;
+        self
+    }
+
+    /// Maximal number of operators and operands in a single operator expression, 65536 by default.
+    pub fn max_expression_tokens(mut self, max_expression_tokens: usize) -> Self {
+        self.metadata.max_expression_tokens = max_expression_tokens;
+        self
+    }
+
+    /// Directory that parsed files are cached in between runs, caching is disabled by default.
+    ///
+    /// Cache is only used if the `serde` feature is enabled.
+    pub fn cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+        self.metadata.cache_dir = cache_dir;
+        self
+    }
+
+    /// Creates [Context], opening the main file of the crate.
+    ///
+    /// # Errors
+    ///
+    /// Error is returned if the main file isn't set or couldn't be opened, or if the root
+    /// directory is not found. Root directory is not checked if the main file is in-memory.
+    ///
+    /// In-memory main file at [STDIN_PATH] is considered to be read from the standard input, so
+    /// the crate has no root directory even if it is set.
+    pub fn build(mut self) -> Result<Context, SourceError> {
+        let main = self.main.take().ok_or(SourceError::MissingMain)?;
+        let in_memory = self
+            .virtual_files
+            .iter()
+            .position(|(path, _)| *path == main);
+        let mut source_map = match (in_memory, self.root.take()) {
+            (Some(index), root) => {
+                let (main, src) = self.virtual_files.remove(index);
+                let root = match root {
+                    _ if main == Path::new(STDIN_PATH) => None,
+                    Some(root) => Some(root),
+                    None => Some(parent(&main)),
+                };
+                SourceMap::in_memory(root, main, src)
+            }
+            (None, Some(root)) => SourceMap::with_root(root, main)?,
+            (None, None) => SourceMap::new(main)?,
+        };
+        for (path, contents) in self.virtual_files {
+            source_map.insert_virtual(path, contents);
+        }
+        Ok(Context::with_source(
+            Arc::new(Mutex::new(source_map)),
+            self.metadata,
+        ))
+    }
+
+    /// Creates [Context] with the main file given by its content.
+    ///
+    /// Path to the main file is only used to refer to it, `main.sun` by default.
+    pub fn build_str(self, src: &str) -> Context {
+        let main = self
+            .main
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("main.sun"));
+        self.main(main.clone())
+            .virtual_file(main, src)
+            .build()
+            .expect("in-memory main file should always be available")
+    }
+}
+
+/// Directory that contains `path`.
+fn parent(path: &Path) -> PathBuf {
+    let mut parent = path.to_owned();
+    parent.pop();
+    parent
+}
+
+impl Default for ContextBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Metadata {
     pub crate_name: Identifier,
     pub emit_type: Emit,
+    pub error_format: ErrorFormat,
+    /// Whether constant expressions should be computed at compile time.
+    pub fold_constants: bool,
+    /// Levels that override default severity of errors, keyed by error code.
+    pub lint_levels: HashMap<String, LintLevel>,
+    /// Maximal number of errors after which compilation is aborted.
+    pub max_errors: Option<usize>,
+    /// Number of columns a tab is rendered with in snippets of the code.
+    pub tab_width: usize,
+    /// Maximal number of threads used to parse files, files are parsed sequentially if it is 1.
+    pub jobs: usize,
+    /// Whether duration and size of compilation phases should be recorded.
+    pub timings: bool,
+    /// Maximal depth of nested expressions and modules.
+    pub max_nesting: usize,
+    /// Maximal number of operators and operands in a single operator expression.
+    pub max_expression_tokens: usize,
+    /// Name of the function at the root of the crate that execution starts from.
+    pub entry_point: Identifier,
+    /// Directory that parsed files are cached in, see [ContextBuilder::cache_dir].
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Metadata {
+    /// Create metadata with default settings.
+    pub fn new(crate_name: Identifier) -> Self {
+        Self {
+            crate_name,
+            emit_type: Emit::default(),
+            error_format: ErrorFormat::default(),
+            fold_constants: false,
+            lint_levels: HashMap::new(),
+            max_errors: None,
+            tab_width: 4,
+            jobs: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            timings: false,
+            max_nesting: 2000,
+            max_expression_tokens: 65536,
+            entry_point: Identifier(String::from("main")),
+            cache_dir: None,
+        }
+    }
+
+    /// Size of the stack that is enough to parse and translate code nested up to
+    /// [max_nesting](Metadata::max_nesting) levels.
+    pub fn stack_size(&self) -> usize {
+        /// Stack used by a single level of nesting, measured in debug build with some margin.
+        const STACK_PER_LEVEL: usize = 20 * 1024;
+        const BASE_STACK: usize = 2 * 1024 * 1024;
+        BASE_STACK.saturating_add(self.max_nesting.saturating_mul(STACK_PER_LEVEL))
+    }
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -61,3 +312,123 @@ pub enum Emit {
     #[default]
     Binary,
 }
+
+/// Format of the diagnostics.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// Diagnostics with snippets of the source code.
+    #[default]
+    Human,
+    /// JSON object per diagnostic on separate lines.
+    Json,
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, num::NonZeroUsize, path::PathBuf, thread};
+
+    use crate::{error::LintLevel, source::SourceError, Identifier};
+
+    use super::{Context, Emit, ErrorFormat, Metadata};
+
+    fn fixtures() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+    }
+
+    #[test]
+    fn defaults() {
+        let context = Context::builder()
+            .main(fixtures().join("cascade.sun"))
+            .build()
+            .unwrap();
+        assert_eq!(
+            *context.metadata,
+            Metadata {
+                crate_name: Identifier(String::from("main")),
+                emit_type: Emit::Binary,
+                error_format: ErrorFormat::Human,
+                fold_constants: false,
+                lint_levels: HashMap::new(),
+                max_errors: None,
+                tab_width: 4,
+                jobs: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+                timings: false,
+                max_nesting: 2000,
+                max_expression_tokens: 65536,
+                entry_point: Identifier(String::from("main")),
+                cache_dir: None,
+            }
+        );
+        assert_eq!(
+            context.source.lock().unwrap().main(),
+            fixtures().join("cascade.sun")
+        );
+    }
+
+    #[test]
+    fn settings() {
+        let context = Context::builder()
+            .crate_name(Identifier(String::from("cascade")))
+            .main(fixtures().join("cascade.sun"))
+            .root(fixtures())
+            .emit(Emit::Hir)
+            .error_format(ErrorFormat::Json)
+            .lint_level("e0202", LintLevel::Deny)
+            .max_errors(Some(3))
+            .tab_width(2)
+            .jobs(3)
+            .fold_constants(true)
+            .timings(true)
+            .max_nesting(100)
+            .max_expression_tokens(500)
+            .entry_point(Identifier(String::from("start")))
+            .build()
+            .unwrap();
+        let metadata = &context.metadata;
+        assert_eq!(metadata.crate_name, Identifier(String::from("cascade")));
+        assert_eq!(metadata.emit_type, Emit::Hir);
+        assert_eq!(metadata.error_format, ErrorFormat::Json);
+        assert_eq!(
+            metadata.lint_levels,
+            HashMap::from([(String::from("E0202"), LintLevel::Deny)])
+        );
+        assert_eq!(metadata.max_errors, Some(3));
+        assert_eq!(metadata.tab_width, 2);
+        assert_eq!(metadata.jobs, 3);
+        assert!(metadata.fold_constants);
+        assert!(metadata.timings);
+        assert_eq!(metadata.max_nesting, 100);
+        assert_eq!(metadata.max_expression_tokens, 500);
+        assert_eq!(metadata.entry_point, Identifier(String::from("start")));
+    }
+
+    #[test]
+    fn missing_main() {
+        assert!(matches!(
+            Context::builder().build(),
+            Err(SourceError::MissingMain)
+        ));
+    }
+
+    #[test]
+    fn nonexistent_main() {
+        assert!(matches!(
+            Context::builder()
+                .main(fixtures().join("missing.sun"))
+                .build(),
+            Err(SourceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_root() {
+        let main = fixtures().join("cascade.sun");
+        let missing = Context::builder()
+            .main(&main)
+            .root(fixtures().join("missing"))
+            .build();
+        assert!(matches!(missing, Err(SourceError::NotFound(_))));
+        let file = Context::builder().main(&main).root(&main).build();
+        assert!(matches!(file, Err(SourceError::NotADirectory(_))));
+    }
+}
@@ -1,10 +1,14 @@
 //! Error reporting.
 
+mod diagnostic;
 mod error_reporter;
 mod expected_token;
+pub mod json;
 pub mod library;
+pub mod render;
 mod report_provider;
 
+pub use diagnostic::*;
 pub use error_reporter::*;
 pub use expected_token::*;
 pub use report_provider::*;
@@ -21,7 +25,18 @@ use crate::{
 /// Error that may be reported.
 pub trait ReportableError: Error {
     fn severity(&self) -> Severity;
-    fn span(&self) -> Span;
+    /// Stable code of the error, such as `E0004`.
+    fn code(&self) -> &'static str;
+    /// Location of the erroneous code, if it is known.
+    fn span(&self) -> Option<Span>;
+    /// Short explanation shown next to the erroneous code.
+    fn label(&self) -> Option<String> {
+        None
+    }
+    /// Other code related to the error, such as the one that imposed violated expectation.
+    fn secondary_labels(&self) -> Vec<Label> {
+        Vec::new()
+    }
 }
 
 /// Fatal error occured during compilation.
@@ -58,7 +73,7 @@ impl Token {
             Token::Str(s) => format!("\"{s}\""),
             Token::Kw(kw) => format!("keyword `{kw}`"),
             Token::Ident(ident) => format!("`{ident}`"),
-            Token::Eof => todo!(),
+            Token::Eof => String::from("end of file"),
         }
     }
 }
@@ -0,0 +1,95 @@
+use crate::{
+    error::{ReportableError, Severity},
+    util::Span,
+};
+
+/// Reported error in a form that is ready to be shown to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Severity of the error, adjusted according to [lint levels](LintLevel).
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    /// Short explanation shown next to the code covered by `span`.
+    pub label: Option<String>,
+    pub secondary_labels: Vec<Label>,
+    pub suggestion: Option<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn new(error: &dyn ReportableError) -> Self {
+        Self {
+            severity: error.severity(),
+            code: error.code(),
+            message: error.to_string(),
+            span: error.span(),
+            label: error.label(),
+            secondary_labels: error.secondary_labels(),
+            suggestion: None,
+        }
+    }
+}
+
+/// Code related to the error, shown along with the explanation of its relation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Label {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+/// Proposed fix of the error: code covered by `span` should be replaced with `replacement`.
+///
+/// Empty span denotes insertion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+    pub applicability: Applicability,
+}
+
+impl Suggestion {
+    pub fn new(
+        message: impl Into<String>,
+        span: Span,
+        replacement: impl Into<String>,
+        applicability: Applicability,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            replacement: replacement.into(),
+            applicability,
+        }
+    }
+}
+
+/// Confidence that [Suggestion] is correct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applicability {
+    /// Suggestion is definitely what the user intended and may be applied automatically.
+    MachineApplicable,
+    /// Suggestion may be what the user intended, but it is uncertain.
+    MaybeIncorrect,
+}
+
+/// Level that overrides default severity of errors with specific code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintLevel {
+    /// Error is not reported at all.
+    Allow,
+    /// Error is reported as a warning.
+    Warn,
+    /// Error is reported and fails compilation.
+    Deny,
+}
@@ -1,60 +1,313 @@
 use std::{
     fmt::Display,
+    io::{self, Write},
     sync::{Arc, Mutex},
 };
 
 use crate::{
-    error::{ReportableError, Severity},
-    source::SourceMap,
+    context::{ErrorFormat, Metadata},
+    error::{
+        json::write_json, library::lexer::TokenMismatch, render::render_error, Diagnostic, Label,
+        LintLevel, ReportableError, Severity, Suggestion,
+    },
+    input_stream::Location,
+    source::{SourceId, SourceMap},
+    util::Span,
 };
 
+/// Number of tokens after an error within which [TokenMismatch] errors on the same line are
+/// considered to be caused by it.
+const FOLLOW_UP_TOKENS: usize = 3;
+
 /// Interface to report errors conveniently.
 #[derive(Debug)]
 pub struct ErrorReporter {
     source_map: Arc<Mutex<SourceMap>>,
-    errors: Mutex<Vec<Box<dyn ReportableError>>>,
+    metadata: Arc<Metadata>,
+    diagnostics: Mutex<Vec<Diagnostic>>,
+    /// Lint levels set by attributes of items, which override the ones of metadata.
+    lint_scopes: Mutex<Vec<LintScope>>,
+}
+
+/// Part of the source code with its own lint levels.
+#[derive(Debug)]
+struct LintScope {
+    source: Option<SourceId>,
+    start: Location,
+    /// End of the scope, `None` while it's still being parsed.
+    end: Option<Location>,
+    /// Levels by codes of lints, the later ones take precedence.
+    levels: Vec<(&'static str, LintLevel)>,
+}
+
+impl LintScope {
+    fn contains(&self, span: Span) -> bool {
+        self.source == span.source
+            && self.start.pos <= span.start.pos
+            && self.end.is_none_or(|end| span.end.pos <= end.pos)
+    }
 }
 
 impl ErrorReporter {
     /// Create new ErrorReporter.
-    pub fn new(source_map: Arc<Mutex<SourceMap>>) -> Self {
+    ///
+    /// Lint levels and maximal number of errors are taken from `metadata`.
+    pub fn new(source_map: Arc<Mutex<SourceMap>>, metadata: Arc<Metadata>) -> Self {
         Self {
             source_map,
-            errors: Mutex::new(Vec::new()),
+            metadata,
+            diagnostics: Mutex::new(Vec::new()),
+            lint_scopes: Mutex::new(Vec::new()),
         }
     }
 
-    pub fn report(&self, error: impl ReportableError + 'static) {
-        self.errors.lock().unwrap().push(Box::new(error));
+    /// Reports an error, unless its code is allowed.
+    pub fn report(&self, error: impl ReportableError) {
+        self.push(Diagnostic::new(&error));
     }
 
-    /// Check if any fatal error occurred.
-    pub fn compilation_failed(&self) -> bool {
-        !self.errors.lock().unwrap().is_empty()
+    /// Reports an error along with a `suggestion` how to fix it, unless its code is allowed.
+    pub fn report_with(&self, error: impl ReportableError, suggestion: Suggestion) {
+        self.push(Diagnostic {
+            suggestion: Some(suggestion),
+            ..Diagnostic::new(&error)
+        });
+    }
+
+    /// Reports an error along with `labels` of the related code, unless its code is allowed.
+    pub fn report_labeled(&self, error: impl ReportableError, labels: Vec<Label>) {
+        let mut diagnostic = Diagnostic::new(&error);
+        diagnostic.secondary_labels.extend(labels);
+        self.push(diagnostic);
+    }
+
+    /// Opens a scope that starts at `start` of the `source`, within which lint `levels` override
+    /// the ones of metadata and of the enclosing scopes.
+    ///
+    /// Scope covers the rest of the file until it's [closed](ErrorReporter::close_lint_scope), so
+    /// it may be opened before the item it belongs to is parsed.
+    pub fn open_lint_scope(
+        &self,
+        source: Option<SourceId>,
+        start: Location,
+        levels: Vec<(&'static str, LintLevel)>,
+    ) -> usize {
+        let mut scopes = self.lint_scopes.lock().unwrap();
+        scopes.push(LintScope {
+            source,
+            start,
+            end: None,
+            levels,
+        });
+        scopes.len() - 1
+    }
+
+    /// Ends the scope returned by [open_lint_scope](ErrorReporter::open_lint_scope) at `end`.
+    pub fn close_lint_scope(&self, scope: usize, end: Location) {
+        self.lint_scopes.lock().unwrap()[scope].end = Some(end);
     }
 
-    /// Calculates number of warnings and errors.
-    fn calc_number(&self) -> (usize, usize) {
-        self.errors
-            .lock()
-            .unwrap()
+    /// Removes lint scopes of the `source`, e.g. before it's parsed again.
+    pub fn clear_lint_scopes(&self, source: SourceId) {
+        let mut scopes = self.lint_scopes.lock().unwrap();
+        scopes.retain(|scope| scope.source != Some(source));
+    }
+
+    /// Level of lint `code` set by the innermost scope that contains `span`.
+    fn scoped_level(&self, code: &str, span: Span) -> Option<LintLevel> {
+        let scopes = self.lint_scopes.lock().unwrap();
+        scopes
             .iter()
-            .fold((0, 0), |(w, e), err| match err.severity() {
-                Severity::Warn => (w + 1, e),
-                Severity::Deny => (w, e + 1),
+            .filter(|scope| scope.contains(span))
+            .filter_map(|scope| {
+                let (_, level) = scope.levels.iter().rev().find(|(lint, _)| *lint == code)?;
+                Some((scope.start.pos, *level))
             })
+            .max_by_key(|(start, _)| *start)
+            .map(|(_, level)| level)
+    }
+
+    /// Adds `diagnostic` to the list, adjusting its severity according to lint levels.
+    fn push(&self, mut diagnostic: Diagnostic) {
+        let level = diagnostic
+            .span
+            .and_then(|span| self.scoped_level(diagnostic.code, span));
+        match level.or_else(|| self.metadata.lint_levels.get(diagnostic.code).copied()) {
+            Some(LintLevel::Allow) => return,
+            Some(LintLevel::Warn) => diagnostic.severity = Severity::Warn,
+            Some(LintLevel::Deny) => diagnostic.severity = Severity::Deny,
+            None => {}
+        }
+        self.diagnostics.lock().unwrap().push(diagnostic);
     }
+
+    /// Removes every reported diagnostic and returns them in the order they were reported.
+    pub fn take(&self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics.lock().unwrap())
+    }
+
+    /// Adds diagnostics that were [taken](ErrorReporter::take) back.
+    ///
+    /// Lint levels are not applied again, as they were already applied when the diagnostics
+    /// were reported.
+    pub fn restore(&self, diagnostics: impl IntoIterator<Item = Diagnostic>) {
+        self.diagnostics.lock().unwrap().extend(diagnostics);
+    }
+
+    /// Check if any fatal error occurred.
+    pub fn compilation_failed(&self) -> bool {
+        count(&self.diagnostics.lock().unwrap()).1 > 0
+    }
+
+    /// Check if the number of fatal errors reached the maximum, so compilation should be aborted.
+    pub fn limit_reached(&self) -> bool {
+        self.metadata
+            .max_errors
+            .is_some_and(|max| count(&self.diagnostics.lock().unwrap()).1 >= max)
+    }
+
+    /// Diagnostics reported so far, cleaned up to be shown to the user.
+    ///
+    /// Diagnostics are sorted by their location and duplicates with the same code and span are
+    /// merged, diagnostics without span are merged only if their messages are the same as well. [TokenMismatch] errors shortly after another error on the same line are dropped,
+    /// as they are most likely caused by it.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.lock().unwrap().clone();
+        diagnostics.sort_by_key(|diagnostic| {
+            let location = diagnostic.span.map(|span| (span.source, span.start));
+            (location.is_none(), location)
+        });
+
+        let mut source_map = self.source_map.lock().unwrap();
+        let mut cleaned: Vec<Diagnostic> = Vec::with_capacity(diagnostics.len());
+        for diagnostic in diagnostics {
+            let duplicate = cleaned.iter().any(|other| {
+                other.code == diagnostic.code
+                    && other.span == diagnostic.span
+                    && (diagnostic.span.is_some() || other.message == diagnostic.message)
+            });
+            let follow_up = diagnostic.code == TokenMismatch::CODE
+                && cleaned
+                    .last()
+                    .is_some_and(|previous| follows(previous, &diagnostic, &mut source_map));
+            if !duplicate && !follow_up {
+                cleaned.push(diagnostic);
+            }
+        }
+        cleaned
+    }
+
+    /// Warnings reported so far.
+    pub fn warnings(&self) -> Vec<Diagnostic> {
+        self.diagnostics()
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warn)
+            .collect()
+    }
+
+    /// Writes all reported errors with snippets of the code they refer to.
+    ///
+    /// ANSI escape codes are used to highlight the output if `colored` is set.
+    pub fn render(&self, mut w: impl Write, colored: bool) -> io::Result<()> {
+        let diagnostics = self.diagnostics();
+        let (warnings, errors) = count(&diagnostics);
+        let mut source_map = self.source_map.lock().unwrap();
+        for diagnostic in diagnostics.iter() {
+            render_error(
+                &mut w,
+                diagnostic,
+                &mut source_map,
+                colored,
+                self.metadata.tab_width,
+            )?;
+            writeln!(w)?;
+        }
+        if self.limit_reached() {
+            writeln!(w, "note: aborting due to too many errors")?;
+        }
+        writeln!(w, "{warnings} warning(s), {errors} error(s)")
+    }
+
+    /// Writes all reported errors in the [format](ErrorFormat) specified in the metadata.
+    pub fn emit(&self, w: impl Write, colored: bool) -> io::Result<()> {
+        match self.metadata.error_format {
+            ErrorFormat::Human => self.render(w, colored),
+            ErrorFormat::Json => self.render_json(w),
+        }
+    }
+
+    /// Writes all reported errors as JSON, one object per line.
+    ///
+    /// See [json](super::json) module for the description of the format.
+    pub fn render_json(&self, mut w: impl Write) -> io::Result<()> {
+        let diagnostics = self.diagnostics();
+        let source_map = self.source_map.lock().unwrap();
+        for diagnostic in diagnostics.iter() {
+            write_json(&mut w, diagnostic, &source_map)?;
+        }
+        Ok(())
+    }
+}
+
+/// Calculates number of warnings and errors.
+fn count(diagnostics: &[Diagnostic]) -> (usize, usize) {
+    diagnostics
+        .iter()
+        .fold((0, 0), |(w, e), diagnostic| match diagnostic.severity {
+            Severity::Warn => (w + 1, e),
+            Severity::Deny => (w, e + 1),
+        })
+}
+
+/// Checks if `diagnostic` is located on the same line within [FOLLOW_UP_TOKENS] after `previous`.
+fn follows(previous: &Diagnostic, diagnostic: &Diagnostic, source_map: &mut SourceMap) -> bool {
+    let (Some(previous), Some(span)) = (previous.span, diagnostic.span) else {
+        return false;
+    };
+    if previous.source != span.source || previous.start.line != span.start.line {
+        return false;
+    }
+    let Some(line) = span.source.and_then(|id| source_map.get(id).line(span.start.line)) else {
+        return false;
+    };
+    let between: String = line
+        .chars()
+        .skip(previous.start.column)
+        .take(span.start.column - previous.start.column)
+        .collect();
+    count_tokens(&between) <= FOLLOW_UP_TOKENS
+}
+
+/// Roughly counts tokens in `text`.
+///
+/// Every word or number is counted as a single token, as well as any other non-whitespace character.
+fn count_tokens(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for ch in text.chars() {
+        let is_word = ch.is_alphanumeric() || ch == '_';
+        if !(ch.is_whitespace() || is_word && in_word) {
+            count += 1;
+        }
+        in_word = is_word;
+    }
+    count
 }
 
 impl Display for ErrorReporter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for error in self.errors.lock().unwrap().iter() {
-            match error.severity() {
-                Severity::Warn => writeln!(f, "Warning: {error}")?,
-                Severity::Deny => writeln!(f, "Error: {error}")?,
+        let diagnostics = self.diagnostics();
+        for diagnostic in diagnostics.iter() {
+            match diagnostic.severity {
+                Severity::Warn => writeln!(f, "Warning: {}", diagnostic.message)?,
+                Severity::Deny => writeln!(f, "Error: {}", diagnostic.message)?,
             }
-            match error.span().source {
-                Some(file) => writeln!(
+            match diagnostic.span {
+                Some(Span {
+                    source: Some(file),
+                    start,
+                    ..
+                }) => writeln!(
                     f,
                     " --> {}:{}",
                     self.source_map
@@ -62,14 +315,158 @@ impl Display for ErrorReporter {
                         .unwrap()
                         .get_path(file)
                         .to_string_lossy(),
-                    error.span().start
+                    start
                 )?,
-                None => writeln!(f, " --> {}", error.span().start)?,
+                Some(Span { start, .. }) => writeln!(f, " --> {start}")?,
+                None => {}
             }
             writeln!(f)?;
         }
-        let (warnings, error) = self.calc_number();
+        if self.limit_reached() {
+            writeln!(f, "Note: aborting due to too many errors")?;
+        }
+        let (warnings, error) = count(&diagnostics);
         writeln!(f, "{warnings} warning(s), {error} error(s)",)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, path::PathBuf};
+
+    use crate::{
+        context::{Context, Metadata},
+        error::{
+            library::{
+                hir::UnreachableCode,
+                lexer::{TokenMismatch, UnexpectedCharacter},
+                parser::ExpectedItem,
+            },
+            ExpectedToken, LintLevel,
+        },
+        hir::HirBuilder,
+        input_stream::Location,
+        lexer::Token,
+        parser::FileParser,
+        source::SourceId,
+        util::Span,
+        Identifier,
+    };
+
+    const UNREACHABLE: &str = "fn run() -> i32 { return 1; let x: i32 = 0; }";
+
+    fn translate(src: &str, lint_levels: HashMap<String, LintLevel>) -> Context {
+        let context = Context::new_test_with(Metadata {
+            lint_levels,
+            ..Metadata::new(Identifier(String::from("_TEST")))
+        });
+        let parsed = FileParser::new_test(src).parse();
+        assert!(parsed.errors.is_empty(), "parsing failed");
+        let mut builder = HirBuilder::new(context.clone());
+        builder.populate(&parsed.item_table);
+        assert!(builder.build().is_ok());
+        context
+    }
+
+    #[test]
+    fn warning() {
+        let context = translate(UNREACHABLE, HashMap::new());
+        let warnings = context.error_reporter.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, UnreachableCode::CODE);
+        assert!(!context.error_reporter.compilation_failed());
+    }
+
+    #[test]
+    fn denied_warning() {
+        let levels = HashMap::from([(String::from(UnreachableCode::CODE), LintLevel::Deny)]);
+        let context = translate(UNREACHABLE, levels);
+        assert!(context.error_reporter.warnings().is_empty());
+        assert!(context.error_reporter.compilation_failed());
+    }
+
+    #[test]
+    fn allowed_warning() {
+        let levels = HashMap::from([(String::from(UnreachableCode::CODE), LintLevel::Allow)]);
+        let context = translate(UNREACHABLE, levels);
+        assert!(context.error_reporter.warnings().is_empty());
+        assert!(context
+            .error_reporter
+            .to_string()
+            .ends_with("0 warning(s), 0 error(s)\n"));
+    }
+
+    #[test]
+    fn error_limit() {
+        let context = Context::new_test_with(Metadata {
+            max_errors: Some(2),
+            ..Metadata::new(Identifier(String::from("_TEST")))
+        });
+        let reporter = &context.error_reporter;
+        let _ = UnreachableCode::report_at(reporter, span(None, 0, 0));
+        let _ = ExpectedItem::report_at(reporter, span(None, 1, 0));
+        assert!(!reporter.limit_reached());
+        let _ = ExpectedItem::report_at(reporter, span(None, 2, 0));
+        assert!(reporter.limit_reached());
+        assert!(reporter
+            .to_string()
+            .ends_with("Note: aborting due to too many errors\n1 warning(s), 2 error(s)\n"));
+    }
+
+    #[test]
+    fn cleanup() {
+        let main = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cascade.sun");
+        let context = Context::builder()
+            .crate_name(Identifier(String::from("cascade")))
+            .main(main.clone())
+            .build()
+            .unwrap();
+        let source = context.source.lock().unwrap().insert_path(main).ok();
+        let reporter = &context.error_reporter;
+        let mismatch = |line, column| {
+            let expected = vec![ExpectedToken::Expression];
+            let _ = TokenMismatch::report_at(
+                reporter,
+                span(source, line, column),
+                expected,
+                Token::Ident(String::from("x")),
+            );
+        };
+
+        mismatch(2, 18);
+        let _ = UnexpectedCharacter::report_at(reporter, span(source, 1, 19), '$');
+        mismatch(1, 19);
+        mismatch(1, 23);
+        mismatch(1, 32);
+        let _ = UnexpectedCharacter::report_at(reporter, span(source, 1, 19), '$');
+
+        let locations: Vec<_> = reporter
+            .diagnostics()
+            .into_iter()
+            .map(|diagnostic| {
+                let span = diagnostic.span.unwrap();
+                (diagnostic.code, span.start.line, span.start.column)
+            })
+            .collect();
+        assert_eq!(
+            locations,
+            [
+                (UnexpectedCharacter::CODE, 1, 19),
+                (TokenMismatch::CODE, 1, 32),
+                (TokenMismatch::CODE, 2, 18),
+            ]
+        );
+    }
+
+    fn span(source: Option<SourceId>, line: usize, column: usize) -> Span {
+        let start = Location {
+            line,
+            column,
+            ..Location::default()
+        };
+        let mut end = start;
+        end.column += 1;
+        Span { source, start, end }
+    }
+}
@@ -7,6 +7,7 @@ use crate::lexer::{keyword::Keyword, punctuation::Punctuation};
 pub enum ExpectedToken {
     Identifier,
     Expression,
+    Type,
     Keyword(Keyword),
     Punctuation(Punctuation),
 }
@@ -28,6 +29,7 @@ impl Display for ExpectedToken {
         match self {
             ExpectedToken::Identifier => write!(f, "an identifier"),
             ExpectedToken::Expression => write!(f, "an expression"),
+            ExpectedToken::Type => write!(f, "a type"),
             ExpectedToken::Keyword(kw) => write!(f, "keyword `{kw}`"),
             ExpectedToken::Punctuation(punc) => write!(f, "`{punc}`"),
         }
@@ -0,0 +1,261 @@
+//! Machine-readable representation of reported errors.
+//!
+//! Every error is written as a single line with JSON object of the following structure:
+//!
+//! ```json
+//! {
+//!     "severity": "error",
+//!     "code": "E0205",
+//!     "message": "...",
+//!     "file": "src/main.sun",
+//!     "start": { "line": 7, "column": 9 },
+//!     "end": { "line": 7, "column": 13 },
+//!     "labels": [
+//!         {
+//!             "message": "...",
+//!             "file": "src/main.sun",
+//!             "start": { "line": 7, "column": 9 },
+//!             "end": { "line": 7, "column": 13 },
+//!             "primary": true
+//!         },
+//!         {
+//!             "message": "...",
+//!             "file": "src/main.sun",
+//!             "start": { "line": 3, "column": 12 },
+//!             "end": { "line": 3, "column": 15 },
+//!             "primary": false
+//!         }
+//!     ],
+//!     "suggestion": {
+//!         "message": "...",
+//!         "start": { "line": 7, "column": 11 },
+//!         "end": { "line": 7, "column": 12 },
+//!         "replacement": "==",
+//!         "applicability": "maybe-incorrect"
+//!     }
+//! }
+//! ```
+//!
+//! Lines and columns are one-based. `file`, `start` and `end` are `null` if they are unknown.
+//! `labels` lists code related to the error. If the code between `start` and `end` is explained,
+//! its label goes first and is marked as `primary`.
+//! `suggestion` is `null` if there is no proposed fix. Its `applicability` is either
+//! `machine-applicable` or `maybe-incorrect`.
+
+use std::io::{Result, Write};
+
+use crate::{
+    error::{Applicability, Diagnostic, Severity, Suggestion},
+    input_stream::Location,
+    source::SourceMap,
+    util::Span,
+};
+
+/// Writes `diagnostic` as a single line of JSON.
+pub fn write_json(
+    mut w: impl Write,
+    diagnostic: &Diagnostic,
+    source_map: &SourceMap,
+) -> Result<()> {
+    let severity = match diagnostic.severity {
+        Severity::Warn => "warning",
+        Severity::Deny => "error",
+    };
+    let span = diagnostic.span;
+    let file = match span.and_then(|span| span.source) {
+        Some(id) => string(&source_map.get_path(id).to_string_lossy()),
+        None => String::from("null"),
+    };
+    let (start, end) = match span {
+        Some(span) => (location(span.start), location(span.end)),
+        None => (String::from("null"), String::from("null")),
+    };
+    let primary = match (&diagnostic.label, span) {
+        (Some(message), Some(span)) => Some(self::label(message, span, true, source_map)),
+        _ => None,
+    };
+    let labels = primary
+        .into_iter()
+        .chain(
+            diagnostic
+                .secondary_labels
+                .iter()
+                .map(|label| self::label(&label.message, label.span, false, source_map)),
+        )
+        .collect::<Vec<_>>()
+        .join(",");
+    let suggestion = match &diagnostic.suggestion {
+        Some(suggestion) => self::suggestion(suggestion),
+        None => String::from("null"),
+    };
+    writeln!(
+        w,
+        r#"{{"severity":"{severity}","code":{},"message":{},"file":{file},"start":{start},"end":{end},"labels":[{labels}],"suggestion":{suggestion}}}"#,
+        string(diagnostic.code),
+        string(&diagnostic.message),
+    )
+}
+
+fn label(message: &str, span: Span, primary: bool, source_map: &SourceMap) -> String {
+    let file = match span.source {
+        Some(id) => string(&source_map.get_path(id).to_string_lossy()),
+        None => String::from("null"),
+    };
+    format!(
+        r#"{{"message":{},"file":{file},"start":{},"end":{},"primary":{primary}}}"#,
+        string(message),
+        location(span.start),
+        location(span.end),
+    )
+}
+
+fn suggestion(suggestion: &Suggestion) -> String {
+    let applicability = match suggestion.applicability {
+        Applicability::MachineApplicable => "machine-applicable",
+        Applicability::MaybeIncorrect => "maybe-incorrect",
+    };
+    format!(
+        r#"{{"message":{},"start":{},"end":{},"replacement":{},"applicability":"{applicability}"}}"#,
+        string(&suggestion.message),
+        location(suggestion.span.start),
+        location(suggestion.span.end),
+        string(&suggestion.replacement),
+    )
+}
+
+pub(crate) fn location(location: Location) -> String {
+    format!(
+        r#"{{"line":{},"column":{}}}"#,
+        location.line + 1,
+        location.column + 1
+    )
+}
+
+/// Encodes `s` as JSON string literal.
+pub(crate) fn string(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len() + 2);
+    encoded.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => encoded.push_str("\\\""),
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            ch if ch.is_control() => encoded.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => encoded.push(ch),
+        }
+    }
+    encoded.push('"');
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::{
+        context::{Context, Emit},
+        error::{Diagnostic, Label, Severity},
+        hir::HirBuilder,
+        input_stream::Location,
+        parser::Parser,
+        source::SourceMap,
+        util::Span,
+        Identifier,
+    };
+
+    use super::{string, write_json};
+
+    #[test]
+    fn two_errors() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let main = dir.join("two_errors.sun");
+        let context = Context::builder()
+            .crate_name(Identifier(String::from("two_errors")))
+            .main(main)
+            .emit(Emit::Hir)
+            .build()
+            .unwrap();
+        let mut parser = Parser::new(context.clone());
+        let mut builder = HirBuilder::new(context.clone());
+        let (table, _) = parser.parse().expect("fixture should be parsed");
+        builder.populate(&table);
+        assert!(builder.build().is_err(), "translation succeeded");
+
+        let mut output = Vec::new();
+        context.error_reporter.render_json(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let file = string(&dir.join("two_errors.sun").to_string_lossy());
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                format!(
+                    r#"{{"severity":"error","code":"E0208","message":"variable `y` is not declared","file":{file},"start":{{"line":2,"column":5}},"end":{{"line":2,"column":6}},"labels":[],"suggestion":null}}"#
+                ),
+                format!(
+                    r#"{{"severity":"error","code":"E0212","message":"break may not be used outside of the loop","file":{file},"start":{{"line":6,"column":5}},"end":{{"line":6,"column":11}},"labels":[],"suggestion":null}}"#
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn suggestion() {
+        let main = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/suggestions.sun");
+        let context = Context::builder()
+            .crate_name(Identifier(String::from("suggestions")))
+            .main(main)
+            .build()
+            .unwrap();
+        let mut parser = Parser::new(context.clone());
+        assert!(parser.parse().is_err());
+
+        let mut output = Vec::new();
+        context.error_reporter.render_json(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let first = output.lines().next().unwrap();
+        assert!(first.ends_with(
+            r#""suggestion":{"message":"add `;` here","start":{"line":2,"column":19},"end":{"line":2,"column":19},"replacement":";","applicability":"machine-applicable"}}"#
+        ));
+    }
+
+    #[test]
+    fn labels() {
+        let span = |line, start, end| Span {
+            source: None,
+            start: Location {
+                line,
+                column: start,
+                pos: 0,
+            },
+            end: Location {
+                line,
+                column: end,
+                pos: 0,
+            },
+        };
+        let diagnostic = Diagnostic {
+            severity: Severity::Deny,
+            code: "E0205",
+            message: String::from("mismatched types"),
+            span: Some(span(6, 8, 12)),
+            label: Some(String::from("expected `i32`")),
+            secondary_labels: vec![Label::new(span(2, 11, 14), "expected due to this")],
+            suggestion: None,
+        };
+        let mut output = Vec::new();
+        write_json(&mut output, &diagnostic, &SourceMap::new_test().unwrap()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains(r#""label":"#));
+        assert!(output.contains(
+            r#""labels":[{"message":"expected `i32`","file":null,"start":{"line":7,"column":9},"end":{"line":7,"column":13},"primary":true},{"message":"expected due to this","file":null,"start":{"line":3,"column":12},"end":{"line":3,"column":15},"primary":false}]"#
+        ));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(string("say \"hi\"\n\\\u{1}"), r#""say \"hi\"\n\\\u0001""#);
+    }
+}
@@ -9,28 +9,99 @@
 //! ```
 //!
 //! Every error has its own list of additional `parameters`.
+//!
+//! # Error codes
+//!
+//! Every error has a stable code, such as `E0001`. Codes are grouped by the stage that issues them:
+//! `E00XX` for parser, `E01XX` for lexer and `E02XX` for HIR translation.
+//! Doc comments of errors serve as their long-form explanations, see [explain].
 
 #[macro_use]
 mod r#macro;
 
+use crate::error::Severity;
+
+/// Description of an error defined in the library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorInfo {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub severity: Severity,
+    /// Doc comment of the error.
+    pub doc: &'static str,
+}
+
+impl ErrorInfo {
+    /// Long-form explanation of the error, taken from its doc comment.
+    pub fn explanation(&self) -> String {
+        self.doc
+            .lines()
+            .map(|line| line.strip_prefix(' ').unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Iterates over all errors defined in the library.
+pub fn registry() -> impl Iterator<Item = &'static ErrorInfo> {
+    [
+        parser::ERRORS,
+        lexer::ERRORS,
+        hir::ERRORS,
+        hir::TRANSLATION_ERRORS,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Looks up an error by its `code`.
+pub fn explain(code: &str) -> Option<&'static ErrorInfo> {
+    registry().find(|info| info.code.eq_ignore_ascii_case(code))
+}
+
+/// Looks up an error by its code or by its name in snake case, such as `unreachable_code`.
+pub fn lint(name: &str) -> Option<&'static ErrorInfo> {
+    explain(name).or_else(|| registry().find(|info| snake_case(info.name) == name))
+}
+
+/// Converts `name` from camel case to snake case.
+fn snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len() + 4);
+    for (i, char) in name.chars().enumerate() {
+        if char.is_ascii_uppercase() && i != 0 {
+            result.push('_');
+        }
+        result.push(char.to_ascii_lowercase());
+    }
+    result
+}
+
 /// Errors issued by parser.
 pub mod parser {
-    use crate::lexer::{keyword::Keyword, punctuation::Punctuation};
+    use std::path::PathBuf;
+
+    use crate::{
+        lexer::{keyword::Keyword, operator::BinaryOp, punctuation::Punctuation},
+        path::{AbsolutePath, RelativePath},
+        Identifier,
+    };
 
     define_error! {
         /// Expected an item.
-        deny ExpectedItem = "expected an item";
+        deny [E0001] ExpectedItem = "expected an item";
 
         /// Expected expression.
-        deny ExpectedExpression = "expected expression";
+        deny [E0002] ExpectedExpression = "expected expression";
 
         /// Assignment in expression position.
         ///
         /// Assignment is not an expression.
-        deny AssignmentInExpressionPosition = "assignment in expression position";
+        deny [E0003] AssignmentInExpressionPosition = "assignment in expression position";
 
         /// Unclosed parenthesis.
-        deny UnclosedParenthesis = "unclosed parenthesis";
+        ///
+        /// Error is reported at the innermost parenthesis that is never closed.
+        deny [E0004] UnclosedParenthesis = "this parenthesis is never closed";
 
         /// Else may only be used directly after if conditional's body.
         ///
@@ -43,38 +114,249 @@ pub mod parser {
         ///     x -= 1;
         /// }
         /// ```
-        deny ElseWithoutIf = "else may only be used directly after if conditional's body";
+        deny [E0005] ElseWithoutIf = "else may only be used directly after if conditional's body";
 
         /// Assignments can't be chained.
         ///
         /// ```notrust
         /// x = y = 5;
         /// ```
-        deny ChainedAssignment = "assignments can't be chained";
+        deny [E0006] ChainedAssignment = "assignments can't be chained";
 
-        /// Invalid assigned was used in assignment statement.
+        /// Invalid assignee was used in assignment statement.
         ///
-        /// At the moment variables are the only valid assignees.
+        /// Variables, their fields and elements are the only valid assignees.
         ///
         /// ```notrust
         /// 5 = 6; ✗
+        /// f() = 6; ✗
         /// x = 6; 🗸
+        /// point.x = 6; 🗸
+        /// values[0] = 6; 🗸
         /// ```
-        deny InvalidAssignee = "assignments can't be chained";
+        deny [E0007] InvalidAssignee = "only variables, their fields and elements may be assigned to";
 
         /// Punctuation is not allowed.
-        deny InvalidPunctuation { punc: Punctuation }
+        deny [E0008] InvalidPunctuation { punc: Punctuation }
         = "punctuation `{punc:?}` is not allowed";
 
         /// Keyword is not allowed in operator expression.
-        deny KeywordNotAllowedInOperatorExpression { kw: Keyword }
+        deny [E0009] KeywordNotAllowedInOperatorExpression { kw: Keyword }
         = "keyword `{kw}` is not allowed in operator expression";
 
         /// `super` keyword may only be used in leading segments of the path.
-        deny InvalidSuperKw = "`super` keyword may only be used in leading segments of the path";
+        deny [E0010] InvalidSuperKw = "`super` keyword may only be used in leading segments of the path";
 
         /// `crate` keyword may only be used as the first segment of the path.
-        deny InvalidCrateKw = "`crate` keyword may only be used as the first segment of the path.";
+        deny [E0011] InvalidCrateKw = "`crate` keyword may only be used as the first segment of the path.";
+
+        /// Array length must be known at compile time.
+        ///
+        /// At the moment only integer literals are allowed.
+        deny [E0012] InvalidArrayLength = "array length must be an integer literal";
+
+        /// Imported path doesn't end with a name, so alias is required.
+        ///
+        /// ```notrust
+        /// use super; ✗
+        /// use super as parent; 🗸
+        /// ```
+        deny [E0013] ImportWithoutName = "import must either end with an item name or be given an alias";
+
+        /// The same name is imported into the module multiple times.
+        deny [E0014] DuplicateImport { name: Identifier }
+        = "the name `{name}` is imported multiple times";
+
+        /// Multiple items are defined with the same path.
+        ///
+        /// ```notrust
+        /// mod geometry {}
+        /// fn geometry() {}
+        /// ```
+        deny [E0015] DuplicateDefinition { path: AbsolutePath, previous: String }
+        = "`{path}` is defined multiple times, previous definition is at {previous}";
+
+        /// Opening brace has no matching closing brace before the end of file.
+        ///
+        /// Error is reported at the innermost brace that is never closed.
+        deny [E0016] UnclosedBrace = "this brace is never closed";
+
+        /// File of the module declared as `mod NAME;` is not found.
+        ///
+        /// File of the module `crate::a::b` is expected to be either `a/b.sun` or `a/b/mod.sun`
+        /// relative to the root of the crate.
+        deny [E0017] ModuleFileNotFound { name: Identifier, file: PathBuf, mod_file: PathBuf }
+        = format!(
+            "file for module `{name}` is not found at `{}` or `{}`",
+            file.display(),
+            mod_file.display(),
+        );
+
+        /// Both possible files of the module declared as `mod NAME;` exist.
+        ///
+        /// ```notrust
+        /// geometry.sun
+        /// geometry/mod.sun
+        /// ```
+        deny [E0018] AmbiguousModuleFile { name: Identifier, file: PathBuf, mod_file: PathBuf }
+        = format!(
+            "file for module `{name}` is found at both `{}` and `{}`",
+            file.display(),
+            mod_file.display(),
+        );
+
+        /// File of the module declared as `mod NAME;` was already loaded as another module.
+        ///
+        /// That happens if modules are declared cyclically, e.g. if main file of the crate is
+        /// located in a subdirectory of the root and declared as a module of the crate again.
+        deny [E0019] ModuleFileReused { name: Identifier, previous: AbsolutePath }
+        = "file for module `{name}` is already loaded as module `{previous}`";
+
+        /// Module is declared as `mod NAME;` in the crate that has no root directory.
+        ///
+        /// That happens if the crate is read from the standard input. Such module should be
+        /// declared inline instead:
+        ///
+        /// ```notrust
+        /// mod geometry {
+        ///     fn area() -> i32 { 4 }
+        /// }
+        /// ```
+        deny [E0020] ModuleWithoutRoot { name: Identifier }
+        = "file for module `{name}` can't be looked up, as the crate is not located in a directory";
+
+        /// Expressions or modules are nested deeper than the compiler allows.
+        ///
+        /// Every parenthesis, unary operator, block, call argument and inline module adds a level
+        /// of nesting. The limit protects the compiler from running out of stack on pathological
+        /// input and may be raised with `--max-nesting` if the code is nested that deeply on
+        /// purpose. Otherwise, split the construct into several ones:
+        ///
+        /// ```notrust
+        /// let inner = ((((x))));
+        /// let outer = ((((inner))));
+        /// ```
+        deny [E0021] NestingTooDeep { limit: usize }
+        = "construct is nested more than {limit} levels deep";
+
+        /// Comparison operators are chained.
+        ///
+        /// Comparison produces `bool`, so `a < b < c` would compare the result of `a < b` with
+        /// `c`. Combine comparisons with `&&` instead, or parenthesize one of them if comparison
+        /// of booleans is intended:
+        ///
+        /// ```notrust
+        /// a < b < c ✗
+        /// a < b && b < c 🗸
+        /// (a < b) == c 🗸
+        /// ```
+        deny [E0022] ChainedComparison
+        = "comparison operators can't be chained, combine them like `a < b && b < c`";
+
+        /// Binary operator follows block-like expression in statement position.
+        ///
+        /// Block, `if`, `while` and `for` expressions at the start of a statement end it, so
+        /// the operator that follows starts the next statement. `+` and `-` are then parsed as
+        /// unary operators. Parenthesize the expression to use it as an operand instead:
+        ///
+        /// ```notrust
+        /// { 1 } - x; // block, then `-x`
+        /// ({ 1 }) - x; // subtraction
+        /// let y: i32 = { 1 } - x; // subtraction, as the block is not in statement position
+        /// ```
+        warn [E0023] OperatorAfterBlock { operator: BinaryOp }
+        = "`{operator}` after block-like expression starts a new statement, wrap the expression in parentheses to use it as an operand";
+
+        /// Attribute is not known to the compiler.
+        ///
+        /// Unknown attributes are ignored. Known ones are `derive`, `inline`, `test`, `allow`,
+        /// `warn` and `deny`.
+        warn [E0024] UnknownAttribute { path: RelativePath }
+        = "unknown attribute `{path}`";
+
+        /// Attribute is applied to an item of the wrong kind.
+        ///
+        /// ```notrust
+        /// #[derive(to_string)] struct Point { x: i32 } 🗸
+        /// #[derive(to_string)] fn run() {} ✗
+        /// ```
+        deny [E0025] MisplacedAttribute { name: Identifier, items: &'static str }
+        = "attribute `{name}` may only be applied to {items}";
+
+        /// Attribute is used with arguments of the wrong shape.
+        ///
+        /// ```notrust
+        /// #[inline] 🗸
+        /// #[inline(always)] ✗
+        /// #[allow(unreachable_code, E0203)] 🗸
+        /// #[allow] ✗
+        /// ```
+        deny [E0026] InvalidAttributeArguments { name: Identifier, expected: &'static str }
+        = "attribute `{name}` expects {expected}";
+
+        /// Lint level attribute refers to an unknown lint.
+        ///
+        /// Lints are referred to either by their code, such as `E0202`, or by their name in
+        /// snake case, such as `unreachable_code`.
+        warn [E0027] UnknownLint { name: Identifier }
+        = "unknown lint `{name}`";
+
+        /// Operator expression consists of more operators and operands than the compiler allows.
+        ///
+        /// The limit protects the compiler from running out of memory on pathological, usually
+        /// generated, input and may be raised with `--max-expression-tokens`. Otherwise, split the
+        /// expression into several ones:
+        ///
+        /// ```notrust
+        /// let first = a + b + c;
+        /// let sum = first + d + e;
+        /// ```
+        deny [E0028] ExpressionTooComplex { limit: usize }
+        = "expression too complex: it has more than {limit} operators and operands";
+
+        /// Item is declared where a value is expected.
+        ///
+        /// Functions, structs and modules are not expressions, so they can't be assigned to
+        /// variables or passed as arguments. Declare the item as a statement of the block, then
+        /// refer to it by name:
+        ///
+        /// ```notrust
+        /// let x = fn double(a: i32) -> i32 { a * 2 }; ✗
+        ///
+        /// fn double(a: i32) -> i32 { a * 2 } 🗸
+        /// let x = double(1); 🗸
+        /// ```
+        deny [E0029] ItemInExpression { item: &'static str }
+        = "items are not expressions; declare the {item} before using it";
+
+        /// Struct declares multiple fields with the same name.
+        ///
+        /// Only the first field is kept.
+        ///
+        /// ```notrust
+        /// struct Point { x: i32, x: i32 } ✗
+        /// struct Point { x: i32, y: i32 } 🗸
+        /// ```
+        deny [E0030] DuplicateField { name: Identifier }
+        = "field `{name}` is declared multiple times";
+
+        /// Function declares multiple parameters with the same name.
+        ///
+        /// ```notrust
+        /// fn add(a: i32, a: i32) -> i32 ✗
+        /// fn add(a: i32, b: i32) -> i32 🗸
+        /// ```
+        deny [E0031] DuplicateParameter { name: Identifier }
+        = "parameter `{name}` is declared multiple times";
+
+        /// Parenthesized expression contains a comma, as if it was a tuple.
+        ///
+        /// Tuple types may be used in signatures, but values of them can't be constructed yet.
+        ///
+        /// ```notrust
+        /// let t: (i32, bool) = (1, true); ✗
+        /// ```
+        deny [E0032] TupleExpression = "tuple expressions are not supported";
     }
 }
 
@@ -83,13 +365,16 @@ pub mod parser {
 pub mod lexer {
     use itertools::Itertools;
 
-    use crate::{error::ExpectedToken, lexer::Token};
+    use crate::{
+        error::ExpectedToken,
+        lexer::{keyword::Keyword, Token},
+    };
 
     define_error! {
         /// Token mismatch occured.
-        deny TokenMismatch { expected: Vec<ExpectedToken>, found: Token }
+        deny [E0101] TokenMismatch { expected: Vec<ExpectedToken>, found: Token }
         = match expected.as_slice() {
-            [] => panic!("empty token mismatch error"),
+            [] => format!("unexpected {}", found.pretty_print()),
             [expected] => format!("expected {expected}, found {}", found.pretty_print()),
             [expected1, expected2] => format!("expected {expected1} or {expected2}, found {}", found.pretty_print()),
             [expected @ .., last] => format!(
@@ -103,41 +388,394 @@ pub mod lexer {
         };
 
         /// String literal wasn't terminated.
-        deny UnterminatedString = "string literal wasn't terminated";
+        deny [E0102] UnterminatedString = "string literal wasn't terminated";
 
         /// Invalid identifier.
         ///
         /// identifier must contain only ascii alphanumeric and underscore characters.
-        deny InvalidIdentifier = "identifier must contain only ascii alphanumeric and underscore characters";
+        deny [E0103] InvalidIdentifier = "identifier must contain only ascii alphanumeric and underscore characters";
 
         /// Invalid escape sentence in string.
-        deny InvalidEscape = "invalid escape sentence";
+        deny [E0104] InvalidEscape = "invalid escape sentence";
 
         /// Parsed number is invalid.
-        deny InvalidNumber = "invalid number";
+        deny [E0105] InvalidNumber = "invalid number";
 
         /// Valid punctuation sequence found, but it is unknown to the compiler.
-        deny UnknownPunctuation { found: String }
+        deny [E0106] UnknownPunctuation { found: String }
         = "`{found}` is not a valid punctuation";
 
         /// Character not expected.
         ///
         /// Only ASCII is supported as the moment.
-        deny UnexpectedCharacter { ch: char }
+        deny [E0107] UnexpectedCharacter { ch: char }
         = "character `{ch}` wasn't expected";
 
         /// End of file wasn't expected.
-        deny UnexpectedEOF = "unexpected EOF";
+        deny [E0108] UnexpectedEOF = "unexpected EOF";
+
+        /// Number literal has a base prefix, but no digits of that base follow it.
+        ///
+        /// ```notrust
+        /// 0x ✗
+        /// 0b2 ✗
+        /// 0x0 🗸
+        /// ```
+        deny [E0109] MissingDigits { prefix: &'static str }
+        = "missing digits after `{prefix}` prefix";
+
+        /// Number literal with fractional part is written in binary, octal or hexadecimal base.
+        ///
+        /// Only decimal float literals are supported.
+        ///
+        /// ```notrust
+        /// 0x1.8 ✗
+        /// 1.5 🗸
+        /// ```
+        deny [E0110] NonDecimalFloat { base: &'static str, text: String }
+        = "{base} float literals are not supported, found `{text}`";
+
+        /// Keyword is used as a name.
+        ///
+        /// Keywords, including `true`, `false` and `self`, are reserved and can't name variables,
+        /// parameters, fields or items.
+        ///
+        /// ```notrust
+        /// let true = 5; ✗
+        /// fn false() {} ✗
+        /// let truth = true; 🗸
+        /// ```
+        deny [E0111] KeywordAsIdentifier { kw: Keyword }
+        = "expected identifier, found keyword `{kw}`";
     }
 }
 
 /// Errors issued by HIR translation.
 pub mod hir {
-    use crate::hir::types::TypeId;
+    use crate::{
+        error::{library::ErrorInfo, Severity},
+        path::AbsolutePath,
+        Identifier,
+    };
 
     define_error! {
         /// Types don't match.
-        deny TypeMismatch { expected: Option<TypeId>, found: Option<TypeId> }
-        = "types don't match. Expected to get {expected:?}, got {found:?}";
+        ///
+        /// Types are rendered by [TypeTable::display](crate::hir::types::TypeTable::display).
+        deny [E0201] TypeMismatch { expected: String, found: String }
+        = "mismatched types: expected `{expected}`, found `{found}`";
+
+        /// Code after `return` or `break` is never executed.
+        warn [E0202] UnreachableCode = "unreachable code";
+
+        /// Variable is declared again in the same scope.
+        ///
+        /// Previous variable can't be accessed by its name afterwards.
+        warn [E0203] ShadowedVariable { name: Identifier }
+        = "variable `{name}` shadows the one declared earlier in the same scope";
+
+        /// Function is never called from the entry point of the crate or its public functions.
+        warn [E0229] DeadFunction { path: AbsolutePath }
+        = "function `{path}` is never used";
+    }
+
+    /// Descriptions of [TranslationError](crate::hir::TranslationError) variants.
+    pub const TRANSLATION_ERRORS: &[ErrorInfo] = &[
+        ErrorInfo {
+            code: "E0204",
+            name: "TypeInference",
+            severity: Severity::Deny,
+            doc: "Type annotation is missing.\n\nType inference is not implemented yet, so every variable binding requires type annotation.\n",
+        },
+        ErrorInfo {
+            code: "E0205",
+            name: "TypeMismatch",
+            severity: Severity::Deny,
+            doc: "Expression has type different from the expected one.\n",
+        },
+        ErrorInfo {
+            code: "E0206",
+            name: "IfBranchTypeMismatch",
+            severity: Severity::Deny,
+            doc: "`if` and `else` branches have different types.\n",
+        },
+        ErrorInfo {
+            code: "E0207",
+            name: "ArgumentCountMismatch",
+            severity: Severity::Deny,
+            doc: "Function is called with incorrect number of arguments.\n",
+        },
+        ErrorInfo {
+            code: "E0208",
+            name: "VariableNotDeclared",
+            severity: Severity::Deny,
+            doc: "Variable is used before its declaration.\n",
+        },
+        ErrorInfo {
+            code: "E0209",
+            name: "FunctionNotFound",
+            severity: Severity::Deny,
+            doc: "Called function is not found.\n",
+        },
+        ErrorInfo {
+            code: "E0210",
+            name: "MissingReturn",
+            severity: Severity::Deny,
+            doc: "Function doesn't return a value on every path.\n",
+        },
+        ErrorInfo {
+            code: "E0211",
+            name: "InvalidSignature",
+            severity: Severity::Deny,
+            doc: "Function can't be called, as its signature contains errors.\n",
+        },
+        ErrorInfo {
+            code: "E0212",
+            name: "InvalidBreak",
+            severity: Severity::Deny,
+            doc: "`break` is used outside of the loop.\n",
+        },
+        ErrorInfo {
+            code: "E0213",
+            name: "NonConstantExpression",
+            severity: Severity::Deny,
+            doc: "Constant initializer isn't a constant expression.\n\nOnly numeric and boolean literals and operators applied to them are allowed.\n",
+        },
+        ErrorInfo {
+            code: "E0214",
+            name: "ConstOverflow",
+            severity: Severity::Deny,
+            doc: "Computation of constant value overflowed.\n",
+        },
+        ErrorInfo {
+            code: "E0215",
+            name: "ConstDivisionByZero",
+            severity: Severity::Deny,
+            doc: "Constant expression divides by zero.\n",
+        },
+        ErrorInfo {
+            code: "E0216",
+            name: "InvalidImplType",
+            severity: Severity::Deny,
+            doc: "Implementation block is declared for a type that is not a struct from the same module.\n",
+        },
+        ErrorInfo {
+            code: "E0217",
+            name: "TypeError",
+            severity: Severity::Deny,
+            doc: "Type is invalid, e.g. it is not found or has infinite size.\n",
+        },
+        ErrorInfo {
+            code: "E0218",
+            name: "UnresolvedPath",
+            severity: Severity::Deny,
+            doc: "Path refers to a parent of the crate root with too many `super` segments.\n",
+        },
+        ErrorInfo {
+            code: "E0219",
+            name: "ConstNotFound",
+            severity: Severity::Deny,
+            doc: "Path used as a value doesn't refer to a constant.\n\nOnly constants may be referred to by paths, while variables are referred to by their names.\n",
+        },
+        ErrorInfo {
+            code: "E0220",
+            name: "NotIterable",
+            severity: Severity::Deny,
+            doc: "Value iterated over by `for` loop is not iterable.\n\nOnly ranges, such as `0..len`, and arrays may be iterated over at the moment.\n",
+        },
+        ErrorInfo {
+            code: "E0221",
+            name: "RangeOutsideFor",
+            severity: Severity::Deny,
+            doc: "Range is used as a value.\n\nRanges don't have a type of their own, so they may only be iterated over by `for` loop.\n",
+        },
+        ErrorInfo {
+            code: "E0222",
+            name: "FieldNotFound",
+            severity: Severity::Deny,
+            doc: "Accessed field is not declared by the struct.\n\nFields may only be accessed on values of struct types.\n",
+        },
+        ErrorInfo {
+            code: "E0223",
+            name: "NotIndexable",
+            severity: Severity::Deny,
+            doc: "Value that is not an array is indexed.\n\nOnly elements of arrays may be accessed by index, which must be an integer.\n",
+        },
+        ErrorInfo {
+            code: "E0224",
+            name: "InvalidCast",
+            severity: Severity::Deny,
+            doc: "Value is cast to a type it can't be converted to.\n\nNumbers may be cast to any numeric type and booleans may be cast to integers, other casts are not allowed.\n",
+        },
+        ErrorInfo {
+            code: "E0225",
+            name: "InvalidCondition",
+            severity: Severity::Deny,
+            doc: "Condition of `if` or `while` expression is not a boolean.\n\nIntegers and other values are not implicitly converted to `bool`, compare them explicitly instead.\n",
+        },
+        ErrorInfo {
+            code: "E0226",
+            name: "DivisionByZero",
+            severity: Severity::Deny,
+            doc: "Integer is divided by zero.\n\nDivision and remainder of integers by zero are never valid. Floats may be divided by zero, producing infinity or NaN.\n",
+        },
+        ErrorInfo {
+            code: "E0227",
+            name: "InvalidLiteral",
+            severity: Severity::Deny,
+            doc: "Numeric literal doesn't fit into its type.\n\nInteger literal is out of the range of its integer type, or literal with fractional part is used as an integer.\n",
+        },
+        ErrorInfo {
+            code: "E0228",
+            name: "BuiltinTypeRedefined",
+            severity: Severity::Deny,
+            doc: "Struct is declared with the name of builtin type.\n\nNames of primitive types such as `bool` or `i32` are available in every module and may not be redefined.\n",
+        },
+        ErrorInfo {
+            code: "E0230",
+            name: "UninitializedVariable",
+            severity: Severity::Deny,
+            doc: "Variable declared without a value is used before it is assigned on some path.\n\nVariable assigned in only one branch of `if` or in the body of a loop, which may not be executed at all, is not considered assigned afterwards.\n",
+        },
+        ErrorInfo {
+            code: "E0231",
+            name: "VariableCalled",
+            severity: Severity::Deny,
+            doc: "Local variable is called like a function.\n",
+        },
+        ErrorInfo {
+            code: "E0232",
+            name: "StructCalled",
+            severity: Severity::Deny,
+            doc: "Struct is called like a function.\n",
+        },
+        ErrorInfo {
+            code: "E0233",
+            name: "ModuleCalled",
+            severity: Severity::Deny,
+            doc: "Module is called like a function.\n",
+        },
+        ErrorInfo {
+            code: "E0234",
+            name: "SelfOutsideImpl",
+            severity: Severity::Deny,
+            doc: "Function that isn't declared in an implementation block takes `self` parameter.\n\nOnly methods of a type may take `self`, which has the type of the implementation block.\n",
+        },
+        ErrorInfo {
+            code: "E0235",
+            name: "MethodNotFound",
+            severity: Severity::Deny,
+            doc: "Method called with `value.method()` syntax is not declared for the type of the value.\n",
+        },
+        ErrorInfo {
+            code: "E0236",
+            name: "NotAMethod",
+            severity: Severity::Deny,
+            doc: "Associated function without `self` receiver is called with `value.method()` syntax.\n\nSuch functions are called through the path of the type instead, such as `Point::new()`.\n",
+        },
+        ErrorInfo {
+            code: "E0237",
+            name: "MissingReceiver",
+            severity: Severity::Deny,
+            doc: "Method is called through the path of the type without the receiver.\n\nWhen method is called as `Type::method(..)`, the receiver must be passed explicitly as the first argument.\n",
+        },
+        ErrorInfo {
+            code: "E0238",
+            name: "OperatorNotImplemented",
+            severity: Severity::Deny,
+            doc: "Operator is applied to a struct that doesn't implement it.\n\nOperators `+`, `-`, `*`, `/` and `==` are implemented for a struct by methods `add`, `sub`, `mul`, `div` and `eq` respectively, that take `self` and one more parameter. Method `eq` must return `bool`, and `a != b` is the same as `!a.eq(b)`.\n",
+        },
+        ErrorInfo {
+            code: "E0239",
+            name: "CaptureInNestedFunction",
+            severity: Severity::Deny,
+            doc: "Function declared in the body of another function uses a variable of the enclosing function.\n\nNested functions don't capture variables, so values must be passed to them as parameters.\n",
+        },
+        ErrorInfo {
+            code: "E0240",
+            name: "NestedFunctionOutOfScope",
+            severity: Severity::Deny,
+            doc: "Function declared in the body of another function is called outside of it.\n\nItems declared in the body of a function may only be referenced by name inside of that function, including functions nested in it.\n",
+        },
+        ErrorInfo {
+            code: "E0241",
+            name: "UnknownDerive",
+            severity: Severity::Deny,
+            doc: "Function listed in `#[derive(..)]` attribute can't be derived.\n\nOnly `to_string` may be derived so far.\n\n```notrust\n#[derive(to_string)] 🗸\n#[derive(clone)] ✗\n```\n",
+        },
+        ErrorInfo {
+            code: "E0242",
+            name: "UnsupportedDeriveField",
+            severity: Severity::Deny,
+            doc: "Function can't be derived for a struct, as one of its fields doesn't support it.\n\n`to_string` may be derived if every field is an integer, `bool`, `str` or a struct that derives `to_string` itself.\n",
+        },
+        ErrorInfo {
+            code: "E0243",
+            name: "DerivedFunctionRedefined",
+            severity: Severity::Deny,
+            doc: "Function listed in `#[derive(..)]` attribute is also declared in an implementation block of the struct.\n\nRemove either the declaration or the name from the attribute.\n",
+        },
+        ErrorInfo {
+            code: "E0244",
+            name: "InvalidTestSignature",
+            severity: Severity::Deny,
+            doc: "Function annotated with `#[test]` can't be called by the test runner.\n\nTests take no parameters and either return nothing or `bool`, where `false` means that the test failed.\n\n```notrust\n#[test] fn sum() -> bool { 2 + 2 == 4 } 🗸\n#[test] fn sum(a: i32) -> bool { a + a == 4 } ✗\n```\n",
+        },
+        ErrorInfo {
+            code: "E0245",
+            name: "UnsupportedOperator",
+            severity: Severity::Deny,
+            doc: "Operator is applied to a struct, but it can't be implemented for structs.\n\nOnly operators `+`, `-`, `*`, `/`, `==` and `!=` may be implemented, see E0238.\n",
+        },
+    ];
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::{explain, lint, parser::ExpectedItem, registry};
+
+    #[test]
+    fn unique_codes() {
+        let mut codes = HashSet::new();
+        for info in registry() {
+            assert!(
+                codes.insert(info.code),
+                "duplicate error code {}",
+                info.code
+            );
+        }
+    }
+
+    #[test]
+    fn explanation() {
+        let info = explain("E0007").unwrap();
+        assert_eq!(info.name, "InvalidAssignee");
+        assert_eq!(
+            info.explanation(),
+            "Invalid assignee was used in assignment statement.
+
+Variables, their fields and elements are the only valid assignees.
+
+```notrust
+5 = 6; ✗
+f() = 6; ✗
+x = 6; 🗸
+point.x = 6; 🗸
+values[0] = 6; 🗸
+```"
+        );
+        assert_eq!(explain(ExpectedItem::CODE).unwrap().name, "ExpectedItem");
+        assert_eq!(explain("e0212").unwrap().name, "InvalidBreak");
+        assert!(explain("E9999").is_none());
+    }
+
+    #[test]
+    fn lint_names() {
+        assert_eq!(lint("unreachable_code").unwrap().code, "E0202");
+        assert_eq!(lint("e0203").unwrap().name, "ShadowedVariable");
+        assert!(lint("UnreachableCode").is_none());
+        assert!(lint("unused_variable").is_none());
     }
 }
@@ -4,13 +4,13 @@ macro_rules! define_error {
     (
         $(
             $(#[doc = $doc:expr])*
-            $severity:ident $name:ident
+            $severity:ident [$code:ident] $name:ident
             $({$($field:ident: $type:ty),*})?
             = $message:expr
             $(=> $into:ty = $into_by:expr)*
             ;
         )*
-    ) => ($(
+    ) => {$(
         $(#[doc = $doc])*
         #[derive(Debug)]
         pub struct $name {
@@ -21,6 +21,9 @@ macro_rules! define_error {
         }
 
         impl $name {
+            /// Stable code of the error.
+            pub const CODE: &'static str = stringify!($code);
+
             pub fn report(
                 provider: &impl crate::error::ReportProvider,
                 start: crate::input_stream::Location,
@@ -37,6 +40,56 @@ macro_rules! define_error {
                 provider.error_reporter().report(error);
                 Err(crate::error::CompilerError)
             }
+
+            /// Reports error with already known `span`.
+            #[allow(dead_code)]
+            pub fn report_at(
+                reporter: &crate::error::ErrorReporter,
+                span: crate::util::Span,
+                $($($field: $type,)*)?
+            ) -> Result<std::convert::Infallible, crate::error::CompilerError> {
+                reporter.report(Self {
+                    span,
+                    $($($field,)*)?
+                });
+                Err(crate::error::CompilerError)
+            }
+
+            /// Reports error with already known `span` along with a `suggestion` how to fix it.
+            #[allow(dead_code)]
+            pub fn report_with(
+                reporter: &crate::error::ErrorReporter,
+                span: crate::util::Span,
+                suggestion: crate::error::Suggestion,
+                $($($field: $type,)*)?
+            ) -> Result<std::convert::Infallible, crate::error::CompilerError> {
+                reporter.report_with(
+                    Self {
+                        span,
+                        $($($field,)*)?
+                    },
+                    suggestion,
+                );
+                Err(crate::error::CompilerError)
+            }
+
+            /// Reports error with already known `span` along with `labels` of the related code.
+            #[allow(dead_code)]
+            pub fn report_labeled(
+                reporter: &crate::error::ErrorReporter,
+                span: crate::util::Span,
+                labels: Vec<crate::error::Label>,
+                $($($field: $type,)*)?
+            ) -> Result<std::convert::Infallible, crate::error::CompilerError> {
+                reporter.report_labeled(
+                    Self {
+                        span,
+                        $($($field,)*)?
+                    },
+                    labels,
+                );
+                Err(crate::error::CompilerError)
+            }
         }
 
         impl crate::error::ReportableError for $name {
@@ -44,8 +97,12 @@ macro_rules! define_error {
                 severity!($severity)
             }
 
-            fn span(&self) -> crate::util::Span {
-                self.span
+            fn code(&self) -> &'static str {
+                Self::CODE
+            }
+
+            fn span(&self) -> Option<crate::util::Span> {
+                Some(self.span)
             }
         }
 
@@ -65,7 +122,18 @@ macro_rules! define_error {
                 }
             }
         )*
-    )*)
+    )*
+
+    /// Descriptions of all errors defined in this module.
+    pub const ERRORS: &[crate::error::library::ErrorInfo] = &[$(
+        crate::error::library::ErrorInfo {
+            code: stringify!($code),
+            name: stringify!($name),
+            severity: severity!($severity),
+            doc: concat!($($doc, "\n",)*),
+        },
+    )*];
+    }
 }
 
 macro_rules! severity {
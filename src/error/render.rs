@@ -0,0 +1,479 @@
+//! Rendering of reported errors in a human-readable form.
+//!
+//! Errors are printed in a way similar to rustc: header with severity and message, location of
+//! the error and a snippet of the source code with the offending part underlined.
+
+use std::{
+    fmt::Display,
+    io::{Result, Write},
+};
+
+use crate::{
+    error::{Diagnostic, Severity, Suggestion},
+    source::SourceMap,
+    util::{LineIndex, Span},
+};
+
+/// Writes `diagnostic` with a snippet of the code it refers to, followed by its suggestion.
+///
+/// Snippet is only written if the diagnostic has a span and its file is known. ANSI escape codes
+/// are used to highlight the output if `colored` is set. Tabs in the code are replaced with
+/// `tab_width` spaces.
+pub fn render_error(
+    mut w: impl Write,
+    diagnostic: &Diagnostic,
+    source_map: &mut SourceMap,
+    colored: bool,
+    tab_width: usize,
+) -> Result<()> {
+    let palette = Palette { colored };
+    let (label, color) = match diagnostic.severity {
+        Severity::Warn => ("warning", Palette::YELLOW),
+        Severity::Deny => ("error", Palette::RED),
+    };
+    writeln!(
+        w,
+        "{}{}",
+        palette.paint(color, format!("{label}[{}]", diagnostic.code)),
+        palette.paint(Palette::BOLD, format!(": {}", diagnostic.message))
+    )?;
+    if let Some(span) = diagnostic.span {
+        let mut annotations = vec![Annotation {
+            span,
+            marker: '^',
+            color,
+            message: diagnostic.label.as_deref(),
+        }];
+        annotations.extend(diagnostic.secondary_labels.iter().map(|label| Annotation {
+            span: label.span,
+            marker: '-',
+            color: Palette::BLUE,
+            message: Some(&label.message),
+        }));
+        render_snippet(&mut w, &annotations, source_map, palette, tab_width)?;
+    }
+    if let Some(suggestion) = &diagnostic.suggestion {
+        render_suggestion(&mut w, suggestion, source_map, palette, tab_width)?;
+    }
+    Ok(())
+}
+
+/// Code underlined in the snippet, optionally explained by a message.
+struct Annotation<'a> {
+    span: Span,
+    marker: char,
+    color: &'a str,
+    message: Option<&'a str>,
+}
+
+/// Writes snippets of the code covered by `annotations`, the first of which is the primary one.
+///
+/// Annotations of each file are shown together, starting with the file of the primary one.
+fn render_snippet(
+    mut w: impl Write,
+    annotations: &[Annotation],
+    source_map: &mut SourceMap,
+    palette: Palette,
+    tab_width: usize,
+) -> Result<()> {
+    let mut files = Vec::new();
+    for annotation in annotations {
+        if !files.contains(&annotation.span.source) {
+            files.push(annotation.span.source);
+        }
+    }
+    for (i, file) in files.into_iter().enumerate() {
+        let annotations: Vec<_> = annotations
+            .iter()
+            .filter(|annotation| annotation.span.source == file)
+            .collect();
+        let arrow = if i == 0 { "-->" } else { ":::" };
+        render_file_snippet(&mut w, &annotations, source_map, palette, arrow, tab_width)?;
+    }
+    Ok(())
+}
+
+/// Writes location of the first of `annotations` from the same file, marked by `arrow`, and
+/// the lines they cover with the code underlined.
+fn render_file_snippet(
+    mut w: impl Write,
+    annotations: &[&Annotation],
+    source_map: &mut SourceMap,
+    palette: Palette,
+    arrow: &str,
+    tab_width: usize,
+) -> Result<()> {
+    let location = annotations[0].span.start;
+    let Some(file) = annotations[0].span.source else {
+        return writeln!(w, " {} {location}", palette.paint(Palette::BLUE, arrow));
+    };
+    let path = source_map.get_path(file).to_string_lossy().into_owned();
+    // Every line covered by the annotations, along with the annotation and whether its message
+    // should be shown there.
+    let mut lines = Vec::new();
+    if let Ok(index) = source_map.get(file).line_index() {
+        for annotation in annotations {
+            let snippet = snippet(index, annotation.span, tab_width);
+            let count = snippet.len();
+            for (i, line) in snippet.into_iter().enumerate() {
+                lines.push((line, *annotation, i + 1 == count));
+            }
+        }
+    }
+    lines.sort_by_key(|(line, ..)| (line.number, line.padding.len()));
+
+    let width = lines
+        .iter()
+        .map(|(line, ..)| (line.number + 1).to_string().len())
+        .max()
+        .unwrap_or(1);
+    let gutter = palette.paint(Palette::BLUE, format!("{:width$} |", ""));
+    writeln!(
+        w,
+        "{:width$}{} {path}:{location}",
+        "",
+        palette.paint(Palette::BLUE, arrow),
+    )?;
+    if lines.is_empty() {
+        return Ok(());
+    }
+    writeln!(w, "{gutter}")?;
+    for (i, (line, annotation, last)) in lines.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| lines[i].0.number);
+        if previous != Some(line.number) {
+            if previous.is_some_and(|previous| line.number > previous + 1) {
+                writeln!(w, "{}", palette.paint(Palette::BLUE, "..."))?;
+            }
+            let number = palette.paint(Palette::BLUE, format!("{:>width$} |", line.number + 1));
+            writeln!(w, "{number} {}", line.text)?;
+        }
+        let underline = annotation.marker.to_string().repeat(line.underline);
+        let message = match annotation.message {
+            Some(message) if *last => format!("{underline} {message}"),
+            _ => underline,
+        };
+        writeln!(
+            w,
+            "{gutter} {}{}",
+            line.padding,
+            palette.paint(annotation.color, message)
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes `suggestion` with the line of the code it changes, if the change fits into that line.
+///
+/// Inserted text is marked with `+`, replaced text is marked with `~`.
+fn render_suggestion(
+    mut w: impl Write,
+    suggestion: &Suggestion,
+    source_map: &mut SourceMap,
+    palette: Palette,
+    tab_width: usize,
+) -> Result<()> {
+    writeln!(
+        w,
+        "{}: {}",
+        palette.paint(Palette::BOLD, "help"),
+        suggestion.message
+    )?;
+    let span = suggestion.span;
+    let line = match span.source {
+        Some(file) if span.start.line == span.end.line => {
+            source_map.get(file).line(span.start.line)
+        }
+        _ => None,
+    };
+    let Some(line) = line else {
+        return Ok(());
+    };
+
+    let chars: Vec<char> = line.chars().collect();
+    let from = span.start.column.min(chars.len());
+    let to = span.end.column.clamp(from, chars.len());
+    let prefix = expand_tabs(chars[..from].iter().copied(), tab_width);
+    let suffix = expand_tabs(chars[to..].iter().copied(), tab_width);
+    let padding = " ".repeat(prefix.chars().count());
+    let marker = if from == to { "+" } else { "~" };
+
+    let number = (span.start.line + 1).to_string();
+    let width = number.len();
+    let gutter = palette.paint(Palette::BLUE, format!("{:width$} |", ""));
+    writeln!(w, "{gutter}")?;
+    writeln!(
+        w,
+        "{} {prefix}{}{suffix}",
+        palette.paint(Palette::BLUE, format!("{number} |")),
+        suggestion.replacement
+    )?;
+    writeln!(
+        w,
+        "{gutter} {padding}{}",
+        palette.paint(
+            Palette::GREEN,
+            marker.repeat(suggestion.replacement.chars().count().max(1))
+        )
+    )
+}
+
+/// Line of the source code shown in the snippet.
+struct SnippetLine {
+    /// Zero-based number of the line.
+    number: usize,
+    /// Text of the line with tabs expanded.
+    text: String,
+    /// Whitespace preceding the underline.
+    padding: String,
+    /// Length of the underline.
+    underline: usize,
+}
+
+/// Collects lines of the code indexed by `index` that are covered by `span`.
+///
+/// Only the first and the last lines are collected for multi-line spans.
+fn snippet(index: &LineIndex, span: Span, tab_width: usize) -> Vec<SnippetLine> {
+    let (start, mut end) = (span.start, span.end);
+    // Span that ends right after a newline doesn't really cover the following line.
+    if end.line > start.line && end.column == 0 {
+        end.line -= 1;
+        end.column = index.line(end.line).map_or(0, |line| line.chars().count());
+    }
+    let Some(first) = index.line(start.line) else {
+        return Vec::new();
+    };
+
+    let line = |number: usize, from: usize, to: usize| {
+        let chars = || index.line(number).unwrap_or_default().chars();
+        let padding = expand_tabs(chars().take(from), tab_width).chars().count();
+        let underline = expand_tabs(chars().take(to).skip(from), tab_width)
+            .chars()
+            .count();
+        SnippetLine {
+            number,
+            text: expand_tabs(chars(), tab_width),
+            padding: " ".repeat(padding),
+            underline: underline.max(1),
+        }
+    };
+
+    if end.line <= start.line {
+        return vec![line(start.line, start.column, end.column)];
+    }
+    let mut snippet = vec![line(start.line, start.column, first.chars().count())];
+    if let Some(last) = index.line(end.line) {
+        let indent = last.chars().take_while(|ch| ch.is_whitespace()).count();
+        snippet.push(line(end.line, indent, end.column));
+    }
+    snippet
+}
+
+/// Collects `chars` into string, replacing every tab with `tab_width` spaces.
+fn expand_tabs(chars: impl Iterator<Item = char>, tab_width: usize) -> String {
+    let mut text = String::new();
+    for ch in chars {
+        match ch {
+            '\t' => text.push_str(&" ".repeat(tab_width)),
+            ch => text.push(ch),
+        }
+    }
+    text
+}
+
+/// Set of ANSI escape codes used to highlight the output.
+#[derive(Debug, Clone, Copy)]
+struct Palette {
+    colored: bool,
+}
+
+impl Palette {
+    const BOLD: &'static str = "1";
+    const RED: &'static str = "1;31";
+    const GREEN: &'static str = "1;32";
+    const YELLOW: &'static str = "1;33";
+    const BLUE: &'static str = "1;34";
+
+    /// Wraps `text` into escape code if colors are enabled.
+    fn paint(self, code: &str, text: impl Display) -> String {
+        if self.colored {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::{
+        context::{Context, Emit},
+        hir::HirBuilder,
+        input_stream::Location,
+        parser::Parser,
+        util::{LineIndex, Span},
+        Identifier,
+    };
+
+    use super::snippet;
+
+    fn render(fixture: &str, colored: bool) -> String {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let main = dir.join(fixture);
+        let context = Context::builder()
+            .crate_name(Identifier(String::from("diagnostics")))
+            .main(main)
+            .emit(Emit::Hir)
+            .build()
+            .unwrap();
+        let mut parser = Parser::new(context.clone());
+        if let Ok((table, _)) = parser.parse() {
+            let mut builder = HirBuilder::new(context.clone());
+            builder.populate(&table);
+            let _ = builder.build();
+        }
+
+        let mut output = Vec::new();
+        context.error_reporter.render(&mut output, colored).unwrap();
+        let prefix = format!("{}/", dir.display());
+        String::from_utf8(output).unwrap().replace(&prefix, "")
+    }
+
+    #[test]
+    fn snapshot() {
+        assert_eq!(
+            render("diagnostics.sun", false),
+            "\
+warning[E0202]: unreachable code
+ --> diagnostics.sun:3:5
+  |
+3 |     let unused: i32 = 0;
+  |     ^^^^^^^^^^^^^^^^^^^^
+
+error[E0205]: mismatched types: expected `i32`, found `bool`
+ --> diagnostics.sun:7:9
+  |
+7 |     x + true
+  |     - expected due to the type of this operand
+  |         ^^^^ expected `i32`, found `bool`
+
+error[E0210]: function must return a value of type `i32` on every path
+  --> diagnostics.sun:10:1
+   |
+10 | fn missing(flag: bool) -> i32 {
+   | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+...
+14 | }
+   | ^
+
+1 warning(s), 2 error(s)
+"
+        );
+    }
+
+    #[test]
+    fn secondary_label() {
+        assert_eq!(
+            render("argument_type.sun", false),
+            "\
+error[E0205]: mismatched types: expected `i32`, found `bool`
+ --> argument_type.sun:6:14
+  |
+1 | fn scale(value: i32, factor: i32) -> i32 {
+  |                      ----------- parameter declared here
+...
+6 |     scale(2, true)
+  |              ^^^^ expected `i32`, found `bool`
+
+0 warning(s), 1 error(s)
+"
+        );
+    }
+
+    #[test]
+    fn struct_called() {
+        assert_eq!(
+            render("struct_called.sun", false),
+            "\
+error[E0232]: diagnostics::Point is a struct, not a function
+ --> struct_called.sun:7:5
+  |
+7 |     Point(0, 0);
+  |     ^^^^^^^^^^^
+
+0 warning(s), 1 error(s)
+"
+        );
+    }
+
+    #[test]
+    fn colors() {
+        let output = render("diagnostics.sun", true);
+        assert!(output.contains("\x1b[1;31merror[E0205]\x1b[0m"));
+        assert!(output.contains("\x1b[1;33mwarning[E0202]\x1b[0m"));
+    }
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(
+            render("suggestions.sun", false),
+            "\
+error[E0101]: expected `;`, found `x`
+ --> suggestions.sun:2:19
+  |
+2 |     let x: i32 = 1
+  |                   ^
+help: add `;` here
+  |
+2 |     let x: i32 = 1;
+  |                   +
+
+error[E0003]: assignment in expression position
+ --> suggestions.sun:7:8
+  |
+7 |     if x = 1 {}
+  |        ^^^^^
+help: use `==` to compare values
+  |
+7 |     if x == 1 {}
+  |          ~~
+
+error[E0001]: expected an item
+  --> suggestions.sun:10:1
+   |
+10 | fnn third() {}
+   | ^^^
+help: did you mean `fn`?
+   |
+10 | fn third() {}
+   | ~~
+
+0 warning(s), 3 error(s)
+"
+        );
+    }
+
+    #[test]
+    fn tabs() {
+        let start = Location {
+            line: 1,
+            column: 1,
+            ..Location::default()
+        };
+        let mut end = start;
+        end.column = 9;
+        let span = Span {
+            source: None,
+            start,
+            end,
+        };
+        let lines = snippet(&LineIndex::new(String::from("fn run() {\n\tx\t+ true\n}")), span, 4);
+        let [line] = lines.as_slice() else {
+            panic!("snippet should contain a single line");
+        };
+        assert_eq!(line.text, "    x    + true");
+        assert_eq!(line.padding, "    ");
+        assert_eq!(line.underline, 11);
+    }
+}
@@ -3,17 +3,35 @@
 //! AST to HIR translation includes type checking and desugaring.
 
 mod builder;
+pub mod callgraph;
+pub mod fold;
+pub mod interp;
+pub mod intrinsic;
+pub mod layout;
+pub mod pretty_print;
 pub mod scope;
+pub mod test_runner;
 pub mod types;
+pub mod validate;
 
-pub use builder::{HirBuilder, TranslationError};
+pub use builder::{Expectation, HirBuilder, TranslationError};
+pub use validate::{validate, ValidationError};
+
+use std::collections::HashMap;
 
 use crate::{
     ast::expression::Literal,
     lexer::operator::{BinaryOp, UnaryOp},
+    path::AbsolutePath,
+    util::Span,
+    Identifier,
 };
 
+#[cfg(test)]
+use crate::{context::Context, parser::FileParser};
+
 use self::{
+    intrinsic::Intrinsic,
     scope::VarId,
     types::{TypeId, TypeTable},
 };
@@ -21,33 +39,113 @@ use self::{
 #[derive(Debug, Default)]
 pub struct Hir {
     type_table: TypeTable,
-    functions: Vec<Function>,
+    mapping: HashMap<AbsolutePath, FunctionId>,
+    /// Functions indexed by [FunctionId], `None` if it failed to translate.
+    functions: Vec<Option<Function>>,
+    /// Public functions declared at the root of the crate.
+    exported: Vec<FunctionId>,
+    /// Functions annotated with `#[test]`.
+    tests: Vec<FunctionId>,
 }
 
 impl Hir {
     pub fn get_function(&self, id: FunctionId) -> Option<&Function> {
-        self.functions.get(id.0 as usize)
+        self.functions.get(id.0 as usize)?.as_ref()
+    }
+
+    /// Looks up id of the function declared at `path`.
+    pub fn function_id(&self, path: &AbsolutePath) -> Option<FunctionId> {
+        self.mapping.get(path).copied()
+    }
+
+    /// Looks up path of the function with `id`.
+    pub fn function_path(&self, id: FunctionId) -> Option<&AbsolutePath> {
+        self.mapping
+            .iter()
+            .find_map(|(path, function)| (*function == id).then_some(path))
+    }
+
+    /// Paths and ids of all declared functions, in arbitrary order.
+    pub fn function_paths(&self) -> impl Iterator<Item = (&AbsolutePath, FunctionId)> {
+        self.mapping.iter().map(|(path, id)| (path, *id))
+    }
+
+    /// Public functions declared at the root of the crate, that may be called from outside of it.
+    pub fn exported(&self) -> &[FunctionId] {
+        &self.exported
+    }
+
+    /// Functions annotated with `#[test]`, that take no parameters and return `()` or `bool`.
+    pub fn tests(&self) -> &[FunctionId] {
+        &self.tests
+    }
+
+    pub fn type_table(&self) -> &TypeTable {
+        &self.type_table
+    }
+
+    /// Builds HIR of the source code of a single file, panicking if it fails to translate.
+    #[cfg(test)]
+    pub(crate) fn new_test(src: &str) -> Self {
+        Self::new_test_with(Context::new_test(), src)
+    }
+
+    /// Builds HIR with the `context`, that diagnostics are reported to.
+    #[cfg(test)]
+    pub(crate) fn new_test_with(context: Context, src: &str) -> Self {
+        let parsed = FileParser::new_test(src).parse();
+        assert!(parsed.errors.is_empty(), "parsing failed");
+        let mut builder = HirBuilder::new(context);
+        builder.populate(&parsed.item_table);
+        builder.build().expect("translation failed")
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FunctionId(u32);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Function {
     pub params: Vec<TypeId>,
-    pub return_type: Option<TypeId>,
+    pub return_type: TypeId,
     pub body: Block,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Expression {
-    type_: Option<TypeId>,
-    kind: ExpressionKind,
+pub(crate) struct Expression {
+    pub(crate) type_: TypeId,
+    pub(crate) kind: ExpressionKind,
+}
+
+impl Expression {
+    /// Checks if control flow never leaves the expression.
+    fn diverges(&self, with_break: bool) -> bool {
+        match &self.kind {
+            ExpressionKind::Block(block) => block.diverges(with_break),
+            ExpressionKind::If {
+                body,
+                else_body: Some(else_body),
+                ..
+            } => body.diverges(with_break) && else_body.diverges(with_break),
+            _ => false,
+        }
+    }
+
+    /// Checks if the expression is a variable or an access to its field or element, so it may be
+    /// assigned to.
+    fn is_place(&self) -> bool {
+        match &self.kind {
+            ExpressionKind::Var(_) => true,
+            ExpressionKind::Field { value, .. } | ExpressionKind::Index { value, .. } => {
+                value.is_place()
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum ExpressionKind {
+pub(crate) enum ExpressionKind {
     Block(Block),
     If {
         condition: Box<Expression>,
@@ -56,21 +154,65 @@ enum ExpressionKind {
     },
     Loop(Block),
     Literal(Literal),
+    Const(ConstValue),
     FnCall(FunctionId, Vec<Expression>),
+    /// Call of the function provided by the compiler, with the span of the call that is
+    /// reported if it stops the execution.
+    Intrinsic(Intrinsic, Vec<Expression>, Span),
     Var(VarId),
+    Field {
+        value: Box<Expression>,
+        field: Identifier,
+    },
+    Index {
+        value: Box<Expression>,
+        index: Box<Expression>,
+    },
+    /// Unary operator applied to the value, with the span of the whole expression that is
+    /// reported if folding it fails.
     UnaryOp {
         operator: UnaryOp,
         value: Box<Expression>,
+        span: Span,
     },
+    /// Conversion of the value to the type of the expression, see [TypeId::can_cast_to].
+    Cast(Box<Expression>),
+    /// Binary operator applied to the operands, with the span of the whole expression that is
+    /// reported if folding it fails.
     BinaryOp {
         operator: BinaryOp,
         left: Box<Expression>,
         right: Box<Expression>,
+        span: Span,
+        /// Location of the right operand, that folded zero divisor is reported at.
+        right_span: Span,
     },
 }
 
+/// Value of the constant computed at compile time.
+#[derive(Debug, Clone, Copy)]
+pub enum ConstValue {
+    /// Integer of any type, wide enough to store values of both `i64` and `u64`.
+    Integer(i128),
+    Float(f32),
+    Boolean(bool),
+}
+
+impl PartialEq for ConstValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ConstValue::Integer(a), ConstValue::Integer(b)) => a == b,
+            (ConstValue::Float(a), ConstValue::Float(b)) => a.to_bits() == b.to_bits(),
+            (ConstValue::Boolean(a), ConstValue::Boolean(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ConstValue {}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum Statement {
+pub(crate) enum Statement {
     ExprStmt(Expression),
     LetStmt {
         var: VarId,
@@ -78,21 +220,111 @@ enum Statement {
         value: Option<Box<Expression>>,
     },
     Assignment {
-        assignee: VarId,
+        /// Place that is assigned to, see [Expression::is_place].
+        assignee: Expression,
         value: Expression,
     },
     Return(Expression),
     Break,
 }
 
+impl Statement {
+    /// Checks if control flow never reaches the next statement.
+    fn diverges(&self, with_break: bool) -> bool {
+        match self {
+            Statement::Return(_) => true,
+            Statement::Break => with_break,
+            Statement::ExprStmt(expr) => expr.diverges(with_break),
+            Statement::LetStmt { .. } | Statement::Assignment { .. } => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Block {
-    statements: Vec<Statement>,
-    tail: Option<Box<Expression>>,
+    pub(crate) statements: Vec<Statement>,
+    pub(crate) tail: Option<Box<Expression>>,
 }
 
 impl Block {
-    pub fn type_id(&self) -> Option<TypeId> {
-        self.tail.as_ref().and_then(|expr| expr.type_)
+    /// Checks if control flow never reaches the end of the block.
+    ///
+    /// `break` is only taken into account if `with_break` is set, as it doesn't leave the function.
+    pub fn diverges(&self, with_break: bool) -> bool {
+        self.statements.iter().any(|stmt| stmt.diverges(with_break))
+            || self
+                .tail
+                .as_ref()
+                .is_some_and(|expr| expr.diverges(with_break))
+    }
+
+    /// Type of the block's tail expression or unit if it has none.
+    pub fn type_id(&self) -> TypeId {
+        self.tail.as_ref().map_or(TypeId::UNIT, |expr| expr.type_)
+    }
+
+    /// Variables declared in the block and blocks nested in it, along with their types.
+    pub(crate) fn locals(&self) -> Vec<(VarId, TypeId)> {
+        let mut locals = Vec::new();
+        collect_locals(self, &mut locals);
+        locals
+    }
+}
+
+fn collect_locals(block: &Block, locals: &mut Vec<(VarId, TypeId)>) {
+    for stmt in block.statements.iter() {
+        match stmt {
+            Statement::LetStmt { var, type_, value } => {
+                locals.push((*var, *type_));
+                if let Some(value) = value {
+                    collect_expr_locals(value, locals);
+                }
+            }
+            Statement::ExprStmt(expr) | Statement::Return(expr) => {
+                collect_expr_locals(expr, locals)
+            }
+            Statement::Assignment { assignee, value } => {
+                collect_expr_locals(assignee, locals);
+                collect_expr_locals(value, locals);
+            }
+            Statement::Break => {}
+        }
+    }
+    if let Some(tail) = &block.tail {
+        collect_expr_locals(tail, locals);
+    }
+}
+
+fn collect_expr_locals(expr: &Expression, locals: &mut Vec<(VarId, TypeId)>) {
+    match &expr.kind {
+        ExpressionKind::Block(block) | ExpressionKind::Loop(block) => collect_locals(block, locals),
+        ExpressionKind::If {
+            condition,
+            body,
+            else_body,
+        } => {
+            collect_expr_locals(condition, locals);
+            collect_locals(body, locals);
+            if let Some(else_body) = else_body {
+                collect_locals(else_body, locals);
+            }
+        }
+        ExpressionKind::FnCall(_, args) | ExpressionKind::Intrinsic(_, args, _) => {
+            for arg in args.iter() {
+                collect_expr_locals(arg, locals);
+            }
+        }
+        ExpressionKind::Field { value, .. }
+        | ExpressionKind::Cast(value)
+        | ExpressionKind::UnaryOp { value, .. } => collect_expr_locals(value, locals),
+        ExpressionKind::Index { value, index } => {
+            collect_expr_locals(value, locals);
+            collect_expr_locals(index, locals);
+        }
+        ExpressionKind::BinaryOp { left, right, .. } => {
+            collect_expr_locals(left, locals);
+            collect_expr_locals(right, locals);
+        }
+        ExpressionKind::Literal(_) | ExpressionKind::Const(_) | ExpressionKind::Var(_) => {}
     }
 }
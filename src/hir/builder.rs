@@ -1,186 +1,2443 @@
 mod body;
+pub(super) mod constant;
+mod derive;
+mod init;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     ast::{
-        expression::Block as AstBlock,
+        expression::{Block as AstBlock, ExprArena, ExpressionKind as AstExpressionKind},
         item::Function as AstFunction,
-        item::{Field, ItemKind, Parameter},
+        item::{Const, Field, ItemKind, Parameter, Visibility},
+        statement::{LetStatement, StatementKind},
+        types::TypeExpr,
     },
+    context::Context,
+    error::{library::hir::DeadFunction, Label, ReportableError, Severity},
     item_table::ItemTable,
-    path::AbsolutePath,
+    lexer::{number::NumberError, operator::BinaryOp},
+    path::{resolve_path, AbsolutePath, RelativePath},
+    util::{edit_distance, Span},
     Identifier,
 };
 
-use self::body::BodyBuilder;
+use self::{body::BodyBuilder, derive::DerivedFunction};
 
 use super::{
+    callgraph::CallGraph,
+    fold::fold_constants,
+    intrinsic::Intrinsic,
+    layout::compute_layouts,
     types::{TypeError, TypeId, TypeTable},
-    Block, Function, FunctionId, Hir,
+    validate::validate,
+    Block, ConstValue, Function, FunctionId, Hir,
 };
 
 use thiserror::Error;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct HirBuilder {
+    context: Context,
     type_table: TypeTable,
-    errors: Vec<TranslationError>,
+    /// Number of errors reported during translation.
+    errors: usize,
 
+    imports: HashMap<AbsolutePath, HashMap<Identifier, RelativePath>>,
+    constants: HashMap<AbsolutePath, (TypeId, ConstValue)>,
     mapping: HashMap<AbsolutePath, FunctionId>,
-    signatures: Vec<(Vec<TypeId>, Option<TypeId>)>,
-    bodies: Vec<Block>,
+    /// Paths of declared modules, used to explain attempts to call them.
+    modules: HashSet<AbsolutePath>,
+    /// Names of variables declared in functions that contain nested functions, used to explain
+    /// attempts to capture them.
+    variables: HashMap<AbsolutePath, HashSet<Identifier>>,
+    /// Functions that take `self` receiver as the first parameter.
+    methods: HashSet<FunctionId>,
+    /// Signatures of functions indexed by [FunctionId], `None` if it failed to translate.
+    signatures: Vec<Option<(Vec<TypeId>, TypeId)>>,
+    /// Bodies of functions indexed by [FunctionId], `None` if it failed to translate.
+    bodies: Vec<Option<Block>>,
+    /// Spans of functions indexed by [FunctionId].
+    spans: Vec<Span>,
+    /// Spans of parameters of functions indexed by [FunctionId], empty if the signature failed
+    /// to translate.
+    param_spans: Vec<Vec<Span>>,
+    /// Function at the root of the crate that execution starts from.
+    entry_point: Option<FunctionId>,
+    /// Public functions declared at the root of the crate, that may be called from outside of it.
+    exported: Vec<FunctionId>,
+    /// Functions derived for structs, which ids follow ids of functions of the source code.
+    derived: Vec<DerivedFunction>,
+    /// Functions annotated with `#[test]`.
+    tests: Vec<FunctionId>,
 }
 
 impl HirBuilder {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(context: Context) -> Self {
+        Self {
+            context,
+            type_table: TypeTable::with_builtins(),
+            errors: 0,
+            imports: HashMap::new(),
+            constants: HashMap::new(),
+            mapping: HashMap::new(),
+            modules: HashSet::new(),
+            methods: HashSet::new(),
+            variables: HashMap::new(),
+            signatures: Vec::new(),
+            bodies: Vec::new(),
+            spans: Vec::new(),
+            param_spans: Vec::new(),
+            entry_point: None,
+            exported: Vec::new(),
+            derived: Vec::new(),
+            tests: Vec::new(),
+        }
     }
 
-    pub fn build(self) -> Result<Hir, Vec<TranslationError>> {
-        if !self.errors.is_empty() {
-            return Err(self.errors);
+    /// Builds HIR.
+    ///
+    /// Errors are reported to the [ErrorReporter](crate::error::ErrorReporter) of the context,
+    /// only their number is returned.
+    pub fn build(self) -> Result<Hir, usize> {
+        match self.build_partial() {
+            (hir, 0) => {
+                if cfg!(debug_assertions) {
+                    let errors = validate(&hir);
+                    assert!(errors.is_empty(), "malformed HIR: {errors:?}");
+                }
+                Ok(hir)
+            }
+            (_, errors) => Err(errors),
         }
+    }
 
+    /// Builds HIR even if some functions failed to translate, returning it with the number of
+    /// reported errors.
+    ///
+    /// Such functions are missing from the [Hir], but ids of other functions are kept intact.
+    ///
+    /// Functions that are never used are reported if translation succeeded and the crate has
+    /// an entry point.
+    pub fn build_partial(self) -> (Hir, usize) {
         let HirBuilder {
+            context,
             type_table,
+            mut errors,
+            mapping,
             signatures,
             bodies,
+            spans,
+            entry_point,
+            exported,
+            derived,
+            tests,
             ..
         } = self;
-        debug_assert_eq!(signatures.len(), bodies.len());
-
         let functions = signatures
             .into_iter()
             .zip(bodies)
-            .map(|((params, return_type), body)| Function {
-                params,
-                return_type,
-                body,
+            .map(|function| match function {
+                (Some((params, return_type)), Some(body)) => Some(Function {
+                    params,
+                    return_type,
+                    body,
+                }),
+                _ => None,
             })
             .collect();
 
-        Ok(Hir {
+        let mut hir = Hir {
             type_table,
+            mapping,
             functions,
-        })
+            exported: exported.clone(),
+            tests: tests.clone(),
+        };
+        // Calls in branches removed by folding still count as uses.
+        if let (0, Some(entry_point)) = (errors, entry_point) {
+            // Tests are called by the test runner.
+            let mut roots = exported;
+            roots.extend(tests);
+            roots.push(entry_point);
+            for id in CallGraph::new(&hir).unreachable_from(&roots) {
+                if derived.iter().any(|function| function.id == id) {
+                    continue;
+                }
+                let path = hir.function_path(id).expect("function should be declared");
+                let _ = DeadFunction::report_at(
+                    &context.error_reporter,
+                    spans[id.0 as usize],
+                    path.clone(),
+                );
+            }
+        }
+        if context.metadata.fold_constants {
+            for error in fold_constants(&mut hir) {
+                context.error_reporter.report(error);
+                errors += 1;
+            }
+        }
+        (hir, errors)
     }
 
-    pub fn populate(&mut self, item_table: ItemTable) {
-        let mut strukts: Vec<(TypeId, Vec<Field>)> = Vec::new();
-        let mut functions: Vec<(AbsolutePath, AstFunction)> = Vec::new();
+    /// Reports translation error.
+    fn report(&mut self, error: TranslationError) {
+        self.context.error_reporter.report(error);
+        self.errors += 1;
+    }
 
-        for (path, item) in item_table.into_iter() {
-            match item.kind {
-                ItemKind::Module(_) => {}
-                ItemKind::Struct(strukt) => {
-                    let id = self.type_table.define_name(strukt.name.clone());
-                    strukts.push((id, strukt.fields));
+    /// Translates every item of the `item_table`.
+    ///
+    /// Translation is done in two passes, so items may be referenced regardless of the order of
+    /// their declaration: all types and function signatures are declared first, then bodies of
+    /// functions are translated.
+    pub fn populate(&mut self, item_table: &ItemTable) {
+        let partial_functions = self.declare_items(item_table);
+        self.translate_bodies(item_table.exprs(), partial_functions);
+        for function in self.derived.clone() {
+            let body = self
+                .derived_body(&function)
+                .map_err(|error| self.report(error))
+                .ok();
+            self.bodies.push(body);
+        }
+    }
+
+    /// Declares types, constants and imports, and translates signatures of functions.
+    ///
+    /// Returns partially translated functions indexed by [FunctionId], `None` if translation of
+    /// function's signature failed.
+    fn declare_items(&mut self, item_table: &ItemTable) -> Vec<Option<PartiallyParsedFunction>> {
+        let mut strukts: Vec<(AbsolutePath, TypeId, Vec<Field>)> = Vec::new();
+        let mut derives: Vec<(AbsolutePath, TypeId, Span, Vec<Identifier>)> = Vec::new();
+        let mut functions: Vec<(AbsolutePath, Span, AstFunction)> = Vec::new();
+        let mut constants: Vec<(AbsolutePath, Span, Const)> = Vec::new();
+
+        let impls: Vec<(AbsolutePath, Span)> = item_table
+            .impls()
+            .iter()
+            .filter_map(|(module, item)| match &item.kind {
+                ItemKind::Impl(implementation) => {
+                    let mut path = module.clone();
+                    path.push(implementation.type_name.clone());
+                    Some((path, implementation.type_span))
                 }
-                ItemKind::Function(function) => {
-                    let id = FunctionId(self.mapping.len() as u32);
-                    self.mapping.insert(path.clone(), id);
-                    functions.push((path, function));
+                _ => None,
+            })
+            .collect();
+
+        for (path, item, strukt) in item_table.structs() {
+            if self.type_table.is_builtin(path.last()) {
+                self.report(TranslationError::BuiltinTypeRedefined {
+                    name: path.last().clone(),
+                    span: item.span,
+                });
+                continue;
+            }
+            let id = self.type_table.define_name(path.clone());
+            let names: Vec<Identifier> = item.derives().cloned().collect();
+            if !names.is_empty() {
+                derives.push((path.clone(), id, item.span, names));
+            }
+            let mut module = path.clone();
+            module.pop();
+            strukts.push((module, id, strukt.fields.clone()));
+        }
+        for (path, item, function) in item_table.functions() {
+            let id = FunctionId(self.mapping.len() as u32);
+            if path.iter().count() == 1 {
+                if *path.last() == self.context.metadata.entry_point {
+                    self.entry_point = Some(id);
+                }
+                if item.visibility == Visibility::Public {
+                    self.exported.push(id);
                 }
             }
+            if item.attributes_named("test").next().is_some() {
+                self.tests.push(id);
+            }
+            self.mapping.insert(path.clone(), id);
+            self.spans.push(item.span);
+            functions.push((path.clone(), item.span, function.clone()));
+        }
+        for (path, id, span, names) in derives {
+            self.declare_derived(&path, id, span, &names);
+        }
+        let enclosing: HashSet<AbsolutePath> = functions
+            .iter()
+            .flat_map(|(path, ..)| {
+                let mut parent = path.clone();
+                parent.pop();
+                self.item_scope(parent).1
+            })
+            .collect();
+        for (path, _, function) in functions.iter() {
+            if enclosing.contains(path) {
+                let variables = declared_variables(item_table.exprs(), function);
+                self.variables.insert(path.clone(), variables);
+            }
+        }
+        for (path, item) in item_table.iter() {
+            match &item.kind {
+                ItemKind::Const(constant) => {
+                    constants.push((path.clone(), item.span, constant.clone()))
+                }
+                ItemKind::Module(_) => {
+                    self.modules.insert(path.clone());
+                }
+                ItemKind::Use(import) => {
+                    let mut module = path.clone();
+                    let name = module.pop().expect("import should have a name");
+                    self.imports
+                        .entry(module)
+                        .or_default()
+                        .insert(name, import.path.clone());
+                }
+                _ => {}
+            }
+        }
+
+        for (path, span) in impls {
+            if !self.type_table.mapping.contains_key(&path) {
+                self.report(TranslationError::InvalidImplType {
+                    path: Box::new(path),
+                    span,
+                });
+            }
+        }
+
+        for (module, id, fields) in strukts {
+            let (module, bodies) = self.item_scope(module);
+            for Field { name, type_, span } in fields {
+                match self.get_type(&bodies, &module, &type_, span) {
+                    Ok(type_) => {
+                        if let Err(error) = self.type_table.add_field(id, name, type_) {
+                            self.report(TranslationError::TypeError {
+                                error: Box::new(error),
+                                span,
+                            });
+                        }
+                    }
+                    Err(err) => self.report(err),
+                }
+            }
+        }
+
+        // Layout can only be computed if no type contains itself.
+        let cycles = self.type_table.find_cycles();
+        if cycles.is_empty() {
+            compute_layouts(&mut self.type_table);
+        }
+        for cycle in cycles {
+            let span = self.closing_field_span(item_table, &cycle);
+            let error = TypeError::InfiniteSize(cycle);
+            self.report(TranslationError::TypeError {
+                error: Box::new(error),
+                span,
+            });
         }
 
-        for (id, fields) in strukts {
-            for Field { name, type_ } in fields {
-                let result = self.type_table.add_field(id, name, type_);
-                if let Err(err) = result {
-                    self.errors.push(err.into());
+        for (path, span, constant) in constants {
+            match self.translate_const(item_table.exprs(), &path, span, constant) {
+                Ok(constant) => {
+                    self.constants.insert(path, constant);
                 }
+                Err(err) => self.report(err),
             }
         }
 
         let mut partial_functions = Vec::with_capacity(functions.len());
-        for (path, function) in functions {
-            match self.partially_translate_function(path, function) {
+        for (path, span, function) in functions {
+            match self.partially_translate_function(path, span, function) {
                 Ok(partial) => {
-                    let params = partial.params.iter().map(|(_, type_id)| *type_id).collect();
+                    if partial.is_method {
+                        self.methods
+                            .insert(FunctionId(self.signatures.len() as u32));
+                    }
+                    let params = partial
+                        .params
+                        .iter()
+                        .map(|(_, type_id, _)| *type_id)
+                        .collect();
                     let return_type = partial.return_type;
-                    self.signatures.push((params, return_type));
-                    partial_functions.push(partial);
+                    self.signatures.push(Some((params, return_type)));
+                    let spans = partial.params.iter().map(|(_, _, span)| *span).collect();
+                    self.param_spans.push(spans);
+                    partial_functions.push(Some(partial));
+                }
+                Err(err) => {
+                    self.report(err);
+                    self.signatures.push(None);
+                    self.param_spans.push(Vec::new());
+                    partial_functions.push(None);
                 }
-                Err(err) => self.errors.push(err),
             }
         }
+        for function in self.derived.iter() {
+            self.signatures.push(Some(self.derived_signature(function)));
+            self.param_spans.push(vec![function.span]);
+        }
+        for id in self.tests.clone() {
+            let Some((params, return_type)) = &self.signatures[id.0 as usize] else {
+                continue;
+            };
+            if !params.is_empty() || ![TypeId::UNIT, TypeId::BOOL].contains(return_type) {
+                let path = self.mapping.iter().find(|(_, function)| **function == id);
+                let (path, _) = path.expect("test should be declared");
+                let error = TranslationError::InvalidTestSignature {
+                    path: Box::new(path.clone()),
+                    span: self.spans[id.0 as usize],
+                };
+                self.report(error);
+            }
+        }
+        partial_functions
+    }
 
+    /// Translates bodies of functions, which signatures are already known.
+    fn translate_bodies(
+        &mut self,
+        exprs: &ExprArena,
+        partial_functions: Vec<Option<PartiallyParsedFunction>>,
+    ) {
         for partial in partial_functions {
-            match BodyBuilder::translate(self, partial) {
-                Ok(body) => self.bodies.push(body),
-                Err(error) => self.errors.push(error),
-            }
+            let body = match partial.map(|partial| BodyBuilder::translate(self, exprs, partial)) {
+                Some(Ok(body)) => Some(body),
+                Some(Err(error)) => {
+                    self.report(error);
+                    None
+                }
+                None => None,
+            };
+            self.bodies.push(body);
         }
     }
 
     fn partially_translate_function(
         &self,
-        mut path: AbsolutePath,
+        path: AbsolutePath,
+        span: Span,
         func: AstFunction,
     ) -> Result<PartiallyParsedFunction, TranslationError> {
+        let mut parent = path.clone();
+        parent.pop();
+        let impl_type = self.type_table.mapping.get(&parent).copied();
+        let (module, mut bodies) = self.item_scope(parent);
+        bodies.insert(0, path);
         let mut partial_func = PartiallyParsedFunction {
-            module: {
-                path.pop();
-                path
-            },
-            params: Vec::with_capacity(func.params.len()),
-            return_type: None,
+            span,
+            module,
+            bodies,
+            is_method: func.receiver.is_some(),
+            params: Vec::with_capacity(func.params.len() + 1),
+            return_type: TypeId::UNIT,
+            return_type_span: func.return_type_span,
             body: func.body,
         };
 
-        for Parameter { name, type_ } in func.params {
-            let type_id = self.type_table.get(type_)?;
-            partial_func.params.push((name, type_id))
+        if let Some(span) = func.receiver {
+            let Some(impl_type) = impl_type else {
+                return Err(TranslationError::SelfOutsideImpl { span });
+            };
+            let name = Identifier(String::from("self"));
+            partial_func.params.push((name, impl_type, span));
+        }
+        for Parameter { name, type_, span } in func.params {
+            let type_id =
+                self.get_type(&partial_func.bodies, &partial_func.module, &type_, span)?;
+            partial_func.params.push((name, type_id, span))
         }
         partial_func.return_type = func
             .return_type
-            .map(|type_| self.type_table.get(type_))
-            .transpose()?;
+            .map(|type_| {
+                let span = func.return_type_span.unwrap_or(span);
+                self.get_type(&partial_func.bodies, &partial_func.module, &type_, span)
+            })
+            .transpose()?
+            .unwrap_or(TypeId::UNIT);
 
         Ok(partial_func)
     }
 
+    /// Finds location of the field of the last struct of the `cycle`, that contains the first one.
+    fn closing_field_span(&self, item_table: &ItemTable, cycle: &[AbsolutePath]) -> Span {
+        let last = cycle.last().expect("cycle shouldn't be empty");
+        let item = item_table.get(last).expect("struct should be declared");
+        let ItemKind::Struct(strukt) = &item.kind else { return item.span; };
+        let TypeId::Compound(first) = self.type_table.mapping[&cycle[0]] else {
+            unreachable!("only user-defined types may form a cycle");
+        };
+        let id = self.type_table.mapping[last];
+        strukt
+            .fields
+            .iter()
+            .find(|field| {
+                let mut contained = Vec::new();
+                if let Some(type_) = self.type_table.field(id, &field.name) {
+                    self.type_table.contained_compounds(type_, &mut contained);
+                }
+                contained.contains(&first)
+            })
+            .map_or(item.span, |field| field.span)
+    }
+
+    /// Evaluates constant's initializer stored in `exprs` and checks it against declared type.
+    ///
+    /// Declaration of the constant is located at `span`.
+    fn translate_const(
+        &self,
+        exprs: &ExprArena,
+        path: &AbsolutePath,
+        span: Span,
+        constant: Const,
+    ) -> Result<(TypeId, ConstValue), TranslationError> {
+        let mut module = path.clone();
+        module.pop();
+        let type_ = self.get_type(&[], &module, &constant.type_, span)?;
+        let span = exprs[constant.value].span;
+        let (value, value_type) =
+            constant::evaluate(exprs, constant.value, Some(type_), &self.type_table)?;
+        if value_type != type_ {
+            return Err(TranslationError::TypeMismatch {
+                expected: self.type_table.type_name(type_),
+                received: self.type_table.type_name(value_type),
+                span,
+                origin: None,
+            });
+        }
+        Ok((type_, value))
+    }
+
+    /// Finds the module that item declared in `parent` belongs to, along with bodies of functions
+    /// that enclose the item, innermost first.
+    ///
+    /// Items may be declared in bodies of functions, and associated functions are declared in
+    /// implementation blocks, which are both skipped.
+    fn item_scope(&self, mut parent: AbsolutePath) -> (AbsolutePath, Vec<AbsolutePath>) {
+        let mut bodies = Vec::new();
+        loop {
+            if self.mapping.contains_key(&parent) {
+                bodies.push(parent.clone());
+            } else if !self.type_table.mapping.contains_key(&parent) {
+                return (parent, bodies);
+            }
+            parent.pop();
+        }
+    }
+
+    /// Checks if an item that may be referenced by path is declared at `path`.
+    fn is_declared(&self, path: &AbsolutePath) -> bool {
+        self.mapping.contains_key(path)
+            || self.type_table.mapping.contains_key(path)
+            || self.constants.contains_key(path)
+            || self.modules.contains(path)
+    }
+
+    /// Maps relative path to absolute, taking imports of the `module` into account.
+    ///
+    /// Items declared in `bodies` of functions shadow items of the module, see
+    /// [resolve_path].
+    fn resolve_path(
+        &self,
+        bodies: &[AbsolutePath],
+        module: &AbsolutePath,
+        path: &RelativePath,
+    ) -> Option<AbsolutePath> {
+        let imports = self.imports.get(module);
+        resolve_path(
+            path,
+            module,
+            bodies,
+            |name| imports.and_then(|imports| imports.get(name)),
+            |item| self.is_declared(item),
+        )
+    }
+
+    /// Resolves type expression met in the `module`, inside of `bodies` of functions.
+    ///
+    /// Errors are reported at `span`, that the type expression is written at.
+    fn get_type(
+        &self,
+        bodies: &[AbsolutePath],
+        module: &AbsolutePath,
+        type_: &TypeExpr,
+        span: Span,
+    ) -> Result<TypeId, TranslationError> {
+        self.type_table
+            .get(type_, &|path| self.resolve_path(bodies, module, path))
+            .map_err(|error| match error {
+                // Reported the same way as unresolved paths in bodies of functions.
+                TypeError::UnresolvedPath(path) => TranslationError::UnresolvedPath {
+                    path: Box::new(path),
+                    span,
+                },
+                error => TranslationError::TypeError {
+                    error: Box::new(error),
+                    span,
+                },
+            })
+    }
+
+    fn query_const(&self, path: &AbsolutePath) -> Option<(TypeId, ConstValue)> {
+        self.constants.get(path).copied()
+    }
+
+    /// Looks up signature of the function called at `span`.
+    ///
+    /// Intrinsics are only looked up if there is no function declared at `path`.
     fn query_function_info(
         &self,
         path: &AbsolutePath,
-    ) -> Option<(FunctionId, &[TypeId], Option<TypeId>)> {
-        let id = self.mapping.get(path).copied()?;
-        let signature = &self.signatures[id.0 as usize];
-        Some((id, signature.0.as_slice(), signature.1))
+        span: Span,
+    ) -> Result<(Callee, &[TypeId], TypeId), TranslationError> {
+        let Some(id) = self.mapping.get(path).copied() else {
+            if let Some(intrinsic) = Intrinsic::at(path) {
+                return Ok((
+                    Callee::Intrinsic(intrinsic),
+                    intrinsic.params(),
+                    intrinsic.return_type(),
+                ));
+            }
+            return Err(TranslationError::FunctionNotFound {
+                path: Box::new(path.clone()),
+                candidates: self.similar_functions(path),
+                span,
+            });
+        };
+        let Some((params, return_type)) = &self.signatures[id.0 as usize] else {
+            return Err(TranslationError::InvalidSignature {
+                path: path.clone(),
+                span,
+            });
+        };
+        Ok((Callee::Function(id), params.as_slice(), *return_type))
+    }
+
+    /// Looks up function `name` declared in an implementation block of the user-defined `type_`.
+    fn associated_function(
+        &self,
+        type_: TypeId,
+        name: &Identifier,
+    ) -> Option<(AbsolutePath, FunctionId)> {
+        let mut path = self.type_table.path_of(type_)?.clone();
+        path.push(name.clone());
+        let id = self.mapping.get(&path).copied()?;
+        Some((path, id))
+    }
+
+    /// Checks if function with `id` takes `self` receiver.
+    fn is_method(&self, id: FunctionId) -> bool {
+        self.methods.contains(&id)
+    }
+
+    /// Checks if `path` refers to a function or an intrinsic.
+    fn is_callable(&self, path: &AbsolutePath) -> bool {
+        self.mapping.contains_key(path) || Intrinsic::at(path).is_some()
+    }
+
+    /// Looks up span of the parameter of the function with `id` at `index`.
+    fn param_span(&self, id: FunctionId, index: usize) -> Option<Span> {
+        self.param_spans.get(id.0 as usize)?.get(index).copied()
+    }
+
+    /// Looks up to three functions that might have been meant instead of missing `path`.
+    ///
+    /// These are functions with the same name declared in other modules, and functions of the
+    /// same module with similar name.
+    fn similar_functions(&self, path: &AbsolutePath) -> Vec<AbsolutePath> {
+        const MAX_DISTANCE: usize = 2;
+        const MAX_CANDIDATES: usize = 3;
+
+        let name = path.last().as_str();
+        let mut module = path.clone();
+        module.pop();
+
+        let mut candidates: Vec<(usize, &AbsolutePath)> = self
+            .mapping
+            .keys()
+            .filter_map(|candidate| {
+                let candidate_name = candidate.last().as_str();
+                if candidate_name == name {
+                    return Some((0, candidate));
+                }
+                let mut candidate_module = candidate.clone();
+                candidate_module.pop();
+                let distance = edit_distance(name, candidate_name);
+                (candidate_module == module && distance <= MAX_DISTANCE)
+                    .then_some((distance, candidate))
+            })
+            .collect();
+        candidates.sort_by_key(|(distance, candidate)| (*distance, candidate.to_string()));
+        candidates
+            .into_iter()
+            .take(MAX_CANDIDATES)
+            .map(|(_, candidate)| candidate.clone())
+            .collect()
+    }
+
+    /// Renders function's signature, such as `fn(i32, bool) -> i32`.
+    fn render_signature(&self, params: &[TypeId], return_type: TypeId) -> String {
+        let params = params
+            .iter()
+            .map(|param| self.type_table.type_name(*param))
+            .collect::<Vec<_>>()
+            .join(", ");
+        match return_type {
+            TypeId::UNIT => format!("fn({params})"),
+            _ => format!("fn({params}) -> {}", self.type_table.type_name(return_type)),
+        }
     }
 }
 
+/// Function that is called by the expression.
+#[derive(Clone, Copy)]
+enum Callee {
+    Function(FunctionId),
+    Intrinsic(Intrinsic),
+}
+
 struct PartiallyParsedFunction {
+    pub span: Span,
     pub module: AbsolutePath,
-    pub params: Vec<(Identifier, TypeId)>,
-    pub return_type: Option<TypeId>,
+    /// Paths of the function and functions that enclose it, innermost first. Items declared in
+    /// their bodies may be referenced by name.
+    pub bodies: Vec<AbsolutePath>,
+    /// Whether the first parameter is `self` receiver.
+    pub is_method: bool,
+    /// Names, types and spans of parameters, including receiver.
+    pub params: Vec<(Identifier, TypeId, Span)>,
+    pub return_type: TypeId,
+    pub return_type_span: Option<Span>,
     pub body: AstBlock,
 }
 
 #[derive(Debug, Error)]
 pub enum TranslationError {
     #[error("type inference is not implemented yet, so type annotation is required for every variable binding")]
-    TypeInference,
-    #[error("type does not match. Expected {expected:?}, received {received:?}.")]
+    TypeInference { span: Span },
+    #[error("mismatched types: expected `{expected}`, found `{received}`")]
     TypeMismatch {
-        expected: Option<TypeId>,
-        received: Option<TypeId>,
+        expected: String,
+        received: String,
+        span: Span,
+        /// Code that requires the expression to have the expected type, if it is known.
+        origin: Option<Box<Expectation>>,
     },
-    #[error("`if` and `else` have incompatible types. Expected {body:?}, found {else_body:?}.")]
+    #[error("`if` and `else` have incompatible types: expected `{body}`, found `{else_body}`")]
     IfBranchTypeMismatch {
-        body: Option<TypeId>,
-        else_body: Option<TypeId>,
+        body: String,
+        else_body: String,
+        span: Span,
+    },
+    #[error("incorrect number of arguments provided for function {path} with signature `{signature}`. Expected {expected:?}, received {received:?}.")]
+    ArgumentCountMismatch {
+        path: Box<AbsolutePath>,
+        signature: String,
+        expected: usize,
+        received: usize,
+        span: Span,
     },
-    #[error("incorrect number of arguments provided for function. Expected {expected:?}, received {received:?}.")]
-    ArgumentCountMismatch { expected: usize, received: usize },
-    #[error("variable `{0}` is not declared")]
-    VariableNotDeclared(Identifier),
-    #[error("function {0} is not found")]
-    FunctionNotFound(AbsolutePath),
+    #[error("variable `{name}` is not declared")]
+    VariableNotDeclared { name: Identifier, span: Span },
+    #[error("function {path} is not found{}", suggestion(.candidates))]
+    FunctionNotFound {
+        path: Box<AbsolutePath>,
+        candidates: Vec<AbsolutePath>,
+        span: Span,
+    },
+    #[error("`{name}` is a variable, not a function")]
+    VariableCalled { name: Identifier, span: Span },
+    #[error("{path} is a struct, not a function")]
+    StructCalled { path: Box<AbsolutePath>, span: Span },
+    #[error("{path} is a module, not a function")]
+    ModuleCalled { path: Box<AbsolutePath>, span: Span },
+    #[error("`self` parameter is only allowed in functions of implementation blocks")]
+    SelfOutsideImpl { span: Span },
+    #[error("no method `{method}` found for type `{type_}`")]
+    MethodNotFound {
+        type_: String,
+        method: Identifier,
+        span: Span,
+    },
+    #[error(
+        "associated function {path} has no `self` receiver, so it must be called as `{path}(..)`"
+    )]
+    NotAMethod { path: Box<AbsolutePath>, span: Span },
+    #[error("no `{method}` method found for type `{type_}`")]
+    OperatorNotImplemented {
+        method: &'static str,
+        type_: String,
+        span: Span,
+    },
+    #[error("method {path} takes `self` as the first argument, which is missing")]
+    MissingReceiver { path: Box<AbsolutePath>, span: Span },
+    #[error("can't capture dynamic environment in a nested function; use parameters instead")]
+    CaptureInNestedFunction { name: Identifier, span: Span },
+    #[error(
+        "function {path} is declared in the body of {function} and may only be called inside of it"
+    )]
+    NestedFunctionOutOfScope {
+        path: Box<AbsolutePath>,
+        function: Box<AbsolutePath>,
+        span: Span,
+    },
+    #[error("function must return a value of type `{expected}` on every path")]
+    MissingReturn { expected: String, span: Span },
+    #[error("function {path} can't be called, as its signature contains errors")]
+    InvalidSignature { path: AbsolutePath, span: Span },
     #[error("break may not be used outside of the loop")]
-    InvalidBreak,
-    #[error(transparent)]
-    TypeError(#[from] TypeError),
+    InvalidBreak { span: Span },
+    #[error("constant initializer may only contain numeric and boolean literals and operators applied to them")]
+    NonConstantExpression { span: Span },
+    #[error("attempt to compute constant value overflowed")]
+    ConstOverflow { span: Span },
+    #[error("attempt to divide by zero in constant expression")]
+    ConstDivisionByZero { span: Span },
+    #[error("implementation block for {path} is not allowed, as it is not a struct declared in the same module")]
+    InvalidImplType { path: Box<AbsolutePath>, span: Span },
+    #[error("{error}")]
+    TypeError { error: Box<TypeError>, span: Span },
+    #[error("cannot resolve path {path}: too many `super` segments")]
+    UnresolvedPath { path: Box<RelativePath>, span: Span },
+    #[error("constant {path} is not found")]
+    ConstNotFound { path: Box<AbsolutePath>, span: Span },
+    #[error("value of type `{type_}` can't be iterated over, only ranges and arrays are iterable")]
+    NotIterable { type_: String, span: Span },
+    #[error("range may only be used as the iterable of `for` loop")]
+    RangeOutsideFor { span: Span },
+    #[error("value of type `{type_}` has no field `{field}`")]
+    FieldNotFound {
+        type_: String,
+        field: Identifier,
+        span: Span,
+    },
+    #[error("value of type `{type_}` can't be indexed, only arrays are indexable")]
+    NotIndexable { type_: String, span: Span },
+    #[error("value of type `{from}` can't be cast to `{to}`, only numbers and booleans may be cast to numeric types")]
+    InvalidCast {
+        from: String,
+        to: String,
+        span: Span,
+    },
+    #[error("{error}")]
+    InvalidLiteral { error: NumberError, span: Span },
+    #[error("attempt to divide by zero")]
+    DivisionByZero { span: Span },
+    #[error("{keyword} condition must be `bool`, found `{found}`")]
+    InvalidCondition {
+        keyword: &'static str,
+        found: String,
+        span: Span,
+    },
+    #[error("type `{name}` is builtin and can't be redefined")]
+    BuiltinTypeRedefined { name: Identifier, span: Span },
+    #[error("use of possibly uninitialized variable `{name}` declared at {}", .declared.start)]
+    UninitializedVariable {
+        name: Identifier,
+        declared: Box<Span>,
+        span: Span,
+    },
+    #[error("`{name}` can't be derived, supported derives are: {supported}")]
+    UnknownDerive {
+        name: Identifier,
+        supported: String,
+        span: Span,
+    },
+    #[error(
+        "`{derive}` can't be derived, as field `{field}` of type `{type_}` doesn't support it"
+    )]
+    UnsupportedDeriveField {
+        derive: &'static str,
+        field: Identifier,
+        type_: String,
+        span: Span,
+    },
+    #[error("function {path} is declared explicitly, so it can't be derived")]
+    DerivedFunctionRedefined { path: Box<AbsolutePath>, span: Span },
+    #[error("test {path} must take no parameters and return `bool` or nothing")]
+    InvalidTestSignature { path: Box<AbsolutePath>, span: Span },
+    #[error("operator `{operator}` is not supported for type `{type_}`")]
+    UnsupportedOperator {
+        operator: BinaryOp,
+        type_: String,
+        span: Span,
+    },
+}
+
+impl TranslationError {
+    /// Location of the code the error complains about.
+    pub fn span(&self) -> Span {
+        match self {
+            TranslationError::TypeInference { span }
+            | TranslationError::TypeMismatch { span, .. }
+            | TranslationError::IfBranchTypeMismatch { span, .. }
+            | TranslationError::ArgumentCountMismatch { span, .. }
+            | TranslationError::VariableNotDeclared { span, .. }
+            | TranslationError::FunctionNotFound { span, .. }
+            | TranslationError::MissingReturn { span, .. }
+            | TranslationError::InvalidSignature { span, .. }
+            | TranslationError::InvalidBreak { span }
+            | TranslationError::UnresolvedPath { span, .. }
+            | TranslationError::ConstNotFound { span, .. }
+            | TranslationError::NotIterable { span, .. }
+            | TranslationError::RangeOutsideFor { span }
+            | TranslationError::FieldNotFound { span, .. }
+            | TranslationError::NotIndexable { span, .. }
+            | TranslationError::InvalidCast { span, .. }
+            | TranslationError::InvalidCondition { span, .. }
+            | TranslationError::DivisionByZero { span }
+            | TranslationError::InvalidLiteral { span, .. }
+            | TranslationError::BuiltinTypeRedefined { span, .. }
+            | TranslationError::UninitializedVariable { span, .. }
+            | TranslationError::VariableCalled { span, .. }
+            | TranslationError::StructCalled { span, .. }
+            | TranslationError::ModuleCalled { span, .. }
+            | TranslationError::SelfOutsideImpl { span }
+            | TranslationError::MethodNotFound { span, .. }
+            | TranslationError::NotAMethod { span, .. }
+            | TranslationError::MissingReceiver { span, .. }
+            | TranslationError::OperatorNotImplemented { span, .. }
+            | TranslationError::CaptureInNestedFunction { span, .. }
+            | TranslationError::NestedFunctionOutOfScope { span, .. }
+            | TranslationError::UnknownDerive { span, .. }
+            | TranslationError::UnsupportedDeriveField { span, .. }
+            | TranslationError::DerivedFunctionRedefined { span, .. }
+            | TranslationError::InvalidTestSignature { span, .. }
+            | TranslationError::UnsupportedOperator { span, .. }
+            | TranslationError::NonConstantExpression { span }
+            | TranslationError::ConstOverflow { span }
+            | TranslationError::ConstDivisionByZero { span }
+            | TranslationError::InvalidImplType { span, .. }
+            | TranslationError::TypeError { span, .. } => *span,
+        }
+    }
+}
+
+impl ReportableError for TranslationError {
+    fn severity(&self) -> Severity {
+        Severity::Deny
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            TranslationError::TypeInference { .. } => "E0204",
+            TranslationError::TypeMismatch { .. } => "E0205",
+            TranslationError::IfBranchTypeMismatch { .. } => "E0206",
+            TranslationError::ArgumentCountMismatch { .. } => "E0207",
+            TranslationError::VariableNotDeclared { .. } => "E0208",
+            TranslationError::FunctionNotFound { .. } => "E0209",
+            TranslationError::MissingReturn { .. } => "E0210",
+            TranslationError::InvalidSignature { .. } => "E0211",
+            TranslationError::InvalidBreak { .. } => "E0212",
+            TranslationError::NonConstantExpression { .. } => "E0213",
+            TranslationError::ConstOverflow { .. } => "E0214",
+            TranslationError::ConstDivisionByZero { .. } => "E0215",
+            TranslationError::InvalidImplType { .. } => "E0216",
+            TranslationError::TypeError { .. } => "E0217",
+            TranslationError::UnresolvedPath { .. } => "E0218",
+            TranslationError::ConstNotFound { .. } => "E0219",
+            TranslationError::NotIterable { .. } => "E0220",
+            TranslationError::RangeOutsideFor { .. } => "E0221",
+            TranslationError::FieldNotFound { .. } => "E0222",
+            TranslationError::NotIndexable { .. } => "E0223",
+            TranslationError::InvalidCast { .. } => "E0224",
+            TranslationError::InvalidCondition { .. } => "E0225",
+            TranslationError::DivisionByZero { .. } => "E0226",
+            TranslationError::InvalidLiteral { .. } => "E0227",
+            TranslationError::BuiltinTypeRedefined { .. } => "E0228",
+            TranslationError::UninitializedVariable { .. } => "E0230",
+            TranslationError::VariableCalled { .. } => "E0231",
+            TranslationError::StructCalled { .. } => "E0232",
+            TranslationError::ModuleCalled { .. } => "E0233",
+            TranslationError::SelfOutsideImpl { .. } => "E0234",
+            TranslationError::MethodNotFound { .. } => "E0235",
+            TranslationError::NotAMethod { .. } => "E0236",
+            TranslationError::MissingReceiver { .. } => "E0237",
+            TranslationError::OperatorNotImplemented { .. } => "E0238",
+            TranslationError::CaptureInNestedFunction { .. } => "E0239",
+            TranslationError::NestedFunctionOutOfScope { .. } => "E0240",
+            TranslationError::UnknownDerive { .. } => "E0241",
+            TranslationError::UnsupportedDeriveField { .. } => "E0242",
+            TranslationError::DerivedFunctionRedefined { .. } => "E0243",
+            TranslationError::InvalidTestSignature { .. } => "E0244",
+            TranslationError::UnsupportedOperator { .. } => "E0245",
+        }
+    }
+
+    fn span(&self) -> Option<Span> {
+        Some(self.span())
+    }
+
+    fn label(&self) -> Option<String> {
+        match self {
+            TranslationError::TypeMismatch {
+                expected, received, ..
+            } => Some(format!("expected `{expected}`, found `{received}`")),
+            TranslationError::CaptureInNestedFunction { name, .. } => Some(format!(
+                "variable `{name}` is declared in the enclosing function"
+            )),
+            _ => None,
+        }
+    }
+
+    fn secondary_labels(&self) -> Vec<Label> {
+        match self {
+            TranslationError::TypeMismatch {
+                origin: Some(origin),
+                ..
+            } => vec![origin.label()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Code that requires an expression to have a specific type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expectation {
+    /// Type annotation of the variable.
+    Annotation(Span),
+    /// Declared return type of the function.
+    ReturnType(Span),
+    /// Parameter of the called function.
+    Parameter(Span),
+    /// Left operand of the binary operator, which the right one must match.
+    LeftOperand(Span),
+}
+
+impl Expectation {
+    fn label(&self) -> Label {
+        match *self {
+            Expectation::Annotation(span) => Label::new(span, "expected due to this annotation"),
+            Expectation::ReturnType(span) => Label::new(span, "expected due to the return type"),
+            Expectation::Parameter(span) => Label::new(span, "parameter declared here"),
+            Expectation::LeftOperand(span) => {
+                Label::new(span, "expected due to the type of this operand")
+            }
+        }
+    }
+}
+
+/// Collects names of parameters and variables declared anywhere in the body of `function`.
+fn declared_variables(exprs: &ExprArena, function: &AstFunction) -> HashSet<Identifier> {
+    let mut names: HashSet<Identifier> = function
+        .params
+        .iter()
+        .map(|param| param.name.clone())
+        .collect();
+    if function.receiver.is_some() {
+        names.insert(Identifier(String::from("self")));
+    }
+    let mut blocks = vec![&function.body];
+    let mut pending = Vec::new();
+    while let Some(block) = blocks.pop() {
+        for statement in block.statements.iter() {
+            match &statement.kind {
+                StatementKind::LetStmt(LetStatement { name, value, .. }) => {
+                    names.insert(name.clone());
+                    pending.extend(value);
+                }
+                StatementKind::ExprStmt(expr) | StatementKind::Return(expr) => pending.push(*expr),
+                StatementKind::Assignment { expression, .. } => pending.push(*expression),
+                StatementKind::Break => {}
+            }
+        }
+        pending.extend(block.expression);
+        while let Some(expr) = pending.pop() {
+            match &exprs[expr].kind {
+                AstExpressionKind::Block(block) => blocks.push(block),
+                AstExpressionKind::If {
+                    condition,
+                    body,
+                    else_body,
+                } => {
+                    pending.push(*condition);
+                    blocks.push(body);
+                    blocks.extend(else_body);
+                }
+                AstExpressionKind::While { condition, body } => {
+                    pending.push(*condition);
+                    blocks.push(body);
+                }
+                AstExpressionKind::For { var, expr, body } => {
+                    names.insert(var.clone());
+                    pending.push(*expr);
+                    blocks.push(body);
+                }
+                AstExpressionKind::Unary { value, .. }
+                | AstExpressionKind::Cast { value, .. }
+                | AstExpressionKind::FieldAccess { value, .. } => pending.push(*value),
+                AstExpressionKind::Binary { left, right, .. } => pending.extend([*left, *right]),
+                AstExpressionKind::Index { value, index } => pending.extend([*value, *index]),
+                AstExpressionKind::FnCall { params, .. } => pending.extend(params),
+                AstExpressionKind::MethodCall { value, params, .. } => {
+                    pending.push(*value);
+                    pending.extend(params);
+                }
+                AstExpressionKind::Var(_)
+                | AstExpressionKind::Path(_)
+                | AstExpressionKind::Literal(_) => {}
+            }
+        }
+    }
+    names
+}
+
+/// Renders list of candidates for [TranslationError::FunctionNotFound].
+fn suggestion(candidates: &[AbsolutePath]) -> String {
+    match candidates {
+        [] => String::new(),
+        [candidate] => format!(", did you mean `{candidate}`?"),
+        candidates => {
+            let candidates = candidates
+                .iter()
+                .map(|candidate| format!("`{candidate}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(", did you mean one of: {candidates}?")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{
+        ast::expression::Literal,
+        context::Context,
+        error::Diagnostic,
+        hir::{
+            scope::VarId, types::TypeId, Block, ConstValue, Expression, ExpressionKind, FunctionId,
+            Hir, Statement,
+        },
+        item_table::ItemTable,
+        lexer::operator::{BinaryOp, UnaryOp},
+        parser::FileParser,
+        path::AbsolutePath,
+    };
+
+    use super::HirBuilder;
+
+    fn parse(src: &str) -> ItemTable {
+        let parsed = FileParser::new_test(src).parse();
+        assert!(parsed.errors.is_empty(), "parsing failed");
+        parsed.item_table
+    }
+
+    /// Translates `src`, returning diagnostics reported in case of failure.
+    fn translate(src: &str) -> Result<Hir, Vec<Diagnostic>> {
+        let context = Context::new_test();
+        let mut builder = HirBuilder::new(context.clone());
+        builder.populate(&parse(src));
+        builder
+            .build()
+            .map_err(|_| context.error_reporter.diagnostics())
+    }
+
+    fn messages(errors: &[Diagnostic]) -> Vec<String> {
+        errors.iter().map(|error| error.message.clone()).collect()
+    }
+
+    fn translates(src: &str) -> bool {
+        translate(src).is_ok()
+    }
+
+    #[test]
+    fn aliased_import() {
+        assert!(translates(
+            "
+            mod geometry {
+                fn area() -> i32 { 42 }
+            }
+            use crate::geometry::area as compute;
+            fn main() -> i32 { compute() }
+            "
+        ));
+    }
+
+    #[test]
+    fn super_relative_import() {
+        assert!(translates(
+            "
+            fn helper() -> i32 { 1 }
+            mod inner {
+                use super::helper;
+                fn run() -> i32 { helper() }
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn unresolved_without_import() {
+        assert!(!translates(
+            "
+            fn helper() -> i32 { 1 }
+            mod inner {
+                fn run() -> i32 { helper() }
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn constant_in_function() {
+        assert!(translates(
+            "
+            const LIMIT: i32 = 4 * 8;
+            fn limit() -> i32 { LIMIT }
+            "
+        ));
+    }
+
+    #[test]
+    fn constant_by_path() {
+        assert!(translates(
+            "
+            mod limits {
+                const MAX: i32 = 32;
+            }
+            const MIN: i32 = 0;
+            mod inner {
+                fn range() -> i32 { crate::limits::MAX - super::MIN }
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn invalid_path_as_value() {
+        let Err(errors) = translate(
+            "
+            fn beyond_root() -> i32 { super::x }
+            fn missing() -> i32 { crate::limits::MAX }
+            fn missing_call() -> i32 { super::super::f() }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0218", "E0219", "E0218"]);
+        assert_eq!(
+            messages(&errors[..2]),
+            [
+                "cannot resolve path super::x: too many `super` segments",
+                "constant crate::limits::MAX is not found",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_path_as_type() {
+        let src = "fn param(x: super::T) {}\nfn local() { let y: super::T; }";
+        let Err(errors) = translate(src) else { panic!("translation succeeded"); };
+        let spans: Vec<_> = errors
+            .iter()
+            .map(|error| {
+                let span = error.span.expect("unresolved path should have a span");
+                (error.code, span.start.line, span.start.column)
+            })
+            .collect();
+        assert_eq!(spans, [("E0218", 0, 9), ("E0218", 1, 20)]);
+        assert_eq!(
+            errors[0].message,
+            "cannot resolve path super::T: too many `super` segments"
+        );
+    }
+
+    #[test]
+    fn forward_reference() {
+        assert!(translates(
+            "
+            fn area(rect: Rect) -> i32 { rect.width * rect.height }
+            fn unit() -> Rect { Rect::square(1) }
+            struct Rect { width: i32, height: i32 }
+            impl Rect {
+                fn square(side: i32) -> Rect { square(side) }
+            }
+            fn square(side: i32) -> Rect { unit() }
+            "
+        ));
+    }
+
+    #[test]
+    fn type_path_beyond_root() {
+        let Err(errors) = translate("fn run(value: super::super::Value) {}") else {
+            panic!("translation succeeded");
+        };
+        assert_eq!(
+            messages(&errors),
+            ["cannot resolve path super::super::Value: too many `super` segments"]
+        );
+    }
+
+    #[test]
+    fn for_loop() {
+        assert!(translates(
+            "
+            const LEN: i32 = 10;
+            fn sum() -> i32 {
+                let total: i32 = 0;
+                for i in 0..LEN {
+                    for j in i..LEN + 1 {
+                        if j == 5 { break; }
+                        total = total + i * j;
+                    }
+                }
+                total
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn for_loop_scope() {
+        let Err(errors) = translate(
+            "
+            fn leaked() -> i32 { for i in 0..3 {} i }
+            fn mismatched() { for i in 0..true {} }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0208", "E0205"]);
+    }
+
+    #[test]
+    fn array_iteration() {
+        assert!(translates(
+            "fn sum(a: [i32; 3]) -> i32 { let s: i32 = 0; for x in a { s = s + x; } s }"
+        ));
+        let Err(errors) = translate("fn run(a: [bool; 2]) -> i32 { for x in a { return x; } 0 }")
+        else {
+            panic!("translation succeeded");
+        };
+        assert_eq!(
+            messages(&errors),
+            ["mismatched types: expected `i32`, found `bool`"]
+        );
+    }
+
+    #[test]
+    fn not_iterable() {
+        let Err(errors) = translate(
+            "
+            fn bool() { for x in true {} }
+            fn range() -> i32 { let r: i32 = 0..3; r }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0220", "E0221"]);
+        assert_eq!(
+            messages(&errors[..1]),
+            ["value of type `bool` can't be iterated over, only ranges and arrays are iterable"]
+        );
+    }
+
+    #[test]
+    fn signed_literal() {
+        let hir = translate("fn run() -> i8 { let x: i8 = -128; +x }").unwrap();
+        let body = &hir.get_function(FunctionId(0)).unwrap().body;
+        assert!(matches!(
+            &body.statements[0],
+            Statement::LetStmt { value: Some(value), .. }
+                if value.kind == ExpressionKind::Const(ConstValue::Integer(-128))
+        ));
+        assert!(matches!(
+            &body.statements[1],
+            Statement::Return(Expression {
+                kind: ExpressionKind::UnaryOp { .. },
+                ..
+            })
+        ));
+
+        let Err(errors) = translate(
+            "
+            fn unsigned() -> u8 { -1 }
+            fn overflow() -> i8 { -129 }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0205", "E0214"]);
+        let span = errors[1].span.expect("overflow should have a span");
+        assert_eq!((span.start.line, span.start.column, span.end.column), (2, 35, 38));
+
+        let hir = translate("fn min() -> i64 { -9223372036854775808 }").unwrap();
+        let body = &hir.get_function(FunctionId(0)).unwrap().body;
+        assert!(matches!(
+            &body.statements[0],
+            Statement::Return(Expression { kind: ExpressionKind::Const(value), .. })
+                if *value == ConstValue::Integer(i64::MIN.into())
+        ));
+    }
+
+    #[test]
+    fn place_assignment() {
+        assert!(translates(
+            "
+            struct Point { x: i32, y: i32 }
+            struct Line { ends: [Point; 2] }
+            fn run(line: Line, i: u8) -> i32 {
+                line.ends[0].x = 1;
+                line.ends[i].y += line.ends[0].x;
+                line.ends[1].y
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn invalid_place() {
+        let Err(errors) = translate(
+            "
+            struct Point { x: i32 }
+            fn field(p: Point) { p.z = 1; }
+            fn index(p: Point) { p[0] = 1; }
+            fn position(a: [i32; 2]) { a[true] = 1; }
+            fn value(p: Point) { p.x = false; }
+            fn undeclared() { q.x = 1; }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0222", "E0223", "E0205", "E0205", "E0208"]);
+        assert_eq!(
+            messages(&errors[..1]),
+            ["value of type `crate::Point` has no field `z`"]
+        );
+    }
+
+    #[test]
+    fn compound_type_annotations() {
+        assert!(translates(
+            "
+            mod geometry {
+                struct Point { x: i32, y: i32 }
+            }
+            fn area(corners: [crate::geometry::Point; 2], flags: (bool, bool)) {}
+            "
+        ));
+    }
+
+    #[test]
+    fn type_not_found() {
+        assert!(!translates(
+            "
+            mod geometry {
+                struct Point { x: i32, y: i32 }
+            }
+            fn area(point: Point) {}
+            "
+        ));
+    }
+
+    #[test]
+    fn associated_function() {
+        assert!(translates(
+            "
+            struct Color { r: i32, g: i32, b: i32 }
+            impl Color {
+                fn new() -> Color { Color::black() }
+                fn black() -> Color { Color::new() }
+            }
+            fn main() { Color::new(); }
+            "
+        ));
+    }
+
+    #[test]
+    fn method_call() {
+        let hir = translate(
+            "
+            struct Point { x: f32, y: f32 }
+            impl Point {
+                fn scale(self, k: f32) -> Point { self }
+                fn area(self) -> f32 { self.scale(2.0).x * self.y }
+            }
+            fn both(p: Point) { Point::area(p); p.area(); }
+            ",
+        )
+        .unwrap();
+        let path = |path| AbsolutePath::from_str(path).unwrap();
+        let both = hir.function_id(&path("crate::both")).unwrap();
+        let area = hir.function_id(&path("crate::Point::area")).unwrap();
+        let body = &hir.get_function(both).unwrap().body;
+        assert_eq!(body.statements.len(), 2);
+        for statement in body.statements.iter() {
+            let Statement::ExprStmt(Expression {
+                kind: ExpressionKind::FnCall(id, args),
+                ..
+            }) = statement
+            else {
+                panic!("statement should be function call");
+            };
+            assert_eq!(*id, area);
+            assert_eq!(
+                args.as_slice(),
+                [Expression {
+                    type_: hir.get_function(area).unwrap().params[0],
+                    kind: ExpressionKind::Var(VarId::param(0)),
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn method_call_errors() {
+        let Err(errors) = translate(
+            "
+            fn outside(self) {}
+            struct Point { x: f32 }
+            impl Point {
+                fn new() -> Point { Point::new() }
+                fn area(self) -> f32 { self.x }
+            }
+            fn missing_receiver() -> f32 { Point::area() }
+            fn not_a_method(p: Point) -> Point { p.new() }
+            fn not_found(p: Point) -> f32 { p.perimeter() }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0234", "E0237", "E0236", "E0235"]);
+        assert_eq!(
+            messages(&errors[1..]),
+            [
+                "method crate::Point::area takes `self` as the first argument, which is missing",
+                "associated function crate::Point::new has no `self` receiver, so it must be called as `crate::Point::new(..)`",
+                "no method `perimeter` found for type `crate::Point`",
+            ]
+        );
+    }
+
+    #[test]
+    fn operator_overloading() {
+        let hir = translate(
+            "
+            struct Vec2 { x: f32, y: f32 }
+            impl Vec2 {
+                fn add(self, other: Vec2) -> Vec2 { self }
+                fn eq(self, other: Vec2) -> bool { self.x == other.x && self.y == other.y }
+            }
+            fn run(a: Vec2, b: Vec2) -> bool { let c: Vec2 = a + b; c += a; c == b }
+            fn differ(a: Vec2, b: Vec2) -> bool { a != b }
+            ",
+        )
+        .unwrap();
+        let path = |path| AbsolutePath::from_str(path).unwrap();
+        let run = hir.function_id(&path("crate::run")).unwrap();
+        let add = hir.function_id(&path("crate::Vec2::add")).unwrap();
+        let eq = hir.function_id(&path("crate::Vec2::eq")).unwrap();
+        let body = &hir.get_function(run).unwrap().body;
+        let callee = |expr: &Expression| match &expr.kind {
+            ExpressionKind::FnCall(id, args) if args.len() == 2 => Some(*id),
+            _ => None,
+        };
+        let [
+            Statement::LetStmt { value: Some(sum), .. },
+            Statement::Assignment { value: assigned, .. },
+            Statement::Return(compared),
+        ] = body.statements.as_slice()
+        else {
+            panic!("unexpected body: {body:?}");
+        };
+        assert_eq!(callee(sum), Some(add));
+        assert_eq!(callee(assigned), Some(add));
+        assert_eq!(callee(compared), Some(eq));
+        assert_eq!(compared.type_, TypeId::BOOL);
+
+        let differ = hir.function_id(&path("crate::differ")).unwrap();
+        let body = &hir.get_function(differ).unwrap().body;
+        let [Statement::Return(negated)] = body.statements.as_slice() else {
+            panic!("unexpected body: {body:?}");
+        };
+        let ExpressionKind::UnaryOp {
+            operator: UnaryOp::Not,
+            value,
+            ..
+        } = &negated.kind
+        else {
+            panic!("`!=` should be negated call of `eq`");
+        };
+        assert_eq!(callee(value), Some(eq));
+    }
+
+    #[test]
+    fn operator_not_implemented() {
+        let Err(errors) = translate(
+            "
+            struct Vec2 { x: f32, y: f32 }
+            impl Vec2 {
+                fn sub(other: Vec2) -> Vec2 { other }
+            }
+            fn add(a: Vec2, b: Vec2) -> Vec2 { a + b }
+            fn sub(a: Vec2, b: Vec2) -> Vec2 { a - b }
+            fn less(a: Vec2, b: Vec2) -> bool { a < b }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0238", "E0238", "E0245"]);
+        assert_eq!(
+            messages(&errors),
+            [
+                "no `add` method found for type `crate::Vec2`",
+                "no `sub` method found for type `crate::Vec2`",
+                "operator `<` is not supported for type `crate::Vec2`",
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_items() {
+        assert!(translates(
+            "
+            fn twice(x: i32) -> i32 { x * 2 }
+            fn outer(x: i32) -> i32 {
+                struct Pair { a: i32, b: i32 }
+                fn first(pair: Pair) -> i32 { pair.a }
+                fn helper(value: i32) -> i32 {
+                    if value > 0 { helper(value - 1) } else { twice(value) + 1 }
+                }
+                helper(x)
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn nested_function_errors() {
+        let Err(errors) = translate(
+            "
+            fn outer(x: i32) -> i32 {
+                let y: i32 = x;
+                fn helper() -> i32 { y }
+                helper()
+            }
+            fn other() -> i32 { outer::helper() }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0239", "E0240"]);
+        assert_eq!(
+            errors[0].label.as_deref(),
+            Some("variable `y` is declared in the enclosing function")
+        );
+        assert_eq!(
+            messages(&errors),
+            [
+                "can't capture dynamic environment in a nested function; use parameters instead",
+                "function crate::outer::helper is declared in the body of crate::outer and may only be called inside of it",
+            ]
+        );
+    }
+
+    #[test]
+    fn impl_for_unknown_type() {
+        assert!(!translates("impl Color { fn new() {} }"));
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert!(translates("fn sum() -> i32 { 1 + 2 }"));
+    }
+
+    #[test]
+    fn negation() {
+        assert!(translates("fn negate(x: i32) -> i32 { -x }"));
+    }
+
+    #[test]
+    fn logical_not() {
+        assert!(translates("fn invert(flag: bool) -> bool { !flag }"));
+    }
+
+    #[test]
+    fn cast() {
+        assert!(translates(
+            "
+            fn to_float(x: i32) -> f32 { x as f32 }
+            fn to_int(x: f64) -> u8 { x as u8 }
+            fn from_bool(flag: bool) -> i32 { flag as i32 }
+            fn mean(a: i32, b: f32) -> f32 { (a as f32 + b) / 2.0 }
+            "
+        ));
+    }
+
+    #[test]
+    fn invalid_cast() {
+        let Err(errors) = translate(
+            "
+            struct Point { x: i32 }
+            fn from_struct(p: Point) -> i32 { p as i32 }
+            fn to_bool(x: i32) -> bool { x as bool }
+            fn to_unknown(x: i32) -> i32 { x as Missing }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0224", "E0224", "E0217"]);
+        assert_eq!(
+            messages(&errors[1..2]),
+            ["value of type `i32` can't be cast to `bool`, only numbers and booleans may be cast to numeric types"]
+        );
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let Err(errors) = translate(
+            "
+            fn div(x: i32) -> i32 { x / 0 }
+            fn rem(x: u8) -> u8 {
+                let y: u8 = x;
+                y = y % 0x0;
+                y
+            }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0226", "E0226"]);
+        let spans: Vec<_> = errors
+            .iter()
+            .map(|error| {
+                let span = error.span.expect("division by zero should have a span");
+                (span.start.line, span.start.column, span.end.column)
+            })
+            .collect();
+        assert_eq!(spans, [(1, 40, 41), (4, 24, 27)]);
+
+        assert!(translates("fn div(x: f32) -> f32 { x / 0.0 }"));
+    }
+
+    #[test]
+    fn literal_out_of_range() {
+        assert!(translates(
+            "fn run() -> u8 { let x: i8 = -128; let y: u16 = 0xFFFF; 255 }"
+        ));
+        assert!(translates(
+            "const MAX: u64 = 18446744073709551615; fn run() { let x: u64 = 18446744073709551615; }"
+        ));
+        assert!(translates(
+            "const X: i8 = -128; const Y: i64 = -9223372036854775808; fn run() -> i8 { X }"
+        ));
+        let Err(errors) = translate(
+            "
+            fn byte() { let x: u8 = 300; }
+            fn huge() -> i64 { 0xFFFFFFFFFFFFFFFFFFF }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0227", "E0227"]);
+        assert_eq!(
+            messages(&errors),
+            [
+                "integer literal out of range for `u8`",
+                "integer literal out of range for `i64`"
+            ]
+        );
+        let span = errors[0].span.expect("invalid literal should have a span");
+        assert_eq!((span.start.line, span.start.column), (1, 36));
+        assert_eq!((span.end.line, span.end.column), (1, 39));
+    }
+
+    #[test]
+    fn every_primitive_annotation() {
+        assert!(translates(
+            r#"
+            fn run() {
+                let a: bool = true;
+                let b: i8 = 1; let c: i16 = 1; let d: i32 = 1; let e: i64 = 1; let f: isize = 1;
+                let g: u8 = 1; let h: u16 = 1; let i: u32 = 1; let j: u64 = 1; let k: usize = 1;
+                let l: f32 = 1.0; let m: f64 = 1.0;
+                let n: str = "text";
+            }
+            "#
+        ));
+    }
+
+    #[test]
+    fn builtin_type_redefined() {
+        let Err(errors) = translate(
+            "
+            struct bool { value: i32 }
+            mod numbers { struct i32 {} }
+            fn negate(flag: bool) -> bool { !flag }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0228", "E0228"]);
+        // Structs are visited in arbitrary order.
+        let mut spans: Vec<_> = errors
+            .iter()
+            .map(|error| error.span.expect("redefinition should have a span"))
+            .map(|span| (span.start.line, span.start.column))
+            .collect();
+        spans.sort();
+        assert_eq!(spans, [(1, 12), (2, 26)]);
+        let mut messages = messages(&errors);
+        messages.sort();
+        assert_eq!(
+            messages,
+            [
+                "type `bool` is builtin and can't be redefined",
+                "type `i32` is builtin and can't be redefined"
+            ]
+        );
+    }
+
+    #[test]
+    fn float_comparison() {
+        assert!(translates("fn compare(a: f32, b: f32) -> bool { a <= b }"));
+    }
+
+    #[test]
+    fn equality_of_any_primitive() {
+        assert!(translates(
+            "
+            fn strings() -> bool { \"a\" == \"b\" }
+            fn booleans() -> bool { true != false }
+            fn flag(flag: bool) -> bool { flag == true }
+            "
+        ));
+    }
+
+    #[test]
+    fn invalid_condition() {
+        let Err(errors) = translate(
+            "
+            fn branch() { if 1 {} }
+            fn repeat(s: str) { while s {} }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(codes, ["E0225", "E0225"]);
+        assert_eq!(
+            messages(&errors),
+            [
+                "if condition must be `bool`, found `i32`",
+                "while condition must be `bool`, found `str`"
+            ]
+        );
+    }
+
+    #[test]
+    fn operand_type_mismatch() {
+        let Err(errors) = translate("fn sum() -> i32 { true + 1 }") else { panic!("translation succeeded"); };
+        assert_eq!(
+            messages(&errors),
+            ["mismatched types: expected `i32`, found `bool`"]
+        );
+    }
+
+    #[test]
+    fn error_span() {
+        let Err(errors) = translate("fn sum(a: i32) -> i32 {\n    a + true\n}") else { panic!("translation succeeded"); };
+        let [error] = errors.as_slice() else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(
+            error.message,
+            "mismatched types: expected `i32`, found `bool`"
+        );
+        let span = error.span.expect("type mismatch should have a span");
+        assert_eq!((span.start.line, span.start.column), (1, 8));
+        assert_eq!((span.end.line, span.end.column), (1, 12));
+    }
+
+    #[test]
+    fn item_error_spans() {
+        let src = "fn f(x: Unknown) {}\nimpl Missing {}\nconst C: u8 = 255 + 1;";
+        let Err(errors) = translate(src) else { panic!("translation succeeded"); };
+        let mut spans: Vec<_> = errors
+            .iter()
+            .map(|error| {
+                let span = error.span.expect("error should have a span");
+                (error.code, span.start.line, span.start.column)
+            })
+            .collect();
+        spans.sort();
+        assert_eq!(spans, [("E0214", 2, 14), ("E0216", 1, 5), ("E0217", 0, 5)]);
+    }
+
+    #[test]
+    fn short_circuit_and() {
+        let hir = translate("fn both(a: bool, b: bool) { let c: bool = a && b; }").unwrap();
+        let body = &hir.get_function(FunctionId(0)).unwrap().body;
+        let Statement::LetStmt { value: Some(value), .. } = &body.statements[0] else {
+            panic!("function should start with `let` statement");
+        };
+        let ExpressionKind::If { condition, body, else_body: Some(else_body) } = &value.kind else {
+            panic!("`&&` should be lowered into `if` expression");
+        };
+        assert!(matches!(condition.kind, ExpressionKind::Var(_)));
+        assert!(matches!(
+            body.tail.as_deref(),
+            Some(Expression {
+                kind: ExpressionKind::Var(_),
+                ..
+            })
+        ));
+        assert_eq!(
+            else_body,
+            &Block {
+                statements: Vec::new(),
+                tail: Some(Box::new(Expression {
+                    type_: TypeId::BOOL,
+                    kind: ExpressionKind::Literal(Literal::Boolean(false)),
+                })),
+            }
+        );
+    }
+
+    #[test]
+    fn tail_lowered_into_return() {
+        let hir = translate("fn pick(flag: bool, a: i32, b: i32) -> i32 { if flag { a } else { b } }")
+            .unwrap();
+        let var = |index, type_| Expression {
+            type_,
+            kind: ExpressionKind::Var(VarId::param(index)),
+        };
+        let returns = |index| Block {
+            statements: vec![Statement::Return(var(index, TypeId::I32))],
+            tail: None,
+        };
+        let body = &hir.get_function(FunctionId(0)).unwrap().body;
+        assert_eq!(
+            body,
+            &Block {
+                statements: vec![Statement::ExprStmt(Expression {
+                    type_: TypeId::UNIT,
+                    kind: ExpressionKind::If {
+                        condition: Box::new(var(0, TypeId::BOOL)),
+                        body: returns(1),
+                        else_body: Some(returns(2)),
+                    },
+                })],
+                tail: None,
+            }
+        );
+        assert_eq!(body.type_id(), TypeId::UNIT);
+        assert!(body.diverges(false));
+
+        // Unit functions and loops are left as is.
+        let hir = translate("fn run(x: i32) { while x > 0 { x = x - 1; } }").unwrap();
+        let body = &hir.get_function(FunctionId(0)).unwrap().body;
+        assert!(matches!(
+            body.tail.as_deref(),
+            Some(Expression {
+                kind: ExpressionKind::Loop(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn while_loop_exits_when_condition_fails() {
+        let hir = translate("fn run(x: i32) { while x > 0 { x = x - 1; } }").unwrap();
+        let body = &hir.get_function(FunctionId(0)).unwrap().body;
+        let Some(Expression { kind: ExpressionKind::Loop(body), .. }) = body.tail.as_deref() else {
+            panic!("`while` should be lowered into loop");
+        };
+        let Statement::ExprStmt(Expression {
+            kind: ExpressionKind::If { condition, body, else_body: None },
+            ..
+        }) = &body.statements[0] else {
+            panic!("loop should start with exit condition");
+        };
+        assert!(matches!(
+            &condition.kind,
+            ExpressionKind::UnaryOp {
+                operator: UnaryOp::Not,
+                value,
+                ..
+            } if matches!(value.kind, ExpressionKind::BinaryOp { operator: BinaryOp::More, .. })
+        ));
+        assert_eq!(body.statements, [Statement::Break]);
+    }
+
+    #[test]
+    fn intrinsic_argument_mismatch() {
+        let Err(errors) = translate("fn main() { println(1); }") else { panic!("translation succeeded"); };
+        assert_eq!(
+            messages(&errors),
+            ["mismatched types: expected `str`, found `i32`"]
+        );
+        assert!(translates(
+            "fn print(x: i32) {} fn main() { print(1); println(\"shadowed\"); }"
+        ));
+    }
+
+    #[test]
+    fn logical_operand_mismatch() {
+        let Err(errors) = translate("fn both(b: bool) -> bool { 1 && b }") else { panic!("translation succeeded"); };
+        assert_eq!(
+            messages(&errors),
+            ["mismatched types: expected `bool`, found `i32`"]
+        );
+    }
+
+    #[test]
+    fn strings() {
+        assert!(translates(
+            "
+            fn echo(s: str) -> str { s }
+            fn is_greeting(s: str) -> bool {
+                let greeting: str = \"hi\";
+                echo(s) == greeting
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn inferred_literal_width() {
+        assert!(translates(
+            "
+            fn wide(x: i64) -> i64 { x * 2 }
+            fn main() -> u8 {
+                let small: u8 = 5;
+                wide(10);
+                small + 1
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn width_mismatch() {
+        let Err(errors) = translate(
+            "
+            fn wide(x: i64) -> i64 { x }
+            fn main() {
+                let small: u8 = 5;
+                wide(small);
+            }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        assert_eq!(messages(&errors), ["mismatched types: expected `i64`, found `u8`"]);
+    }
+
+    #[test]
+    fn if_without_else_has_unit_body() {
+        assert!(translates(
+            "
+            fn run(flag: bool) {
+                let x: i32 = 0;
+                if flag { x = 1; }
+            }
+            "
+        ));
+        let Err(errors) = translate("fn run(flag: bool) -> i32 { if flag { 1 } }") else {
+            panic!("translation succeeded");
+        };
+        assert_eq!(
+            messages(&errors),
+            ["mismatched types: expected `()`, found `i32`"]
+        );
+    }
+
+    #[test]
+    fn unit_function_with_tail() {
+        assert!(!translates("fn run() { 1 }"));
+    }
+
+    #[test]
+    fn return_on_every_path() {
+        assert!(translates(
+            "
+            fn sign(x: i32) -> i32 {
+                if x < 0 { return 0 - 1; } else { return 1; }
+            }
+            "
+        ));
+    }
+
+    #[test]
+    fn return_in_one_branch() {
+        let Err(errors) = translate(
+            "
+            fn sign(x: i32) -> i32 {
+                if x < 0 { return 0 - 1; } else { x = 1; }
+            }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        assert_eq!(
+            messages(&errors),
+            ["function must return a value of type `i32` on every path"]
+        );
+    }
+
+    #[test]
+    fn unreachable_statement() {
+        let context = Context::new_test();
+        let mut builder = HirBuilder::new(context.clone());
+        builder.populate(&parse("fn run() -> i32 { return 1; 2 }"));
+        assert!(builder.build().is_ok());
+        assert!(context.error_reporter.to_string().contains("unreachable"));
+    }
+
+    #[test]
+    fn shadowed_variable() {
+        let context = Context::new_test();
+        let mut builder = HirBuilder::new(context.clone());
+        builder.populate(&parse(
+            "fn run() -> i32 { let x: i32 = 1; let x: i32 = x + 1; x }",
+        ));
+        assert!(builder.build().is_ok());
+        let diagnostics = context.error_reporter.diagnostics();
+        let [warning] = diagnostics.as_slice() else {
+            panic!("unexpected diagnostics: {diagnostics:?}");
+        };
+        assert_eq!(
+            warning.message,
+            "variable `x` shadows the one declared earlier in the same scope"
+        );
+        let span = warning.span.expect("shadowing should have a span");
+        assert_eq!((span.start.column, span.end.column), (34, 53));
+        let [label] = warning.secondary_labels.as_slice() else {
+            panic!("earlier declaration should be labeled");
+        };
+        assert_eq!(label.message, "previously declared here");
+        assert_eq!((label.span.start.column, label.span.end.column), (18, 33));
+    }
+
+    #[test]
+    fn function_suggestions() {
+        let Err(errors) = translate(
+            "
+            mod geometry {
+                fn area() -> i32 { 1 }
+            }
+            fn areas() -> i32 { 2 }
+            fn main() -> i32 { area() }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let [error] = errors.as_slice() else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(
+            error.message,
+            "function crate::area is not found, did you mean one of: `crate::geometry::area`, `crate::areas`?"
+        );
+    }
+
+    #[test]
+    fn variable_called() {
+        let Err(errors) = translate("fn run(foo: i32) -> i32 { foo(1) }") else {
+            panic!("translation succeeded");
+        };
+        let [error] = errors.as_slice() else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(error.code, "E0231");
+        assert_eq!(error.message, "`foo` is a variable, not a function");
+    }
+
+    #[test]
+    fn struct_called() {
+        let Err(errors) = translate(
+            "
+            struct Point { x: i32, y: i32 }
+            fn origin() { Point(0, 0); }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let [error] = errors.as_slice() else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(error.code, "E0232");
+        assert_eq!(error.message, "crate::Point is a struct, not a function");
+    }
+
+    #[test]
+    fn module_called() {
+        let Err(errors) = translate(
+            "
+            mod geometry {
+                fn area() -> i32 { 1 }
+            }
+            fn main() -> i32 { geometry() }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let [error] = errors.as_slice() else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(error.code, "E0233");
+        assert_eq!(error.message, "crate::geometry is a module, not a function");
+    }
+
+    #[test]
+    fn argument_count_signature() {
+        let Err(errors) = translate(
+            "
+            fn scale(x: i32, factor: (f32, bool)) -> i32 { x }
+            fn main() -> i32 { scale(1) }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        assert_eq!(
+            errors[0].message,
+            "incorrect number of arguments provided for function crate::scale with signature `fn(i32, (f32, bool)) -> i32`. Expected 2, received 1."
+        );
+    }
+
+    #[test]
+    fn direct_recursion() {
+        assert!(translates(
+            "fn fact(n: i32) -> i32 { if n <= 1 { 1 } else { n * fact(n - 1) } }"
+        ));
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        assert!(translates(
+            "
+            fn is_even(n: i32) -> bool { if n == 0 { true } else { is_odd(n - 1) } }
+            fn is_odd(n: i32) -> bool { if n == 0 { false } else { is_even(n - 1) } }
+            "
+        ));
+    }
+
+    #[test]
+    fn recursion_with_wrong_argument() {
+        let Err(errors) =
+            translate("fn fact(n: i32) -> i32 { if n <= 1 { 1 } else { n * fact(n < 1) } }")
+        else {
+            panic!("translation succeeded");
+        };
+        assert_eq!(
+            messages(&errors),
+            ["mismatched types: expected `i32`, found `bool`"]
+        );
+    }
+
+    #[test]
+    fn call_with_invalid_signature() {
+        let Err(errors) = translate(
+            "
+            fn broken(point: Point) -> i32 { 1 }
+            fn main() -> i32 { broken(1) }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        assert!(errors
+            .iter()
+            .any(|error| error.message.contains("signature contains errors")));
+    }
+
+    #[test]
+    fn partial_translation() {
+        let mut builder = HirBuilder::new(Context::new_test());
+        builder.populate(&parse(
+            "
+            fn first() -> i32 { 1 }
+            fn second() -> i32 { true }
+            fn third() -> bool { false }
+            ",
+        ));
+        let (hir, errors) = builder.build_partial();
+        assert_eq!(errors, 1);
+
+        let function = |name: &str| {
+            let path = AbsolutePath::from_str(&format!("crate::{name}")).unwrap();
+            hir.function_id(&path).and_then(|id| hir.get_function(id))
+        };
+        assert_eq!(function("first").map(|f| f.return_type), Some(TypeId::I32));
+        assert!(function("second").is_none());
+        assert_eq!(function("third").map(|f| f.return_type), Some(TypeId::BOOL));
+    }
+
+    #[test]
+    fn infinite_size() {
+        let Err(errors) = translate(
+            "
+            struct A { b: B }
+            struct B { values: [(i32, A); 2] }
+            struct S { s: S }
+            struct C { a: A }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let mut messages = messages(&errors);
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec![
+                "recursive type `crate::S` has infinite size",
+                "recursive types `crate::A` -> `crate::B` -> `crate::A` have infinite size",
+            ]
+        );
+        // Errors point at the field that closes the cycle.
+        let mut spans: Vec<_> = errors
+            .iter()
+            .map(|error| {
+                let span = error.span.expect("infinite size error should have a span");
+                (span.start.line, span.start.column, span.end.column)
+            })
+            .collect();
+        spans.sort();
+        assert_eq!(spans, [(2, 23, 44), (3, 23, 27)]);
+    }
+
+    #[test]
+    fn derive_errors() {
+        let Err(errors) = translate(
+            "
+            #[derive(clone)]
+            struct A { x: i32 }
+            #[derive(to_string)]
+            struct B { x: f32 }
+            #[derive(to_string)]
+            struct C {}
+            impl C {
+                fn to_string(self) -> str { \"C\" }
+            }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let mut codes: Vec<_> = errors.iter().map(|error| error.code).collect();
+        codes.sort();
+        assert_eq!(codes, vec!["E0241", "E0242", "E0243"]);
+    }
+
+    #[test]
+    fn test_signatures() {
+        let Err(errors) = translate(
+            "
+            #[test] fn unit() {}
+            #[test] fn boolean() -> bool { true }
+            #[test] fn parameter(x: i32) {}
+            #[test] fn integer() -> i32 { 1 }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let mut messages = messages(&errors);
+        messages.sort();
+        assert_eq!(
+            messages,
+            [
+                "test crate::integer must take no parameters and return `bool` or nothing",
+                "test crate::parameter must take no parameters and return `bool` or nothing",
+            ]
+        );
+    }
+
+    #[test]
+    fn assertion_types() {
+        let Err(errors) = translate(
+            "
+            struct Point { x: i32 }
+            fn valid(x: i32, flag: bool) { assert(flag); assert_eq(x, 1); }
+            fn condition(x: i32) { assert(x); }
+            fn operands(x: i32, flag: bool) { assert_eq(x, flag); }
+            fn no_eq(point: Point) { assert_eq(point, point); }
+            fn arity(x: i32) { assert_eq(x); }
+            fn message(x: i32) { panic(x); }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        let mut messages = messages(&errors);
+        messages.sort();
+        assert_eq!(
+            messages,
+            [
+                "incorrect number of arguments provided for function crate::assert_eq with \
+                 signature `fn(T, T)`. Expected 2, received 1.",
+                "mismatched types: expected `bool`, found `i32`",
+                "mismatched types: expected `i32`, found `bool`",
+                "mismatched types: expected `str`, found `i32`",
+                "no `eq` method found for type `crate::Point`",
+            ]
+        );
+    }
+
+    #[test]
+    fn assigned_in_one_branch() {
+        let Err(errors) = translate("fn run(c: bool) -> i32 { let x: i32; if c { x = 1; } x }")
+        else {
+            panic!("translation succeeded");
+        };
+        let [error] = errors.as_slice() else {
+            panic!("unexpected errors: {errors:?}");
+        };
+        assert_eq!(
+            error.message,
+            "use of possibly uninitialized variable `x` declared at 1:26"
+        );
+        assert_eq!(error.span.map(|span| span.start.column), Some(53));
+    }
+
+    #[test]
+    fn assigned_in_loop() {
+        let Err(errors) = translate(
+            "
+            fn run(n: i32) -> i32 {
+                let x: i32;
+                while n > 0 { x = n; n = n - 1; }
+                x
+            }
+            ",
+        ) else {
+            panic!("translation succeeded");
+        };
+        assert!(errors[0]
+            .message
+            .starts_with("use of possibly uninitialized variable `x`"));
+
+        assert!(!translates(
+            "fn run() -> i32 { let x: i32; for i in 0..3 { x = i; } x }"
+        ));
+        assert!(!translates("fn run() -> i32 { let x: i32; x += 1; x }"));
+    }
+
+    #[test]
+    fn late_initialization() {
+        assert!(translates(
+            "
+            fn run(c: bool) -> i32 {
+                let x: i32;
+                if c { x = 1; } else { x = 2; }
+                let y: i32;
+                if c { y = 3; } else { return 0; }
+                while x < y { let z: i32; z = x; x = z + 1; }
+                x + y
+            }
+            "
+        ));
+    }
 }
@@ -1,59 +1,106 @@
+use std::sync::Arc;
+
 use crate::{
     ast::{
         expression::Block as AstBlock,
-        expression::{Expression as AstExpression, Literal},
-        statement::LetStatement,
-        statement::Statement as AstStatement,
+        expression::{ExprArena, ExprId, ExpressionKind as AstExpressionKind, Literal},
+        statement::{LetStatement, Place, Projection},
+        statement::{Statement as AstStatement, StatementKind as AstStatementKind},
+    },
+    error::{
+        library::hir::{ShadowedVariable, UnreachableCode},
+        ErrorReporter, Label, ReportProvider,
     },
     hir::{
-        scope::Scope,
-        types::{PrimitiveType, TypeId},
-        Block, Expression, ExpressionKind, HirBuilder, Statement, TranslationError,
+        intrinsic::Intrinsic,
+        scope::{Scope, VarId},
+        types::TypeId,
+        Block, ConstValue, Expectation, Expression, ExpressionKind, HirBuilder, Statement,
+        TranslationError,
+    },
+    identifier::Identifier,
+    input_stream::Location,
+    lexer::{
+        number::{Base, Number},
+        operator::{BinaryOp, UnaryOp},
     },
-    lexer::number::Number,
-    path::{AbsolutePath, RelativePath},
+    path::{AbsolutePath, RelativePath, RelativePathStart},
+    source::SourceId,
+    util::Span,
 };
 
-use super::PartiallyParsedFunction;
+use super::{
+    constant::{check_bounds, evaluate_literal, evaluate_unary},
+    init::InitState,
+    Callee, PartiallyParsedFunction,
+};
 
 pub(super) struct BodyBuilder<'b> {
     parent: &'b HirBuilder,
+    /// Expressions of the translated function.
+    exprs: &'b ExprArena,
+    span: Span,
     module: AbsolutePath,
-    return_type: Option<TypeId>,
+    /// Paths of the function and functions that enclose it, innermost first.
+    bodies: Vec<AbsolutePath>,
+    return_type: TypeId,
+    return_type_span: Option<Span>,
     scope: Scope,
+    init: InitState,
 }
 
 impl<'b> BodyBuilder<'b> {
     pub fn translate(
         parent: &'b HirBuilder,
+        exprs: &'b ExprArena,
         partial: PartiallyParsedFunction,
     ) -> Result<Block, TranslationError> {
         let mut builder = Self {
             parent,
+            exprs,
+            span: partial.span,
             module: partial.module,
+            bodies: partial.bodies,
             return_type: partial.return_type,
+            return_type_span: partial.return_type_span,
             scope: Scope::new(),
+            init: InitState::default(),
         };
 
-        for (name, type_id) in partial.params {
-            builder.scope.insert(name, type_id);
+        for (name, type_id, span) in partial.params {
+            builder.scope.insert(name, type_id, span);
         }
 
-        let body = builder.translate_block(partial.body, false)?;
-        if body.type_id() != partial.return_type {
+        let mut body = builder.translate_block(&partial.body, false, Some(partial.return_type))?;
+        if body.type_id() != partial.return_type && !body.diverges(false) {
+            if body.type_id() == TypeId::UNIT {
+                return Err(TranslationError::MissingReturn {
+                    expected: builder.type_name(partial.return_type),
+                    span: partial.span,
+                });
+            }
             return Err(TranslationError::TypeMismatch {
-                expected: partial.return_type,
-                received: body.type_id(),
+                expected: builder.type_name(partial.return_type),
+                received: builder.type_name(body.type_id()),
+                span: partial.span,
+                origin: partial
+                    .return_type_span
+                    .map(|span| Box::new(Expectation::ReturnType(span))),
             });
         }
 
+        if partial.return_type != TypeId::UNIT {
+            lower_tail_return(&mut body, partial.return_type);
+        }
         Ok(body)
     }
 
+    /// Translates block. `expected` is a type its tail expression is expected to have, if known.
     fn translate_block(
         &mut self,
-        block: AstBlock,
+        block: &AstBlock,
         is_loop: bool,
+        expected: Option<TypeId>,
     ) -> Result<Block, TranslationError> {
         if is_loop {
             self.scope = self.scope.child_loop();
@@ -62,13 +109,15 @@ impl<'b> BodyBuilder<'b> {
         }
         let block = {
             let mut tail = None;
-            let mut statements = Vec::new();
-            for stmt in block.statements {
+            let mut statements: Vec<Statement> = Vec::new();
+            for stmt in block.statements.iter() {
+                self.check_reachable(&statements, stmt.span);
                 let stmt = self.translate_stmt(stmt)?;
                 statements.push(stmt);
             }
             if let Some(expr) = block.expression {
-                let expr = self.translate_expr(*expr)?;
+                self.check_reachable(&statements, self.exprs[expr].span);
+                let expr = self.translate_expr(expr, expected)?;
                 tail = Some(Box::new(expr));
             }
             Ok(Block { statements, tail })
@@ -77,194 +126,523 @@ impl<'b> BodyBuilder<'b> {
         block
     }
 
-    fn translate_stmt(&mut self, stmt: AstStatement) -> Result<Statement, TranslationError> {
-        match stmt {
-            AstStatement::ExprStmt(expr) => self.translate_expr(expr).map(Statement::ExprStmt),
-            AstStatement::LetStmt(LetStatement { name, type_, value }) => {
-                let Some(type_) = type_ else { return Err(TranslationError::TypeInference)};
-                let type_ = self.parent.type_table.get(type_)?;
-                let value = match value {
+    /// Warns if the last of already translated `statements` interrupts control flow.
+    ///
+    /// `span` is the span of the code following them.
+    fn check_reachable(&self, statements: &[Statement], span: Span) {
+        if statements.last().is_some_and(|stmt| stmt.diverges(true)) {
+            let _ = UnreachableCode::report_at(&self.error_reporter(), span);
+        }
+    }
+
+    fn translate_stmt(&mut self, stmt: &AstStatement) -> Result<Statement, TranslationError> {
+        let span = stmt.span;
+        match &stmt.kind {
+            AstStatementKind::ExprStmt(expr) => {
+                self.translate_expr(*expr, None).map(Statement::ExprStmt)
+            }
+            AstStatementKind::LetStmt(LetStatement {
+                name,
+                type_,
+                type_span,
+                value,
+            }) => {
+                let Some(type_) = type_ else { return Err(TranslationError::TypeInference { span })};
+                let annotation_span = type_span.unwrap_or(span);
+                let type_ =
+                    self.parent
+                        .get_type(&self.bodies, &self.module, type_, annotation_span)?;
+                let value = match *value {
                     Some(value) => {
-                        let value = self.translate_expr(*value)?;
-                        if value.type_ != Some(type_) {
-                            return Err(TranslationError::TypeMismatch {
-                                expected: Some(type_),
-                                received: value.type_,
-                            });
-                        }
+                        let value_span = self.exprs[value].span;
+                        let value = self.translate_expr(value, Some(type_))?;
+                        let origin = type_span.map(Expectation::Annotation);
+                        self.expect_type(type_, &value, value_span, origin)?;
                         Some(Box::new(value))
                     }
                     None => None,
                 };
-                let var = self.scope.insert(name, type_);
+                if let Some((_, earlier)) = self.scope.lookup_local(name) {
+                    let labels = vec![Label::new(earlier, "previously declared here")];
+                    let reporter = &self.error_reporter();
+                    let _ = ShadowedVariable::report_labeled(reporter, span, labels, name.clone());
+                }
+                let var = self.scope.insert(name.clone(), type_, span);
+                if value.is_none() {
+                    self.init.declare(var, span);
+                }
                 Ok(Statement::LetStmt { var, type_, value })
             }
-            AstStatement::Assignment {
+            AstStatementKind::Assignment {
                 assignee,
                 operator,
-                mut expression,
+                expression,
             } => {
-                let Some((var, type_id)) = self.scope.lookup(&assignee) else {
-                    return Err(TranslationError::VariableNotDeclared(assignee))
-                };
+                // Only plain assignment to the variable itself initializes it.
+                let initializes =
+                    assignee.projections.is_empty() && operator.to_respective_binary_op().is_none();
+                let assignee = self.translate_place(assignee, !initializes, span)?;
+                let type_id = assignee.type_;
 
-                if let Some(operator) = operator.to_respective_binary_op() {
-                    expression = AstExpression::Binary {
-                        op: operator,
-                        left: Box::new(AstExpression::Var(assignee)),
-                        right: Box::new(expression),
-                    };
-                }
+                let value_span = self.exprs[*expression].span;
+                let value = match operator.to_respective_binary_op() {
+                    // `x op= value` is translated as `x = x op value`, so indices of the place
+                    // are computed twice.
+                    Some(operator) => {
+                        self.translate_binary_op(operator, assignee.clone(), span, *expression)?
+                    }
+                    None => self.translate_expr(*expression, Some(type_id))?,
+                };
+                self.expect_type(type_id, &value, value_span, None)?;
 
-                let value = self.translate_expr(expression)?;
-                if value.type_ != Some(type_id) {
-                    return Err(TranslationError::TypeMismatch {
-                        expected: Some(type_id),
-                        received: value.type_,
-                    });
+                if let ExpressionKind::Var(var) = &assignee.kind {
+                    self.init.assign(*var);
                 }
-
-                Ok(Statement::Assignment {
-                    assignee: var,
-                    value,
-                })
+                Ok(Statement::Assignment { assignee, value })
             }
-            AstStatement::Return(expr) => {
-                let expr = self.translate_expr(expr)?;
-                if expr.type_ != self.return_type {
-                    return Err(TranslationError::TypeMismatch {
-                        expected: self.return_type,
-                        received: expr.type_,
-                    });
-                }
+            AstStatementKind::Return(expr) => {
+                let expr_span = self.exprs[*expr].span;
+                let expr = self.translate_expr(*expr, Some(self.return_type))?;
+                let origin = self.return_type_span.map(Expectation::ReturnType);
+                self.expect_type(self.return_type, &expr, expr_span, origin)?;
+                self.init.diverge();
                 Ok(Statement::Return(expr))
             }
-            AstStatement::Break => {
+            AstStatementKind::Break => {
                 if self.scope.is_loop() {
+                    self.init.diverge();
                     Ok(Statement::Break)
                 } else {
-                    Err(TranslationError::InvalidBreak)
+                    Err(TranslationError::InvalidBreak { span })
                 }
             }
         }
     }
 
-    fn translate_expr(&mut self, expr: AstExpression) -> Result<Expression, TranslationError> {
-        Ok(match expr {
-            AstExpression::Block(block) => {
-                let block = self.translate_block(block, false)?;
+    /// Translates expression.
+    ///
+    /// `expected` is a type the expression is expected to have, if known.
+    /// It is used to infer type of integer and float literals and isn't checked here.
+    fn translate_expr(
+        &mut self,
+        expr: ExprId,
+        expected: Option<TypeId>,
+    ) -> Result<Expression, TranslationError> {
+        let exprs = self.exprs;
+        let span = exprs[expr].span;
+        Ok(match &exprs[expr].kind {
+            AstExpressionKind::Block(block) => {
+                let block = self.translate_block(block, false, expected)?;
                 Expression {
                     type_: block.type_id(),
                     kind: ExpressionKind::Block(block),
                 }
             }
-            AstExpression::If {
+            AstExpressionKind::If {
                 condition,
                 body,
                 else_body,
-            } => self.translate_if_expr(*condition, body, else_body)?,
-            AstExpression::While { condition, body } => {
+            } => self.translate_if_expr(*condition, body, else_body.as_ref(), expected, span)?,
+            AstExpressionKind::While { condition, body } => {
                 self.translate_while_loop(*condition, body)?
             }
-            AstExpression::For { .. } => todo!(),
-            AstExpression::Unary { op, value } => {
-                let value = self.translate_expr(*value)?;
-                if value.type_ != Some(op.in_type()) {
+            AstExpressionKind::For { var, expr, body } => {
+                self.translate_for_loop(var, *expr, body)?
+            }
+            AstExpressionKind::Unary {
+                op: op @ (UnaryOp::Add | UnaryOp::Sub),
+                value,
+            } if matches!(
+                exprs[*value].kind,
+                AstExpressionKind::Literal(Literal::Number(_))
+            ) =>
+            {
+                self.translate_signed_literal(*op, *value, expected, span)?
+            }
+            AstExpressionKind::Unary { op, value } => {
+                let in_types = op.in_types();
+                let hint = expected.filter(|type_| in_types.contains(type_));
+                let value_span = exprs[*value].span;
+                let value = self.translate_expr(*value, hint)?;
+                if !in_types.contains(&value.type_) {
                     return Err(TranslationError::TypeMismatch {
-                        expected: Some(op.in_type()),
-                        received: value.type_,
+                        expected: self.type_name(in_types[0]),
+                        received: self.type_name(value.type_),
+                        span: value_span,
+                        origin: None,
                     });
                 }
                 Expression {
-                    type_: Some(op.out_type()),
+                    type_: op.out_type(value.type_),
                     kind: ExpressionKind::UnaryOp {
-                        operator: op,
+                        operator: *op,
                         value: Box::new(value),
+                        span,
                     },
                 }
             }
-            AstExpression::Binary { op, left, right } => {
-                let left = self.translate_expr(*left)?;
-                if left.type_ != Some(op.in_type()) {
-                    return Err(TranslationError::TypeMismatch {
-                        expected: Some(op.in_type()),
-                        received: left.type_,
-                    });
-                }
-                let right = self.translate_expr(*right)?;
-                if right.type_ != Some(op.in_type()) {
-                    return Err(TranslationError::TypeMismatch {
-                        expected: Some(op.in_type()),
-                        received: right.type_,
+            AstExpressionKind::Cast { value, type_ } => {
+                let target = self
+                    .parent
+                    .get_type(&self.bodies, &self.module, type_, span)?;
+                let value = self.translate_expr(*value, None)?;
+                if !value.type_.can_cast_to(target) {
+                    return Err(TranslationError::InvalidCast {
+                        from: self.type_name(value.type_),
+                        to: self.type_name(target),
+                        span,
                     });
                 }
                 Expression {
-                    type_: Some(op.out_type()),
-                    kind: ExpressionKind::BinaryOp {
-                        operator: op,
-                        left: Box::new(left),
-                        right: Box::new(right),
-                    },
+                    type_: target,
+                    kind: ExpressionKind::Cast(Box::new(value)),
                 }
             }
-            AstExpression::FnCall {
+            AstExpressionKind::Binary {
+                op: op @ (BinaryOp::And | BinaryOp::Or),
+                left,
+                right,
+            } => self.translate_logical_op(*op, *left, *right)?,
+            AstExpressionKind::Binary {
+                op: BinaryOp::Range,
+                ..
+            } => return Err(TranslationError::RangeOutsideFor { span }),
+            AstExpressionKind::Binary { op, left, right } => {
+                let in_types = op.in_types();
+                let hint = match op.is_comparison() {
+                    true => None,
+                    false => expected.filter(|type_| in_types.contains(type_)),
+                };
+                let left_span = exprs[*left].span;
+                let left = self.translate_expr(*left, hint)?;
+                self.translate_binary_op(*op, left, left_span, *right)?
+            }
+            AstExpressionKind::FnCall {
                 path,
                 params: ast_args,
-            } => self.translate_fn_call(path, ast_args)?,
-            AstExpression::Var(var) => match self.scope.lookup(&var) {
-                Some((var, type_)) => Expression {
-                    type_: Some(type_),
-                    kind: ExpressionKind::Var(var),
-                },
-                None => return Err(TranslationError::VariableNotDeclared(var)),
-            },
-            AstExpression::Literal(lit) => {
-                let type_ = match lit {
-                    Literal::Number(Number { fraction: None, .. }) => {
-                        TypeId::Primitive(PrimitiveType::I32)
+            } => self.translate_fn_call(path, ast_args, span)?,
+            AstExpressionKind::MethodCall {
+                value,
+                method,
+                params: ast_args,
+            } => self.translate_method_call(*value, method, ast_args, span)?,
+            AstExpressionKind::Var(name) => match self.scope.lookup(name) {
+                Some((var, type_)) => {
+                    self.check_initialized(var, name, span)?;
+                    Expression {
+                        type_,
+                        kind: ExpressionKind::Var(var),
                     }
-                    Literal::Number(Number {
-                        fraction: Some(_), ..
-                    }) => TypeId::Primitive(PrimitiveType::F32),
-                    Literal::String(_) => todo!(),
-                    Literal::Boolean(_) => TypeId::Primitive(PrimitiveType::Bool),
-                };
+                }
+                None => {
+                    let mut path = self.module.clone();
+                    path.push(name.clone());
+                    match self.parent.query_const(&path) {
+                        Some((type_, value)) => Expression {
+                            type_,
+                            kind: ExpressionKind::Const(value),
+                        },
+                        None => return Err(self.undeclared_variable(name, span)),
+                    }
+                }
+            },
+            AstExpressionKind::Path(path) => self.translate_path(path, span)?,
+            AstExpressionKind::FieldAccess { value, field } => {
+                let value = self.translate_expr(*value, None)?;
+                self.access_field(value, field, span)?
+            }
+            AstExpressionKind::Index { value, index } => {
+                let value = self.translate_expr(*value, None)?;
+                self.access_element(value, *index, span)?
+            }
+            AstExpressionKind::Literal(lit) => {
+                let type_ = literal_type(lit, expected);
+                if let (Literal::Number(number), TypeId::Primitive(primitive)) = (lit, type_) {
+                    number
+                        .to_value(primitive)
+                        .map_err(|error| TranslationError::InvalidLiteral { error, span })?;
+                }
                 Expression {
-                    type_: Some(type_),
-                    kind: ExpressionKind::Literal(lit),
+                    type_,
+                    kind: ExpressionKind::Literal(lit.clone()),
                 }
             }
         })
     }
 
-    fn translate_if_expr(
+    /// Translates numeric literal `value` preceded by unary plus or minus into a constant, so
+    /// the sign isn't applied at runtime.
+    ///
+    /// Signed literal is located at `span`, that overflow is reported at.
+    fn translate_signed_literal(
+        &self,
+        op: UnaryOp,
+        value: ExprId,
+        expected: Option<TypeId>,
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let in_types = op.in_types();
+        let hint = expected.filter(|type_| in_types.contains(type_));
+        let AstExpressionKind::Literal(literal) = &self.exprs[value].kind else {
+            unreachable!("only numeric literals may be signed");
+        };
+        // Range of the literal is checked after the sign is applied, so `-128` is a valid `i8`.
+        let type_ = literal_type(literal, hint);
+        if !in_types.contains(&type_) {
+            return Err(TranslationError::TypeMismatch {
+                expected: self.type_name(in_types[0]),
+                received: self.type_name(type_),
+                span: self.exprs[value].span,
+                origin: None,
+            });
+        }
+        let value = evaluate_literal(literal.clone(), Some(type_))
+            .and_then(|(literal, _)| evaluate_unary(op, literal, type_))
+            .and_then(|value| check_bounds(value, type_))
+            .map_err(|err| err.at(span))?;
+        Ok(Expression {
+            type_,
+            kind: ExpressionKind::Const(value),
+        })
+    }
+
+    /// Translates place that is assigned to by statement at `span`.
+    ///
+    /// Unlike variables used as values, the root of the place can't be a constant. It must be
+    /// initialized if `is_use` is set, e.g. if only its field is assigned.
+    fn translate_place(
+        &mut self,
+        place: &Place,
+        is_use: bool,
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let Some((var, type_)) = self.scope.lookup(&place.var) else {
+            return Err(self.undeclared_variable(&place.var, span));
+        };
+        if is_use {
+            self.check_initialized(var, &place.var, span)?;
+        }
+        let mut expr = Expression {
+            type_,
+            kind: ExpressionKind::Var(var),
+        };
+        for projection in place.projections.iter() {
+            expr = match projection {
+                Projection::Field(field) => self.access_field(expr, field, span)?,
+                Projection::Index(index) => self.access_element(expr, *index, span)?,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Translates access to `field` of already translated `value`.
+    fn access_field(
+        &self,
+        value: Expression,
+        field: &Identifier,
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let Some(type_) = self.parent.type_table.field(value.type_, field) else {
+            return Err(TranslationError::FieldNotFound {
+                type_: self.type_name(value.type_),
+                field: field.clone(),
+                span,
+            });
+        };
+        Ok(Expression {
+            type_,
+            kind: ExpressionKind::Field {
+                value: Box::new(value),
+                field: field.clone(),
+            },
+        })
+    }
+
+    /// Translates access to element of already translated `value` by `index`.
+    fn access_element(
+        &mut self,
+        value: Expression,
+        index: ExprId,
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let Some((type_, _)) = self.parent.type_table.array_element(value.type_) else {
+            return Err(TranslationError::NotIndexable {
+                type_: self.type_name(value.type_),
+                span,
+            });
+        };
+        let index_span = self.exprs[index].span;
+        let index = self.translate_expr(index, Some(TypeId::USIZE))?;
+        if !index.type_.is_integer() {
+            return Err(TranslationError::TypeMismatch {
+                expected: self.type_name(TypeId::USIZE),
+                received: self.type_name(index.type_),
+                span: index_span,
+                origin: None,
+            });
+        }
+        Ok(Expression {
+            type_,
+            kind: ExpressionKind::Index {
+                value: Box::new(value),
+                index: Box::new(index),
+            },
+        })
+    }
+
+    /// Translates binary operator applied to already translated `left` operand, that was
+    /// translated from code at `left_span`.
+    fn translate_binary_op(
+        &mut self,
+        op: BinaryOp,
+        left: Expression,
+        left_span: Span,
+        right: ExprId,
+    ) -> Result<Expression, TranslationError> {
+        if let TypeId::Compound(_) = left.type_ {
+            let Some(method) = op.method_name() else {
+                return Err(TranslationError::UnsupportedOperator {
+                    operator: op,
+                    type_: self.type_name(left.type_),
+                    span: left_span.join(self.exprs[right].span),
+                });
+            };
+            return self.translate_overloaded_op(method, op, left, left_span, right);
+        }
+        let right_span = self.exprs[right].span;
+        let right = self.translate_right_operand(op, &left, left_span, right)?;
+        if matches!(op, BinaryOp::Div | BinaryOp::Mod) && is_integer_zero(&right) {
+            return Err(TranslationError::DivisionByZero { span: right_span });
+        }
+        Ok(Expression {
+            type_: op.out_type(left.type_),
+            kind: ExpressionKind::BinaryOp {
+                operator: op,
+                left: Box::new(left),
+                right: Box::new(right),
+                span: left_span.join(right_span),
+                right_span,
+            },
+        })
+    }
+
+    /// Translates `op` applied to the value of a struct into a call of the method of the struct
+    /// named `method`, so `left + right` becomes `left.add(right)`.
+    ///
+    /// The method must take `self` and one more parameter, and `eq` must return `bool`.
+    /// `left != right` is negated call of `eq`, i.e. `!left.eq(right)`.
+    fn translate_overloaded_op(
+        &mut self,
+        method: &'static str,
+        op: BinaryOp,
+        left: Expression,
+        left_span: Span,
+        right: ExprId,
+    ) -> Result<Expression, TranslationError> {
+        let name = Identifier(String::from(method));
+        let overload = self
+            .parent
+            .associated_function(left.type_, &name)
+            .filter(|(_, id)| self.parent.is_method(*id))
+            .and_then(|(path, id)| {
+                let (_, params, return_type) =
+                    self.parent.query_function_info(&path, left_span).ok()?;
+                let compatible = matches!(params, [receiver, _] if *receiver == left.type_)
+                    && (method != "eq" || return_type == TypeId::BOOL);
+                compatible.then(|| (id, params[1], return_type))
+            });
+        let Some((id, param, return_type)) = overload else {
+            return Err(TranslationError::OperatorNotImplemented {
+                method,
+                type_: self.type_name(left.type_),
+                span: left_span,
+            });
+        };
+
+        let right_span = self.exprs[right].span;
+        let right = self.translate_expr(right, Some(param))?;
+        let origin = self.parent.param_span(id, 1).map(Expectation::Parameter);
+        self.expect_type(param, &right, right_span, origin)?;
+        let call = Expression {
+            type_: return_type,
+            kind: ExpressionKind::FnCall(id, vec![left, right]),
+        };
+        if op != BinaryOp::Neq {
+            return Ok(call);
+        }
+        Ok(Expression {
+            type_: TypeId::BOOL,
+            kind: ExpressionKind::UnaryOp {
+                operator: UnaryOp::Not,
+                value: Box::new(call),
+                span: left_span.join(right_span),
+            },
+        })
+    }
+
+    /// Checks type of already translated `left` operand of `op` and translates `right` one,
+    /// that is expected to have the same type.
+    fn translate_right_operand(
         &mut self,
-        condition: AstExpression,
-        body: AstBlock,
-        else_body: Option<AstBlock>,
+        op: BinaryOp,
+        left: &Expression,
+        left_span: Span,
+        right: ExprId,
     ) -> Result<Expression, TranslationError> {
-        let condition = self.translate_expr(condition)?;
-        if condition.type_ != Some(TypeId::BOOL) {
+        let in_types = op.in_types();
+        let type_ = left.type_;
+        if !in_types.contains(&type_) {
             return Err(TranslationError::TypeMismatch {
-                expected: Some(TypeId::BOOL),
-                received: condition.type_,
+                expected: self.type_name(in_types[0]),
+                received: self.type_name(type_),
+                span: left_span,
+                origin: None,
             });
         }
+        let right_span = self.exprs[right].span;
+        let right = self.translate_expr(right, Some(type_))?;
+        let origin = Expectation::LeftOperand(left_span);
+        self.expect_type(type_, &right, right_span, Some(origin))?;
+        Ok(right)
+    }
 
-        let body = self.translate_block(body, false)?;
+    /// Translates `if` expression. `span` is the span of the whole expression.
+    fn translate_if_expr(
+        &mut self,
+        condition: ExprId,
+        body: &AstBlock,
+        else_body: Option<&AstBlock>,
+        expected: Option<TypeId>,
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let condition = self.translate_condition("if", condition)?;
+
+        let before = self.init.clone();
+        let body = self.translate_block(body, false, expected)?;
+        let after_body = std::mem::replace(&mut self.init, before);
         let else_body = match else_body {
             Some(else_body) => {
-                let else_body = self.translate_block(else_body, false)?;
+                let else_body = self.translate_block(else_body, false, expected)?;
                 if body.type_id() != else_body.type_id() {
                     return Err(TranslationError::IfBranchTypeMismatch {
-                        body: body.type_id(),
-                        else_body: else_body.type_id(),
+                        body: self.type_name(body.type_id()),
+                        else_body: self.type_name(else_body.type_id()),
+                        span,
                     });
                 }
                 Some(else_body)
             }
+            None if body.type_id() != TypeId::UNIT => {
+                return Err(TranslationError::TypeMismatch {
+                    expected: self.type_name(TypeId::UNIT),
+                    received: self.type_name(body.type_id()),
+                    span,
+                    origin: None,
+                });
+            }
             None => None,
         };
+        self.init = after_body.merge(std::mem::take(&mut self.init));
 
         Ok(Expression {
             type_: body.type_id(),
@@ -276,68 +654,513 @@ impl<'b> BodyBuilder<'b> {
         })
     }
 
-    fn translate_fn_call(
+    /// Translates condition of `if` or `while` expression, named by its `keyword`.
+    fn translate_condition(
         &mut self,
-        path: RelativePath,
-        args: Vec<AstExpression>,
+        keyword: &'static str,
+        condition: ExprId,
     ) -> Result<Expression, TranslationError> {
-        let path = {
-            let Some(path) = path.to_absolute(&self.module) else {
-                todo!();
-            };
-            path
+        let span = self.exprs[condition].span;
+        let condition = self.translate_expr(condition, Some(TypeId::BOOL))?;
+        if condition.type_ != TypeId::BOOL {
+            return Err(TranslationError::InvalidCondition {
+                keyword,
+                found: self.type_name(condition.type_),
+                span,
+            });
+        }
+        Ok(condition)
+    }
+
+    /// Lowers logical operator into `if` expression to make it short-circuiting.
+    ///
+    /// `a && b` becomes `if a { b } else { false }` and `a || b` becomes `if a { true } else { b }`.
+    fn translate_logical_op(
+        &mut self,
+        op: BinaryOp,
+        left: ExprId,
+        right: ExprId,
+    ) -> Result<Expression, TranslationError> {
+        let (left_span, right_span) = (self.exprs[left].span, self.exprs[right].span);
+        let left = self.translate_expr(left, Some(TypeId::BOOL))?;
+        self.expect_type(TypeId::BOOL, &left, left_span, None)?;
+        // Right operand may not be evaluated, so variables it assigns aren't initialized after.
+        let before = self.init.clone();
+        let right = self.translate_expr(right, Some(TypeId::BOOL))?;
+        self.expect_type(TypeId::BOOL, &right, right_span, None)?;
+        self.init = before;
+
+        let block = |expr| Block {
+            statements: Vec::new(),
+            tail: Some(Box::new(expr)),
         };
-        let Some((func_id, params, return_type)) = self.parent.query_function_info(&path) else {
-            return Err(TranslationError::FunctionNotFound(path));
+        let literal = |value| Expression {
+            type_: TypeId::BOOL,
+            kind: ExpressionKind::Literal(Literal::Boolean(value)),
         };
+        let (body, else_body) = match op {
+            BinaryOp::And => (block(right), block(literal(false))),
+            BinaryOp::Or => (block(literal(true)), block(right)),
+            _ => unreachable!("only logical operators are lowered"),
+        };
+        Ok(Expression {
+            type_: TypeId::BOOL,
+            kind: ExpressionKind::If {
+                condition: Box::new(left),
+                body,
+                else_body: Some(else_body),
+            },
+        })
+    }
+
+    /// Translates function call. `span` is the span of the whole call.
+    fn translate_fn_call(
+        &mut self,
+        path: &RelativePath,
+        args: &[ExprId],
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let resolved = self.resolve_path(path, span)?;
+        if let Some(error) = self.not_callable(path, &resolved, span) {
+            return Err(error);
+        }
+        let path = resolved;
+        let mut parent = path.clone();
+        parent.pop();
+        if let Some(function) = self.parent.item_scope(parent).1.into_iter().next() {
+            if !self.bodies.contains(&function) {
+                return Err(TranslationError::NestedFunctionOutOfScope {
+                    path: Box::new(path),
+                    function: Box::new(function),
+                    span,
+                });
+            }
+        }
+        let (callee, params, return_type) = self.parent.query_function_info(&path, span)?;
+        if let Callee::Intrinsic(Intrinsic::AssertEq) = callee {
+            return self.translate_assert_eq(path, args, span);
+        }
 
         if args.len() != params.len() {
+            if let Callee::Function(id) = callee {
+                if self.parent.is_method(id) && args.len() + 1 == params.len() {
+                    return Err(TranslationError::MissingReceiver {
+                        path: Box::new(path),
+                        span,
+                    });
+                }
+            }
             return Err(TranslationError::ArgumentCountMismatch {
+                signature: self.parent.render_signature(params, return_type),
+                path: Box::new(path),
                 expected: params.len(),
                 received: args.len(),
+                span,
             });
         }
 
-        let args = args
-            .into_iter()
-            .zip(params.iter())
-            .map(|(arg, expected)| {
-                let arg = self.translate_expr(arg)?;
-                if arg.type_ != Some(*expected) {
-                    return Err(TranslationError::TypeMismatch {
-                        expected: Some(*expected),
-                        received: arg.type_,
-                    });
-                }
-                Ok(arg)
-            })
-            .collect::<Result<_, _>>()?;
+        let args = self.translate_args(callee, args, params, 0)?;
+        Ok(Expression {
+            type_: return_type,
+            kind: match callee {
+                Callee::Function(id) => ExpressionKind::FnCall(id, args),
+                Callee::Intrinsic(intrinsic) => ExpressionKind::Intrinsic(intrinsic, args, span),
+            },
+        })
+    }
 
+    /// Translates `assert_eq(left, right)`, which is lowered to the assertion of `left == right`.
+    ///
+    /// Operands may be of any type that supports `==`, as long as it's the same for both.
+    fn translate_assert_eq(
+        &mut self,
+        path: AbsolutePath,
+        args: &[ExprId],
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let &[left, right] = args else {
+            return Err(TranslationError::ArgumentCountMismatch {
+                signature: String::from("fn(T, T)"),
+                path: Box::new(path),
+                expected: 2,
+                received: args.len(),
+                span,
+            });
+        };
+        let left_span = self.exprs[left].span;
+        let left = self.translate_expr(left, None)?;
+        let condition = self.translate_binary_op(BinaryOp::Eq, left, left_span, right)?;
+        Ok(Expression {
+            type_: TypeId::UNIT,
+            kind: ExpressionKind::Intrinsic(Intrinsic::AssertEq, vec![condition], span),
+        })
+    }
+
+    /// Translates call of the method `value.method(args)`, which is lowered to a call of the
+    /// function `TYPE::method` with the receiver passed as the first argument.
+    ///
+    /// Only functions of the type with `self` receiver may be called this way.
+    fn translate_method_call(
+        &mut self,
+        value: ExprId,
+        method: &Identifier,
+        args: &[ExprId],
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let receiver = self.translate_expr(value, None)?;
+        let Some((path, id)) = self.parent.associated_function(receiver.type_, method) else {
+            return Err(TranslationError::MethodNotFound {
+                type_: self.type_name(receiver.type_),
+                method: method.clone(),
+                span,
+            });
+        };
+        if !self.parent.is_method(id) {
+            return Err(TranslationError::NotAMethod {
+                path: Box::new(path),
+                span,
+            });
+        }
+
+        let (callee, params, return_type) = self.parent.query_function_info(&path, span)?;
+        if args.len() + 1 != params.len() {
+            return Err(TranslationError::ArgumentCountMismatch {
+                signature: self.parent.render_signature(params, return_type),
+                path: Box::new(path),
+                expected: params.len() - 1,
+                received: args.len(),
+                span,
+            });
+        }
+
+        let mut translated = vec![receiver];
+        translated.extend(self.translate_args(callee, args, &params[1..], 1)?);
         Ok(Expression {
             type_: return_type,
-            kind: ExpressionKind::FnCall(func_id, args),
+            kind: ExpressionKind::FnCall(id, translated),
         })
     }
 
-    fn translate_while_loop(
+    /// Translates arguments of the call, checking them against types of parameters of `callee`
+    /// starting from the one at `offset`.
+    fn translate_args(
+        &mut self,
+        callee: Callee,
+        args: &[ExprId],
+        params: &[TypeId],
+        offset: usize,
+    ) -> Result<Vec<Expression>, TranslationError> {
+        args.iter()
+            .zip(params.iter())
+            .enumerate()
+            .map(|(index, (arg, expected))| {
+                let arg_span = self.exprs[*arg].span;
+                let arg = self.translate_expr(*arg, Some(*expected))?;
+                let origin = match callee {
+                    Callee::Function(id) => self.parent.param_span(id, offset + index),
+                    Callee::Intrinsic(_) => None,
+                };
+                let origin = origin.map(Expectation::Parameter);
+                self.expect_type(*expected, &arg, arg_span, origin)
+                    .map(|()| arg)
+            })
+            .collect()
+    }
+
+    /// Reports use of variable `name` that isn't declared in the function.
+    ///
+    /// Nested functions can't use variables of enclosing ones, which is reported separately.
+    fn undeclared_variable(&self, name: &Identifier, span: Span) -> TranslationError {
+        let captured = self.bodies[1..].iter().any(|body| {
+            self.parent
+                .variables
+                .get(body)
+                .is_some_and(|variables| variables.contains(name))
+        });
+        match captured {
+            true => TranslationError::CaptureInNestedFunction {
+                name: name.clone(),
+                span,
+            },
+            false => TranslationError::VariableNotDeclared {
+                name: name.clone(),
+                span,
+            },
+        }
+    }
+
+    /// Explains why `path` resolved to `resolved` can't be called, if it doesn't refer to a
+    /// function, but refers to a variable, a struct or a module.
+    fn not_callable(
+        &self,
+        path: &RelativePath,
+        resolved: &AbsolutePath,
+        span: Span,
+    ) -> Option<TranslationError> {
+        if self.parent.is_callable(resolved) {
+            return None;
+        }
+        if let (RelativePathStart::Identifier(name), []) = (&path.start, path.other.as_slice()) {
+            if self.scope.lookup(name).is_some() {
+                return Some(TranslationError::VariableCalled {
+                    name: name.clone(),
+                    span,
+                });
+            }
+        }
+        let path = Box::new(resolved.clone());
+        if self.parent.type_table.mapping.contains_key(resolved) {
+            Some(TranslationError::StructCalled { path, span })
+        } else if self.parent.modules.contains(resolved) {
+            Some(TranslationError::ModuleCalled { path, span })
+        } else {
+            None
+        }
+    }
+
+    /// Translates `path` used as a value, which may only refer to a constant.
+    fn translate_path(
+        &self,
+        path: &RelativePath,
+        span: Span,
+    ) -> Result<Expression, TranslationError> {
+        let path = self.resolve_path(path, span)?;
+        match self.parent.query_const(&path) {
+            Some((type_, value)) => Ok(Expression {
+                type_,
+                kind: ExpressionKind::Const(value),
+            }),
+            None => Err(TranslationError::ConstNotFound {
+                path: Box::new(path),
+                span,
+            }),
+        }
+    }
+
+    /// Resolves `path` met at `span` in the module of the function.
+    fn resolve_path(
+        &self,
+        path: &RelativePath,
+        span: Span,
+    ) -> Result<AbsolutePath, TranslationError> {
+        self.parent
+            .resolve_path(&self.bodies, &self.module, path)
+            .ok_or_else(|| TranslationError::UnresolvedPath {
+                path: Box::new(path.clone()),
+                span,
+            })
+    }
+
+    /// Translates `for` loop that binds elements of `iterable` to `var`.
+    ///
+    /// Range `start..end` and arrays are iterable. The loop is desugared into a loop over hidden
+    /// index, which is incremented before the body, so the body may leave the iteration at
+    /// any point:
+    ///
+    /// ```notrust
+    /// {
+    ///     let index = start;
+    ///     let end = end;
+    ///     loop {
+    ///         if index >= end { break; }
+    ///         let var = index;
+    ///         index = index + 1;
+    ///         body
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Array is stored in another hidden variable `array` beforehand, and is iterated over by
+    /// index from `0` to its length, binding `array[index]` to `var`.
+    fn translate_for_loop(
         &mut self,
-        condition: AstExpression,
-        body: AstBlock,
+        var: &Identifier,
+        iterable: ExprId,
+        body: &AstBlock,
     ) -> Result<Expression, TranslationError> {
-        let condition = self.translate_expr(condition)?;
-        if condition.type_ != Some(TypeId::BOOL) {
-            return Err(TranslationError::TypeMismatch {
-                expected: Some(TypeId::BOOL),
-                received: condition.type_,
+        let exprs = self.exprs;
+        let span = exprs[iterable].span;
+        let (array, start, end) = match exprs[iterable].kind {
+            AstExpressionKind::Binary {
+                op: BinaryOp::Range,
+                left,
+                right,
+            } => {
+                let start = self.translate_expr(left, None)?;
+                let left_span = exprs[left].span;
+                let end =
+                    self.translate_right_operand(BinaryOp::Range, &start, left_span, right)?;
+                (None, start, end)
+            }
+            _ => {
+                let array = self.translate_expr(iterable, None)?;
+                let Some((_, length)) = self.parent.type_table.array_element(array.type_) else {
+                    return Err(TranslationError::NotIterable {
+                        type_: self.type_name(array.type_),
+                        span,
+                    });
+                };
+                let usize_const = |value: usize| Expression {
+                    type_: TypeId::USIZE,
+                    kind: ExpressionKind::Const(ConstValue::Integer(value as i128)),
+                };
+                (Some(array), usize_const(0), usize_const(length))
+            }
+        };
+        let type_ = start.type_;
+        let index = self.scope.insert_anonymous(type_);
+        let end_var = self.scope.insert_anonymous(type_);
+        let var_expr = |var| Expression {
+            type_,
+            kind: ExpressionKind::Var(var),
+        };
+        let array = array.map(|array| (self.scope.insert_anonymous(array.type_), array));
+        let (element_type, element_value) = match &array {
+            Some((array_var, array)) => {
+                let (element_type, _) = self
+                    .parent
+                    .type_table
+                    .array_element(array.type_)
+                    .expect("iterable is checked to be an array");
+                let value = Expression {
+                    type_: element_type,
+                    kind: ExpressionKind::Index {
+                        value: Box::new(Expression {
+                            type_: array.type_,
+                            kind: ExpressionKind::Var(*array_var),
+                        }),
+                        index: Box::new(var_expr(index)),
+                    },
+                };
+                (element_type, value)
+            }
+            None => (type_, var_expr(index)),
+        };
+
+        self.scope = self.scope.child_loop();
+        let element = self.scope.insert(var.clone(), element_type, span);
+        let before = self.init.clone();
+        let body = self.translate_block(body, false, None);
+        self.init = before;
+        self.scope = self.scope.parent().expect("Scope should have parent");
+        let body = body?;
+
+        let exit = Expression {
+            type_: TypeId::UNIT,
+            kind: ExpressionKind::If {
+                condition: Box::new(Expression {
+                    type_: TypeId::BOOL,
+                    kind: ExpressionKind::BinaryOp {
+                        operator: BinaryOp::MoreEq,
+                        left: Box::new(var_expr(index)),
+                        right: Box::new(var_expr(end_var)),
+                        span,
+                        right_span: span,
+                    },
+                }),
+                body: Block {
+                    statements: vec![Statement::Break],
+                    tail: None,
+                },
+                else_body: None,
+            },
+        };
+        let increment = Expression {
+            type_,
+            kind: ExpressionKind::BinaryOp {
+                operator: BinaryOp::Add,
+                left: Box::new(var_expr(index)),
+                right: Box::new(Expression {
+                    type_,
+                    kind: ExpressionKind::Literal(Literal::Number(Number {
+                        integer: String::from("1"),
+                        fraction: None,
+                        base: Base::Decimal,
+                    })),
+                }),
+                span,
+                right_span: span,
+            },
+        };
+        let mut statements = vec![
+            Statement::ExprStmt(exit),
+            Statement::LetStmt {
+                var: element,
+                type_: element_type,
+                value: Some(Box::new(element_value)),
+            },
+            Statement::Assignment {
+                assignee: var_expr(index),
+                value: increment,
+            },
+        ];
+        statements.extend(body.statements);
+        if let Some(tail) = body.tail {
+            statements.push(Statement::ExprStmt(*tail));
+        }
+
+        let mut outer = Vec::with_capacity(4);
+        if let Some((array_var, array)) = array {
+            outer.push(Statement::LetStmt {
+                var: array_var,
+                type_: array.type_,
+                value: Some(Box::new(array)),
             });
         }
-        let mut body = self.translate_block(body, true)?;
+        outer.extend([
+            Statement::LetStmt {
+                var: index,
+                type_,
+                value: Some(Box::new(start)),
+            },
+            Statement::LetStmt {
+                var: end_var,
+                type_,
+                value: Some(Box::new(end)),
+            },
+            Statement::ExprStmt(Expression {
+                type_: TypeId::UNIT,
+                kind: ExpressionKind::Loop(Block {
+                    statements,
+                    tail: None,
+                }),
+            }),
+        ]);
+        Ok(Expression {
+            type_: TypeId::UNIT,
+            kind: ExpressionKind::Block(Block {
+                statements: outer,
+                tail: None,
+            }),
+        })
+    }
+
+    fn translate_while_loop(
+        &mut self,
+        condition: ExprId,
+        body: &AstBlock,
+    ) -> Result<Expression, TranslationError> {
+        let condition_span = self.exprs[condition].span;
+        let condition = self.translate_condition("while", condition)?;
+        // Body may be executed zero times, so variables it assigns aren't initialized after it.
+        let before = self.init.clone();
+        let mut body = self.translate_block(body, true, None)?;
+        self.init = before;
+        // Loop is exited once the condition doesn't hold.
+        let exit_condition = Expression {
+            type_: TypeId::BOOL,
+            kind: ExpressionKind::UnaryOp {
+                operator: UnaryOp::Not,
+                value: Box::new(condition),
+                span: condition_span,
+            },
+        };
         body.statements.insert(
             0,
             Statement::ExprStmt(Expression {
-                type_: None,
+                type_: TypeId::UNIT,
                 kind: ExpressionKind::If {
-                    condition: Box::new(condition),
+                    condition: Box::new(exit_condition),
                     body: Block {
                         statements: vec![Statement::Break],
                         tail: None,
@@ -347,8 +1170,136 @@ impl<'b> BodyBuilder<'b> {
             }),
         );
         Ok(Expression {
-            type_: None,
+            type_: TypeId::UNIT,
             kind: ExpressionKind::Loop(body),
         })
     }
+
+    /// Checks that variable `var` named `name` and used at `span` is assigned on every path.
+    fn check_initialized(
+        &self,
+        var: VarId,
+        name: &Identifier,
+        span: Span,
+    ) -> Result<(), TranslationError> {
+        match self.init.check(var) {
+            Some(declared) => Err(TranslationError::UninitializedVariable {
+                name: name.clone(),
+                declared: Box::new(declared),
+                span,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that `expr` translated from code at `span` has `expected` type, required by the
+    /// code at `origin`.
+    fn expect_type(
+        &self,
+        expected: TypeId,
+        expr: &Expression,
+        span: Span,
+        origin: Option<Expectation>,
+    ) -> Result<(), TranslationError> {
+        if expr.type_ == expected {
+            Ok(())
+        } else {
+            Err(TranslationError::TypeMismatch {
+                expected: self.type_name(expected),
+                received: self.type_name(expr.type_),
+                span,
+                origin: origin.map(Box::new),
+            })
+        }
+    }
+
+    /// Renders type for error messages.
+    fn type_name(&self, id: TypeId) -> String {
+        self.parent.type_table.type_name(id)
+    }
+}
+
+/// Rewrites tail expression of the `block` into [Statement::Return] if it has `return_type`, so
+/// the block ends with explicit `return`.
+///
+/// `if` expressions and blocks are rewritten recursively, so each of their branches ends with
+/// `return` instead. Loops and tails of other types are diverging and left as is.
+pub(super) fn lower_tail_return(block: &mut Block, return_type: TypeId) {
+    let Some(tail) = block.tail.take() else { return; };
+    if tail.type_ != return_type {
+        block.tail = Some(tail);
+        return;
+    }
+    let stmt = match tail.kind {
+        ExpressionKind::If {
+            condition,
+            mut body,
+            else_body: Some(mut else_body),
+        } => {
+            lower_tail_return(&mut body, return_type);
+            lower_tail_return(&mut else_body, return_type);
+            Statement::ExprStmt(Expression {
+                type_: TypeId::UNIT,
+                kind: ExpressionKind::If {
+                    condition,
+                    body,
+                    else_body: Some(else_body),
+                },
+            })
+        }
+        ExpressionKind::Block(mut inner) => {
+            lower_tail_return(&mut inner, return_type);
+            Statement::ExprStmt(Expression {
+                type_: TypeId::UNIT,
+                kind: ExpressionKind::Block(inner),
+            })
+        }
+        kind => Statement::Return(Expression {
+            type_: return_type,
+            kind,
+        }),
+    };
+    block.statements.push(stmt);
+}
+
+/// Type of the literal. Numeric literals get `expected` type if it is suitable for them.
+fn literal_type(lit: &Literal, expected: Option<TypeId>) -> TypeId {
+    match lit {
+        Literal::Number(Number { fraction: None, .. }) => {
+            expected.filter(TypeId::is_integer).unwrap_or(TypeId::I32)
+        }
+        Literal::Number(Number {
+            fraction: Some(_), ..
+        }) => expected.filter(TypeId::is_float).unwrap_or(TypeId::F32),
+        Literal::String(_) => TypeId::STR,
+        Literal::Boolean(_) => TypeId::BOOL,
+    }
+}
+
+/// Checks if `expr` is an integer literal or constant equal to zero.
+///
+/// Floats may be divided by zero, producing infinity or NaN.
+fn is_integer_zero(expr: &Expression) -> bool {
+    let value = match &expr.kind {
+        ExpressionKind::Literal(lit) => evaluate_literal(lit.clone(), Some(expr.type_))
+            .ok()
+            .map(|(value, _)| value),
+        ExpressionKind::Const(value) => Some(*value),
+        _ => None,
+    };
+    value == Some(ConstValue::Integer(0))
+}
+
+impl ReportProvider for BodyBuilder<'_> {
+    fn error_reporter(&self) -> Arc<ErrorReporter> {
+        self.parent.context.error_reporter.clone()
+    }
+
+    fn location(&self) -> Location {
+        self.span.end
+    }
+
+    fn source(&self) -> Option<SourceId> {
+        self.span.source
+    }
 }
@@ -0,0 +1,366 @@
+//! Evaluation of constant expressions.
+
+use crate::{
+    ast::expression::{ExprArena, ExprId, ExpressionKind as AstExpressionKind, Literal},
+    hir::{
+        types::{PrimitiveType, TypeId, TypeTable},
+        ConstValue, TranslationError,
+    },
+    lexer::operator::{BinaryOp, UnaryOp},
+    util::Span,
+};
+
+/// Evaluates constant expression `expr` stored in `exprs`, producing its value and type.
+///
+/// Only literals and operators applied to them are allowed in constant expressions.
+/// `expected` is used to infer type of numeric literals, `types` is used to render types in errors.
+pub(super) fn evaluate(
+    exprs: &ExprArena,
+    expr: ExprId,
+    expected: Option<TypeId>,
+    types: &TypeTable,
+) -> Result<(ConstValue, TypeId), TranslationError> {
+    let span = exprs[expr].span;
+    let (value, type_) = match &exprs[expr].kind {
+        AstExpressionKind::Literal(lit) => {
+            evaluate_literal(lit.clone(), expected).map_err(|err| err.at(span))?
+        }
+        AstExpressionKind::Unary { op, value } => {
+            let in_types = op.in_types();
+            let hint = expected.filter(|type_| in_types.contains(type_));
+            let value_span = exprs[*value].span;
+            let (value, type_) = match &exprs[*value].kind {
+                // Range of signed literal is checked after the sign is applied, so `-128` is
+                // a valid `i8`.
+                AstExpressionKind::Literal(lit @ Literal::Number(_))
+                    if matches!(op, UnaryOp::Add | UnaryOp::Sub) =>
+                {
+                    evaluate_literal(lit.clone(), hint).map_err(|err| err.at(value_span))?
+                }
+                _ => evaluate(exprs, *value, hint, types)?,
+            };
+            expect_one_of(type_, in_types, value_span, types)?;
+            let value = evaluate_unary(*op, value, type_).map_err(|err| err.at(span))?;
+            (value, op.out_type(type_))
+        }
+        AstExpressionKind::Binary {
+            op: BinaryOp::Range,
+            ..
+        } => return Err(TranslationError::NonConstantExpression { span }),
+        AstExpressionKind::Binary { op, left, right } => {
+            let in_types = op.in_types();
+            let hint = match op.is_comparison() {
+                true => None,
+                false => expected.filter(|type_| in_types.contains(type_)),
+            };
+            let (left_span, right_span) = (exprs[*left].span, exprs[*right].span);
+            let (left, type_) = evaluate(exprs, *left, hint, types)?;
+            expect_one_of(type_, in_types, left_span, types)?;
+            let right = evaluate(exprs, *right, Some(type_), types)?;
+            let right = expect_type(right, type_, right_span, types)?;
+            let value = evaluate_binary(*op, left, right).map_err(|err| err.at(span))?;
+            (value, op.out_type(type_))
+        }
+        _ => return Err(TranslationError::NonConstantExpression { span }),
+    };
+    let value = check_bounds(value, type_).map_err(|err| err.at(span))?;
+    Ok((value, type_))
+}
+
+/// Failure of evaluation of a constant expression, that doesn't know where the expression is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(in crate::hir) enum ConstError {
+    Overflow,
+    DivisionByZero,
+    NonConstant,
+}
+
+impl ConstError {
+    /// Converts the failure into error reported at `span`.
+    pub(in crate::hir) fn at(self, span: Span) -> TranslationError {
+        match self {
+            ConstError::Overflow => TranslationError::ConstOverflow { span },
+            ConstError::DivisionByZero => TranslationError::ConstDivisionByZero { span },
+            ConstError::NonConstant => TranslationError::NonConstantExpression { span },
+        }
+    }
+}
+
+/// Checks that integer value fits into its type.
+pub(in crate::hir) fn check_bounds(
+    value: ConstValue,
+    type_: TypeId,
+) -> Result<ConstValue, ConstError> {
+    if let ConstValue::Integer(integer) = value {
+        let (min, max) = integer_bounds(type_);
+        if integer < min || integer > max {
+            return Err(ConstError::Overflow);
+        }
+    }
+    Ok(value)
+}
+
+/// Minimal and maximal values of the integer type, see [PrimitiveType::integer_bounds].
+fn integer_bounds(type_: TypeId) -> (i128, i128) {
+    match type_ {
+        TypeId::Primitive(primitive) => primitive.integer_bounds(),
+        _ => None,
+    }
+    .unwrap_or((i128::MIN, i128::MAX))
+}
+
+fn expect_one_of(
+    type_: TypeId,
+    expected: &[TypeId],
+    span: Span,
+    types: &TypeTable,
+) -> Result<(), TranslationError> {
+    if expected.contains(&type_) {
+        Ok(())
+    } else {
+        Err(TranslationError::TypeMismatch {
+            expected: types.type_name(expected[0]),
+            received: types.type_name(type_),
+            span,
+            origin: None,
+        })
+    }
+}
+
+fn expect_type(
+    (value, type_): (ConstValue, TypeId),
+    expected: TypeId,
+    span: Span,
+    types: &TypeTable,
+) -> Result<ConstValue, TranslationError> {
+    if type_ == expected {
+        Ok(value)
+    } else {
+        Err(TranslationError::TypeMismatch {
+            expected: types.type_name(expected),
+            received: types.type_name(type_),
+            span,
+            origin: None,
+        })
+    }
+}
+
+pub(in crate::hir) fn evaluate_literal(
+    lit: Literal,
+    expected: Option<TypeId>,
+) -> Result<(ConstValue, TypeId), ConstError> {
+    match lit {
+        // Integer literal isn't checked against its type, as it may be negated afterwards.
+        Literal::Number(number) if number.fraction.is_none() => Ok((
+            number
+                .to_value(PrimitiveType::U64)
+                .map_err(|_| ConstError::Overflow)?,
+            expected.filter(TypeId::is_integer).unwrap_or(TypeId::I32),
+        )),
+        Literal::Number(number) => Ok((
+            number
+                .to_value(PrimitiveType::F32)
+                .map_err(|_| ConstError::Overflow)?,
+            expected.filter(TypeId::is_float).unwrap_or(TypeId::F32),
+        )),
+        Literal::Boolean(value) => Ok((ConstValue::Boolean(value), TypeId::BOOL)),
+        Literal::String(_) => Err(ConstError::NonConstant),
+    }
+}
+
+pub(in crate::hir) fn evaluate_unary(
+    op: UnaryOp,
+    value: ConstValue,
+    type_: TypeId,
+) -> Result<ConstValue, ConstError> {
+    use ConstValue::*;
+    Ok(match (op, value) {
+        (UnaryOp::Add, value) => value,
+        (UnaryOp::Sub, Integer(x)) => Integer(x.checked_neg().ok_or(ConstError::Overflow)?),
+        (UnaryOp::Sub, Float(x)) => Float(-x),
+        (UnaryOp::Not, Boolean(x)) => Boolean(!x),
+        (UnaryOp::Not, Integer(x)) => match integer_bounds(type_) {
+            (0, max) => Integer(x ^ max),
+            _ => Integer(!x),
+        },
+        _ => unreachable!("operand type is checked beforehand"),
+    })
+}
+
+pub(in crate::hir) fn evaluate_binary(
+    op: BinaryOp,
+    left: ConstValue,
+    right: ConstValue,
+) -> Result<ConstValue, ConstError> {
+    use BinaryOp::*;
+    use ConstValue::*;
+
+    let overflow = || ConstError::Overflow;
+    Ok(match (left, right) {
+        (Integer(a), Integer(b)) => match op {
+            Add => Integer(a.checked_add(b).ok_or_else(overflow)?),
+            Sub => Integer(a.checked_sub(b).ok_or_else(overflow)?),
+            Mul => Integer(a.checked_mul(b).ok_or_else(overflow)?),
+            Div | Mod if b == 0 => return Err(ConstError::DivisionByZero),
+            Div => Integer(a.checked_div(b).ok_or_else(overflow)?),
+            Mod => Integer(a.checked_rem(b).ok_or_else(overflow)?),
+            Rsh => Integer(
+                u32::try_from(b)
+                    .ok()
+                    .and_then(|b| a.checked_shr(b))
+                    .ok_or_else(overflow)?,
+            ),
+            Lsh => Integer(
+                u32::try_from(b)
+                    .ok()
+                    .and_then(|b| a.checked_shl(b))
+                    .ok_or_else(overflow)?,
+            ),
+            BinAnd => Integer(a & b),
+            BinOr => Integer(a | b),
+            BinXor => Integer(a ^ b),
+            Eq => Boolean(a == b),
+            Neq => Boolean(a != b),
+            More => Boolean(a > b),
+            Less => Boolean(a < b),
+            MoreEq => Boolean(a >= b),
+            LessEq => Boolean(a <= b),
+            And | Or => unreachable!("operand type is checked beforehand"),
+            Range => unreachable!("range is never constant"),
+        },
+        (Float(a), Float(b)) => match op {
+            Add => Float(a + b),
+            Sub => Float(a - b),
+            Mul => Float(a * b),
+            Div => Float(a / b),
+            Mod => Float(a % b),
+            Eq => Boolean(a == b),
+            Neq => Boolean(a != b),
+            More => Boolean(a > b),
+            Less => Boolean(a < b),
+            MoreEq => Boolean(a >= b),
+            LessEq => Boolean(a <= b),
+            _ => unreachable!("operand type is checked beforehand"),
+        },
+        (Boolean(a), Boolean(b)) => match op {
+            And => Boolean(a && b),
+            Or => Boolean(a || b),
+            BinAnd => Boolean(a & b),
+            BinOr => Boolean(a | b),
+            BinXor => Boolean(a ^ b),
+            Eq => Boolean(a == b),
+            Neq => Boolean(a != b),
+            _ => unreachable!("operand type is checked beforehand"),
+        },
+        _ => unreachable!("operand type is checked beforehand"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        hir::{
+            types::{TypeId, TypeTable},
+            ConstValue, TranslationError,
+        },
+        parser::FileParser,
+    };
+
+    use super::evaluate;
+
+    fn evaluate_str(src: &str) -> Result<(ConstValue, TypeId), TranslationError> {
+        let mut parser = FileParser::new_test(src);
+        let expr = parser.parse_expr().expect("parsing failed");
+        evaluate(parser.item_table.exprs(), expr, None, &TypeTable::new())
+    }
+
+    #[test]
+    fn arithmetic() {
+        let (value, type_) = evaluate_str("2 + 3 * (4 - 1)").unwrap();
+        assert_eq!(value, ConstValue::Integer(11));
+        assert_eq!(type_, TypeId::I32);
+    }
+
+    #[test]
+    fn comparison() {
+        let (value, type_) = evaluate_str("2 * 3 >= 6").unwrap();
+        assert_eq!(value, ConstValue::Boolean(true));
+        assert_eq!(type_, TypeId::BOOL);
+    }
+
+    #[test]
+    fn float_comparison() {
+        let (value, _) = evaluate_str("1.5 < 2.0").unwrap();
+        assert_eq!(value, ConstValue::Boolean(true));
+    }
+
+    #[test]
+    fn inferred_width() {
+        let mut parser = FileParser::new_test("!0");
+        let expr = parser.parse_expr().unwrap();
+        let types = TypeTable::new();
+        let (value, type_) =
+            evaluate(parser.item_table.exprs(), expr, Some(TypeId::U8), &types).unwrap();
+        assert_eq!(value, ConstValue::Integer(255));
+        assert_eq!(type_, TypeId::U8);
+
+        let mut parser = FileParser::new_test("200 + 100");
+        let expr = parser.parse_expr().unwrap();
+        let result = evaluate(parser.item_table.exprs(), expr, Some(TypeId::U8), &types);
+        let Err(TranslationError::ConstOverflow { span }) = result else {
+            panic!("overflow wasn't detected");
+        };
+        assert_eq!((span.start.column, span.end.column), (0, 9));
+    }
+
+    #[test]
+    fn signed_literal() {
+        let types = TypeTable::new();
+        let cases = [
+            ("-128", TypeId::I8, Some(i8::MIN.into())),
+            ("-129", TypeId::I8, None),
+            ("+127", TypeId::I8, Some(i8::MAX.into())),
+            ("-9223372036854775808", TypeId::I64, Some(i64::MIN.into())),
+            ("-9223372036854775809", TypeId::I64, None),
+        ];
+        for (src, type_, expected) in cases {
+            let mut parser = FileParser::new_test(src);
+            let expr = parser.parse_expr().unwrap();
+            let result = evaluate(parser.item_table.exprs(), expr, Some(type_), &types);
+            match expected {
+                Some(value) => assert_eq!(result.unwrap(), (ConstValue::Integer(value), type_)),
+                None => assert!(
+                    matches!(result, Err(TranslationError::ConstOverflow { .. })),
+                    "{src} should overflow"
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn non_constant() {
+        let result = evaluate_str("x + 1");
+        assert!(matches!(
+            result,
+            Err(TranslationError::NonConstantExpression { .. })
+        ));
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let error = evaluate_str("1 + true").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "mismatched types: expected `i32`, found `bool`"
+        );
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let result = evaluate_str("1 / (2 - 2)");
+        assert!(matches!(
+            result,
+            Err(TranslationError::ConstDivisionByZero { .. })
+        ));
+    }
+}
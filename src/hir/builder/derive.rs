@@ -0,0 +1,209 @@
+//! Functions generated for structs with `#[derive(..)]` attributes.
+
+use crate::{
+    ast::expression::Literal,
+    hir::{
+        intrinsic::Intrinsic, scope::VarId, types::TypeId, Block, Expression, ExpressionKind,
+        FunctionId,
+    },
+    path::AbsolutePath,
+    util::Span,
+    Identifier,
+};
+
+use super::{body::lower_tail_return, HirBuilder, TranslationError};
+
+/// Function that may be derived for a struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Derive {
+    /// `fn to_string(self) -> str`, that renders the struct like `Point { x: 1, y: 2 }`.
+    ToString,
+}
+
+impl Derive {
+    /// Every derivable function.
+    pub const ALL: [Derive; 1] = [Derive::ToString];
+
+    /// Name the function is declared with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Derive::ToString => "to_string",
+        }
+    }
+
+    fn from_name(name: &Identifier) -> Option<Derive> {
+        Derive::ALL
+            .into_iter()
+            .find(|derive| derive.name() == name.as_str())
+    }
+}
+
+/// Function derived for the struct, which body is generated once types of all fields are known.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct DerivedFunction {
+    pub id: FunctionId,
+    pub derive: Derive,
+    pub strukt: TypeId,
+    /// Span of the struct.
+    pub span: Span,
+}
+
+impl HirBuilder {
+    /// Declares functions listed in `derives` for the struct at `path`.
+    ///
+    /// Signatures are declared separately, after ones of functions of the source code.
+    pub(super) fn declare_derived(
+        &mut self,
+        path: &AbsolutePath,
+        strukt: TypeId,
+        span: Span,
+        derives: &[Identifier],
+    ) {
+        for name in derives {
+            let Some(derive) = Derive::from_name(name) else {
+                let supported: Vec<_> = Derive::ALL.iter().map(Derive::name).collect();
+                self.report(TranslationError::UnknownDerive {
+                    name: name.clone(),
+                    supported: supported.join(", "),
+                    span,
+                });
+                continue;
+            };
+            let mut function = path.clone();
+            function.push(Identifier(String::from(derive.name())));
+            if self.mapping.contains_key(&function) {
+                self.report(TranslationError::DerivedFunctionRedefined {
+                    path: Box::new(function),
+                    span,
+                });
+                continue;
+            }
+            let id = FunctionId(self.mapping.len() as u32);
+            self.mapping.insert(function, id);
+            self.methods.insert(id);
+            self.spans.push(span);
+            self.derived.push(DerivedFunction {
+                id,
+                derive,
+                strukt,
+                span,
+            });
+        }
+    }
+
+    /// Signature of the derived `function`.
+    pub(super) fn derived_signature(&self, function: &DerivedFunction) -> (Vec<TypeId>, TypeId) {
+        match function.derive {
+            Derive::ToString => (vec![function.strukt], TypeId::STR),
+        }
+    }
+
+    /// Generates body of the derived `function`.
+    ///
+    /// Body ends with explicit `return`, just like bodies of the declared functions.
+    pub(super) fn derived_body(
+        &self,
+        function: &DerivedFunction,
+    ) -> Result<Block, TranslationError> {
+        let mut body = match function.derive {
+            Derive::ToString => self.derive_to_string(function)?,
+        };
+        let (_, return_type) = self.derived_signature(function);
+        lower_tail_return(&mut body, return_type);
+        Ok(body)
+    }
+
+    /// Generates body that concatenates names of the fields with their values.
+    fn derive_to_string(&self, function: &DerivedFunction) -> Result<Block, TranslationError> {
+        let name = self
+            .type_table
+            .name_of(function.strukt)
+            .expect("struct should have a name");
+        let fields = self.type_table.fields_of(function.strukt).unwrap_or(&[]);
+        if fields.is_empty() {
+            return Ok(block(string(format!("{name} {{}}"))));
+        }
+
+        let receiver = Expression {
+            type_: function.strukt,
+            kind: ExpressionKind::Var(VarId::param(0)),
+        };
+        let mut result = string(format!("{name} {{ "));
+        for (index, (field, type_)) in fields.iter().enumerate() {
+            let separator = if index == 0 { "" } else { ", " };
+            result = concat(result, string(format!("{separator}{field}: ")));
+            let value = Expression {
+                type_: *type_,
+                kind: ExpressionKind::Field {
+                    value: Box::new(receiver.clone()),
+                    field: field.clone(),
+                },
+            };
+            let Some(value) = self.stringify(value) else {
+                return Err(TranslationError::UnsupportedDeriveField {
+                    derive: Derive::ToString.name(),
+                    field: field.clone(),
+                    type_: self.type_table.type_name(*type_),
+                    span: function.span,
+                });
+            };
+            result = concat(result, value);
+        }
+        Ok(block(concat(result, string(String::from(" }")))))
+    }
+
+    /// Converts `value` to a string, `None` if its type has no string representation.
+    ///
+    /// Integers, booleans and strings are supported, as well as structs that derive `to_string`.
+    fn stringify(&self, value: Expression) -> Option<Expression> {
+        let kind = match value.type_ {
+            TypeId::STR => return Some(value),
+            TypeId::BOOL => ExpressionKind::If {
+                condition: Box::new(value),
+                body: block(string(String::from("true"))),
+                else_body: Some(block(string(String::from("false")))),
+            },
+            TypeId::I64 => {
+                ExpressionKind::Intrinsic(Intrinsic::ToString, vec![value], Span::default())
+            }
+            type_ if type_.is_integer() => {
+                let value = Expression {
+                    type_: TypeId::I64,
+                    kind: ExpressionKind::Cast(Box::new(value)),
+                };
+                ExpressionKind::Intrinsic(Intrinsic::ToString, vec![value], Span::default())
+            }
+            type_ => {
+                let derived = self.derived.iter().find(|function| {
+                    function.strukt == type_ && function.derive == Derive::ToString
+                })?;
+                ExpressionKind::FnCall(derived.id, vec![value])
+            }
+        };
+        Some(Expression {
+            type_: TypeId::STR,
+            kind,
+        })
+    }
+}
+
+fn string(value: String) -> Expression {
+    Expression {
+        type_: TypeId::STR,
+        kind: ExpressionKind::Literal(Literal::String(value)),
+    }
+}
+
+fn concat(left: Expression, right: Expression) -> Expression {
+    Expression {
+        type_: TypeId::STR,
+        kind: ExpressionKind::Intrinsic(Intrinsic::Concat, vec![left, right], Span::default()),
+    }
+}
+
+fn block(tail: Expression) -> Block {
+    Block {
+        statements: Vec::new(),
+        tail: Some(Box::new(tail)),
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{hir::scope::VarId, util::Span};
+
+/// Tracks variables declared without a value, to ensure that they are assigned before use.
+///
+/// The state describes the point of the function that is currently translated. As functions
+/// are translated in the order of execution, only branches require explicit handling.
+#[derive(Debug, Clone, Default)]
+pub(super) struct InitState {
+    /// Spans of variables declared without a value.
+    declarations: HashMap<VarId, Span>,
+    /// Variables declared without a value that are definitely assigned at this point.
+    assigned: HashSet<VarId>,
+    /// Control flow never reaches this point, so every variable counts as assigned.
+    diverged: bool,
+}
+
+impl InitState {
+    pub fn declare(&mut self, var: VarId, span: Span) {
+        self.declarations.insert(var, span);
+        self.assigned.remove(&var);
+    }
+
+    pub fn assign(&mut self, var: VarId) {
+        if self.declarations.contains_key(&var) {
+            self.assigned.insert(var);
+        }
+    }
+
+    /// Marks the rest of the code as unreachable.
+    pub fn diverge(&mut self) {
+        self.diverged = true;
+    }
+
+    /// Returns the span of declaration of `var` if it may be used before assignment.
+    pub fn check(&self, var: VarId) -> Option<Span> {
+        if self.diverged || self.assigned.contains(&var) {
+            return None;
+        }
+        self.declarations.get(&var).copied()
+    }
+
+    /// Computes the state at the point where control flow from `self` and `other` merges.
+    pub fn merge(self, other: InitState) -> InitState {
+        match (self.diverged, other.diverged) {
+            (true, _) => other,
+            (_, true) => self,
+            _ => InitState {
+                assigned: self
+                    .assigned
+                    .intersection(&other.assigned)
+                    .copied()
+                    .collect(),
+                declarations: self.declarations,
+                diverged: false,
+            },
+        }
+    }
+}
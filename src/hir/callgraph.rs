@@ -0,0 +1,205 @@
+//! Graph of calls between functions.
+//!
+//! Edges are collected from [ExpressionKind::FnCall] expressions in bodies of functions, so
+//! a function is considered to call another one even if the call is never executed.
+
+use super::{Block, Expression, ExpressionKind, FunctionId, Hir, Statement};
+
+/// Graph of calls between functions of the [Hir].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallGraph {
+    /// Functions called by each function, indexed by [FunctionId].
+    callees: Vec<Vec<FunctionId>>,
+    /// Functions that call each function, indexed by [FunctionId].
+    callers: Vec<Vec<FunctionId>>,
+}
+
+impl CallGraph {
+    /// Builds call graph of every function of the `hir`.
+    ///
+    /// Functions that failed to translate call nothing.
+    pub fn new(hir: &Hir) -> Self {
+        let mut callees: Vec<Vec<FunctionId>> = hir
+            .functions
+            .iter()
+            .map(|function| {
+                let mut callees = Vec::new();
+                if let Some(function) = function {
+                    collect_block(&function.body, &mut callees);
+                }
+                callees
+            })
+            .collect();
+        let mut callers = vec![Vec::new(); callees.len()];
+        for (caller, callees) in callees.iter_mut().enumerate() {
+            callees.sort_unstable();
+            callees.dedup();
+            for callee in callees.iter() {
+                callers[callee.0 as usize].push(FunctionId(caller as u32));
+            }
+        }
+        Self { callees, callers }
+    }
+
+    /// Functions called by `function`, ordered by id.
+    pub fn callees(&self, function: FunctionId) -> &[FunctionId] {
+        self.callees
+            .get(function.0 as usize)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Functions that call `function`, ordered by id.
+    pub fn callers(&self, function: FunctionId) -> &[FunctionId] {
+        self.callers
+            .get(function.0 as usize)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Finds functions that can't be called from any of the `roots`, ordered by id.
+    pub fn unreachable_from(&self, roots: &[FunctionId]) -> Vec<FunctionId> {
+        let mut reachable = vec![false; self.callees.len()];
+        let mut stack = roots.to_vec();
+        while let Some(function) = stack.pop() {
+            if !reachable[function.0 as usize] {
+                reachable[function.0 as usize] = true;
+                stack.extend_from_slice(self.callees(function));
+            }
+        }
+        (0..self.callees.len() as u32)
+            .map(FunctionId)
+            .filter(|function| !reachable[function.0 as usize])
+            .collect()
+    }
+}
+
+fn collect_block(block: &Block, callees: &mut Vec<FunctionId>) {
+    for stmt in block.statements.iter() {
+        match stmt {
+            Statement::ExprStmt(expr) | Statement::Return(expr) => collect_expr(expr, callees),
+            Statement::LetStmt {
+                value: Some(value), ..
+            } => collect_expr(value, callees),
+            Statement::Assignment { assignee, value } => {
+                collect_expr(assignee, callees);
+                collect_expr(value, callees);
+            }
+            Statement::LetStmt { value: None, .. } | Statement::Break => {}
+        }
+    }
+    if let Some(tail) = &block.tail {
+        collect_expr(tail, callees);
+    }
+}
+
+fn collect_expr(expr: &Expression, callees: &mut Vec<FunctionId>) {
+    match &expr.kind {
+        ExpressionKind::Block(block) | ExpressionKind::Loop(block) => collect_block(block, callees),
+        ExpressionKind::If {
+            condition,
+            body,
+            else_body,
+        } => {
+            collect_expr(condition, callees);
+            collect_block(body, callees);
+            if let Some(else_body) = else_body {
+                collect_block(else_body, callees);
+            }
+        }
+        ExpressionKind::FnCall(function, args) => {
+            callees.push(*function);
+            for arg in args.iter() {
+                collect_expr(arg, callees);
+            }
+        }
+        ExpressionKind::Field { value, .. }
+        | ExpressionKind::Cast(value)
+        | ExpressionKind::UnaryOp { value, .. } => collect_expr(value, callees),
+        ExpressionKind::Intrinsic(_, args, _) => {
+            for arg in args.iter() {
+                collect_expr(arg, callees);
+            }
+        }
+        ExpressionKind::Index { value, index } => {
+            collect_expr(value, callees);
+            collect_expr(index, callees);
+        }
+        ExpressionKind::BinaryOp { left, right, .. } => {
+            collect_expr(left, callees);
+            collect_expr(right, callees);
+        }
+        ExpressionKind::Literal(_) | ExpressionKind::Const(_) | ExpressionKind::Var(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{
+        context::Context,
+        hir::{FunctionId, Hir},
+        path::AbsolutePath,
+    };
+
+    use super::CallGraph;
+
+    const PROGRAM: &str = "
+        fn main() -> i32 { helper(1) + helper(2) }
+        fn helper(x: i32) -> i32 { if x > 0 { helper(x - 1) } else { 0 } }
+        fn dead() -> i32 { helper(3) }
+        pub fn exported() {}
+        mod inner { pub fn nested() {} }
+    ";
+
+    #[test]
+    fn queries() {
+        let hir = Hir::new_test(PROGRAM);
+        let id = |name: &str| {
+            let path = AbsolutePath::from_str(&format!("crate::{name}")).unwrap();
+            hir.function_id(&path).unwrap()
+        };
+        let graph = CallGraph::new(&hir);
+        assert_eq!(graph.callees(id("main")), [id("helper")]);
+        assert_eq!(graph.callees(id("helper")), [id("helper")]);
+        let mut callers = vec![id("main"), id("helper"), id("dead")];
+        callers.sort();
+        assert_eq!(graph.callers(id("helper")), callers);
+        assert!(graph.callers(id("main")).is_empty());
+        assert!(graph.callees(FunctionId(u32::MAX)).is_empty());
+
+        let mut unreachable = vec![id("dead"), id("exported"), id("inner::nested")];
+        unreachable.sort();
+        assert_eq!(graph.unreachable_from(&[id("main")]), unreachable);
+    }
+
+    #[test]
+    fn dead_function_warning() {
+        let context = Context::new_test();
+        Hir::new_test_with(context.clone(), PROGRAM);
+        let diagnostics = context.error_reporter.diagnostics();
+        let warnings: Vec<_> = diagnostics
+            .iter()
+            .map(|warning| {
+                let span = warning.span.expect("dead function should have a span");
+                (warning.message.as_str(), span.start.line, span.start.column)
+            })
+            .collect();
+        // Functions of private modules aren't exported, even if they are public themselves.
+        assert_eq!(
+            warnings,
+            [
+                ("function `crate::dead` is never used", 3, 8),
+                ("function `crate::inner::nested` is never used", 5, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn library_without_entry_point() {
+        let context = Context::new_test();
+        Hir::new_test_with(context.clone(), "fn unused() {}");
+        assert!(context.error_reporter.diagnostics().is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Constant folding.
+//!
+//! Operators applied to literals and constants are computed at compile time, and `if` expressions
+//! with constant condition are replaced with the taken branch.
+//!
+//! Folded values are stored as [ExpressionKind::Const], as literals can't represent negative
+//! numbers. If computation overflows the type of expression or divides by zero, an error is
+//! reported and the expression is left as is. Integer division by zero is reported even if the
+//! dividend isn't constant.
+
+use std::mem;
+
+use crate::lexer::operator::BinaryOp;
+
+use super::{
+    builder::constant::{check_bounds, evaluate_binary, evaluate_literal, evaluate_unary},
+    Block, ConstValue, Expression, ExpressionKind, Hir, Statement, TranslationError,
+};
+
+/// Folds constant expressions in every function of the HIR.
+pub fn fold_constants(hir: &mut Hir) -> Vec<TranslationError> {
+    let mut errors = Vec::new();
+    for function in hir.functions.iter_mut().flatten() {
+        fold_block(&mut function.body, &mut errors);
+    }
+    errors
+}
+
+fn fold_block(block: &mut Block, errors: &mut Vec<TranslationError>) {
+    for stmt in block.statements.iter_mut() {
+        match stmt {
+            Statement::ExprStmt(expr) | Statement::Return(expr) => fold_expr(expr, errors),
+            Statement::LetStmt {
+                value: Some(value), ..
+            } => fold_expr(value, errors),
+            Statement::Assignment { assignee, value } => {
+                fold_expr(assignee, errors);
+                fold_expr(value, errors);
+            }
+            Statement::LetStmt { value: None, .. } | Statement::Break => {}
+        }
+    }
+    if let Some(tail) = &mut block.tail {
+        fold_expr(tail, errors);
+    }
+}
+
+fn fold_expr(expr: &mut Expression, errors: &mut Vec<TranslationError>) {
+    let type_ = expr.type_;
+    let folded = match &mut expr.kind {
+        ExpressionKind::Block(block) | ExpressionKind::Loop(block) => {
+            fold_block(block, errors);
+            None
+        }
+        ExpressionKind::If {
+            condition,
+            body,
+            else_body,
+        } => {
+            fold_expr(condition, errors);
+            fold_block(body, errors);
+            if let Some(else_body) = else_body {
+                fold_block(else_body, errors);
+            }
+            match value_of(condition) {
+                Some(ConstValue::Boolean(true)) => Some(ExpressionKind::Block(mem::take(body))),
+                Some(ConstValue::Boolean(false)) => {
+                    Some(ExpressionKind::Block(else_body.take().unwrap_or_default()))
+                }
+                _ => None,
+            }
+        }
+        ExpressionKind::FnCall(_, args) | ExpressionKind::Intrinsic(_, args, _) => {
+            for arg in args.iter_mut() {
+                fold_expr(arg, errors);
+            }
+            None
+        }
+        ExpressionKind::Field { value, .. } | ExpressionKind::Cast(value) => {
+            fold_expr(value, errors);
+            None
+        }
+        ExpressionKind::Index { value, index } => {
+            fold_expr(value, errors);
+            fold_expr(index, errors);
+            None
+        }
+        ExpressionKind::UnaryOp {
+            operator,
+            value,
+            span,
+        } => {
+            fold_expr(value, errors);
+            value_of(value).and_then(|operand| {
+                evaluate_unary(*operator, operand, value.type_)
+                    .and_then(|result| check_bounds(result, type_))
+                    .map_err(|error| errors.push(error.at(*span)))
+                    .ok()
+                    .map(ExpressionKind::Const)
+            })
+        }
+        ExpressionKind::BinaryOp {
+            operator,
+            left,
+            right,
+            span,
+            right_span,
+        } => {
+            fold_expr(left, errors);
+            fold_expr(right, errors);
+            match (value_of(left), value_of(right)) {
+                (Some(a), Some(b)) => evaluate_binary(*operator, a, b)
+                    .and_then(|result| check_bounds(result, type_))
+                    .map_err(|error| errors.push(error.at(*span)))
+                    .ok()
+                    .map(ExpressionKind::Const),
+                // Divisor is known to be zero even if the dividend isn't known.
+                (None, Some(ConstValue::Integer(0)))
+                    if matches!(operator, BinaryOp::Div | BinaryOp::Mod) =>
+                {
+                    errors.push(TranslationError::ConstDivisionByZero { span: *right_span });
+                    None
+                }
+                _ => None,
+            }
+        }
+        ExpressionKind::Literal(_) | ExpressionKind::Const(_) | ExpressionKind::Var(_) => None,
+    };
+    if let Some(folded) = folded {
+        expr.kind = folded;
+    }
+}
+
+/// Gets value of the expression if it is known at compile time.
+fn value_of(expr: &Expression) -> Option<ConstValue> {
+    match &expr.kind {
+        ExpressionKind::Literal(lit) => evaluate_literal(lit.clone(), Some(expr.type_))
+            .ok()
+            .map(|(value, _)| value),
+        ExpressionKind::Const(value) => Some(*value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hir::{
+        types::TypeId, ConstValue, Expression, ExpressionKind, FunctionId, Hir, Statement,
+        TranslationError,
+    };
+
+    use super::fold_constants;
+
+    /// Expression of the last statement of the function, that tail expression is lowered into.
+    fn last(hir: &Hir) -> &ExpressionKind {
+        let function = hir.get_function(FunctionId(0)).unwrap();
+        match function.body.statements.last() {
+            Some(Statement::Return(expr) | Statement::ExprStmt(expr)) => &expr.kind,
+            _ => panic!("function should end with an expression"),
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut hir = Hir::new_test("fn run() -> i32 { 1 + 2 * 3 }");
+        assert!(fold_constants(&mut hir).is_empty());
+        assert_eq!(last(&hir), &ExpressionKind::Const(ConstValue::Integer(7)));
+    }
+
+    #[test]
+    fn dead_branch() {
+        let mut hir = Hir::new_test("fn run(a: i32, b: i32) -> i32 { if true { a } else { b } }");
+        assert!(fold_constants(&mut hir).is_empty());
+        let ExpressionKind::Block(block) = last(&hir) else { panic!("`if` wasn't folded"); };
+        assert!(matches!(
+            block.statements.as_slice(),
+            [Statement::Return(Expression {
+                kind: ExpressionKind::Var(_),
+                ..
+            })]
+        ));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut hir = Hir::new_test("fn run() -> u8 { 200 + 100 }");
+        let errors = fold_constants(&mut hir);
+        let [TranslationError::ConstOverflow { span }] = errors.as_slice() else {
+            panic!("overflow wasn't reported");
+        };
+        assert_eq!((span.start.column, span.end.column), (17, 26));
+        assert!(matches!(last(&hir), ExpressionKind::BinaryOp { .. }));
+        assert_eq!(
+            hir.get_function(FunctionId(0)).unwrap().return_type,
+            TypeId::U8
+        );
+    }
+
+    #[test]
+    fn division_by_folded_zero() {
+        let mut hir = Hir::new_test("fn run(x: i32) -> i32 { x / (2 - 2) }");
+        let errors = fold_constants(&mut hir);
+        let [TranslationError::ConstDivisionByZero { span }] = errors.as_slice() else {
+            panic!("division by zero wasn't reported");
+        };
+        assert_eq!((span.start.column, span.end.column), (29, 34));
+    }
+
+    #[test]
+    fn float_division_by_zero() {
+        let mut hir = Hir::new_test("fn run(x: f32) -> f32 { x / (1.0 - 1.0) + 1.0 / 0.0 }");
+        assert!(fold_constants(&mut hir).is_empty());
+    }
+}
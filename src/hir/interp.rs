@@ -0,0 +1,920 @@
+//! Interpreter of HIR.
+//!
+//! Interpreter evaluates translated functions directly, which allows to check behaviour of the
+//! translation end to end without a backend. HIR is expected to be valid, so type errors are not
+//! reported and cause a panic instead; only errors that depend on the values are reported.
+
+use std::{cmp::Ordering, collections::HashMap, io::Write};
+
+use thiserror::Error;
+
+use crate::{
+    ast::expression::Literal,
+    lexer::operator::{BinaryOp, UnaryOp},
+    util::Span,
+    Identifier,
+};
+
+use super::{
+    builder::constant::evaluate_literal,
+    intrinsic::Intrinsic,
+    layout::Layout,
+    scope::VarId,
+    types::{PrimitiveType, TypeId, TypeTable},
+    Block, ConstValue, Expression, ExpressionKind, FunctionId, Hir, Statement,
+};
+
+/// Value computed at runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    Isize(isize),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    Usize(usize),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    /// Values of the fields in order of their declaration.
+    Struct(Vec<Value>),
+    Array(Vec<Value>),
+    Unit,
+}
+
+impl Value {
+    /// Creates integer of type `type_`, `None` if the type is not an integer or `value` doesn't
+    /// fit into it.
+    pub fn integer(type_: PrimitiveType, value: i128) -> Option<Value> {
+        let (min, max) = integer_range(type_)?;
+        (min..=max)
+            .contains(&value)
+            .then(|| Value::wrapping(type_, value))
+    }
+
+    /// Type of the primitive value, `None` for structs, arrays and unit.
+    pub fn primitive_type(&self) -> Option<PrimitiveType> {
+        use PrimitiveType::*;
+        Some(match self {
+            Value::Bool(_) => Bool,
+            Value::F32(_) => F32,
+            Value::F64(_) => F64,
+            Value::Str(_) => Str,
+            Value::Struct(_) | Value::Array(_) | Value::Unit => return None,
+            integer => integer.as_integer()?.0,
+        })
+    }
+
+    /// Creates integer of type `type_`, keeping only the bits that fit into it.
+    fn wrapping(type_: PrimitiveType, value: i128) -> Value {
+        use PrimitiveType::*;
+        match type_ {
+            I8 => Value::I8(value as i8),
+            I16 => Value::I16(value as i16),
+            I32 => Value::I32(value as i32),
+            I64 => Value::I64(value as i64),
+            Isize => Value::Isize(value as isize),
+            U8 => Value::U8(value as u8),
+            U16 => Value::U16(value as u16),
+            U32 => Value::U32(value as u32),
+            U64 => Value::U64(value as u64),
+            Usize => Value::Usize(value as usize),
+            Bool | F32 | F64 | Str => unreachable!("type is checked to be an integer"),
+        }
+    }
+
+    fn as_integer(&self) -> Option<(PrimitiveType, i128)> {
+        use PrimitiveType::*;
+        Some(match *self {
+            Value::I8(x) => (I8, x.into()),
+            Value::I16(x) => (I16, x.into()),
+            Value::I32(x) => (I32, x.into()),
+            Value::I64(x) => (I64, x.into()),
+            Value::Isize(x) => (Isize, x as i128),
+            Value::U8(x) => (U8, x.into()),
+            Value::U16(x) => (U16, x.into()),
+            Value::U32(x) => (U32, x.into()),
+            Value::U64(x) => (U64, x.into()),
+            Value::Usize(x) => (Usize, x as i128),
+            _ => return None,
+        })
+    }
+
+    /// Checks if the value may be stored in a variable of type `type_`.
+    fn has_type(&self, type_: TypeId, table: &TypeTable) -> bool {
+        match (self, type_) {
+            (Value::Unit, TypeId::UNIT) => true,
+            (Value::Struct(values), TypeId::Compound(_)) => {
+                table.fields_of(type_).is_some_and(|fields| {
+                    fields.len() == values.len()
+                        && values
+                            .iter()
+                            .zip(fields)
+                            .all(|(value, (_, type_))| value.has_type(*type_, table))
+                })
+            }
+            (Value::Array(values), TypeId::Array(_)) => {
+                table.array_element(type_).is_some_and(|(element, length)| {
+                    length == values.len()
+                        && values.iter().all(|value| value.has_type(element, table))
+                })
+            }
+            (value, TypeId::Primitive(primitive)) => value.primitive_type() == Some(primitive),
+            _ => false,
+        }
+    }
+}
+
+/// Error that stopped the execution.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RuntimeError {
+    #[error("function `{0}` is not found")]
+    UnknownFunction(String),
+    #[error("function `{0}` failed to translate")]
+    UntranslatedFunction(String),
+    #[error("arguments don't match parameters of function `{0}`")]
+    ArgumentMismatch(String),
+    #[error("call stack exceeded its maximal depth of {0}")]
+    StackOverflow(usize),
+    #[error("attempt to compute value that overflows its type")]
+    Overflow,
+    #[error("attempt to divide by zero")]
+    DivisionByZero,
+    #[error("index {index} is out of bounds for array of length {length}")]
+    IndexOutOfBounds { index: i128, length: usize },
+    #[error("variable {0} is used before being initialized")]
+    UninitializedVariable(VarId),
+    #[error("failed to write output: {0}")]
+    Output(String),
+    #[error("assertion failed")]
+    AssertionFailed { span: Span },
+    #[error("panicked: {message}")]
+    Panic { message: String, span: Span },
+}
+
+impl RuntimeError {
+    /// Span of the call that stopped the execution, if the error was raised by one.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RuntimeError::AssertionFailed { span } | RuntimeError::Panic { span, .. } => {
+                Some(*span)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Reason to stop evaluation of the expression before its value is computed.
+enum Unwind {
+    Break,
+    Return(Value),
+    Error(RuntimeError),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+type Eval<T = Value> = Result<T, Unwind>;
+
+/// Interpreter of the [Hir].
+pub struct Interpreter<'hir> {
+    hir: &'hir Hir,
+    max_depth: usize,
+    /// Local variables of functions being executed, the last one is the current function.
+    stack: Vec<HashMap<VarId, Value>>,
+    /// Sink that printing intrinsics write to.
+    output: Box<dyn Write + 'hir>,
+}
+
+impl<'hir> Interpreter<'hir> {
+    /// Maximal depth of the call stack used by default.
+    ///
+    /// Interpreter evaluates calls recursively, so the limit is kept low to not overflow the
+    /// native stack.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    pub fn new(hir: &'hir Hir) -> Self {
+        Self {
+            hir,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            stack: Vec::new(),
+            output: Box::new(std::io::stdout()),
+        }
+    }
+
+    /// Sets sink that printing intrinsics write to, which is standard output by default.
+    pub fn with_output(mut self, output: impl Write + 'hir) -> Self {
+        self.output = Box::new(output);
+        self
+    }
+
+    /// Sets maximal number of nested calls, including the call of the entry function.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Calls function at `entry` path relative to the crate root, such as `math::factorial`.
+    pub fn run(&mut self, entry: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let function = self
+            .hir
+            .mapping
+            .iter()
+            .find_map(|(path, id)| {
+                let segments = path.other.iter().map(Identifier::as_str);
+                segments.eq(entry.split("::")).then_some(*id)
+            })
+            .ok_or_else(|| RuntimeError::UnknownFunction(entry.to_owned()))?;
+        self.run_function(function, args)
+    }
+
+    /// Calls function with `id`.
+    pub fn run_function(
+        &mut self,
+        id: FunctionId,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.stack.clear();
+        self.call(id, args)
+    }
+
+    fn call(&mut self, id: FunctionId, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let hir = self.hir;
+        let name = || {
+            hir.function_path(id)
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        };
+        let function = hir
+            .get_function(id)
+            .ok_or_else(|| RuntimeError::UntranslatedFunction(name()))?;
+        if args.len() != function.params.len()
+            || !args
+                .iter()
+                .zip(function.params.iter())
+                .all(|(arg, type_)| arg.has_type(*type_, &hir.type_table))
+        {
+            return Err(RuntimeError::ArgumentMismatch(name()));
+        }
+        if self.stack.len() >= self.max_depth {
+            return Err(RuntimeError::StackOverflow(self.max_depth));
+        }
+        let vars = args
+            .into_iter()
+            .enumerate()
+            .map(|(n, arg)| (VarId::param(n as u32), arg))
+            .collect();
+        self.stack.push(vars);
+        let result = self.block(&function.body);
+        self.stack.pop();
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(error)) => Err(error),
+            Err(Unwind::Break) => {
+                unreachable!("`break` outside of loop is rejected by translation")
+            }
+        }
+    }
+
+    fn frame(&mut self) -> &mut HashMap<VarId, Value> {
+        self.stack.last_mut().expect("function is being executed")
+    }
+
+    fn block(&mut self, block: &Block) -> Eval {
+        for stmt in block.statements.iter() {
+            self.statement(stmt)?;
+        }
+        match &block.tail {
+            Some(tail) => self.expr(tail),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) -> Eval<()> {
+        match stmt {
+            Statement::ExprStmt(expr) => {
+                self.expr(expr)?;
+            }
+            Statement::LetStmt {
+                var,
+                value: Some(value),
+                ..
+            } => {
+                let value = self.expr(value)?;
+                self.frame().insert(*var, value);
+            }
+            // Variable declared in a loop is uninitialized on every iteration.
+            Statement::LetStmt {
+                var, value: None, ..
+            } => {
+                self.frame().remove(var);
+            }
+            Statement::Assignment { assignee, value } => {
+                let value = self.expr(value)?;
+                *self.place(assignee)? = value;
+            }
+            Statement::Return(expr) => return Err(Unwind::Return(self.expr(expr)?)),
+            Statement::Break => return Err(Unwind::Break),
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &Expression) -> Eval {
+        Ok(match &expr.kind {
+            ExpressionKind::Block(block) => self.block(block)?,
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                if self.expr(condition)? == Value::Bool(true) {
+                    self.block(body)?
+                } else if let Some(else_body) = else_body {
+                    self.block(else_body)?
+                } else {
+                    Value::Unit
+                }
+            }
+            ExpressionKind::Loop(body) => loop {
+                match self.block(body) {
+                    Ok(_) => {}
+                    Err(Unwind::Break) => break Value::Unit,
+                    Err(unwind) => return Err(unwind),
+                }
+            },
+            ExpressionKind::Literal(Literal::String(string)) => Value::Str(string.clone()),
+            ExpressionKind::Literal(literal) => {
+                let (value, _) = evaluate_literal(literal.clone(), Some(expr.type_))
+                    .expect("literals are checked by translation");
+                constant(value, expr.type_)?
+            }
+            ExpressionKind::Const(value) => constant(*value, expr.type_)?,
+            ExpressionKind::FnCall(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Eval<Vec<_>>>()?;
+                self.call(*function, args)?
+            }
+            ExpressionKind::Intrinsic(intrinsic, args, span) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Eval<Vec<_>>>()?;
+                self.intrinsic(*intrinsic, args, *span)?
+            }
+            ExpressionKind::Var(var) => self
+                .frame()
+                .get(var)
+                .cloned()
+                .ok_or(RuntimeError::UninitializedVariable(*var))?,
+            ExpressionKind::Field { value, field } => {
+                let index = self.field_index(value.type_, field);
+                let Value::Struct(mut fields) = self.expr(value)? else {
+                    unreachable!("field of non-struct value");
+                };
+                fields.swap_remove(index)
+            }
+            ExpressionKind::Index { value, index } => {
+                let Value::Array(mut elements) = self.expr(value)? else {
+                    unreachable!("index of non-array value");
+                };
+                let index = expect_integer(&self.expr(index)?);
+                let index = element_index(index, elements.len())?;
+                elements.swap_remove(index)
+            }
+            ExpressionKind::UnaryOp {
+                operator, value, ..
+            } => unary(*operator, self.expr(value)?)?,
+            ExpressionKind::Cast(value) => cast(self.expr(value)?, expr.type_),
+            ExpressionKind::BinaryOp {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                let left = self.expr(left)?;
+                let right = self.expr(right)?;
+                binary(*operator, left, right)?
+            }
+        })
+    }
+
+    /// Calls the `intrinsic`, `span` of the call is reported if it stops the execution.
+    fn intrinsic(
+        &mut self,
+        intrinsic: Intrinsic,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        let written = match (intrinsic, args.as_slice()) {
+            (Intrinsic::Print, [Value::Str(string)]) => write!(self.output, "{string}"),
+            (Intrinsic::Println, [Value::Str(string)]) => writeln!(self.output, "{string}"),
+            (Intrinsic::ToString, [Value::I64(x)]) => return Ok(Value::Str(x.to_string())),
+            (Intrinsic::Concat, [Value::Str(a), Value::Str(b)]) => {
+                return Ok(Value::Str(format!("{a}{b}")))
+            }
+            (Intrinsic::Assert | Intrinsic::AssertEq, [Value::Bool(holds)]) => {
+                return match holds {
+                    true => Ok(Value::Unit),
+                    false => Err(RuntimeError::AssertionFailed { span }),
+                };
+            }
+            (Intrinsic::Panic, [Value::Str(message)]) => {
+                return Err(RuntimeError::Panic {
+                    message: message.clone(),
+                    span,
+                })
+            }
+            _ => unreachable!("arguments of intrinsic are checked by translation"),
+        };
+        written.map_err(|error| RuntimeError::Output(error.to_string()))?;
+        Ok(Value::Unit)
+    }
+
+    /// Finds value stored at the `place`, see [Expression::is_place].
+    fn place(&mut self, place: &Expression) -> Eval<&mut Value> {
+        enum Projection {
+            Field(usize),
+            Index(i128),
+        }
+
+        // Indices are evaluated beforehand, as the variable is borrowed while the place is found.
+        let mut projections = Vec::new();
+        let mut current = place;
+        let var = loop {
+            match &current.kind {
+                ExpressionKind::Var(var) => break *var,
+                ExpressionKind::Field { value, field } => {
+                    projections.push(Projection::Field(self.field_index(value.type_, field)));
+                    current = value;
+                }
+                ExpressionKind::Index { value, index } => {
+                    let index = expect_integer(&self.expr(index)?);
+                    projections.push(Projection::Index(index));
+                    current = value;
+                }
+                _ => unreachable!("assignee is checked to be a place"),
+            }
+        };
+        let mut target = self
+            .frame()
+            .get_mut(&var)
+            .ok_or(RuntimeError::UninitializedVariable(var))?;
+        for projection in projections.into_iter().rev() {
+            target = match (projection, target) {
+                (Projection::Field(index), Value::Struct(fields)) => &mut fields[index],
+                (Projection::Index(index), Value::Array(elements)) => {
+                    let index = element_index(index, elements.len())?;
+                    &mut elements[index]
+                }
+                _ => unreachable!("projection doesn't match type of the value"),
+            };
+        }
+        Ok(target)
+    }
+
+    fn field_index(&self, strukt: TypeId, field: &Identifier) -> usize {
+        self.hir
+            .type_table
+            .fields_of(strukt)
+            .and_then(|fields| fields.iter().position(|(name, _)| name == field))
+            .expect("fields are checked by translation")
+    }
+}
+
+/// Minimal and maximal values of the integer type, `None` if the type is not an integer.
+fn integer_range(type_: PrimitiveType) -> Option<(i128, i128)> {
+    use PrimitiveType::*;
+    Some(match type_ {
+        I8 => (i8::MIN.into(), i8::MAX.into()),
+        I16 => (i16::MIN.into(), i16::MAX.into()),
+        I32 => (i32::MIN.into(), i32::MAX.into()),
+        I64 => (i64::MIN.into(), i64::MAX.into()),
+        Isize => (isize::MIN as i128, isize::MAX as i128),
+        U8 => (0, u8::MAX.into()),
+        U16 => (0, u16::MAX.into()),
+        U32 => (0, u32::MAX.into()),
+        U64 => (0, u64::MAX.into()),
+        Usize => (0, usize::MAX as i128),
+        Bool | F32 | F64 | Str => return None,
+    })
+}
+
+fn expect_integer(value: &Value) -> i128 {
+    value
+        .as_integer()
+        .expect("operand type is checked by translation")
+        .1
+}
+
+fn element_index(index: i128, length: usize) -> Result<usize, RuntimeError> {
+    usize::try_from(index)
+        .ok()
+        .filter(|index| *index < length)
+        .ok_or(RuntimeError::IndexOutOfBounds { index, length })
+}
+
+fn constant(value: ConstValue, type_: TypeId) -> Result<Value, RuntimeError> {
+    Ok(match (value, type_) {
+        (ConstValue::Integer(x), TypeId::Primitive(primitive)) => {
+            Value::integer(primitive, x).ok_or(RuntimeError::Overflow)?
+        }
+        (ConstValue::Float(x), TypeId::F64) => Value::F64(x.into()),
+        (ConstValue::Float(x), _) => Value::F32(x),
+        (ConstValue::Boolean(x), _) => Value::Bool(x),
+        (ConstValue::Integer(_), _) => unreachable!("integer constant of non-primitive type"),
+    })
+}
+
+fn unary(op: UnaryOp, value: Value) -> Result<Value, RuntimeError> {
+    Ok(match (op, value) {
+        (UnaryOp::Add, value) => value,
+        (UnaryOp::Sub, Value::F32(x)) => Value::F32(-x),
+        (UnaryOp::Sub, Value::F64(x)) => Value::F64(-x),
+        (UnaryOp::Not, Value::Bool(x)) => Value::Bool(!x),
+        (op, value) => {
+            let (type_, x) = value
+                .as_integer()
+                .expect("operand type is checked by translation");
+            match op {
+                UnaryOp::Sub => Value::integer(type_, -x).ok_or(RuntimeError::Overflow)?,
+                // Two's complement of the value has the same lower bits for any width.
+                _ => Value::wrapping(type_, !x),
+            }
+        }
+    })
+}
+
+/// Converts `value` to the `target` type following semantics of `as` in Rust: integers are
+/// truncated and floats are saturated.
+fn cast(value: Value, target: TypeId) -> Value {
+    let TypeId::Primitive(target) = target else {
+        unreachable!("only primitives may be cast");
+    };
+    let float = match value {
+        value if value.primitive_type() == Some(target) => return value,
+        Value::Bool(x) => return Value::wrapping(target, x.into()),
+        Value::F32(x) => f64::from(x),
+        Value::F64(x) => x,
+        value => {
+            let x = expect_integer(&value);
+            return match target {
+                PrimitiveType::F32 => Value::F32(x as f32),
+                PrimitiveType::F64 => Value::F64(x as f64),
+                _ => Value::wrapping(target, x),
+            };
+        }
+    };
+    match target {
+        PrimitiveType::F32 => Value::F32(float as f32),
+        PrimitiveType::F64 => Value::F64(float),
+        _ => {
+            let (min, max) = integer_range(target).expect("cast target is checked by translation");
+            Value::wrapping(target, (float as i128).clamp(min, max))
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    use BinaryOp::*;
+
+    if let (Some((type_, a)), Some((_, b))) = (left.as_integer(), right.as_integer()) {
+        return integer_binary(op, type_, a, b);
+    }
+    Ok(match (left, right) {
+        (Value::F32(a), Value::F32(b)) => {
+            float_binary(op, a.into(), b.into(), |x| Value::F32(x as f32))
+        }
+        (Value::F64(a), Value::F64(b)) => float_binary(op, a, b, Value::F64),
+        (Value::Bool(a), Value::Bool(b)) => Value::Bool(match op {
+            And | BinAnd => a & b,
+            Or | BinOr => a | b,
+            BinXor => a ^ b,
+            _ => compare(op, Some(a.cmp(&b))),
+        }),
+        (Value::Str(a), Value::Str(b)) => Value::Bool(compare(op, Some(a.cmp(&b)))),
+        (left, right) => match op {
+            Eq => Value::Bool(left == right),
+            Neq => Value::Bool(left != right),
+            _ => unreachable!("operand type is checked by translation"),
+        },
+    })
+}
+
+fn integer_binary(
+    op: BinaryOp,
+    type_: PrimitiveType,
+    a: i128,
+    b: i128,
+) -> Result<Value, RuntimeError> {
+    use BinaryOp::*;
+
+    let bits = Layout::primitive(type_).size as i128 * 8;
+    let value = match op {
+        Add => a + b,
+        Sub => a - b,
+        Mul => a.checked_mul(b).ok_or(RuntimeError::Overflow)?,
+        Div | Mod if b == 0 => return Err(RuntimeError::DivisionByZero),
+        Div => a / b,
+        Mod => a % b,
+        Rsh | Lsh if !(0..bits).contains(&b) => return Err(RuntimeError::Overflow),
+        Rsh => a >> b,
+        // Bits shifted out of the type are discarded, as in Rust.
+        Lsh => return Ok(Value::wrapping(type_, a.wrapping_shl(b as u32))),
+        BinAnd => a & b,
+        BinOr => a | b,
+        BinXor => a ^ b,
+        _ => return Ok(Value::Bool(compare(op, Some(a.cmp(&b))))),
+    };
+    Value::integer(type_, value).ok_or(RuntimeError::Overflow)
+}
+
+fn float_binary(op: BinaryOp, a: f64, b: f64, wrap: impl Fn(f64) -> Value) -> Value {
+    use BinaryOp::*;
+
+    match op {
+        Add => wrap(a + b),
+        Sub => wrap(a - b),
+        Mul => wrap(a * b),
+        Div => wrap(a / b),
+        Mod => wrap(a % b),
+        _ => Value::Bool(compare(op, a.partial_cmp(&b))),
+    }
+}
+
+/// Applies comparison operator to operands with given `ordering`, `None` if they are unordered.
+fn compare(op: BinaryOp, ordering: Option<Ordering>) -> bool {
+    use BinaryOp::*;
+
+    match op {
+        Eq => ordering == Some(Ordering::Equal),
+        Neq => ordering != Some(Ordering::Equal),
+        More => ordering == Some(Ordering::Greater),
+        Less => ordering == Some(Ordering::Less),
+        MoreEq => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        LessEq => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        _ => unreachable!("operator {op:?} is not a comparison"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hir::Hir;
+
+    use super::{Interpreter, RuntimeError, Value};
+
+    #[test]
+    fn factorial() {
+        let hir = Hir::new_test(
+            "
+            fn factorial(n: u64) -> u64 {
+                if n == 0 { 1 } else { n * factorial(n - 1) }
+            }
+            ",
+        );
+        let mut interpreter = Interpreter::new(&hir);
+        assert_eq!(
+            interpreter.run("factorial", vec![Value::U64(20)]),
+            Ok(Value::U64(2432902008176640000))
+        );
+        assert_eq!(
+            interpreter.run("factorial", vec![Value::U64(21)]),
+            Err(RuntimeError::Overflow)
+        );
+    }
+
+    #[test]
+    fn fibonacci() {
+        let hir = Hir::new_test(
+            "
+            fn fibonacci(n: u32) -> u32 {
+                let previous: u32 = 0;
+                let current: u32 = 1;
+                while n > 0 {
+                    let next: u32 = previous + current;
+                    previous = current;
+                    current = next;
+                    n = n - 1;
+                }
+                previous
+            }
+            fn slow(n: u32) -> u32 {
+                if n < 2 { return n; }
+                slow(n - 1) + slow(n - 2)
+            }
+            ",
+        );
+        let mut interpreter = Interpreter::new(&hir);
+        for (n, expected) in [(0, 0), (1, 1), (2, 1), (10, 55), (30, 832040)] {
+            assert_eq!(
+                interpreter.run("fibonacci", vec![Value::U32(n)]),
+                Ok(Value::U32(expected))
+            );
+        }
+        assert_eq!(
+            interpreter.run("slow", vec![Value::U32(15)]),
+            Ok(Value::U32(610))
+        );
+    }
+
+    #[test]
+    fn struct_field_math() {
+        let hir = Hir::new_test(
+            "
+            struct Point { x: i32, y: i32 }
+            struct Rect { min: Point, max: Point }
+            mod geometry {
+                fn area(rect: super::Rect) -> i32 {
+                    (rect.max.x - rect.min.x) * (rect.max.y - rect.min.y)
+                }
+                fn normalized(rect: super::Rect) -> super::Rect {
+                    if rect.min.x > rect.max.x {
+                        let x: i32 = rect.min.x;
+                        rect.min.x = rect.max.x;
+                        rect.max.x = x;
+                    }
+                    rect
+                }
+            }
+            ",
+        );
+        let point = |x, y| Value::Struct(vec![Value::I32(x), Value::I32(y)]);
+        let rect = Value::Struct(vec![point(5, -1), point(2, 3)]);
+        let mut interpreter = Interpreter::new(&hir);
+        assert_eq!(
+            interpreter.run("geometry::area", vec![rect.clone()]),
+            Ok(Value::I32(-12))
+        );
+        assert_eq!(
+            interpreter.run("geometry::normalized", vec![rect]),
+            Ok(Value::Struct(vec![point(2, -1), point(5, 3)]))
+        );
+    }
+
+    #[test]
+    fn loop_with_break() {
+        let hir = Hir::new_test(
+            "
+            fn first_square_above(limit: u16) -> u16 {
+                let i: u16 = 0;
+                while true {
+                    if i * i > limit { break; }
+                    i = i + 1;
+                }
+                i
+            }
+            fn sum(values: [i64; 4]) -> i64 {
+                let total: i64 = 0;
+                for i in 0..4 { total = total + values[i]; }
+                total
+            }
+            fn max(values: [i64; 4]) -> i64 {
+                let max: i64 = values[0];
+                for value in values { if value > max { max = value; } }
+                max
+            }
+            ",
+        );
+        let mut interpreter = Interpreter::new(&hir);
+        assert_eq!(
+            interpreter.run("first_square_above", vec![Value::U16(50)]),
+            Ok(Value::U16(8))
+        );
+        let values = Value::Array([1, -2, 30, 400].map(Value::I64).to_vec());
+        assert_eq!(interpreter.run("sum", vec![values.clone()]), Ok(Value::I64(429)));
+        assert_eq!(interpreter.run("max", vec![values]), Ok(Value::I64(400)));
+    }
+
+    #[test]
+    fn hello_world() {
+        let hir = Hir::new_test(
+            r#"
+            fn main() {
+                println("Hello, world!");
+                nested::answer();
+            }
+            mod nested {
+                fn answer() {
+                    crate::print("answer: ");
+                    crate::println(crate::to_string(6 * 7));
+                }
+            }
+            "#,
+        );
+        let mut output = Vec::new();
+        let mut interpreter = Interpreter::new(&hir).with_output(&mut output);
+        assert_eq!(interpreter.run("main", Vec::new()), Ok(Value::Unit));
+        drop(interpreter);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Hello, world!\nanswer: 42\n"
+        );
+    }
+
+    #[test]
+    fn derived_to_string() {
+        let hir = Hir::new_test(
+            r#"
+            #[derive(to_string)]
+            struct Point { x: i32, y: u8 }
+            #[derive(to_string)]
+            struct Label { text: str, visible: bool, at: Point }
+            #[derive(to_string)]
+            struct Empty {}
+            fn describe(label: Label, empty: Empty) -> str {
+                concat(label.to_string(), Empty::to_string(empty))
+            }
+            "#,
+        );
+        let point = Value::Struct(vec![Value::I32(-3), Value::U8(7)]);
+        let text = Value::Str(String::from("origin"));
+        let label = Value::Struct(vec![text, Value::Bool(true), point]);
+        let mut interpreter = Interpreter::new(&hir);
+        assert_eq!(
+            interpreter.run("describe", vec![label, Value::Struct(Vec::new())]),
+            Ok(Value::Str(String::from(
+                "Label { text: origin, visible: true, at: Point { x: -3, y: 7 } }Empty {}"
+            )))
+        );
+    }
+
+    #[test]
+    fn assertions() {
+        let hir = Hir::new_test(
+            r#"
+            struct Point { x: i32 }
+            impl Point {
+                fn eq(self, other: Point) -> bool { self.x == other.x }
+            }
+            fn check(x: i32, y: i32, a: Point, b: Point) {
+                assert(x < 10);
+                assert_eq(a, b);
+                assert_eq(x, y);
+            }
+            fn fail(message: str) -> i32 {
+                panic(message);
+                0
+            }
+            "#,
+        );
+        let mut interpreter = Interpreter::new(&hir);
+        let failed_line = |result: Result<Value, RuntimeError>| {
+            let error = result.unwrap_err();
+            assert!(matches!(error, RuntimeError::AssertionFailed { .. }));
+            error.span().unwrap().start.line
+        };
+        let point = |x| Value::Struct(vec![Value::I32(x)]);
+        let mut check = |x, y| {
+            let args = vec![Value::I32(x), Value::I32(y), point(x), point(y)];
+            interpreter.run("check", args)
+        };
+        assert_eq!(check(1, 1), Ok(Value::Unit));
+        assert_eq!(failed_line(check(10, 10)), 6);
+        assert_eq!(failed_line(check(1, 2)), 7);
+
+        let message = Value::Str(String::from("unreachable"));
+        let error = interpreter.run("fail", vec![message]).unwrap_err();
+        assert_eq!(error.to_string(), "panicked: unreachable");
+        assert_eq!(error.span().unwrap().start.line, 11);
+    }
+
+    #[test]
+    fn errors() {
+        let hir = Hir::new_test(
+            "
+            fn forever(n: i32) -> i32 { forever(n + 1) }
+            fn divide(a: i8, b: i8) -> i8 { a / b }
+            ",
+        );
+        let mut interpreter = Interpreter::new(&hir).with_max_depth(16);
+        assert_eq!(
+            interpreter.run("forever", vec![Value::I32(0)]),
+            Err(RuntimeError::StackOverflow(16))
+        );
+        assert_eq!(
+            interpreter.run("divide", vec![Value::I8(1), Value::I8(0)]),
+            Err(RuntimeError::DivisionByZero)
+        );
+        assert_eq!(
+            interpreter.run("divide", vec![Value::I8(-128), Value::I8(-1)]),
+            Err(RuntimeError::Overflow)
+        );
+        assert_eq!(
+            interpreter.run("divide", vec![Value::I32(1), Value::I8(1)]),
+            Err(RuntimeError::ArgumentMismatch("crate::divide".to_owned()))
+        );
+        assert_eq!(
+            interpreter.run("missing", Vec::new()),
+            Err(RuntimeError::UnknownFunction("missing".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Functions provided by the compiler rather than declared in the source code.
+//!
+//! Intrinsics are declared at the root of the crate, but functions declared in the source code
+//! take precedence over them.
+
+use std::fmt::Display;
+
+use crate::path::AbsolutePath;
+
+use super::types::TypeId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Intrinsic {
+    /// Writes string to the output.
+    Print,
+    /// Writes string to the output, followed by a newline.
+    Println,
+    /// Converts integer to its decimal representation.
+    ToString,
+    /// Joins two strings.
+    Concat,
+    /// Stops the execution if the condition is false.
+    Assert,
+    /// Stops the execution if two values are not equal.
+    ///
+    /// `assert_eq(a, b)` is lowered to the call with the result of `a == b`, so values of any
+    /// type that supports `==` may be compared.
+    AssertEq,
+    /// Stops the execution with the message.
+    Panic,
+}
+
+impl Intrinsic {
+    /// Every intrinsic.
+    pub const ALL: [Intrinsic; 7] = [
+        Intrinsic::Print,
+        Intrinsic::Println,
+        Intrinsic::ToString,
+        Intrinsic::Concat,
+        Intrinsic::Assert,
+        Intrinsic::AssertEq,
+        Intrinsic::Panic,
+    ];
+
+    /// Name the intrinsic is declared with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Intrinsic::Print => "print",
+            Intrinsic::Println => "println",
+            Intrinsic::ToString => "to_string",
+            Intrinsic::Concat => "concat",
+            Intrinsic::Assert => "assert",
+            Intrinsic::AssertEq => "assert_eq",
+            Intrinsic::Panic => "panic",
+        }
+    }
+
+    /// Intrinsic that `path` refers to.
+    ///
+    /// Name of the crate is not checked, so intrinsics are found regardless of the name the
+    /// crate is compiled with.
+    pub fn at(path: &AbsolutePath) -> Option<Intrinsic> {
+        let [name] = path.iter().as_slice() else {
+            return None;
+        };
+        Intrinsic::ALL
+            .into_iter()
+            .find(|intrinsic| intrinsic.name() == name.as_str())
+    }
+
+    /// Types of the parameters.
+    pub fn params(&self) -> &'static [TypeId] {
+        match self {
+            Intrinsic::Print | Intrinsic::Println | Intrinsic::Panic => &[TypeId::STR],
+            Intrinsic::ToString => &[TypeId::I64],
+            Intrinsic::Concat => &[TypeId::STR, TypeId::STR],
+            Intrinsic::Assert | Intrinsic::AssertEq => &[TypeId::BOOL],
+        }
+    }
+
+    pub fn return_type(&self) -> TypeId {
+        match self {
+            Intrinsic::Print
+            | Intrinsic::Println
+            | Intrinsic::Assert
+            | Intrinsic::AssertEq
+            | Intrinsic::Panic => TypeId::UNIT,
+            Intrinsic::ToString | Intrinsic::Concat => TypeId::STR,
+        }
+    }
+}
+
+impl Display for Intrinsic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,161 @@
+//! Memory layout of types.
+//!
+//! Layout is C-like: fields are placed in order of declaration, each at the first offset that is
+//! a multiple of its alignment, and size of the type is rounded up to a multiple of its alignment,
+//! which is the largest alignment of its fields. Target is assumed to be 64-bit.
+
+use std::fmt::Display;
+
+use super::types::{PrimitiveType, TypeId, TypeTable};
+
+/// Size and alignment of the type in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Layout {
+    pub size: u64,
+    pub alignment: u64,
+}
+
+impl Layout {
+    /// Layout of types without fields, such as unit.
+    pub const EMPTY: Layout = Layout {
+        size: 0,
+        alignment: 1,
+    };
+
+    /// Layout of primitive type.
+    ///
+    /// [str](PrimitiveType::Str) is stored as a pointer to the data and its length.
+    pub fn primitive(primitive: PrimitiveType) -> Self {
+        use PrimitiveType::*;
+        let size = match primitive {
+            Bool | I8 | U8 => 1,
+            I16 | U16 => 2,
+            I32 | U32 | F32 => 4,
+            I64 | U64 | Isize | Usize | F64 => 8,
+            Str => {
+                return Layout {
+                    size: 16,
+                    alignment: 8,
+                }
+            }
+        };
+        Layout {
+            size,
+            alignment: size,
+        }
+    }
+
+    /// Layout of the type that stores `fields` one after another.
+    pub fn aggregate(fields: impl IntoIterator<Item = Layout>) -> Self {
+        let mut layout = Layout::EMPTY;
+        for field in fields {
+            layout.size = align_up(layout.size, field.alignment) + field.size;
+            layout.alignment = layout.alignment.max(field.alignment);
+        }
+        layout.size = align_up(layout.size, layout.alignment);
+        layout
+    }
+
+    /// Layout of the array of `length` elements with this layout.
+    pub fn array(self, length: usize) -> Self {
+        Layout {
+            size: self.size * length as u64,
+            alignment: self.alignment,
+        }
+    }
+}
+
+impl Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SIZE {}, ALIGN {}", self.size, self.alignment)
+    }
+}
+
+/// Rounds `offset` up to a multiple of `alignment`.
+fn align_up(offset: u64, alignment: u64) -> u64 {
+    offset.div_ceil(alignment) * alignment
+}
+
+/// Computes layouts of all user-defined types, making them available via [TypeTable::layout_of].
+///
+/// Table must not contain types of infinite size, see [TypeTable::find_cycles].
+pub fn compute_layouts(table: &mut TypeTable) {
+    for index in 0..table.iter().count() as u32 {
+        compute(table, index);
+    }
+}
+
+/// Computes layout of user-defined type, computing layouts of types it contains beforehand.
+fn compute(table: &mut TypeTable, index: u32) -> Layout {
+    let id = TypeId::Compound(index);
+    if let Some(layout) = table.layout_of(id) {
+        return layout;
+    }
+    let fields: Vec<TypeId> = table
+        .fields_of(id)
+        .unwrap_or_default()
+        .iter()
+        .map(|(_, type_)| *type_)
+        .collect();
+    let mut contained = Vec::new();
+    for field in fields.iter() {
+        table.contained_compounds(*field, &mut contained);
+    }
+    for dependency in contained {
+        compute(table, dependency);
+    }
+    let layout = Layout::aggregate(fields.into_iter().map(|field| {
+        table
+            .layout_of(field)
+            .expect("layouts of contained types are computed beforehand")
+    }));
+    table.set_layout(id, layout);
+    layout
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{
+        hir::{types::TypeId, Hir},
+        path::AbsolutePath,
+    };
+
+    use super::Layout;
+
+    fn layout(hir: &Hir, name: &str) -> Layout {
+        let path = AbsolutePath::from_str(&format!("crate::{name}")).unwrap();
+        let id = hir.type_table.mapping[&path];
+        hir.type_table
+            .layout_of(id)
+            .expect("layout should be computed")
+    }
+
+    #[test]
+    fn padding() {
+        let hir = Hir::new_test(
+            "
+            struct Wrapper { inner: Mixed, flags: [bool; 3] }
+            struct Mixed { small: u8, large: i32, flag: bool }
+            struct Empty {}
+            ",
+        );
+        assert_eq!(
+            layout(&hir, "Mixed"),
+            Layout {
+                size: 12,
+                alignment: 4
+            }
+        );
+        assert_eq!(
+            layout(&hir, "Wrapper"),
+            Layout {
+                size: 16,
+                alignment: 4
+            }
+        );
+        assert_eq!(layout(&hir, "Empty"), Layout::EMPTY);
+        assert_eq!(hir.type_table.layout_of(TypeId::UNIT), Some(Layout::EMPTY));
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    fmt::Display,
+    io::{Result, Write},
+};
+
+use crate::ast::expression::Literal;
+
+use super::{
+    types::TypeId, Block, ConstValue, Expression, ExpressionKind, Function, FunctionId, Hir,
+    Statement,
+};
+
+/// Prints every struct of the HIR with its layout and then every function, both ordered by path.
+pub fn print_hir(w: impl Write, hir: &Hir) -> Result<()> {
+    let mut paths = vec![String::new(); hir.functions.len()];
+    for (path, id) in hir.mapping.iter() {
+        paths[id.0 as usize] = path.to_string();
+    }
+    let mut order: Vec<_> = (0..hir.functions.len() as u32).map(FunctionId).collect();
+    order.sort_by(|a, b| paths[a.0 as usize].cmp(&paths[b.0 as usize]));
+
+    let mut printer = Printer {
+        writer: w,
+        hir,
+        paths,
+        indent: 0,
+    };
+    let mut structs: Vec<_> = hir.type_table.iter().collect();
+    structs.sort_by_key(|(_, path)| path.to_string());
+    for (id, _) in structs {
+        printer.print_struct(id)?;
+        printer.newline()?;
+    }
+    for id in order {
+        match hir.get_function(id) {
+            Some(function) => printer.print_function(id, function)?,
+            None => printer.println(format!("FN {} FAILED", printer.function_name(id)))?,
+        }
+        printer.newline()?;
+    }
+    Ok(())
+}
+
+struct Printer<'a, W: Write> {
+    writer: W,
+    hir: &'a Hir,
+    /// Paths of functions indexed by [FunctionId].
+    paths: Vec<String>,
+    indent: usize,
+}
+
+impl<W: Write> Printer<'_, W> {
+    /// Width of a single indentation.
+    const IDENT_WIDTH: usize = 4;
+
+    fn print_struct(&mut self, id: TypeId) -> Result<()> {
+        let hir = self.hir;
+        let type_table = &hir.type_table;
+        match type_table.layout_of(id) {
+            Some(layout) => self.println(format!("STRUCT `{}`: {layout}", self.type_name(id)))?,
+            None => self.println(format!("STRUCT `{}`", self.type_name(id)))?,
+        }
+        self.with_indent(|printer| {
+            for (name, type_) in type_table.fields_of(id).unwrap_or_default() {
+                printer.println(format!("{name}: `{}`", printer.type_name(*type_)))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn print_function(&mut self, id: FunctionId, function: &Function) -> Result<()> {
+        self.println(format!("FN {}", self.function_name(id)))?;
+        self.with_indent(|printer| {
+            if !function.params.is_empty() {
+                printer.println("PARAMS")?;
+                printer.with_indent(|printer| {
+                    for (index, type_) in function.params.iter().enumerate() {
+                        printer.println(format!("%{index}: `{}`", printer.type_name(*type_)))?;
+                    }
+                    Ok(())
+                })?;
+            }
+            printer.println(format!(
+                "RETURN `{}`",
+                printer.type_name(function.return_type)
+            ))?;
+            printer.println("BODY")?;
+            printer.print_block(&function.body)?;
+            Ok(())
+        })
+    }
+
+    fn print_stmt(&mut self, stmt: &Statement) -> Result<()> {
+        match stmt {
+            Statement::ExprStmt(expr) => self.print_expr(expr)?,
+            Statement::LetStmt { var, type_, value } => {
+                self.println(format!("LET {var}: `{}`", self.type_name(*type_)))?;
+                if let Some(value) = value {
+                    self.with_indent(|printer| printer.print_expr(value))?;
+                }
+            }
+            Statement::Assignment {
+                assignee:
+                    Expression {
+                        kind: ExpressionKind::Var(var),
+                        ..
+                    },
+                value,
+            } => {
+                self.println(format!("ASSIGN {var}"))?;
+                self.with_indent(|printer| printer.print_expr(value))?;
+            }
+            Statement::Assignment { assignee, value } => {
+                self.println("ASSIGN")?;
+                self.with_indent(|printer| {
+                    printer.print_expr(assignee)?;
+                    printer.print_expr(value)?;
+                    Ok(())
+                })?;
+            }
+            Statement::Return(expr) => {
+                self.println("RETURN")?;
+                self.with_indent(|printer| printer.print_expr(expr))?;
+            }
+            Statement::Break => self.println("BREAK")?,
+        }
+        Ok(())
+    }
+
+    fn print_expr(&mut self, expr: &Expression) -> Result<()> {
+        let type_ = self.type_name(expr.type_);
+        match &expr.kind {
+            ExpressionKind::Block(block) => {
+                self.println(format!("BLOCK: `{type_}`"))?;
+                self.print_block(block)?;
+            }
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                self.println(format!("IF: `{type_}`"))?;
+                self.with_indent(|printer| printer.print_expr(condition))?;
+
+                self.println("THEN")?;
+                self.print_block(body)?;
+
+                if let Some(else_body) = else_body {
+                    self.println("ELSE")?;
+                    self.print_block(else_body)?;
+                }
+            }
+            ExpressionKind::Loop(body) => {
+                self.println(format!("LOOP: `{type_}`"))?;
+                self.print_block(body)?;
+            }
+            ExpressionKind::Literal(Literal::Number(num)) => {
+                self.println(format!("`{num}`: `{type_}`"))?
+            }
+            ExpressionKind::Literal(Literal::String(s)) => {
+                self.println(format!("`\"{s}\"`: `{type_}`"))?
+            }
+            ExpressionKind::Literal(Literal::Boolean(value))
+            | ExpressionKind::Const(ConstValue::Boolean(value)) => {
+                self.println(format!("`{value}`: `{type_}`"))?
+            }
+            ExpressionKind::Const(ConstValue::Integer(value)) => {
+                self.println(format!("`{value}`: `{type_}`"))?
+            }
+            ExpressionKind::Const(ConstValue::Float(value)) => {
+                self.println(format!("`{value}`: `{type_}`"))?
+            }
+            ExpressionKind::FnCall(id, args) => {
+                self.println(format!("FNCALL {}: `{type_}`", self.function_name(*id)))?;
+                self.with_indent(|printer| {
+                    for arg in args {
+                        printer.print_expr(arg)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            ExpressionKind::Intrinsic(intrinsic, args, _) => {
+                self.println(format!("INTRINSIC `{intrinsic}`: `{type_}`"))?;
+                self.with_indent(|printer| {
+                    for arg in args {
+                        printer.print_expr(arg)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            ExpressionKind::Var(var) => self.println(format!("{var}: `{type_}`"))?,
+            ExpressionKind::Field { value, field } => {
+                self.println(format!("FIELD `{field}`: `{type_}`"))?;
+                self.with_indent(|printer| printer.print_expr(value))?;
+            }
+            ExpressionKind::Index { value, index } => {
+                self.println(format!("INDEX: `{type_}`"))?;
+                self.with_indent(|printer| {
+                    printer.print_expr(value)?;
+                    printer.print_expr(index)?;
+                    Ok(())
+                })?;
+            }
+            ExpressionKind::UnaryOp {
+                operator, value, ..
+            } => {
+                self.println(format!("UNARY `{operator}`: `{type_}`"))?;
+                self.with_indent(|printer| printer.print_expr(value))?;
+            }
+            ExpressionKind::Cast(value) => {
+                self.println(format!("CAST: `{type_}`"))?;
+                self.with_indent(|printer| printer.print_expr(value))?;
+            }
+            ExpressionKind::BinaryOp {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                self.println(format!("BINARY `{operator}`: `{type_}`"))?;
+                self.with_indent(|printer| {
+                    printer.print_expr(left)?;
+                    printer.print_expr(right)?;
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn print_block(&mut self, block: &Block) -> Result<()> {
+        self.with_indent(|printer| {
+            for stmt in block.statements.iter() {
+                printer.print_stmt(stmt)?;
+            }
+            if let Some(expr) = &block.tail {
+                printer.print_expr(expr)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn function_name(&self, id: FunctionId) -> String {
+        format!("#{} `{}`", id.0, self.paths[id.0 as usize])
+    }
+
+    fn type_name(&self, id: TypeId) -> String {
+        self.hir.type_table.type_name(id)
+    }
+
+    fn with_indent(&mut self, f: impl Fn(&mut Self) -> Result<()>) -> Result<()> {
+        self.indent += 1;
+        f(self)?;
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn newline(&mut self) -> Result<()> {
+        writeln!(self.writer)
+    }
+
+    fn println(&mut self, line: impl Display) -> Result<()> {
+        self.print_indent()?;
+        write!(self.writer, "{}", line)?;
+        self.newline()?;
+        Ok(())
+    }
+
+    fn print_indent(&mut self) -> Result<()> {
+        write!(
+            self.writer,
+            "{}",
+            " ".repeat(self.indent * Self::IDENT_WIDTH)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{hir::Hir, path::AbsolutePath};
+
+    use super::print_hir;
+
+    #[test]
+    fn golden() {
+        let hir = Hir::new_test(
+            "
+            fn add(a: i32, b: i32) -> i32 { a + b }
+            fn count(n: i32) -> i32 {
+                let total: i32 = 0;
+                while total < n { total = add(total, 1); }
+                if total > 10 { return 10; }
+                total
+            }
+            ",
+        );
+        let id = |name: &str| {
+            let path = AbsolutePath::from_str(&format!("crate::{name}")).unwrap();
+            hir.function_id(&path).unwrap().0
+        };
+        let (add, count) = (id("add"), id("count"));
+
+        let mut output = Vec::new();
+        print_hir(&mut output, &hir).unwrap();
+        let expected = format!(
+            "\
+FN #{add} `crate::add`
+    PARAMS
+        %0: `i32`
+        %1: `i32`
+    RETURN `i32`
+    BODY
+        RETURN
+            BINARY `+`: `i32`
+                %0: `i32`
+                %1: `i32`
+
+FN #{count} `crate::count`
+    PARAMS
+        %0: `i32`
+    RETURN `i32`
+    BODY
+        LET %1: `i32`
+            `0`: `i32`
+        LOOP: `()`
+            IF: `()`
+                UNARY `!`: `bool`
+                    BINARY `<`: `bool`
+                        %1: `i32`
+                        %0: `i32`
+            THEN
+                BREAK
+            ASSIGN %1
+                FNCALL #{add} `crate::add`: `i32`
+                    %1: `i32`
+                    `1`: `i32`
+        IF: `()`
+            BINARY `>`: `bool`
+                %1: `i32`
+                `10`: `i32`
+        THEN
+            RETURN
+                `10`: `i32`
+        RETURN
+            %1: `i32`
+
+"
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn struct_layout() {
+        let hir = Hir::new_test(
+            "
+            struct Pair { first: Single, second: u8 }
+            struct Single { value: i32 }
+            ",
+        );
+        let mut output = Vec::new();
+        print_hir(&mut output, &hir).unwrap();
+        let expected = "\
+STRUCT `crate::Pair`: SIZE 8, ALIGN 4
+    first: `crate::Single`
+    second: `u8`
+
+STRUCT `crate::Single`: SIZE 4, ALIGN 4
+    value: `i32`
+
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
@@ -1,10 +1,11 @@
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
+    fmt::Display,
     rc::Rc,
 };
 
-use crate::{hir::types::TypeId, Identifier};
+use crate::{hir::types::TypeId, util::Span, Identifier};
 
 /// The scope is a portion of code that defines where local variable names are accessible.
 ///
@@ -42,6 +43,7 @@ impl Scope {
                 parent: None,
                 mapping: HashMap::new(),
                 types: HashMap::new(),
+                spans: HashMap::new(),
             })),
             latest_id: Rc::new(Cell::new(0)),
             loop_context: false,
@@ -55,6 +57,7 @@ impl Scope {
                 parent: Some(self.clone()),
                 mapping: HashMap::new(),
                 types: HashMap::new(),
+                spans: HashMap::new(),
             })),
             latest_id: Rc::clone(&self.latest_id),
             loop_context: self.loop_context,
@@ -68,19 +71,32 @@ impl Scope {
                 parent: Some(self.clone()),
                 mapping: HashMap::new(),
                 types: HashMap::new(),
+                spans: HashMap::new(),
             })),
             latest_id: Rc::clone(&self.latest_id),
             loop_context: true,
         }
     }
 
-    /// Inserts variable in the scope.
-    pub fn insert(&mut self, var: Identifier, type_id: TypeId) -> VarId {
+    /// Inserts variable declared at `span` in the scope.
+    ///
+    /// Variable with the same name declared in the scope before is shadowed, but keeps its [VarId].
+    pub fn insert(&mut self, var: Identifier, type_id: TypeId, span: Span) -> VarId {
         let mut scope = self.inner.borrow_mut();
 
         let var_id = VarId(self.latest_id.get());
         scope.mapping.insert(var, var_id);
         scope.types.insert(var_id, type_id);
+        scope.spans.insert(var_id, span);
+        self.latest_id.set(var_id.0 + 1);
+        var_id
+    }
+
+    /// Inserts variable that can't be referred to by name, such as the one introduced by
+    /// desugaring.
+    pub fn insert_anonymous(&mut self, type_id: TypeId) -> VarId {
+        let var_id = VarId(self.latest_id.get());
+        self.inner.borrow_mut().types.insert(var_id, type_id);
         self.latest_id.set(var_id.0 + 1);
         var_id
     }
@@ -98,13 +114,18 @@ impl Scope {
                     .expect("Type should be defined for any `var_id` defined at the same scope");
                 Some((var_id, *type_id))
             }
-            None => {
-                let Some(ref scope) = scope.parent else { return None; };
-                scope.lookup(var)
-            }
+            None => scope.parent.as_ref()?.lookup(var),
         }
     }
 
+    /// Looks variable up in the scope itself, ignoring its parents, along with the location of
+    /// its declaration.
+    pub fn lookup_local(&self, var: &Identifier) -> Option<(VarId, Span)> {
+        let scope = self.inner.borrow();
+        let var_id = scope.mapping.get(var).copied()?;
+        Some((var_id, scope.spans[&var_id]))
+    }
+
     /// Gets the parent scope if there is one.
     pub fn parent(&self) -> Option<Scope> {
         self.inner.borrow().parent.clone()
@@ -129,6 +150,8 @@ struct ScopeInner {
     parent: Option<Scope>,
     mapping: HashMap<Identifier, VarId>,
     types: HashMap<VarId, TypeId>,
+    /// Locations of declarations of named variables.
+    spans: HashMap<VarId, Span>,
 }
 
 /// An id of local variable.
@@ -136,3 +159,43 @@ struct ScopeInner {
 /// These ids are only unique in the same function they were declared at.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct VarId(u32);
+
+impl VarId {
+    /// Id of the function's `n`-th parameter, as parameters are declared before any other variable.
+    pub(crate) fn param(n: u32) -> Self {
+        VarId(n)
+    }
+
+    /// Number of the variable in its function.
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for VarId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{hir::types::TypeId, util::Span, Identifier};
+
+    use super::Scope;
+
+    #[test]
+    fn insert_shadow() {
+        let mut scope = Scope::new();
+        let name = Identifier(String::from("x"));
+
+        let first = scope.insert(name.clone(), TypeId::I32, Span::default());
+        assert_eq!(scope.lookup_local(&name), Some((first, Span::default())));
+        let mut span = Span::default();
+        span.start.line = 1;
+        let second = scope.insert(name.clone(), TypeId::BOOL, span);
+        assert_eq!(scope.lookup_local(&name), Some((second, span)));
+        assert_ne!(first, second);
+        assert_eq!(scope.lookup(&name), Some((second, TypeId::BOOL)));
+    }
+}
@@ -0,0 +1,217 @@
+//! Runner of functions annotated with `#[test]`.
+//!
+//! Tests are executed by the [Interpreter] one after another. Test passes if it returns nothing
+//! or `true`, and fails if it returns `false` or stops with a [RuntimeError]. Failed assertions
+//! and panics are reported along with the file and line of the call.
+
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::{context::Context, path::AbsolutePath, util::Span};
+
+use super::{
+    interp::{Interpreter, RuntimeError, Value},
+    Hir,
+};
+
+/// Reason of the failure of a test.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TestFailure {
+    #[error("returned `false`")]
+    ReturnedFalse,
+    #[error("{0}")]
+    Runtime(#[from] RuntimeError),
+    /// Error raised by the call at `location`.
+    #[error("{error} at {location}")]
+    Located {
+        error: RuntimeError,
+        location: String,
+    },
+}
+
+/// Result of a single test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestOutcome {
+    pub path: AbsolutePath,
+    pub result: Result<(), TestFailure>,
+}
+
+impl Display for TestOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.result {
+            Ok(()) => write!(f, "test {} ... ok", self.path),
+            Err(failure) => write!(f, "test {} ... FAILED: {failure}", self.path),
+        }
+    }
+}
+
+/// Results of the tests that were run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestReport {
+    /// Outcomes of the tests in order of their paths.
+    pub outcomes: Vec<TestOutcome>,
+    /// Number of tests that didn't match the filter.
+    pub filtered_out: usize,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.outcomes.len() - self.failed()
+    }
+
+    pub fn failed(&self) -> usize {
+        let outcomes = self.outcomes.iter();
+        outcomes.filter(|outcome| outcome.result.is_err()).count()
+    }
+}
+
+/// Summary line of the report.
+impl Display for TestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = if self.failed() == 0 { "ok" } else { "FAILED" };
+        write!(
+            f,
+            "test result: {status}. {} passed; {} failed; {} filtered out",
+            self.passed(),
+            self.failed(),
+            self.filtered_out
+        )
+    }
+}
+
+/// Runs tests of the `hir` which paths contain `filter`, or all of them if there is no filter.
+///
+/// `on_outcome` is called after every test, so the progress may be shown while tests run.
+pub fn run_tests(
+    hir: &Hir,
+    context: &Context,
+    filter: Option<&str>,
+    mut on_outcome: impl FnMut(&TestOutcome),
+) -> TestReport {
+    let mut tests: Vec<_> = hir
+        .tests()
+        .iter()
+        .map(|id| {
+            let path = hir.function_path(*id).expect("test should be declared");
+            (path, *id)
+        })
+        .collect();
+    tests.sort_by_key(|(path, _)| path.to_string());
+
+    let mut report = TestReport::default();
+    let mut interpreter = Interpreter::new(hir);
+    for (path, id) in tests {
+        if filter.is_some_and(|filter| !path.to_string().contains(filter)) {
+            report.filtered_out += 1;
+            continue;
+        }
+        let result = match interpreter.run_function(id, Vec::new()) {
+            Ok(Value::Bool(false)) => Err(TestFailure::ReturnedFalse),
+            Ok(_) => Ok(()),
+            Err(error) => Err(match error.span() {
+                Some(span) => TestFailure::Located {
+                    error,
+                    location: location(context, span),
+                },
+                None => error.into(),
+            }),
+        };
+        let outcome = TestOutcome {
+            path: path.clone(),
+            result,
+        };
+        on_outcome(&outcome);
+        report.outcomes.push(outcome);
+    }
+    report
+}
+
+/// Renders the start of the `span` as `FILE:LINE`, or `line LINE` if it's not in a file.
+fn location(context: &Context, span: Span) -> String {
+    let line = span.start.line + 1;
+    match span.source {
+        Some(id) => {
+            let source = context.source.lock().unwrap();
+            format!("{}:{line}", source.get_path(id).display())
+        }
+        None => format!("line {line}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        context::Context,
+        hir::{interp::RuntimeError, Hir},
+    };
+
+    use super::{run_tests, TestFailure};
+
+    const SRC: &str = "
+        fn half(x: i32) -> i32 { x / 2 }
+        #[test]
+        fn halves() -> bool { half(4) == 2 }
+        #[test]
+        fn rounds_up() -> bool { half(3) == 2 }
+        #[test]
+        fn divides_by_zero() { let zero: i32 = 0; half(1 / zero); }
+        #[test]
+        fn asserts() {
+            assert(half(2) == 1);
+            assert_eq(half(5), 3);
+        }
+        mod nested {
+            #[test]
+            fn runs() {}
+        }
+    ";
+
+    #[test]
+    fn outcomes() {
+        let context = Context::new_test();
+        let hir = Hir::new_test_with(context.clone(), SRC);
+
+        let mut progress = Vec::new();
+        let report = run_tests(&hir, &context, None, |outcome| {
+            progress.push(outcome.to_string())
+        });
+        assert_eq!(
+            progress,
+            [
+                "test crate::asserts ... FAILED: assertion failed at line 12",
+                "test crate::divides_by_zero ... FAILED: attempt to divide by zero",
+                "test crate::halves ... ok",
+                "test crate::nested::runs ... ok",
+                "test crate::rounds_up ... FAILED: returned `false`",
+            ]
+        );
+        let results: Vec<_> = report.outcomes.iter().map(|o| o.result.clone()).collect();
+        assert!(matches!(
+            &results[0],
+            Err(TestFailure::Located {
+                error: RuntimeError::AssertionFailed { .. },
+                location,
+            }) if location == "line 12"
+        ));
+        assert_eq!(
+            results[1..],
+            [
+                Err(TestFailure::Runtime(RuntimeError::DivisionByZero)),
+                Ok(()),
+                Ok(()),
+                Err(TestFailure::ReturnedFalse),
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "test result: FAILED. 2 passed; 3 failed; 0 filtered out"
+        );
+
+        let report = run_tests(&hir, &context, Some("halves"), |_| {});
+        assert_eq!(
+            report.to_string(),
+            "test result: ok. 1 passed; 0 failed; 4 filtered out"
+        );
+    }
+}
@@ -1,6 +1,13 @@
-use std::{borrow::Borrow, collections::HashMap, str::FromStr};
+use std::{cell::RefCell, collections::HashMap, fmt::Display, str::FromStr};
 
-use crate::{util::MonotonicVec, Identifier};
+use crate::{
+    ast::types::TypeExpr,
+    path::{AbsolutePath, RelativePath, RelativePathStart},
+    util::MonotonicVec,
+    Identifier,
+};
+
+use super::layout::Layout;
 
 use thiserror::Error;
 
@@ -8,60 +15,429 @@ use thiserror::Error;
 #[derive(Debug, Default)]
 pub struct TypeTable {
     pub(super) latest_compound: u32,
-    pub(super) mapping: HashMap<Identifier, TypeId>,
-    pub(super) fields: MonotonicVec<HashMap<Identifier, TypeId>>,
+    pub(super) mapping: HashMap<AbsolutePath, TypeId>,
+    /// Paths of user-defined types indexed by [TypeId::Compound].
+    paths: MonotonicVec<AbsolutePath>,
+    /// Fields of user-defined types in order of declaration, indexed by [TypeId::Compound].
+    fields: MonotonicVec<Vec<(Identifier, TypeId)>>,
+    /// Layouts of user-defined types indexed by [TypeId::Compound], see [compute_layouts](super::layout::compute_layouts).
+    layouts: MonotonicVec<Option<Layout>>,
+    /// Types that are available by name in every module, see [TypeTable::with_builtins].
+    builtins: HashMap<Identifier, TypeId>,
+    structural: RefCell<StructuralTypes>,
 }
 
 impl TypeTable {
+    /// Creates empty table, without even primitive types available by name.
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn get(&self, id: impl Borrow<Identifier>) -> TypeResult<TypeId> {
-        self.mapping
-            .get(id.borrow())
-            .copied()
-            .or_else(|| {
-                PrimitiveType::from_str(id.borrow().as_str())
-                    .ok()
-                    .map(TypeId::Primitive)
-            })
-            .ok_or_else(|| TypeError::NotFound(id.borrow().clone()))
+    /// Creates table with builtin prelude, which makes every [PrimitiveType] available by its name.
+    pub fn with_builtins() -> Self {
+        let mut table = Self::new();
+        for primitive in PrimitiveType::ALL {
+            let name = Identifier(String::from(primitive.name()));
+            table.builtins.insert(name, TypeId::Primitive(primitive));
+        }
+        table
+    }
+
+    /// Checks if `name` is a name of builtin type, which may not be redefined.
+    pub fn is_builtin(&self, name: &Identifier) -> bool {
+        self.builtins.contains_key(name)
+    }
+
+    /// Resolves type expression into [TypeId].
+    ///
+    /// Paths met in the expression are mapped to absolute ones via `resolve`, which is expected
+    /// to take the module the expression is met in and its imports into account. Path that
+    /// doesn't refer to user-defined type is then looked up among the builtins.
+    pub fn get<F>(&self, type_: &TypeExpr, resolve: &F) -> TypeResult<TypeId>
+    where
+        F: Fn(&RelativePath) -> Option<AbsolutePath>,
+    {
+        match type_ {
+            TypeExpr::Path(path) => self.get_by_path(path, resolve),
+            TypeExpr::Tuple(types) => {
+                let types = types
+                    .iter()
+                    .map(|type_| self.get(type_, resolve))
+                    .collect::<TypeResult<Vec<_>>>()?;
+                Ok(self.structural.borrow_mut().tuple(types))
+            }
+            TypeExpr::Array { element, length } => {
+                let element = self.get(element, resolve)?;
+                Ok(self.structural.borrow_mut().array(element, *length))
+            }
+            TypeExpr::Unit => Ok(TypeId::UNIT),
+        }
+    }
+
+    fn get_by_path<F>(&self, path: &RelativePath, resolve: &F) -> TypeResult<TypeId>
+    where
+        F: Fn(&RelativePath) -> Option<AbsolutePath>,
+    {
+        let not_found = || TypeError::NotFound(TypeExpr::Path(path.clone()));
+        let Some(absolute) = resolve(path) else {
+            return Err(TypeError::UnresolvedPath(path.clone()));
+        };
+        if let Some(id) = self.mapping.get(&absolute).copied() {
+            return Ok(id);
+        }
+        match path {
+            RelativePath {
+                start: RelativePathStart::Identifier(name),
+                other,
+            } if other.is_empty() => self.builtins.get(name).copied().ok_or_else(not_found),
+            _ => Err(not_found()),
+        }
     }
 
     /// Adds user-defined type's name to the table.
-    pub(super) fn define_name(&mut self, name: Identifier) -> TypeId {
+    pub(super) fn define_name(&mut self, path: AbsolutePath) -> TypeId {
         let id = TypeId::Compound(self.latest_compound);
-        self.mapping.insert(name, id);
-        self.fields.push(HashMap::default());
+        self.mapping.insert(path.clone(), id);
+        self.paths.push(path);
+        self.fields.push(Vec::new());
+        self.layouts.push(None);
         self.latest_compound += 1;
         id
     }
 
     /// Adds field to defined struct.
+    ///
+    /// Fields are expected to be unique since parsing, but the table stays consistent regardless:
+    /// a field with the name that is already declared isn't added.
     pub(super) fn add_field(
         &mut self,
         strukt: TypeId,
         name: Identifier,
-        type_: Identifier,
+        type_: TypeId,
     ) -> TypeResult<()> {
-        let type_ = self.get(type_)?;
         if let TypeId::Compound(index) = strukt {
-            self.fields[index as usize].insert(name, type_);
+            let fields = &mut self.fields[index as usize];
+            if fields.iter().any(|(field, _)| *field == name) {
+                let strukt = self.paths[index as usize].clone();
+                return Err(TypeError::DuplicateField(strukt, name));
+            }
+            fields.push((name, type_));
         }
         Ok(())
     }
+
+    /// Gets type of struct's field.
+    pub fn field(&self, strukt: TypeId, name: &Identifier) -> Option<TypeId> {
+        self.fields_of(strukt)?
+            .iter()
+            .find_map(|(field, type_)| (field == name).then_some(*type_))
+    }
+
+    /// Gets fields of user-defined type in order of declaration.
+    pub fn fields_of(&self, id: TypeId) -> Option<&[(Identifier, TypeId)]> {
+        let TypeId::Compound(index) = id else { return None; };
+        self.fields.get(index as usize).map(Vec::as_slice)
+    }
+
+    /// Iterates over user-defined types in order of definition.
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, &AbsolutePath)> {
+        self.paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (TypeId::Compound(index as u32), path))
+    }
+
+    /// Gets name of primitive or user-defined type, as it was declared.
+    ///
+    /// Tuples and arrays have no name, see [TypeTable::display] to render them.
+    pub fn name_of(&self, id: TypeId) -> Option<&str> {
+        match id {
+            TypeId::Primitive(primitive) => Some(primitive.name()),
+            TypeId::Compound(_) => self.path_of(id).map(|path| path.last().0.as_str()),
+            TypeId::Tuple(_) | TypeId::Array(_) => None,
+        }
+    }
+
+    /// Adapter that displays type as it would be written in the source code.
+    pub fn display(&self, id: TypeId) -> DisplayType<'_> {
+        DisplayType { table: self, id }
+    }
+
+    /// Renders type as it would be written in the source code, see [TypeTable::display].
+    pub fn type_name(&self, id: TypeId) -> String {
+        self.display(id).to_string()
+    }
+
+    /// Gets path of user-defined type.
+    pub fn path_of(&self, id: TypeId) -> Option<&AbsolutePath> {
+        let TypeId::Compound(index) = id else { return None; };
+        self.paths.get(index as usize)
+    }
+
+    /// Finds cycles of user-defined types that contain each other by value, so have infinite size.
+    ///
+    /// Each cycle is listed in order of containment starting from its lexicographically first member.
+    pub fn find_cycles(&self) -> Vec<Vec<AbsolutePath>> {
+        let mut state = vec![VisitState::Unvisited; self.fields.len()];
+        let mut stack = Vec::new();
+        let mut cycles = Vec::new();
+        for index in 0..self.fields.len() as u32 {
+            self.visit(index, &mut state, &mut stack, &mut cycles);
+        }
+
+        cycles
+            .into_iter()
+            .map(|cycle| {
+                let mut cycle: Vec<AbsolutePath> = cycle
+                    .into_iter()
+                    .filter_map(|index| self.path_of(TypeId::Compound(index)).cloned())
+                    .collect();
+                let first = (0..cycle.len())
+                    .min_by_key(|index| cycle[*index].to_string())
+                    .unwrap_or_default();
+                cycle.rotate_left(first);
+                cycle
+            })
+            .collect()
+    }
+
+    /// Depth-first search step of [TypeTable::find_cycles].
+    fn visit(
+        &self,
+        index: u32,
+        state: &mut [VisitState],
+        stack: &mut Vec<u32>,
+        cycles: &mut Vec<Vec<u32>>,
+    ) {
+        match state[index as usize] {
+            VisitState::Done => return,
+            VisitState::InProgress => {
+                if let Some(start) = stack.iter().position(|visited| *visited == index) {
+                    cycles.push(stack[start..].to_vec());
+                }
+                return;
+            }
+            VisitState::Unvisited => {}
+        }
+
+        state[index as usize] = VisitState::InProgress;
+        stack.push(index);
+        let mut contained = Vec::new();
+        for (_, field) in self.fields[index as usize].iter() {
+            self.contained_compounds(*field, &mut contained);
+        }
+        contained.sort_unstable();
+        for dependency in contained {
+            self.visit(dependency, state, stack, cycles);
+        }
+        stack.pop();
+        state[index as usize] = VisitState::Done;
+    }
+
+    /// Collects user-defined types stored by value in the value of type `id`.
+    pub(super) fn contained_compounds(&self, id: TypeId, contained: &mut Vec<u32>) {
+        match id {
+            TypeId::Primitive(_) => {}
+            TypeId::Compound(index) => contained.push(index),
+            TypeId::Tuple(_) => {
+                for element in self.tuple_elements(id).unwrap_or_default() {
+                    self.contained_compounds(element, contained);
+                }
+            }
+            TypeId::Array(_) => {
+                if let Some((element, _)) = self.array_element(id) {
+                    self.contained_compounds(element, contained);
+                }
+            }
+        }
+    }
+
+    /// Gets types of tuple's elements.
+    pub fn tuple_elements(&self, id: TypeId) -> Option<Vec<TypeId>> {
+        let TypeId::Tuple(index) = id else { return None; };
+        Some(self.structural.borrow().tuples[index as usize].clone())
+    }
+
+    /// Gets array's element type and length.
+    pub fn array_element(&self, id: TypeId) -> Option<(TypeId, usize)> {
+        let TypeId::Array(index) = id else { return None; };
+        Some(self.structural.borrow().arrays[index as usize])
+    }
+
+    /// Gets size and alignment of the type.
+    ///
+    /// `None` if the type contains user-defined type, which layout wasn't computed.
+    pub fn layout_of(&self, id: TypeId) -> Option<Layout> {
+        match id {
+            TypeId::Primitive(primitive) => Some(Layout::primitive(primitive)),
+            TypeId::Compound(index) => self.layouts.get(index as usize).copied().flatten(),
+            TypeId::Tuple(_) => self
+                .tuple_elements(id)?
+                .into_iter()
+                .map(|element| self.layout_of(element))
+                .collect::<Option<Vec<_>>>()
+                .map(Layout::aggregate),
+            TypeId::Array(_) => {
+                let (element, length) = self.array_element(id)?;
+                Some(self.layout_of(element)?.array(length))
+            }
+        }
+    }
+
+    /// Stores layout of user-defined type.
+    pub(super) fn set_layout(&mut self, strukt: TypeId, layout: Layout) {
+        if let TypeId::Compound(index) = strukt {
+            self.layouts[index as usize] = Some(layout);
+        }
+    }
+}
+
+/// Type displayed with the names from its [TypeTable], see [TypeTable::display].
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayType<'t> {
+    table: &'t TypeTable,
+    id: TypeId,
+}
+
+impl Display for DisplayType<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let table = self.table;
+        match self.id {
+            TypeId::Primitive(primitive) => write!(f, "{primitive}"),
+            TypeId::Compound(_) => match table.path_of(self.id) {
+                Some(path) => write!(f, "{path}"),
+                None => write!(f, "{{unknown}}"),
+            },
+            TypeId::Tuple(_) => {
+                let elements = table.tuple_elements(self.id).unwrap_or_default();
+                write!(f, "(")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", table.display(*element))?;
+                }
+                if elements.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            TypeId::Array(_) => match table.array_element(self.id) {
+                Some((element, length)) => write!(f, "[{}; {length}]", table.display(element)),
+                None => write!(f, "{{unknown}}"),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+    Unvisited,
+    InProgress,
+    Done,
+}
+
+/// Tuple and array types met in the program.
+///
+/// They are interned on demand, so structurally equal types always get the same [TypeId].
+#[derive(Debug)]
+struct StructuralTypes {
+    tuples: MonotonicVec<Vec<TypeId>>,
+    arrays: MonotonicVec<(TypeId, usize)>,
+    mapping: HashMap<StructuralType, TypeId>,
+}
+
+impl Default for StructuralTypes {
+    fn default() -> Self {
+        let mut types = Self {
+            tuples: MonotonicVec::default(),
+            arrays: MonotonicVec::default(),
+            mapping: HashMap::default(),
+        };
+        types.tuple(Vec::new());
+        types
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum StructuralType {
+    Tuple(Vec<TypeId>),
+    Array(TypeId, usize),
+}
+
+impl StructuralTypes {
+    fn tuple(&mut self, types: Vec<TypeId>) -> TypeId {
+        let tuples = &mut self.tuples;
+        *self
+            .mapping
+            .entry(StructuralType::Tuple(types.clone()))
+            .or_insert_with(|| {
+                tuples.push(types);
+                TypeId::Tuple(tuples.len() as u32 - 1)
+            })
+    }
+
+    fn array(&mut self, element: TypeId, length: usize) -> TypeId {
+        let arrays = &mut self.arrays;
+        *self
+            .mapping
+            .entry(StructuralType::Array(element, length))
+            .or_insert_with(|| {
+                arrays.push((element, length));
+                TypeId::Array(arrays.len() as u32 - 1)
+            })
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TypeId {
     Primitive(PrimitiveType),
     Compound(u32),
+    Tuple(u32),
+    Array(u32),
 }
 
 impl TypeId {
     pub const BOOL: TypeId = TypeId::Primitive(PrimitiveType::Bool);
+    pub const I8: TypeId = TypeId::Primitive(PrimitiveType::I8);
+    pub const I16: TypeId = TypeId::Primitive(PrimitiveType::I16);
     pub const I32: TypeId = TypeId::Primitive(PrimitiveType::I32);
+    pub const I64: TypeId = TypeId::Primitive(PrimitiveType::I64);
+    pub const ISIZE: TypeId = TypeId::Primitive(PrimitiveType::Isize);
+    pub const U8: TypeId = TypeId::Primitive(PrimitiveType::U8);
+    pub const U16: TypeId = TypeId::Primitive(PrimitiveType::U16);
+    pub const U32: TypeId = TypeId::Primitive(PrimitiveType::U32);
+    pub const U64: TypeId = TypeId::Primitive(PrimitiveType::U64);
+    pub const USIZE: TypeId = TypeId::Primitive(PrimitiveType::Usize);
+    pub const F32: TypeId = TypeId::Primitive(PrimitiveType::F32);
+    pub const F64: TypeId = TypeId::Primitive(PrimitiveType::F64);
+    pub const STR: TypeId = TypeId::Primitive(PrimitiveType::Str);
+    /// Empty tuple, which is interned before any other type.
+    pub const UNIT: TypeId = TypeId::Tuple(0);
+
+    /// Checks if type is one of integer primitives.
+    pub fn is_integer(&self) -> bool {
+        matches!(self, TypeId::Primitive(primitive) if primitive.is_integer())
+    }
+
+    /// Checks if type is one of floating point primitives.
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            TypeId::Primitive(PrimitiveType::F32 | PrimitiveType::F64)
+        )
+    }
+
+    /// Checks if value of the type may be converted to `target` type with `as` cast.
+    ///
+    /// Numbers may be cast to any numeric type, and booleans may be cast to integers.
+    pub fn can_cast_to(&self, target: TypeId) -> bool {
+        let is_numeric = |type_: &TypeId| type_.is_integer() || type_.is_float();
+        match *self {
+            TypeId::BOOL => target.is_integer(),
+            source => is_numeric(&source) && is_numeric(&target),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -78,6 +454,71 @@ pub enum PrimitiveType {
     U64,
     Usize,
     F32,
+    F64,
+    Str,
+}
+
+impl PrimitiveType {
+    /// Every primitive type.
+    pub const ALL: [PrimitiveType; 14] = {
+        use PrimitiveType::*;
+        [
+            Bool, I8, I16, I32, I64, Isize, U8, U16, U32, U64, Usize, F32, F64, Str,
+        ]
+    };
+
+    pub fn is_integer(&self) -> bool {
+        use PrimitiveType::*;
+        matches!(
+            self,
+            I8 | I16 | I32 | I64 | Isize | U8 | U16 | U32 | U64 | Usize
+        )
+    }
+
+    /// Minimal and maximal values of the integer type, `None` if the type is not an integer.
+    ///
+    /// Pointer-sized types are assumed to be 64-bit.
+    pub fn integer_bounds(&self) -> Option<(i128, i128)> {
+        use PrimitiveType::*;
+        Some(match self {
+            I8 => (i8::MIN.into(), i8::MAX.into()),
+            I16 => (i16::MIN.into(), i16::MAX.into()),
+            I32 => (i32::MIN.into(), i32::MAX.into()),
+            I64 | Isize => (i64::MIN.into(), i64::MAX.into()),
+            U8 => (0, u8::MAX.into()),
+            U16 => (0, u16::MAX.into()),
+            U32 => (0, u32::MAX.into()),
+            U64 | Usize => (0, u64::MAX.into()),
+            Bool | F32 | F64 | Str => return None,
+        })
+    }
+
+    /// Name of the type as it is written in the source code.
+    pub fn name(&self) -> &'static str {
+        use PrimitiveType::*;
+        match self {
+            Bool => "bool",
+            U8 => "u8",
+            U16 => "u16",
+            U32 => "u32",
+            U64 => "u64",
+            Usize => "usize",
+            I8 => "i8",
+            I16 => "i16",
+            I32 => "i32",
+            I64 => "i64",
+            Isize => "isize",
+            F32 => "f32",
+            F64 => "f64",
+            Str => "str",
+        }
+    }
+}
+
+impl Display for PrimitiveType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
 }
 
 impl FromStr for PrimitiveType {
@@ -98,6 +539,8 @@ impl FromStr for PrimitiveType {
             "i64" => I64,
             "isize" => Isize,
             "f32" => F32,
+            "f64" => F64,
+            "str" => Str,
             _ => return Err(()),
         })
     }
@@ -108,7 +551,108 @@ pub type TypeResult<T> = Result<T, TypeError>;
 #[derive(Debug, Error)]
 pub enum TypeError {
     #[error("type `{0}` is not found")]
-    NotFound(Identifier),
+    NotFound(TypeExpr),
     #[error("type `{0}` is already defined")]
     AlreadyDefined(Identifier),
+    #[error("recursive {} infinite size", render_cycle(.0))]
+    InfiniteSize(Vec<AbsolutePath>),
+    #[error("cannot resolve path {0}: too many `super` segments")]
+    UnresolvedPath(RelativePath),
+    #[error("field `{1}` of struct `{0}` is declared multiple times")]
+    DuplicateField(AbsolutePath, Identifier),
+}
+
+fn render_cycle(cycle: &[AbsolutePath]) -> String {
+    match cycle {
+        [strukt] => format!("type `{strukt}` has"),
+        cycle => {
+            let members = cycle
+                .iter()
+                .chain(cycle.first())
+                .map(|strukt| format!("`{strukt}`"))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            format!("types {members} have")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parser::FileParser, path::AbsolutePath, Identifier};
+
+    use super::{PrimitiveType, TypeError, TypeId, TypeTable};
+
+    fn get(table: &TypeTable, src: &str) -> TypeId {
+        let type_ = FileParser::new_test(src).parse_type().unwrap();
+        let module = AbsolutePath::new(Identifier(String::from("crate")));
+        table
+            .get(&type_, &|path| path.to_absolute(&module))
+            .expect("type should be resolved")
+    }
+
+    #[test]
+    fn structural_types_are_interned() {
+        let table = TypeTable::with_builtins();
+        assert_eq!(get(&table, "(i32, bool)"), get(&table, "(i32, bool)"));
+        assert_ne!(get(&table, "(i32, bool)"), get(&table, "(bool, i32)"));
+        assert_eq!(get(&table, "[i32; 4]"), get(&table, "[i32; 4]"));
+        assert_ne!(get(&table, "[i32; 4]"), get(&table, "[i32; 5]"));
+    }
+
+    #[test]
+    fn path() {
+        let mut table = TypeTable::with_builtins();
+        let mut path = AbsolutePath::new(Identifier(String::from("crate")));
+        path.push(Identifier(String::from("geometry")));
+        path.push(Identifier(String::from("Point")));
+        let id = table.define_name(path);
+        assert_eq!(id, get(&table, "crate::geometry::Point"));
+        assert_eq!(id, get(&table, "geometry::Point"));
+        assert_eq!(TypeId::I32, get(&table, "i32"));
+    }
+
+    #[test]
+    fn builtins() {
+        let table = TypeTable::with_builtins();
+        for primitive in PrimitiveType::ALL {
+            assert_eq!(get(&table, primitive.name()), TypeId::Primitive(primitive));
+        }
+        let type_ = FileParser::new_test("i32").parse_type().unwrap();
+        let module = AbsolutePath::new(Identifier(String::from("crate")));
+        assert!(TypeTable::new()
+            .get(&type_, &|path| path.to_absolute(&module))
+            .is_err());
+    }
+
+    #[test]
+    fn names_and_fields() {
+        let mut table = TypeTable::with_builtins();
+        let ident = |name: &str| Identifier(String::from(name));
+        let mut path = AbsolutePath::new(ident("crate"));
+        path.push(ident("Point"));
+        let point = table.define_name(path.clone());
+        table.add_field(point, ident("y"), TypeId::I32).unwrap();
+        table.add_field(point, ident("x"), TypeId::F32).unwrap();
+        assert!(matches!(
+            table.add_field(point, ident("y"), TypeId::BOOL),
+            Err(TypeError::DuplicateField(strukt, field)) if strukt == path && field == ident("y")
+        ));
+
+        assert_eq!(table.name_of(TypeId::BOOL), Some("bool"));
+        assert_eq!(table.name_of(point), Some("Point"));
+        assert_eq!(table.name_of(get(&table, "(i32, bool)")), None);
+        assert_eq!(
+            table.fields_of(point),
+            Some([(ident("y"), TypeId::I32), (ident("x"), TypeId::F32)].as_slice())
+        );
+        assert_eq!(table.fields_of(TypeId::I32), None);
+        assert_eq!(table.iter().collect::<Vec<_>>(), [(point, &path)]);
+
+        let tuple = get(&table, "(Point, [bool; 2], (i32,))");
+        assert_eq!(
+            table.display(tuple).to_string(),
+            "(crate::Point, [bool; 2], (i32,))"
+        );
+    }
 }
@@ -0,0 +1,371 @@
+//! Validation of HIR well-formedness.
+//!
+//! HIR produced by [HirBuilder](super::HirBuilder) and transformed by later passes is expected to
+//! always be valid, so any error found here is a bug in the compiler rather than in the program.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use super::{
+    intrinsic::Intrinsic, scope::VarId, types::TypeId, Block, Expression, ExpressionKind, Function,
+    FunctionId, Hir, Statement,
+};
+
+/// Checks every function of the HIR.
+pub fn validate(hir: &Hir) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for (index, function) in hir.functions.iter().enumerate() {
+        let Some(function) = function else { continue; };
+        let mut validator = Validator {
+            hir,
+            function: FunctionId(index as u32),
+            return_type: function.return_type,
+            scopes: vec![function
+                .params
+                .iter()
+                .enumerate()
+                .map(|(index, type_)| (VarId::param(index as u32), *type_))
+                .collect()],
+            loop_depth: 0,
+            errors: &mut errors,
+        };
+        validator.validate_function(function);
+    }
+    errors
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationError {
+    #[error("variable {var:?} is used before declaration in function {function:?}")]
+    UndeclaredVariable { function: FunctionId, var: VarId },
+    #[error("expected type {expected:?}, found {found:?} in function {function:?}")]
+    TypeMismatch {
+        function: FunctionId,
+        expected: TypeId,
+        found: TypeId,
+    },
+    #[error("function {callee:?} called from function {function:?} doesn't exist")]
+    UnknownFunction {
+        function: FunctionId,
+        callee: FunctionId,
+    },
+    #[error("incorrect number of arguments passed to {callee:?} in function {function:?}")]
+    ArgumentCount {
+        function: FunctionId,
+        callee: FunctionId,
+    },
+    #[error("incorrect number of arguments passed to `{intrinsic}` in function {function:?}")]
+    IntrinsicArgumentCount {
+        function: FunctionId,
+        intrinsic: Intrinsic,
+    },
+    #[error("break outside of the loop in function {function:?}")]
+    BreakOutsideLoop { function: FunctionId },
+    #[error("assignee is not a place in function {function:?}")]
+    InvalidAssignee { function: FunctionId },
+    #[error("field or element of type {type_:?} doesn't exist in function {function:?}")]
+    InvalidAccess { function: FunctionId, type_: TypeId },
+    #[error("type {from:?} can't be cast to {to:?} in function {function:?}")]
+    InvalidCast {
+        function: FunctionId,
+        from: TypeId,
+        to: TypeId,
+    },
+    #[error("non-unit function {function:?} ends without `return`")]
+    MissingReturn { function: FunctionId },
+}
+
+struct Validator<'a> {
+    hir: &'a Hir,
+    function: FunctionId,
+    return_type: TypeId,
+    /// Variables declared in every enclosing block.
+    scopes: Vec<HashMap<VarId, TypeId>>,
+    loop_depth: usize,
+    errors: &'a mut Vec<ValidationError>,
+}
+
+impl Validator<'_> {
+    fn validate_function(&mut self, function: &Function) {
+        let body_type = self.validate_block(&function.body);
+        if function.body.diverges(false) {
+            return;
+        }
+        // Tail expression of non-unit function is lowered into explicit `return`.
+        if function.return_type == TypeId::UNIT {
+            self.expect_type(TypeId::UNIT, body_type);
+        } else {
+            self.errors.push(ValidationError::MissingReturn {
+                function: self.function,
+            });
+        }
+    }
+
+    fn validate_block(&mut self, block: &Block) -> TypeId {
+        self.scopes.push(HashMap::new());
+        for stmt in block.statements.iter() {
+            self.validate_stmt(stmt);
+        }
+        if let Some(tail) = &block.tail {
+            self.validate_expr(tail);
+        }
+        self.scopes.pop();
+        block.type_id()
+    }
+
+    fn validate_stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::ExprStmt(expr) => self.validate_expr(expr),
+            Statement::LetStmt { var, type_, value } => {
+                if let Some(value) = value {
+                    self.validate_expr(value);
+                    self.expect_type(*type_, value.type_);
+                }
+                self.scopes
+                    .last_mut()
+                    .expect("statement should be inside of the block")
+                    .insert(*var, *type_);
+            }
+            Statement::Assignment { assignee, value } => {
+                self.validate_expr(assignee);
+                self.validate_expr(value);
+                if !assignee.is_place() {
+                    self.errors.push(ValidationError::InvalidAssignee {
+                        function: self.function,
+                    });
+                }
+                self.expect_type(assignee.type_, value.type_);
+            }
+            Statement::Return(expr) => {
+                self.validate_expr(expr);
+                self.expect_type(self.return_type, expr.type_);
+            }
+            Statement::Break => {
+                if self.loop_depth == 0 {
+                    self.errors.push(ValidationError::BreakOutsideLoop {
+                        function: self.function,
+                    });
+                }
+            }
+        }
+    }
+
+    fn validate_expr(&mut self, expr: &Expression) {
+        match &expr.kind {
+            ExpressionKind::Block(block) => {
+                let type_ = self.validate_block(block);
+                self.expect_type(expr.type_, type_);
+            }
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                self.validate_expr(condition);
+                self.expect_type(TypeId::BOOL, condition.type_);
+                let body_type = self.validate_block(body);
+                match else_body {
+                    Some(else_body) => {
+                        let else_type = self.validate_block(else_body);
+                        self.expect_type(body_type, else_type);
+                    }
+                    None => self.expect_type(TypeId::UNIT, body_type),
+                }
+                self.expect_type(expr.type_, body_type);
+            }
+            ExpressionKind::Loop(body) => {
+                self.loop_depth += 1;
+                self.validate_block(body);
+                self.loop_depth -= 1;
+            }
+            ExpressionKind::Literal(_) | ExpressionKind::Const(_) => {}
+            ExpressionKind::FnCall(callee, args) => {
+                for arg in args.iter() {
+                    self.validate_expr(arg);
+                }
+                let Some(Some(function)) = self.hir.functions.get(callee.0 as usize) else {
+                    self.errors.push(ValidationError::UnknownFunction {
+                        function: self.function,
+                        callee: *callee,
+                    });
+                    return;
+                };
+                if function.params.len() != args.len() {
+                    self.errors.push(ValidationError::ArgumentCount {
+                        function: self.function,
+                        callee: *callee,
+                    });
+                }
+                for (param, arg) in function.params.iter().zip(args.iter()) {
+                    self.expect_type(*param, arg.type_);
+                }
+                self.expect_type(function.return_type, expr.type_);
+            }
+            ExpressionKind::Intrinsic(intrinsic, args, _) => {
+                for arg in args.iter() {
+                    self.validate_expr(arg);
+                }
+                if intrinsic.params().len() != args.len() {
+                    self.errors.push(ValidationError::IntrinsicArgumentCount {
+                        function: self.function,
+                        intrinsic: *intrinsic,
+                    });
+                }
+                for (param, arg) in intrinsic.params().iter().zip(args.iter()) {
+                    self.expect_type(*param, arg.type_);
+                }
+                self.expect_type(intrinsic.return_type(), expr.type_);
+            }
+            ExpressionKind::Var(var) => {
+                if let Some(type_) = self.lookup(*var) {
+                    self.expect_type(type_, expr.type_);
+                }
+            }
+            ExpressionKind::Field { value, field } => {
+                self.validate_expr(value);
+                match self.hir.type_table.field(value.type_, field) {
+                    Some(type_) => self.expect_type(type_, expr.type_),
+                    None => self.errors.push(ValidationError::InvalidAccess {
+                        function: self.function,
+                        type_: value.type_,
+                    }),
+                }
+            }
+            ExpressionKind::Index { value, index } => {
+                self.validate_expr(value);
+                self.validate_expr(index);
+                if !index.type_.is_integer() {
+                    self.expect_type(TypeId::USIZE, index.type_);
+                }
+                match self.hir.type_table.array_element(value.type_) {
+                    Some((type_, _)) => self.expect_type(type_, expr.type_),
+                    None => self.errors.push(ValidationError::InvalidAccess {
+                        function: self.function,
+                        type_: value.type_,
+                    }),
+                }
+            }
+            ExpressionKind::UnaryOp {
+                operator, value, ..
+            } => {
+                self.validate_expr(value);
+                self.expect_type(operator.out_type(value.type_), expr.type_);
+            }
+            ExpressionKind::Cast(value) => {
+                self.validate_expr(value);
+                if !value.type_.can_cast_to(expr.type_) {
+                    self.errors.push(ValidationError::InvalidCast {
+                        function: self.function,
+                        from: value.type_,
+                        to: expr.type_,
+                    });
+                }
+            }
+            ExpressionKind::BinaryOp {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                self.validate_expr(left);
+                self.validate_expr(right);
+                self.expect_type(left.type_, right.type_);
+                self.expect_type(operator.out_type(left.type_), expr.type_);
+            }
+        }
+    }
+
+    /// Looks variable up, reporting an error if it isn't declared.
+    fn lookup(&mut self, var: VarId) -> Option<TypeId> {
+        let type_ = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&var).copied());
+        if type_.is_none() {
+            self.errors.push(ValidationError::UndeclaredVariable {
+                function: self.function,
+                var,
+            });
+        }
+        type_
+    }
+
+    fn expect_type(&mut self, expected: TypeId, found: TypeId) {
+        if expected != found {
+            self.errors.push(ValidationError::TypeMismatch {
+                function: self.function,
+                expected,
+                found,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hir::{
+        scope::VarId, types::TypeId, Expression, ExpressionKind, FunctionId, Hir, Statement,
+    };
+
+    use super::{validate, ValidationError};
+
+    fn push_statement(hir: &mut Hir, stmt: Statement) {
+        let function = hir.functions[0].as_mut().unwrap();
+        function.body.statements.push(stmt);
+    }
+
+    #[test]
+    fn valid() {
+        let hir = Hir::new_test(
+            "fn run(a: i32) -> i32 { let b: i32 = 0; while b < a { b = b + 1; if b > 10 { break; } } b }",
+        );
+        assert!(validate(&hir).is_empty());
+    }
+
+    #[test]
+    fn break_outside_loop() {
+        let mut hir = Hir::new_test("fn run() {}");
+        push_statement(&mut hir, Statement::Break);
+        assert_eq!(
+            validate(&hir),
+            vec![ValidationError::BreakOutsideLoop {
+                function: FunctionId(0)
+            }]
+        );
+    }
+
+    #[test]
+    fn missing_return() {
+        let mut hir = Hir::new_test("fn run() -> i32 { 1 }");
+        let function = hir.functions[0].as_mut().unwrap();
+        let Some(Statement::Return(value)) = function.body.statements.pop() else {
+            panic!("tail should be lowered into `return`");
+        };
+        function.body.tail = Some(Box::new(value));
+        assert_eq!(
+            validate(&hir),
+            vec![ValidationError::MissingReturn {
+                function: FunctionId(0)
+            }]
+        );
+    }
+
+    #[test]
+    fn undeclared_variable() {
+        let mut hir = Hir::new_test("fn run(a: i32) {}");
+        let expr = Expression {
+            type_: TypeId::I32,
+            kind: ExpressionKind::Var(VarId::param(1)),
+        };
+        push_statement(&mut hir, Statement::ExprStmt(expr));
+        assert_eq!(
+            validate(&hir),
+            vec![ValidationError::UndeclaredVariable {
+                function: FunctionId(0),
+                var: VarId::param(1),
+            }]
+        );
+    }
+}
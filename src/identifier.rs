@@ -3,6 +3,7 @@ use thiserror::Error;
 
 /// Identifier is name of type, variable or function.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier(pub String);
 
 impl Identifier {
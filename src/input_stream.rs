@@ -89,10 +89,16 @@ impl InputStream {
 }
 
 /// Location of character at source code.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Default location is the start of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
-    pos: usize,
+    /// Byte offset of the location from the start of the source code.
+    pub pos: usize,
+    /// Zero-based number of the line.
     pub line: usize,
+    /// Zero-based number of the character within the line.
     pub column: usize,
 }
 
@@ -102,12 +108,10 @@ impl Display for Location {
     }
 }
 
+/// Locations are ordered by their position in the source code: earlier locations are less.
 impl Ord for Location {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.line.cmp(&other.line) {
-            Ordering::Equal => self.column.cmp(&other.column).reverse(),
-            ord => ord.reverse(),
-        }
+        (self.line, self.column, self.pos).cmp(&(other.line, other.column, other.pos))
     }
 }
 
@@ -119,7 +123,7 @@ impl PartialOrd for Location {
 
 #[cfg(test)]
 mod test {
-    use crate::input_stream::InputStream;
+    use crate::input_stream::{InputStream, Location};
 
     #[test]
     fn iteration() {
@@ -192,4 +196,31 @@ mod test {
         let location2 = stream.location();
         assert_eq!("祝你好运!", stream.slice(location1, location2));
     }
+
+    #[test]
+    fn ordering_same_line() {
+        let mut stream = InputStream::new("x = 5;", None);
+        let first = stream.location();
+        stream.nth(2);
+        let second = stream.location();
+        assert!(first < second);
+        assert!(second > first);
+        assert_eq!(first.cmp(&first), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn ordering_different_lines() {
+        let mut stream = InputStream::new("x = 5;\ny;", None);
+        stream.nth(4);
+        let first = stream.location();
+        stream.nth(1);
+        let second = stream.location();
+        assert_eq!((first.line, second.line), (0, 1));
+        assert!(first.column > second.column);
+        assert!(first < second);
+
+        let mut locations = vec![second, Location::default(), first];
+        locations.sort();
+        assert_eq!(locations, [Location::default(), first, second]);
+    }
 }
@@ -11,7 +11,13 @@ use std::{
     fmt::Display,
 };
 
-use crate::ast::item::Item;
+use crate::{
+    ast::{
+        expression::{ExprArena, ExprId, Expression},
+        item::{Function, Item, ItemKind, Module, Struct, Use},
+    },
+    Identifier,
+};
 
 use crate::path::AbsolutePath;
 
@@ -21,9 +27,14 @@ use crate::path::AbsolutePath;
 ///
 /// [module documentation]: crate::item_table
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ItemTable {
     pub declared: HashMap<AbsolutePath, Item>,
     duplicated: Vec<(AbsolutePath, Item)>,
+    /// Implementation blocks with paths to modules they are declared in.
+    impls: Vec<(AbsolutePath, Item)>,
+    /// Expressions of the items.
+    exprs: ExprArena,
 }
 
 impl ItemTable {
@@ -31,23 +42,61 @@ impl ItemTable {
         ItemTable {
             declared: HashMap::new(),
             duplicated: Vec::new(),
+            impls: Vec::new(),
+            exprs: ExprArena::new(),
         }
     }
 
     /// Merge two item tables.
-    pub fn extend(&mut self, other: ItemTable) {
-        self.duplicated.extend(other.duplicated.into_iter());
+    ///
+    /// Items and expressions are moved rather than cloned. Items of `other` that are already
+    /// declared in `self` are recorded as duplicates.
+    pub fn extend(&mut self, mut other: ItemTable) {
+        let offset = self.exprs.append(std::mem::take(&mut other.exprs));
+        if offset != 0 {
+            let items = other
+                .declared
+                .values_mut()
+                .chain(other.duplicated.iter_mut().map(|(_, item)| item))
+                .chain(other.impls.iter_mut().map(|(_, item)| item));
+            for item in items {
+                item.shift_exprs(offset);
+            }
+        }
 
+        self.duplicated.append(&mut other.duplicated);
+        self.impls.append(&mut other.impls);
+
+        if self.declared.is_empty() {
+            self.declared = other.declared;
+            return;
+        }
         self.declared.reserve(other.declared.len());
         for (path, item) in other.declared {
-            self.try_insert(path, item);
+            // Module declared as `mod NAME;` is resolved by its file, that's not a redefinition.
+            let is_loaded = matches!(
+                (self.declared.get(&path).map(|item| &item.kind), &item.kind),
+                (
+                    Some(ItemKind::Module(Module::Loadable(_))),
+                    ItemKind::Module(Module::Inline(_))
+                )
+            );
+            if !is_loaded {
+                self.try_insert(path, item);
+            }
         }
     }
 
     /// Add new entry to item table.
     ///
     /// `scope` is path to `item`'s parent.
+    ///
+    /// Implementation blocks don't have a name of their own, so they are stored separately.
     pub fn declare(&mut self, mut scope: AbsolutePath, item: Item) {
+        if let ItemKind::Impl(_) = item.kind {
+            self.impls.push((scope, item));
+            return;
+        }
         scope.push(item.name().clone());
         self.try_insert(scope, item);
     }
@@ -67,17 +116,138 @@ impl ItemTable {
         }
     }
 
-    pub fn items(&self) -> hash_map::Values<AbsolutePath, Item> {
+    /// Removes items that were declared in the file of the `module`, so the table may be
+    /// [extended](ItemTable::extend) with the items of the file parsed again.
+    ///
+    /// Modules declared as `mod NAME;` are removed, but items from their own files are kept.
+    /// Declaration of the `module` itself is kept if it belongs to another file. Expressions of
+    /// removed items remain in the arena.
+    pub fn remove_module(&mut self, module: &AbsolutePath) {
+        let is_loadable = |item: &Item| matches!(item.kind, ItemKind::Module(Module::Loadable(_)));
+        let loaded: Vec<_> = self
+            .declared
+            .iter()
+            .filter(|(path, item)| path.is_descendant_of(module) && is_loadable(item))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let is_stale = |path: &AbsolutePath| {
+            path.is_descendant_of(module)
+                && !loaded.iter().any(|nested| path.is_descendant_of(nested))
+        };
+        self.declared
+            .retain(|path, item| !is_stale(path) && (path != module || is_loadable(item)));
+        self.duplicated
+            .retain(|(path, _)| !is_stale(path) && path != module);
+        self.impls.retain(|(scope, _)| {
+            let in_file = scope == module || is_stale(scope);
+            !in_file || loaded.contains(scope)
+        });
+    }
+
+    /// Arena that expressions of the items are stored in.
+    pub fn exprs(&self) -> &ExprArena {
+        &self.exprs
+    }
+
+    pub fn exprs_mut(&mut self) -> &mut ExprArena {
+        &mut self.exprs
+    }
+
+    /// Looks up expression of one of the items.
+    pub fn expr(&self, id: ExprId) -> &Expression {
+        self.exprs.get(id)
+    }
+
+    /// Looks up item declared at the `path`.
+    pub fn get(&self, path: &AbsolutePath) -> Option<&Item> {
+        self.declared.get(path)
+    }
+
+    pub fn get_mut(&mut self, path: &AbsolutePath) -> Option<&mut Item> {
+        self.declared.get_mut(path)
+    }
+
+    /// Items declared directly in the `module`.
+    ///
+    /// Items of nested modules are not included, as well as implementation blocks. Associated
+    /// functions are children of their type rather than of the module.
+    pub fn children<'a>(
+        &'a self,
+        module: &'a AbsolutePath,
+    ) -> impl Iterator<Item = (&'a AbsolutePath, &'a Item)> + 'a {
+        self.declared
+            .iter()
+            .filter(move |(path, _)| path.is_child_of(module))
+    }
+
+    /// All declared functions, including associated ones.
+    pub fn functions(&self) -> impl Iterator<Item = (&AbsolutePath, &Item, &Function)> {
+        self.declared
+            .iter()
+            .filter_map(|(path, item)| match &item.kind {
+                ItemKind::Function(function) => Some((path, item, function)),
+                _ => None,
+            })
+    }
+
+    /// All declared structures.
+    pub fn structs(&self) -> impl Iterator<Item = (&AbsolutePath, &Item, &Struct)> {
+        self.declared
+            .iter()
+            .filter_map(|(path, item)| match &item.kind {
+                ItemKind::Struct(strukt) => Some((path, item, strukt)),
+                _ => None,
+            })
+    }
+
+    /// Looks up import of `name` in the `module`.
+    pub fn lookup_import(&self, module: &AbsolutePath, name: &Identifier) -> Option<&Use> {
+        let mut path = module.clone();
+        path.push(name.clone());
+        match self.get(&path) {
+            Some(Item {
+                kind: ItemKind::Use(import),
+                ..
+            }) => Some(import),
+            _ => None,
+        }
+    }
+
+    /// Items that were declared with already occupied path.
+    ///
+    /// Yields path, originally declared item and its duplicate.
+    pub fn duplicates(&self) -> impl Iterator<Item = (&AbsolutePath, &Item, &Item)> {
+        self.duplicated.iter().map(|(path, duplicate)| {
+            let original = self
+                .declared
+                .get(path)
+                .expect("duplicated item should have an original");
+            (path, original, duplicate)
+        })
+    }
+
+    /// Implementation blocks with paths to modules they are declared in.
+    pub fn impls(&self) -> &[(AbsolutePath, Item)] {
+        &self.impls
+    }
+
+    pub fn items(&self) -> hash_map::Values<'_, AbsolutePath, Item> {
         self.declared.values()
     }
 
-    pub fn iter(&self) -> hash_map::Iter<AbsolutePath, Item> {
+    pub fn iter(&self) -> hash_map::Iter<'_, AbsolutePath, Item> {
         self.declared.iter()
     }
 
-    pub fn iter_mut(&mut self) -> hash_map::IterMut<AbsolutePath, Item> {
+    pub fn iter_mut(&mut self) -> hash_map::IterMut<'_, AbsolutePath, Item> {
         self.declared.iter_mut()
     }
+
+    /// Serializes the table to JSON, with declared items keyed by their paths.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("item table should always be serializable")
+    }
 }
 
 impl IntoIterator for ItemTable {
@@ -103,3 +273,193 @@ impl Display for ItemTable {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{
+        ast::{
+            expression::{ExprId, ExpressionKind, Literal},
+            item::{ItemKind, Module, Visibility},
+        },
+        context::Context,
+        lexer::Lexer,
+        parser::FileParser,
+        path::AbsolutePath,
+    };
+
+    use super::ItemTable;
+
+    fn parse(src: &str) -> ItemTable {
+        let parsed = FileParser::new_test(src).parse();
+        assert!(parsed.errors.is_empty(), "parsing failed");
+        parsed.item_table
+    }
+
+    fn parse_in(scope: &str, src: &str) -> ItemTable {
+        let parser = FileParser::new(Lexer::new_test(src), path(scope), Context::new_test());
+        parser.parse().item_table
+    }
+
+    fn path(path: &str) -> AbsolutePath {
+        AbsolutePath::from_str(path).unwrap()
+    }
+
+    fn sorted<'a>(paths: impl Iterator<Item = &'a AbsolutePath>) -> Vec<String> {
+        let mut paths: Vec<_> = paths.map(ToString::to_string).collect();
+        paths.sort();
+        paths
+    }
+
+    const NESTED: &str = "
+        mod outer {
+            mod inner {
+                fn deep() {}
+                struct Hidden {}
+            }
+            struct Point {}
+            impl Point {
+                fn new() {}
+            }
+            fn run() {}
+        }
+        fn main() {}
+    ";
+
+    #[test]
+    fn get() {
+        let mut table = parse(NESTED);
+        assert!(table.get(&path("crate::outer::inner::deep")).is_some());
+        assert!(table.get(&path("crate::outer::deep")).is_none());
+        assert!(table.get(&path("crate::missing")).is_none());
+
+        let item = table.get_mut(&path("crate::main")).unwrap();
+        item.visibility = Visibility::Public;
+        assert_eq!(
+            table.get(&path("crate::main")).unwrap().visibility,
+            Visibility::Public
+        );
+    }
+
+    #[test]
+    fn children() {
+        let table = parse(NESTED);
+        let children = |module: &str| {
+            let module = path(module);
+            sorted(table.children(&module).map(|(path, _)| path))
+        };
+        assert_eq!(children("crate"), ["crate::main", "crate::outer"]);
+        assert_eq!(
+            children("crate::outer"),
+            [
+                "crate::outer::Point",
+                "crate::outer::inner",
+                "crate::outer::run"
+            ]
+        );
+        assert_eq!(
+            children("crate::outer::inner"),
+            ["crate::outer::inner::Hidden", "crate::outer::inner::deep"]
+        );
+        assert_eq!(
+            children("crate::outer::Point"),
+            ["crate::outer::Point::new"]
+        );
+        assert!(children("crate::main").is_empty());
+        assert!(children("other").is_empty());
+    }
+
+    #[test]
+    fn extend() {
+        let mut table = ItemTable::new();
+        table.extend(parse_in("crate", "mod shapes; fn run() {}"));
+        table.extend(parse_in("crate::shapes", "fn area() {}"));
+        assert_eq!(table.duplicates().count(), 0);
+        assert!(matches!(
+            table.get(&path("crate::shapes")).unwrap().kind,
+            ItemKind::Module(Module::Loadable(_))
+        ));
+
+        table.extend(parse_in("crate::shapes", "fn area() {} fn perimeter() {}"));
+        assert!(table.get(&path("crate::shapes::perimeter")).is_some());
+        let duplicates: Vec<_> = table.duplicates().map(|(path, _, _)| path).collect();
+        assert_eq!(duplicates, [&path("crate::shapes::area")]);
+    }
+
+    #[test]
+    fn extend_exprs() {
+        let mut table = parse_in("crate", "const A: i32 = 1 + 2;");
+        table.extend(parse_in(
+            "crate::inner",
+            "const B: bool = !false; fn run() -> i32 { A * 3 }",
+        ));
+        assert_eq!(table.exprs().len(), 8);
+
+        let value = |name: &str| -> ExprId {
+            match &table.get(&path(name)).unwrap().kind {
+                ItemKind::Const(constant) => constant.value,
+                _ => panic!("`{name}` should be a constant"),
+            }
+        };
+        let ExpressionKind::Binary { left, .. } = table.expr(value("crate::A")).kind else {
+            panic!("`A` should be binary expression");
+        };
+        assert!(matches!(table.expr(left).kind, ExpressionKind::Literal(_)));
+        let ExpressionKind::Unary { value, .. } = table.expr(value("crate::inner::B")).kind else {
+            panic!("`B` should be unary expression");
+        };
+        assert_eq!(
+            table.expr(value).kind,
+            ExpressionKind::Literal(Literal::Boolean(false))
+        );
+        let (_, _, run) = table.functions().next().unwrap();
+        let tail = run.body.expression.unwrap();
+        let ExpressionKind::Binary { left, .. } = table.expr(tail).kind else {
+            panic!("`run` should return binary expression");
+        };
+        assert!(matches!(table.expr(left).kind, ExpressionKind::Var(_)));
+    }
+
+    #[test]
+    fn kind_filters() {
+        let table = parse(NESTED);
+        assert_eq!(
+            sorted(table.functions().map(|(path, _, _)| path)),
+            [
+                "crate::main",
+                "crate::outer::Point::new",
+                "crate::outer::inner::deep",
+                "crate::outer::run"
+            ]
+        );
+        assert_eq!(
+            sorted(table.structs().map(|(path, _, _)| path)),
+            ["crate::outer::Point", "crate::outer::inner::Hidden"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json_round_trip() {
+        let src = "
+            mod inner {
+                pub struct Point { x: i32, y: i32 }
+                impl Point {
+                    fn new(x: i32, y: i32) -> Point { Point::new(x, y) }
+                }
+            }
+            use inner::Point as P;
+            const LIMIT: u8 = 0x10;
+            fn run(a: i32, b: bool, pair: (bool, [u8; 4])) -> f32 {
+                let c: f32 = 1.5;
+                while !b && a > 1 { c += 2; break; }
+                if c >= 3.0 { return c; }
+                -c
+            }
+        ";
+        let table = parse(src);
+        let deserialized: ItemTable = serde_json::from_value(table.to_json()).unwrap();
+        assert_eq!(table, deserialized);
+    }
+}
@@ -1,28 +1,40 @@
 //! Iterator of tokens.
 
+pub mod dump;
 pub mod keyword;
 pub mod number;
 pub mod operator;
 pub mod punctuation;
 mod util;
 
-use std::{mem::take, str::FromStr};
+use std::{convert::Infallible, mem::take, str::FromStr};
 
 use thiserror::Error;
 
-use crate::{context::Context, input_stream::InputStream};
+use crate::{
+    context::Context,
+    error::{library::lexer as errors, CompilerError, ReportProvider},
+    input_stream::{InputStream, Location},
+    util::Span,
+};
 
 use self::{
     keyword::Keyword,
-    number::Number,
+    number::{Base, Number},
     punctuation::{NotPunctuation, Punctuation},
 };
 
 /// A stream that returns tokens of programming language.
 #[derive(Debug)]
 pub struct Lexer {
-    /// Cached token.
-    current: Option<Token>,
+    /// Cached token with locations of its start and end.
+    current: Option<(Token, Location, Location)>,
+    /// Location of the start of the last yielded token.
+    last_start: Location,
+    /// Location right after the last yielded token.
+    last_end: Location,
+    /// Number of yielded tokens, not including [Token::Eof].
+    yielded: usize,
     pub input: InputStream,
     pub context: Context,
 }
@@ -31,6 +43,9 @@ impl Lexer {
     pub fn new(input: InputStream, context: Context) -> Self {
         Self {
             current: None,
+            last_start: input.location(),
+            last_end: input.location(),
+            yielded: 0,
             input,
             context,
         }
@@ -41,17 +56,27 @@ impl Lexer {
         let input = InputStream::new(src, None);
         Self {
             current: None,
+            last_start: input.location(),
+            last_end: input.location(),
+            yielded: 0,
             input,
             context: Context::new_test(),
         }
     }
 
     /// Get next token.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Token, LexerError> {
-        match take(&mut self.current) {
-            Some(token) => Ok(token),
-            None => self.read_token(),
+        let (token, start, end) = match take(&mut self.current) {
+            Some(current) => current,
+            None => self.read_located_token()?,
+        };
+        self.last_start = start;
+        self.last_end = end;
+        if token != Token::Eof {
+            self.yielded += 1;
         }
+        Ok(token)
     }
 
     /// Discard next token.
@@ -63,10 +88,72 @@ impl Lexer {
 
     /// Get next token without advancing an iterator.
     pub fn peek(&mut self) -> Result<Token, LexerError> {
-        if self.current.is_none() {
-            self.current = Some(self.read_token()?);
+        let current = match self.current.take() {
+            Some(current) => current,
+            None => self.read_located_token()?,
+        };
+        let (token, ..) = self.current.insert(current);
+        Ok(token.clone())
+    }
+
+    /// Location of the start of the next token.
+    ///
+    /// Unlike [location](crate::error::ReportProvider::location), it is not affected by whitespace
+    /// or tokens that were peeked.
+    pub fn token_start(&mut self) -> Location {
+        match &self.current {
+            Some((_, start, _)) => *start,
+            None => {
+                self.clean();
+                self.input.location()
+            }
         }
-        Ok(self.current.clone().unwrap())
+    }
+
+    /// Location right after the last yielded token.
+    pub fn token_end(&self) -> Location {
+        self.last_end
+    }
+
+    /// Location of the start of the last yielded token.
+    pub fn last_token_start(&self) -> Location {
+        self.last_start
+    }
+
+    /// Read all remaining tokens with their spans, up to and including [Token::Eof].
+    ///
+    /// Errors are reported and lexing is resumed after the invalid input.
+    pub fn tokenize(&mut self) -> Vec<(Token, Span)> {
+        let mut tokens = Vec::new();
+        loop {
+            let start = self.token_start();
+            match self.next() {
+                Ok(token) => {
+                    let span = Span {
+                        source: self.input.source(),
+                        start,
+                        end: self.token_end(),
+                    };
+                    let is_eof = token == Token::Eof;
+                    tokens.push((token, span));
+                    if is_eof {
+                        return tokens;
+                    }
+                }
+                Err(error) => {
+                    let _ = error.report(self, start);
+                    // Unexpected character is only peeked, so it has to be skipped explicitly.
+                    if self.input.location() == start {
+                        self.input.next();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number of tokens yielded so far, not including [Token::Eof].
+    pub fn token_count(&self) -> usize {
+        self.yielded
     }
 
     /// Check if last token was already yielded.
@@ -74,6 +161,17 @@ impl Lexer {
         matches!(self.peek(), Ok(Token::Eof))
     }
 
+    fn read_located_token(&mut self) -> Result<(Token, Location, Location), LexerError> {
+        self.clean();
+        let start = self.input.location();
+        let token = self.read_token()?;
+        Ok((token, start, self.input.location()))
+    }
+
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(level = "trace", skip_all, fields(at = %self.input.location()), ret)
+    )]
     fn read_token(&mut self) -> Result<Token, LexerError> {
         self.clean();
 
@@ -143,23 +241,22 @@ impl Lexer {
 
         fn skip_whitespace(stream: &mut InputStream) -> bool {
             let mut skipped = false;
-            loop {
-                let ch = stream.peek();
-                if ch.is_some() && ch.unwrap().is_whitespace() {
-                    skipped = true;
-                    stream.next();
-                } else {
-                    break;
-                }
+            while stream.peek().is_some_and(char::is_whitespace) {
+                skipped = true;
+                stream.next();
             }
             skipped
         }
     }
 
     /// Read string literal.
+    ///
+    /// Literal with invalid escape sequence is still read up to the closing quote mark, so lexing
+    /// is resumed after it.
     fn read_str(&mut self) -> Result<Token, LexerError> {
         self.input.next(); // Skip opening quote mark
         let mut buffer = String::new();
+        let mut invalid_escape = false;
         loop {
             match self.input.next().ok_or(LexerError::UnterminatedString)? {
                 '\\' => {
@@ -172,7 +269,10 @@ impl Lexer {
                         't' => '\t',
                         '\\' => '\\',
                         '0' => '\0',
-                        _ => return Err(LexerError::InvalidEscape),
+                        _ => {
+                            invalid_escape = true;
+                            continue;
+                        }
                     };
                     buffer.push(value);
                 }
@@ -184,6 +284,9 @@ impl Lexer {
                 }
             }
         }
+        if invalid_escape {
+            return Err(LexerError::InvalidEscape);
+        }
         Ok(Token::Str(buffer))
     }
 
@@ -192,7 +295,8 @@ impl Lexer {
         let mut buffer = String::new();
         while let Some(ch) = self.input.peek() {
             if ch.is_ascii_alphanumeric() || ch == '_' {
-                buffer.push(self.input.next().unwrap());
+                buffer.push(ch);
+                self.input.next();
             } else if !ch.is_ascii() {
                 return Err(LexerError::InvalidIdentifier);
             } else {
@@ -228,12 +332,44 @@ pub enum LexerError {
     InvalidEscape,
     #[error("invalid number")]
     InvalidNumber,
+    #[error("missing digits after `{}` prefix", .0.prefix())]
+    MissingDigits(Base),
+    #[error("{} float literals are not supported, found `{text}`", .base.name())]
+    NonDecimalFloat { base: Base, text: String },
     #[error("unknown punctuation")]
     UnknownPunctuation(#[from] NotPunctuation),
     #[error("character `{0}` wasn't expected")]
     UnexpectedCharacter(char),
 }
 
+impl LexerError {
+    /// Reports the error that occured while reading token that starts at `start`.
+    pub fn report(
+        self,
+        provider: &impl ReportProvider,
+        start: Location,
+    ) -> Result<Infallible, CompilerError> {
+        match self {
+            LexerError::UnterminatedString => errors::UnterminatedString::report(provider, start),
+            LexerError::InvalidIdentifier => errors::InvalidIdentifier::report(provider, start),
+            LexerError::InvalidEscape => errors::InvalidEscape::report(provider, start),
+            LexerError::InvalidNumber => errors::InvalidNumber::report(provider, start),
+            LexerError::MissingDigits(base) => {
+                errors::MissingDigits::report(provider, start, base.prefix())
+            }
+            LexerError::NonDecimalFloat { base, text } => {
+                errors::NonDecimalFloat::report(provider, start, base.name(), text)
+            }
+            LexerError::UnknownPunctuation(NotPunctuation(found)) => {
+                errors::UnknownPunctuation::report(provider, start, found)
+            }
+            LexerError::UnexpectedCharacter(ch) => {
+                errors::UnexpectedCharacter::report(provider, start, ch)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::lexer::{
@@ -245,6 +381,123 @@ mod test {
 
     use super::Lexer;
 
+    /// Returns messages of errors reported by `lexer`.
+    fn messages(lexer: &Lexer) -> Vec<String> {
+        lexer
+            .context
+            .error_reporter
+            .diagnostics()
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn for_without_in() {
+        let mut lexer = Lexer::new_test("for i items {}");
+        assert_eq!(lexer.next(), Ok(Token::Kw(Keyword::For)));
+        assert!(lexer.expect_identifier().is_ok());
+        assert!(lexer.expect_keyword(Keyword::In).is_err());
+        assert_eq!(messages(&lexer), ["expected keyword `in`, found `items`"]);
+    }
+
+    #[test]
+    fn missing_else() {
+        let mut lexer = Lexer::new_test("if");
+        assert!(lexer.expect_keyword(Keyword::Else).is_err());
+        assert_eq!(
+            messages(&lexer),
+            ["expected keyword `else`, found keyword `if`"]
+        );
+    }
+
+    #[test]
+    fn tokenize_recovers() {
+        let number = |integer: &str| {
+            Token::Num(Number {
+                base: Base::Decimal,
+                integer: String::from(integer),
+                fraction: None,
+            })
+        };
+        let mut lexer = Lexer::new_test("let $ x = 1 § 2;");
+        let tokens: Vec<_> = lexer
+            .tokenize()
+            .into_iter()
+            .map(|(token, span)| (token, span.start.column))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (Token::Kw(Keyword::Let), 0),
+                (Token::Ident(String::from("x")), 6),
+                (Token::Punc(Punctuation::new("=")), 8),
+                (number("1"), 10),
+                (number("2"), 14),
+                (Token::Punc(Punctuation::new(";")), 15),
+                (Token::Eof, 16),
+            ]
+        );
+        assert_eq!(
+            messages(&lexer),
+            [
+                "`$` is not a valid punctuation",
+                "character `§` wasn't expected"
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_escape() {
+        let mut lexer = Lexer::new_test(r#"let s = "a\qb\"c"; x"#);
+        let tokens: Vec<_> = lexer
+            .tokenize()
+            .into_iter()
+            .map(|(token, span)| (token, span.start.column))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (Token::Kw(Keyword::Let), 0),
+                (Token::Ident(String::from("s")), 4),
+                (Token::Punc(Punctuation::new("=")), 6),
+                (Token::Punc(Punctuation::new(";")), 17),
+                (Token::Ident(String::from("x")), 19),
+                (Token::Eof, 20),
+            ]
+        );
+        assert_eq!(messages(&lexer), ["invalid escape sentence"]);
+    }
+
+    #[test]
+    fn malformed_numbers() {
+        let mut lexer = Lexer::new_test("0x1.8 0b 0o.5 1");
+        let tokens: Vec<_> = lexer
+            .tokenize()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                Token::Num(Number {
+                    base: Base::Decimal,
+                    integer: String::from("1"),
+                    fraction: None,
+                }),
+                Token::Eof,
+            ]
+        );
+        assert_eq!(
+            messages(&lexer),
+            [
+                "hexadecimal float literals are not supported, found `0x1.8`",
+                "missing digits after `0b` prefix",
+                "octal float literals are not supported, found `0o.5`"
+            ]
+        );
+    }
+
     #[test]
     fn return_string() {
         let mut lexer = Lexer::new_test("return \"x > 0\";");
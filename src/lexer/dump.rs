@@ -0,0 +1,118 @@
+//! Textual representation of token stream, used to debug the lexer.
+
+use std::io::{Result, Write};
+
+use crate::{
+    error::json::{location, string},
+    util::Span,
+};
+
+use super::Token;
+
+/// Writes every token on its own line, preceded by its span.
+///
+/// ```text
+/// 3:5..3:7  Punc(>=)
+/// ```
+pub fn write_tokens(mut w: impl Write, tokens: &[(Token, Span)]) -> Result<()> {
+    for (token, span) in tokens {
+        let (kind, value) = parts(token);
+        match value {
+            Some(value) => writeln!(w, "{}..{}  {kind}({value})", span.start, span.end)?,
+            None => writeln!(w, "{}..{}  {kind}", span.start, span.end)?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes tokens as JSON array of objects of the following structure:
+///
+/// ```json
+/// {
+///     "kind": "Punc",
+///     "value": ">=",
+///     "start": { "line": 3, "column": 5 },
+///     "end": { "line": 3, "column": 7 }
+/// }
+/// ```
+///
+/// Lines and columns are one-based. `value` is `null` for end of file.
+pub fn write_tokens_json(mut w: impl Write, tokens: &[(Token, Span)]) -> Result<()> {
+    write!(w, "[")?;
+    for (i, (token, span)) in tokens.iter().enumerate() {
+        if i != 0 {
+            write!(w, ",")?;
+        }
+        let (kind, value) = parts(token);
+        let value = match value {
+            Some(value) => string(&value),
+            None => String::from("null"),
+        };
+        write!(
+            w,
+            r#"{{"kind":"{kind}","value":{value},"start":{},"end":{}}}"#,
+            location(span.start),
+            location(span.end),
+        )?;
+    }
+    writeln!(w, "]")
+}
+
+/// Splits token into the name of its kind and its value.
+fn parts(token: &Token) -> (&'static str, Option<String>) {
+    match token {
+        Token::Punc(punc) => ("Punc", Some(punc.to_string())),
+        Token::Num(num) => ("Num", Some(num.to_string())),
+        Token::Str(s) => ("Str", Some(format!("{s:?}"))),
+        Token::Kw(kw) => ("Kw", Some(kw.to_string())),
+        Token::Ident(ident) => ("Ident", Some(ident.clone())),
+        Token::Eof => ("Eof", None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lexer::Lexer;
+
+    use super::{write_tokens, write_tokens_json};
+
+    fn dump(src: &str, json: bool) -> String {
+        let tokens = Lexer::new_test(src).tokenize();
+        let mut output = Vec::new();
+        if json {
+            write_tokens_json(&mut output, &tokens).unwrap();
+        } else {
+            write_tokens(&mut output, &tokens).unwrap();
+        }
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            dump("if x >= 1 {\n    \"a\"\n}", false),
+            "1:1..1:3  Kw(if)
+1:4..1:5  Ident(x)
+1:6..1:8  Punc(>=)
+1:9..1:10  Num(1)
+1:11..1:12  Punc({)
+2:5..2:8  Str(\"a\")
+3:1..3:2  Punc(})
+3:2..3:2  Eof
+"
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            dump("x;", true),
+            concat!(
+                r#"[{"kind":"Ident","value":"x","start":{"line":1,"column":1},"end":{"line":1,"column":2}},"#,
+                r#"{"kind":"Punc","value":";","start":{"line":1,"column":2},"end":{"line":1,"column":3}},"#,
+                r#"{"kind":"Eof","value":null,"start":{"line":1,"column":3},"end":{"line":1,"column":3}}]"#,
+                "\n"
+            )
+        );
+    }
+}
@@ -15,6 +15,12 @@ pub enum Keyword {
     Pub,
     Struct,
     Mod,
+    Const,
+    Use,
+    As,
+    Impl,
+    #[strum(serialize = "self")]
+    SelfValue,
     True,
     False,
 }
@@ -1,10 +1,16 @@
 use std::fmt::Display;
 
-use crate::input_stream::InputStream;
+use thiserror::Error;
+
+use crate::{
+    hir::{types::PrimitiveType, ConstValue},
+    input_stream::InputStream,
+};
 
 use super::LexerError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Number {
     pub integer: String,
     pub fraction: Option<String>,
@@ -13,9 +19,20 @@ pub struct Number {
 
 impl Number {
     pub fn parse(stream: &mut InputStream) -> Result<Number, LexerError> {
+        let start = stream.location();
         let base = Self::parse_base(stream);
         let (integer, fraction) = Self::parse_number(stream, base);
 
+        if base != Base::Decimal {
+            if fraction.is_some() {
+                let end = stream.location();
+                let text = stream.slice(start, end).to_owned();
+                return Err(LexerError::NonDecimalFloat { base, text });
+            } else if integer.is_empty() {
+                return Err(LexerError::MissingDigits(base));
+            }
+        }
+
         if let Some(fraction) = &fraction {
             if integer.is_empty() && fraction.is_empty() {
                 return Err(LexerError::InvalidNumber);
@@ -50,6 +67,34 @@ impl Number {
         base
     }
 
+    /// Converts the literal to the value of `target` type.
+    ///
+    /// Integer literals may have any numeric type, as long as they fit into its range.
+    /// Literals with fractional part may only have floating point type.
+    pub fn to_value(&self, target: PrimitiveType) -> Result<ConstValue, NumberError> {
+        match (target, &self.fraction) {
+            (PrimitiveType::F32 | PrimitiveType::F64, fraction) => {
+                let fraction = fraction.as_deref().unwrap_or_default();
+                Ok(ConstValue::Float(parse_float(
+                    &self.integer,
+                    fraction,
+                    self.base,
+                )))
+            }
+            (target, _) if !target.is_integer() => Err(NumberError::NotNumeric(target)),
+            (target, Some(_)) => Err(NumberError::FractionalInteger(target)),
+            (target, None) => {
+                let (min, max) = target.integer_bounds().expect("target is an integer");
+                u128::from_str_radix(&self.integer, self.base.radix())
+                    .ok()
+                    .and_then(|value| i128::try_from(value).ok())
+                    .filter(|value| (min..=max).contains(value))
+                    .map(ConstValue::Integer)
+                    .ok_or(NumberError::OutOfRange(target))
+            }
+        }
+    }
+
     fn parse_number(stream: &mut InputStream, base: Base) -> (String, Option<String>) {
         let mut integer = String::new();
         let mut fraction = String::new();
@@ -63,7 +108,7 @@ impl Number {
                     fraction.push(ch);
                 }
                 stream.next();
-            } else if ch == '.' && !met_dot {
+            } else if ch == '.' && !met_dot && stream.peek_nth(1) != Some('.') {
                 met_dot = true;
                 stream.next();
             } else {
@@ -81,25 +126,42 @@ impl Number {
 
 impl Display for Number {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self.base {
-                Base::Binary => "0b",
-                Base::Octal => "0o",
-                Base::Decimal => "",
-                Base::Hexadecimal => "0x",
-            }
-        )?;
-        write!(f, "{}", self.integer)?;
+        write!(f, "{}{}", self.base.prefix(), self.integer)?;
         if let Some(fraction) = &self.fraction {
-            write!(f, "{fraction}")?;
+            write!(f, ".{fraction}")?;
         }
         Ok(())
     }
 }
 
+/// Computes value of the float literal. Digits are expected to be validated by lexer.
+fn parse_float(integer: &str, fraction: &str, base: Base) -> f32 {
+    if base == Base::Decimal {
+        return format!("{integer}.{fraction}").parse().unwrap_or_default();
+    }
+    let radix = base.radix() as f32;
+    let digit = |ch: char| ch.to_digit(base.radix()).unwrap_or_default() as f32;
+    let integer = integer.chars().fold(0., |acc, ch| acc * radix + digit(ch));
+    let fraction = fraction
+        .chars()
+        .rev()
+        .fold(0., |acc, ch| (acc + digit(ch)) / radix);
+    integer + fraction
+}
+
+/// Error of numeric literal's conversion to a value of specific type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum NumberError {
+    #[error("integer literal out of range for `{0}`")]
+    OutOfRange(PrimitiveType),
+    #[error("literal with fractional part can't have integer type `{0}`")]
+    FractionalInteger(PrimitiveType),
+    #[error("numeric literal can't have non-numeric type `{0}`")]
+    NotNumeric(PrimitiveType),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Base {
     Binary,
     Octal,
@@ -117,13 +179,37 @@ impl Base {
             Base::Hexadecimal => 16,
         }
     }
+
+    /// Prefix of number literals written in the base, empty for decimal ones.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Base::Binary => "0b",
+            Base::Octal => "0o",
+            Base::Decimal => "",
+            Base::Hexadecimal => "0x",
+        }
+    }
+
+    /// Name of the base in lowercase.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Base::Binary => "binary",
+            Base::Octal => "octal",
+            Base::Decimal => "decimal",
+            Base::Hexadecimal => "hexadecimal",
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{input_stream::InputStream, lexer::number::Base};
+    use crate::{
+        hir::{types::PrimitiveType, ConstValue},
+        input_stream::InputStream,
+        lexer::{number::Base, LexerError},
+    };
 
-    use super::Number;
+    use super::{Number, NumberError};
 
     #[test]
     fn parse_integer() {
@@ -173,17 +259,21 @@ mod test {
                 fraction: Some(String::from("56789")),
             })
         );
+    }
 
-        let mut stream = InputStream::new("0xABC.DEF", None);
+    #[test]
+    fn parse_range_start() {
+        let mut stream = InputStream::new("0..10", None);
         let sign = Number::parse(&mut stream);
         assert_eq!(
             sign,
             Ok(Number {
-                base: Base::Hexadecimal,
-                integer: String::from("ABC"),
-                fraction: Some(String::from("DEF")),
+                base: Base::Decimal,
+                integer: String::from("0"),
+                fraction: None,
             })
         );
+        assert_eq!(stream.peek(), Some('.'));
     }
 
     #[test]
@@ -230,27 +320,114 @@ mod test {
                 fraction: Some(String::from("1234")),
             })
         );
+    }
 
-        let mut stream = InputStream::new("0xABCD.", None);
-        let sign = Number::parse(&mut stream);
+    #[test]
+    fn non_decimal_float() {
+        let cases = [
+            ("0xABC.DEF", Base::Hexadecimal, "0xABC.DEF"),
+            ("0x1.8 + 1", Base::Hexadecimal, "0x1.8"),
+            ("0xABCD.", Base::Hexadecimal, "0xABCD."),
+            ("0x.001B", Base::Hexadecimal, "0x.001B"),
+            ("0b1.1", Base::Binary, "0b1.1"),
+            ("0o7.", Base::Octal, "0o7."),
+        ];
+        for (src, base, text) in cases {
+            let number = Number::parse(&mut InputStream::new(src, None));
+            assert_eq!(
+                number,
+                Err(LexerError::NonDecimalFloat {
+                    base,
+                    text: String::from(text)
+                }),
+                "{src}"
+            );
+        }
         assert_eq!(
-            sign,
-            Ok(Number {
+            LexerError::NonDecimalFloat {
                 base: Base::Hexadecimal,
-                integer: String::from("ABCD"),
-                fraction: Some(String::new()),
-            })
+                text: String::from("0x1.8")
+            }
+            .to_string(),
+            "hexadecimal float literals are not supported, found `0x1.8`"
         );
+    }
 
-        let mut stream = InputStream::new("0x.001B", None);
-        let sign = Number::parse(&mut stream);
+    #[test]
+    fn missing_digits() {
+        for (src, base) in [
+            ("0x", Base::Hexadecimal),
+            ("0b;", Base::Binary),
+            ("0o o", Base::Octal),
+            ("0b2", Base::Binary),
+        ] {
+            let number = Number::parse(&mut InputStream::new(src, None));
+            assert_eq!(number, Err(LexerError::MissingDigits(base)), "{src}");
+        }
         assert_eq!(
-            sign,
-            Ok(Number {
-                base: Base::Hexadecimal,
-                integer: String::new(),
-                fraction: Some(String::from("001B")),
-            })
+            LexerError::MissingDigits(Base::Hexadecimal).to_string(),
+            "missing digits after `0x` prefix"
+        );
+    }
+
+    #[test]
+    fn integer_range() {
+        use PrimitiveType::*;
+
+        let cases: &[(PrimitiveType, &str, Option<i128>)] = &[
+            (I8, "127", Some(127)),
+            (I8, "128", None),
+            (I16, "32767", Some(32767)),
+            (I16, "32768", None),
+            (I32, "2147483647", Some(2147483647)),
+            (I32, "2147483648", None),
+            (I64, "9223372036854775807", Some(i64::MAX.into())),
+            (I64, "9223372036854775808", None),
+            (Isize, "9223372036854775807", Some(i64::MAX.into())),
+            (Isize, "9223372036854775808", None),
+            (U8, "255", Some(255)),
+            (U8, "256", None),
+            (U16, "65535", Some(65535)),
+            (U16, "65536", None),
+            (U32, "4294967295", Some(4294967295)),
+            (U32, "4294967296", None),
+            (U64, "18446744073709551615", Some(u64::MAX.into())),
+            (U64, "18446744073709551616", None),
+            (Usize, "0", Some(0)),
+            (Usize, "18446744073709551615", Some(u64::MAX.into())),
+            (Usize, "18446744073709551616", None),
+            (U8, "0xFF", Some(255)),
+            (U8, "0x100", None),
+            (I8, "0b1111111", Some(127)),
+            (I8, "0b10000000", None),
+            (U16, "0o177777", Some(65535)),
+            (U16, "0o200000", None),
+            (I32, "0xFFFFFFFFFFFFFFFFFFF", None),
+        ];
+        for (target, src, expected) in cases {
+            let number = Number::parse(&mut InputStream::new(src, None)).unwrap();
+            let expected = match expected {
+                Some(value) => Ok(ConstValue::Integer(*value)),
+                None => Err(NumberError::OutOfRange(*target)),
+            };
+            assert_eq!(number.to_value(*target), expected, "{src} as {target}");
+        }
+    }
+
+    #[test]
+    fn fraction_on_integer() {
+        let number = Number::parse(&mut InputStream::new("1.5", None)).unwrap();
+        assert_eq!(
+            number.to_value(PrimitiveType::U8),
+            Err(NumberError::FractionalInteger(PrimitiveType::U8))
+        );
+        assert_eq!(
+            number.to_value(PrimitiveType::F64),
+            Ok(ConstValue::Float(1.5))
+        );
+        assert_eq!(
+            number.to_value(PrimitiveType::Bool),
+            Err(NumberError::NotNumeric(PrimitiveType::Bool))
         );
     }
 }
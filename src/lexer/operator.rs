@@ -12,6 +12,7 @@ macro_rules! define_operator {
         $(
             $(#[doc = $doc])?
             #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
             pub enum $name {
                 $($field,)*
             }
@@ -70,6 +71,7 @@ define_operator! {
         Less = "<",
         MoreEq = ">=",
         LessEq = "<=",
+        Range = "..",
     }
 
     /// An operator with two operands: assignee and value.
@@ -82,71 +84,148 @@ define_operator! {
     }
 }
 
+/// Integer types, default one goes first.
+const INTEGER: &[TypeId] = &[
+    TypeId::I32,
+    TypeId::I8,
+    TypeId::I16,
+    TypeId::I64,
+    TypeId::ISIZE,
+    TypeId::U8,
+    TypeId::U16,
+    TypeId::U32,
+    TypeId::U64,
+    TypeId::USIZE,
+];
+const NUMERIC: &[TypeId] = &[
+    TypeId::I32,
+    TypeId::I8,
+    TypeId::I16,
+    TypeId::I64,
+    TypeId::ISIZE,
+    TypeId::U8,
+    TypeId::U16,
+    TypeId::U32,
+    TypeId::U64,
+    TypeId::USIZE,
+    TypeId::F32,
+    TypeId::F64,
+];
+const SIGNED: &[TypeId] = &[
+    TypeId::I32,
+    TypeId::I8,
+    TypeId::I16,
+    TypeId::I64,
+    TypeId::ISIZE,
+    TypeId::F32,
+    TypeId::F64,
+];
+const LOGICAL: &[TypeId] = &[
+    TypeId::BOOL,
+    TypeId::I8,
+    TypeId::I16,
+    TypeId::I32,
+    TypeId::I64,
+    TypeId::ISIZE,
+    TypeId::U8,
+    TypeId::U16,
+    TypeId::U32,
+    TypeId::U64,
+    TypeId::USIZE,
+];
+const BOOLEAN: &[TypeId] = &[TypeId::BOOL];
+const ANY: &[TypeId] = &[
+    TypeId::BOOL,
+    TypeId::I8,
+    TypeId::I16,
+    TypeId::I32,
+    TypeId::I64,
+    TypeId::ISIZE,
+    TypeId::U8,
+    TypeId::U16,
+    TypeId::U32,
+    TypeId::U64,
+    TypeId::USIZE,
+    TypeId::F32,
+    TypeId::F64,
+    TypeId::STR,
+];
+
 impl UnaryOp {
-    pub fn in_type(&self) -> TypeId {
+    /// Types of operand accepted by the operator.
+    pub fn in_types(&self) -> &'static [TypeId] {
         match self {
-            UnaryOp::Add => TypeId::I32,
-            UnaryOp::Sub => TypeId::I32,
-            UnaryOp::Not => TypeId::BOOL,
+            UnaryOp::Add => NUMERIC,
+            UnaryOp::Sub => SIGNED,
+            UnaryOp::Not => LOGICAL,
         }
     }
 
-    pub fn out_type(&self) -> TypeId {
-        match self {
-            UnaryOp::Add => TypeId::I32,
-            UnaryOp::Sub => TypeId::I32,
-            UnaryOp::Not => TypeId::BOOL,
-        }
+    /// Type of the result of operator applied to operand of type `operand`.
+    pub fn out_type(&self, operand: TypeId) -> TypeId {
+        operand
+    }
+
+    /// Unary operators bind tighter than any binary one, so `-x * y` is `(-x) * y`.
+    pub fn priority(&self) -> usize {
+        192
     }
 }
 
 impl BinaryOp {
-    pub fn in_type(&self) -> TypeId {
+    /// Types of operands accepted by the operator. Both operands are expected to be of the same type.
+    pub fn in_types(&self) -> &'static [TypeId] {
+        match self {
+            BinaryOp::Add => NUMERIC,
+            BinaryOp::Sub => NUMERIC,
+            BinaryOp::Mul => NUMERIC,
+            BinaryOp::Div => NUMERIC,
+            BinaryOp::Mod => NUMERIC,
+            BinaryOp::Rsh => INTEGER,
+            BinaryOp::Lsh => INTEGER,
+            BinaryOp::BinAnd => LOGICAL,
+            BinaryOp::BinOr => LOGICAL,
+            BinaryOp::BinXor => LOGICAL,
+            BinaryOp::And => BOOLEAN,
+            BinaryOp::Or => BOOLEAN,
+            BinaryOp::Eq => ANY,
+            BinaryOp::Neq => ANY,
+            BinaryOp::More => NUMERIC,
+            BinaryOp::Less => NUMERIC,
+            BinaryOp::MoreEq => NUMERIC,
+            BinaryOp::LessEq => NUMERIC,
+            BinaryOp::Range => INTEGER,
+        }
+    }
+
+    /// Type of the result of operator applied to operands of type `operand`.
+    pub fn out_type(&self, operand: TypeId) -> TypeId {
+        use BinaryOp::*;
         match self {
-            BinaryOp::Add => TypeId::I32,
-            BinaryOp::Sub => TypeId::I32,
-            BinaryOp::Mul => TypeId::I32,
-            BinaryOp::Div => TypeId::I32,
-            BinaryOp::Mod => TypeId::I32,
-            BinaryOp::Rsh => todo!(),
-            BinaryOp::Lsh => todo!(),
-            BinaryOp::BinAnd => todo!(),
-            BinaryOp::BinOr => todo!(),
-            BinaryOp::BinXor => todo!(),
-            BinaryOp::And => TypeId::BOOL,
-            BinaryOp::Or => TypeId::BOOL,
-            BinaryOp::Eq => TypeId::I32,
-            BinaryOp::Neq => TypeId::I32,
-            BinaryOp::More => TypeId::I32,
-            BinaryOp::Less => TypeId::I32,
-            BinaryOp::MoreEq => TypeId::I32,
-            BinaryOp::LessEq => TypeId::I32,
+            Add | Sub | Mul | Div | Mod | Rsh | Lsh | BinAnd | BinOr | BinXor | Range => operand,
+            And | Or | Eq | Neq | More | Less | MoreEq | LessEq => TypeId::BOOL,
         }
     }
 
-    pub fn out_type(&self) -> TypeId {
+    /// Name of the method that implements the operator for user-defined types, if it may be
+    /// overloaded.
+    pub fn method_name(&self) -> Option<&'static str> {
         match self {
-            BinaryOp::Add => TypeId::I32,
-            BinaryOp::Sub => TypeId::I32,
-            BinaryOp::Mul => TypeId::I32,
-            BinaryOp::Div => TypeId::I32,
-            BinaryOp::Mod => TypeId::I32,
-            BinaryOp::Rsh => todo!(),
-            BinaryOp::Lsh => todo!(),
-            BinaryOp::BinAnd => todo!(),
-            BinaryOp::BinOr => todo!(),
-            BinaryOp::BinXor => todo!(),
-            BinaryOp::And => TypeId::BOOL,
-            BinaryOp::Or => TypeId::BOOL,
-            BinaryOp::Eq => TypeId::BOOL,
-            BinaryOp::Neq => TypeId::BOOL,
-            BinaryOp::More => TypeId::BOOL,
-            BinaryOp::Less => TypeId::BOOL,
-            BinaryOp::MoreEq => TypeId::BOOL,
-            BinaryOp::LessEq => TypeId::BOOL,
+            BinaryOp::Add => Some("add"),
+            BinaryOp::Sub => Some("sub"),
+            BinaryOp::Mul => Some("mul"),
+            BinaryOp::Div => Some("div"),
+            BinaryOp::Eq | BinaryOp::Neq => Some("eq"),
+            _ => None,
         }
     }
 
+    /// Checks if operator is a comparison, so its operands' type doesn't affect its result type.
+    pub fn is_comparison(&self) -> bool {
+        use BinaryOp::*;
+        matches!(self, Eq | Neq | More | Less | MoreEq | LessEq)
+    }
+
     pub fn priority(&self) -> usize {
         use BinaryOp::*;
         match self {
@@ -156,9 +235,10 @@ impl BinaryOp {
             BinAnd => 52,
             BinXor => 51,
             BinOr => 50,
-            And => 31,
-            Or => 30,
             Eq | Neq | More | Less | MoreEq | LessEq => 16,
+            And => 12,
+            Or => 10,
+            Range => 8,
         }
     }
 }
@@ -113,8 +113,11 @@ punc![
     AssignMinus = "-=",
     AssignMul = "*=",
     AssignDiv = "/=",
+    Range = "..",
+    Dot = ".",
+    Pound = "#",
 ];
 
 #[derive(Debug, Clone, PartialEq, Eq, Error)]
 #[error("provided string is not punctuation")]
-pub struct NotPunctuation(String);
+pub struct NotPunctuation(pub(super) String);
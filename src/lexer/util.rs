@@ -1,5 +1,8 @@
 use crate::{
-    error::{library::lexer::TokenMismatch, CompilerError, ExpectedToken, ReportProvider},
+    error::{
+        library::lexer::{KeywordAsIdentifier, TokenMismatch},
+        CompilerError, ExpectedToken,
+    },
     lexer::{
         keyword::Keyword,
         operator::{BinaryOp, UnaryOp},
@@ -44,10 +47,23 @@ impl Lexer {
     }
 
     /// Checks if next token is identifier and consumes it if so.
-    pub fn consume_identifier(&mut self) -> Result<Option<Identifier>, LexerError> {
-        let Token::Ident(ident) = self.peek()? else { return Ok(None); };
-        self.discard();
-        Ok(Some(Identifier(ident)))
+    ///
+    /// # Errors
+    ///
+    /// Keyword is consumed and reported, as it may be mistaken for an identifier.
+    pub fn consume_identifier(&mut self) -> Result<Option<Identifier>, CompilerError> {
+        let start = self.token_start();
+        match self.peek()? {
+            Token::Ident(ident) => {
+                self.discard();
+                Ok(Some(Identifier(ident)))
+            }
+            Token::Kw(kw) => {
+                self.discard();
+                KeywordAsIdentifier::report(self, start, kw).map(|_| unreachable!())
+            }
+            _ => Ok(None),
+        }
     }
 
     /// Checks if next token is unary operator and consumes it if so.
@@ -80,7 +96,7 @@ impl Lexer {
 
     /// Check if next token is provided punctuation or error otherwise.
     pub fn expect_punctuation(&mut self, expected: &'static str) -> Result<(), CompilerError> {
-        let start = self.location();
+        let start = self.token_start();
         let found = self.next()?;
         if found == Token::Punc(Punctuation::new(expected)) {
             Ok(())
@@ -90,27 +106,26 @@ impl Lexer {
         }
     }
 
-    /// Check if next token is provided punctuation or error otherwise.
+    /// Check if next token is provided keyword or error otherwise.
     pub fn expect_keyword(&mut self, keyword: Keyword) -> Result<(), CompilerError> {
-        let start = self.location();
+        let start = self.token_start();
         let found = self.next()?;
         if found == Token::Kw(keyword) {
             Ok(())
         } else {
-            TokenMismatch::report(self, start, vec![Keyword::In.into()], found)
-                .map(|_| unreachable!())
+            TokenMismatch::report(self, start, vec![keyword.into()], found).map(|_| unreachable!())
         }
     }
 
     /// Check if next token is identifier or error otherwise.
     pub fn expect_identifier(&mut self) -> Result<Identifier, CompilerError> {
-        let start = self.location();
+        let start = self.token_start();
         let found = self.next()?;
-        if let Token::Ident(ident) = found {
-            Ok(Identifier(ident))
-        } else {
-            TokenMismatch::report(self, start, vec![ExpectedToken::Identifier], found)
-                .map(|_| unreachable!())
+        match found {
+            Token::Ident(ident) => Ok(Identifier(ident)),
+            Token::Kw(kw) => KeywordAsIdentifier::report(self, start, kw).map(|_| unreachable!()),
+            found => TokenMismatch::report(self, start, vec![ExpectedToken::Identifier], found)
+                .map(|_| unreachable!()),
         }
     }
 }
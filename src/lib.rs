@@ -1,4 +1,6 @@
 pub mod ast;
+pub mod codegen;
+mod compile;
 pub mod context;
 pub mod error;
 pub mod hir;
@@ -6,9 +8,14 @@ pub mod identifier;
 pub mod input_stream;
 pub mod item_table;
 pub mod lexer;
+pub mod mir;
 pub mod parser;
 pub mod path;
+mod query;
 pub mod source;
+pub mod stats;
 pub mod util;
+pub mod watch;
 
+pub use compile::{compile, compile_str, CompileOptions, CompileResult};
 pub use identifier::Identifier;
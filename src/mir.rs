@@ -0,0 +1,268 @@
+//! Mid-level intermediate representation.
+//!
+//! MIR represents body of each function as a control flow graph of [basic blocks](BasicBlock)
+//! that is built from the [HIR](crate::hir). HIR remains the source of truth for types, so MIR
+//! only keeps type of each [local](Local).
+
+mod lower;
+mod print;
+
+pub use lower::lower;
+pub use print::print_mir;
+
+use std::fmt::Display;
+
+use crate::{
+    hir::{intrinsic::Intrinsic, types::TypeId, ConstValue, FunctionId},
+    lexer::operator::{BinaryOp, UnaryOp},
+    path::AbsolutePath,
+    Identifier,
+};
+
+#[derive(Debug, Default)]
+pub struct Mir {
+    /// Bodies of translated functions, ordered by path.
+    functions: Vec<(FunctionId, Body)>,
+}
+
+impl Mir {
+    pub fn get_body(&self, id: FunctionId) -> Option<&Body> {
+        self.functions
+            .iter()
+            .find_map(|(function, body)| (*function == id).then_some(body))
+    }
+
+    /// Bodies of all translated functions, ordered by path.
+    pub fn bodies(&self) -> impl Iterator<Item = (FunctionId, &Body)> {
+        self.functions.iter().map(|(id, body)| (*id, body))
+    }
+}
+
+/// Control flow graph of a single function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Body {
+    pub path: AbsolutePath,
+    /// Types of locals indexed by [Local].
+    ///
+    /// Local `_0` holds the return value and is followed by parameters.
+    pub locals: Vec<TypeId>,
+    pub param_count: usize,
+    /// Blocks indexed by [BlockId], execution starts at [BlockId::ENTRY].
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Body {
+    pub fn block(&self, id: BlockId) -> &BasicBlock {
+        &self.blocks[id.0 as usize]
+    }
+
+    /// Locals that hold parameters of the function.
+    pub fn params(&self) -> impl Iterator<Item = Local> {
+        (1..=self.param_count as u32).map(Local)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockId(u32);
+
+impl BlockId {
+    pub const ENTRY: BlockId = BlockId(0);
+
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for BlockId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+/// Local variable of the function: either declared in the source code or temporary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Local(u32);
+
+impl Local {
+    /// Local that holds return value of the function.
+    pub const RETURN: Local = Local(0);
+
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for Local {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "_{}", self.0)
+    }
+}
+
+/// Sequence of statements that is always executed from the start to the end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub statements: Vec<Statement>,
+    pub terminator: Terminator,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Assign(Place, Rvalue),
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statement::Assign(place, value) => write!(f, "{place} = {value};"),
+        }
+    }
+}
+
+/// Statement that transfers control flow out of the block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Goto(BlockId),
+    If {
+        condition: Operand,
+        then: BlockId,
+        otherwise: BlockId,
+    },
+    /// Returns value of [Local::RETURN] from the function.
+    Return,
+    Call {
+        callee: Callee,
+        args: Vec<Operand>,
+        destination: Place,
+        target: BlockId,
+    },
+    /// Control flow never reaches the end of the block.
+    Unreachable,
+}
+
+impl Terminator {
+    /// Blocks that control flow may be transferred to.
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Goto(target) | Terminator::Call { target, .. } => vec![*target],
+            Terminator::If {
+                then, otherwise, ..
+            } => vec![*then, *otherwise],
+            Terminator::Return | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    fn successors_mut(&mut self) -> Vec<&mut BlockId> {
+        match self {
+            Terminator::Goto(target) | Terminator::Call { target, .. } => vec![target],
+            Terminator::If {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Terminator::Return | Terminator::Unreachable => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Callee {
+    Function(FunctionId),
+    Intrinsic(Intrinsic),
+}
+
+/// Local or its field or element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Place {
+    pub local: Local,
+    pub projections: Vec<Projection>,
+}
+
+impl From<Local> for Place {
+    fn from(local: Local) -> Self {
+        Place {
+            local,
+            projections: Vec::new(),
+        }
+    }
+}
+
+impl Display for Place {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.local)?;
+        for projection in self.projections.iter() {
+            match projection {
+                Projection::Field(field) => write!(f, ".{field}")?,
+                Projection::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Projection {
+    Field(Identifier),
+    Index(Local),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Copy(Place),
+    Const(Constant),
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Copy(place) => write!(f, "{place}"),
+            Operand::Const(constant) => write!(f, "const {constant}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Value(ConstValue, TypeId),
+    Str(String),
+    Unit,
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constant::Value(ConstValue::Integer(value), TypeId::Primitive(primitive)) => {
+                write!(f, "{value}_{primitive}")
+            }
+            Constant::Value(ConstValue::Float(value), TypeId::Primitive(primitive)) => {
+                write!(f, "{value:?}_{primitive}")
+            }
+            Constant::Value(ConstValue::Integer(value), _) => write!(f, "{value}"),
+            Constant::Value(ConstValue::Float(value), _) => write!(f, "{value:?}"),
+            Constant::Value(ConstValue::Boolean(value), _) => write!(f, "{value}"),
+            Constant::Str(string) => write!(f, "{string:?}"),
+            Constant::Unit => write!(f, "()"),
+        }
+    }
+}
+
+/// Value that is assigned to a place.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rvalue {
+    Use(Operand),
+    UnaryOp(UnaryOp, Operand),
+    BinaryOp(BinaryOp, Operand, Operand),
+    /// Conversion of the value to another type, see [TypeId::can_cast_to].
+    Cast(Operand, TypeId),
+}
+
+impl Display for Rvalue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rvalue::Use(operand) => write!(f, "{operand}"),
+            Rvalue::UnaryOp(operator, operand) => write!(f, "{operator}{operand}"),
+            Rvalue::BinaryOp(operator, left, right) => write!(f, "{left} {operator} {right}"),
+            Rvalue::Cast(operand, TypeId::Primitive(primitive)) => {
+                write!(f, "{operand} as {primitive}")
+            }
+            Rvalue::Cast(operand, _) => write!(f, "{operand} as _"),
+        }
+    }
+}
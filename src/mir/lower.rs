@@ -0,0 +1,560 @@
+//! Translation of HIR to MIR.
+
+use std::collections::HashMap;
+
+use crate::{
+    ast::expression::Literal,
+    hir::{
+        scope::VarId,
+        types::{PrimitiveType, TypeId},
+        Block, ConstValue, Expression, ExpressionKind, Function, Hir, Statement as HirStatement,
+    },
+    path::AbsolutePath,
+};
+
+use super::{
+    BasicBlock, BlockId, Body, Callee, Constant, Local, Mir, Operand, Place, Projection, Rvalue,
+    Statement, Terminator,
+};
+
+/// Builds control flow graph of every translated function of the HIR.
+///
+/// Nested blocks, conditionals and loops are flattened into basic blocks and `break` is lowered
+/// into jump to the block that follows the loop. Blocks that may never be reached are removed.
+pub fn lower(hir: &Hir) -> Mir {
+    let mut functions: Vec<_> = hir
+        .function_paths()
+        .filter_map(|(path, id)| {
+            let function = hir.get_function(id)?;
+            Some((id, Builder::new(function).lower(path, function)))
+        })
+        .collect();
+    functions.sort_by_key(|(_, body)| body.path.to_string());
+    Mir { functions }
+}
+
+struct Builder {
+    locals: Vec<TypeId>,
+    vars: HashMap<VarId, Local>,
+    blocks: Vec<PartialBlock>,
+    /// Block that statements are currently appended to.
+    current: BlockId,
+    /// Blocks that follow loops the current block is nested in, innermost last.
+    loop_exits: Vec<BlockId>,
+}
+
+/// Basic block that is still being built.
+#[derive(Default)]
+struct PartialBlock {
+    statements: Vec<Statement>,
+    terminator: Option<Terminator>,
+}
+
+impl Builder {
+    fn new(function: &Function) -> Self {
+        let mut builder = Builder {
+            locals: vec![function.return_type],
+            vars: HashMap::new(),
+            blocks: Vec::new(),
+            current: BlockId::ENTRY,
+            loop_exits: Vec::new(),
+        };
+        for (index, type_) in function.params.iter().enumerate() {
+            let local = builder.local(*type_);
+            builder.vars.insert(VarId::param(index as u32), local);
+        }
+        builder.new_block();
+        builder
+    }
+
+    fn lower(mut self, path: &AbsolutePath, function: &Function) -> Body {
+        let value = self.block(&function.body);
+        if function.return_type == TypeId::UNIT || function.body.tail.is_some() {
+            self.assign_return(value, function.return_type);
+            self.terminate(Terminator::Return);
+        } else {
+            self.terminate(Terminator::Unreachable);
+        }
+
+        let mut body = Body {
+            path: path.clone(),
+            locals: self.locals,
+            param_count: function.params.len(),
+            blocks: self
+                .blocks
+                .into_iter()
+                .map(|block| BasicBlock {
+                    statements: block.statements,
+                    terminator: block.terminator.unwrap_or(Terminator::Unreachable),
+                })
+                .collect(),
+        };
+        remove_unreachable(&mut body);
+        body
+    }
+
+    fn block(&mut self, block: &Block) -> Operand {
+        for stmt in block.statements.iter() {
+            self.statement(stmt);
+        }
+        match &block.tail {
+            Some(tail) => self.operand(tail),
+            None => Operand::Const(Constant::Unit),
+        }
+    }
+
+    fn statement(&mut self, stmt: &HirStatement) {
+        match stmt {
+            HirStatement::ExprStmt(expr) => {
+                self.operand(expr);
+            }
+            HirStatement::LetStmt { var, type_, value } => {
+                let local = self.local(*type_);
+                self.vars.insert(*var, local);
+                if let Some(value) = value {
+                    let value = self.rvalue(value);
+                    self.push(Statement::Assign(local.into(), value));
+                }
+            }
+            HirStatement::Assignment { assignee, value } => {
+                let value = self.rvalue(value);
+                let place = self.place(assignee);
+                self.push(Statement::Assign(place, value));
+            }
+            HirStatement::Return(expr) => {
+                let value = self.operand(expr);
+                self.assign_return(value, expr.type_);
+                self.terminate(Terminator::Return);
+                self.current = self.new_block();
+            }
+            HirStatement::Break => {
+                let exit = *self
+                    .loop_exits
+                    .last()
+                    .expect("break should only be used inside of a loop");
+                self.terminate(Terminator::Goto(exit));
+                self.current = self.new_block();
+            }
+        }
+    }
+
+    /// Lowers expression and stores it in a temporary unless it is already an [Operand].
+    fn operand(&mut self, expr: &Expression) -> Operand {
+        match &expr.kind {
+            ExpressionKind::UnaryOp { .. }
+            | ExpressionKind::BinaryOp { .. }
+            | ExpressionKind::Cast(_) => {
+                let value = self.rvalue(expr);
+                let temp = self.local(expr.type_);
+                self.push(Statement::Assign(temp.into(), value));
+                Operand::Copy(temp.into())
+            }
+            ExpressionKind::Block(block) => self.block(block),
+            ExpressionKind::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                let condition = self.operand(condition);
+                let result = (expr.type_ != TypeId::UNIT).then(|| self.local(expr.type_));
+                let then = self.new_block();
+                let otherwise = self.new_block();
+                let join = match else_body {
+                    Some(_) => self.new_block(),
+                    None => otherwise,
+                };
+                self.terminate(Terminator::If {
+                    condition,
+                    then,
+                    otherwise,
+                });
+
+                self.current = then;
+                self.branch(body, result, join);
+                if let Some(else_body) = else_body {
+                    self.current = otherwise;
+                    self.branch(else_body, result, join);
+                }
+                self.current = join;
+                match result {
+                    Some(result) => Operand::Copy(result.into()),
+                    None => Operand::Const(Constant::Unit),
+                }
+            }
+            ExpressionKind::Loop(body) => {
+                let start = self.new_block();
+                let exit = self.new_block();
+                self.terminate(Terminator::Goto(start));
+
+                self.current = start;
+                self.loop_exits.push(exit);
+                self.block(body);
+                self.loop_exits.pop();
+                self.terminate(Terminator::Goto(start));
+
+                self.current = exit;
+                match expr.type_ {
+                    TypeId::UNIT => Operand::Const(Constant::Unit),
+                    type_ => Operand::Copy(self.local(type_).into()),
+                }
+            }
+            ExpressionKind::Literal(Literal::String(string)) => {
+                Operand::Const(Constant::Str(string.clone()))
+            }
+            ExpressionKind::Literal(Literal::Boolean(value)) => {
+                Operand::Const(Constant::Value(ConstValue::Boolean(*value), TypeId::BOOL))
+            }
+            ExpressionKind::Literal(Literal::Number(number)) => {
+                let target = match expr.type_ {
+                    TypeId::F32 | TypeId::F64 => PrimitiveType::F32,
+                    _ => PrimitiveType::I64,
+                };
+                let value = number
+                    .to_value(target)
+                    .expect("literals are checked by translation");
+                Operand::Const(Constant::Value(value, expr.type_))
+            }
+            ExpressionKind::Const(value) => Operand::Const(Constant::Value(*value, expr.type_)),
+            ExpressionKind::FnCall(function, args) => {
+                self.call(Callee::Function(*function), args, expr.type_)
+            }
+            ExpressionKind::Intrinsic(intrinsic, args, _) => {
+                self.call(Callee::Intrinsic(*intrinsic), args, expr.type_)
+            }
+            ExpressionKind::Var(_)
+            | ExpressionKind::Field { .. }
+            | ExpressionKind::Index { .. } => Operand::Copy(self.place(expr)),
+        }
+    }
+
+    /// Lowers expression to the value that may be directly assigned to a place.
+    fn rvalue(&mut self, expr: &Expression) -> Rvalue {
+        match &expr.kind {
+            ExpressionKind::UnaryOp {
+                operator, value, ..
+            } => Rvalue::UnaryOp(*operator, self.operand(value)),
+            ExpressionKind::BinaryOp {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                let [left, right]: [Operand; 2] = self
+                    .operands(&[left, right])
+                    .try_into()
+                    .expect("operand should be lowered for each expression");
+                Rvalue::BinaryOp(*operator, left, right)
+            }
+            ExpressionKind::Cast(value) => Rvalue::Cast(self.operand(value), expr.type_),
+            _ => Rvalue::Use(self.operand(expr)),
+        }
+    }
+
+    /// Lowers expressions in order.
+    ///
+    /// Places are copied into temporaries if any of the following expressions may modify them.
+    fn operands(&mut self, exprs: &[&Expression]) -> Vec<Operand> {
+        let mut operands = Vec::with_capacity(exprs.len());
+        for (n, expr) in exprs.iter().enumerate() {
+            let operand = self.operand(expr);
+            let spill = exprs[n + 1..].iter().any(|expr| !is_pure(expr));
+            operands.push(match operand {
+                Operand::Copy(place) if spill => {
+                    let temp = self.local(expr.type_);
+                    let value = Rvalue::Use(Operand::Copy(place));
+                    self.push(Statement::Assign(temp.into(), value));
+                    Operand::Copy(temp.into())
+                }
+                operand => operand,
+            });
+        }
+        operands
+    }
+
+    fn call(&mut self, callee: Callee, args: &[Expression], type_: TypeId) -> Operand {
+        let args = self.operands(&args.iter().collect::<Vec<_>>());
+        let destination = self.local(type_);
+        let target = self.new_block();
+        self.terminate(Terminator::Call {
+            callee,
+            args,
+            destination: destination.into(),
+            target,
+        });
+        self.current = target;
+        Operand::Copy(destination.into())
+    }
+
+    /// Lowers branch of the conditional that stores its value in `result` and jumps to `join`.
+    fn branch(&mut self, block: &Block, result: Option<Local>, join: BlockId) {
+        let value = self.block(block);
+        if let Some(result) = result {
+            self.push(Statement::Assign(result.into(), Rvalue::Use(value)));
+        }
+        self.terminate(Terminator::Goto(join));
+    }
+
+    fn place(&mut self, expr: &Expression) -> Place {
+        match &expr.kind {
+            ExpressionKind::Var(var) => Place::from(self.vars[var]),
+            ExpressionKind::Field { value, field } => {
+                let mut place = self.place(value);
+                place.projections.push(Projection::Field(field.clone()));
+                place
+            }
+            ExpressionKind::Index { value, index } => {
+                let mut place = self.place(value);
+                let index = match self.operand(index) {
+                    Operand::Copy(Place { local, projections }) if projections.is_empty() => local,
+                    operand => {
+                        let temp = self.local(index.type_);
+                        self.push(Statement::Assign(temp.into(), Rvalue::Use(operand)));
+                        temp
+                    }
+                };
+                place.projections.push(Projection::Index(index));
+                place
+            }
+            _ => {
+                let value = self.rvalue(expr);
+                let temp = self.local(expr.type_);
+                self.push(Statement::Assign(temp.into(), value));
+                temp.into()
+            }
+        }
+    }
+
+    fn assign_return(&mut self, value: Operand, type_: TypeId) {
+        if type_ != TypeId::UNIT {
+            self.push(Statement::Assign(Local::RETURN.into(), Rvalue::Use(value)));
+        }
+    }
+
+    fn local(&mut self, type_: TypeId) -> Local {
+        self.locals.push(type_);
+        Local(self.locals.len() as u32 - 1)
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(PartialBlock::default());
+        BlockId(self.blocks.len() as u32 - 1)
+    }
+
+    fn push(&mut self, stmt: Statement) {
+        self.blocks[self.current.0 as usize].statements.push(stmt);
+    }
+
+    /// Sets terminator of the current block.
+    ///
+    /// Statements appended to the block afterwards are never executed.
+    fn terminate(&mut self, terminator: Terminator) {
+        let block = &mut self.blocks[self.current.0 as usize];
+        if block.terminator.is_none() {
+            block.terminator = Some(terminator);
+        }
+    }
+}
+
+/// Checks if evaluation of the expression can't modify any variable.
+fn is_pure(expr: &Expression) -> bool {
+    match &expr.kind {
+        ExpressionKind::Block(_)
+        | ExpressionKind::If { .. }
+        | ExpressionKind::Loop(_)
+        | ExpressionKind::FnCall(..)
+        | ExpressionKind::Intrinsic(..) => false,
+        ExpressionKind::Field { value, .. }
+        | ExpressionKind::Cast(value)
+        | ExpressionKind::UnaryOp { value, .. } => is_pure(value),
+        ExpressionKind::Index { value, index } => is_pure(value) && is_pure(index),
+        ExpressionKind::BinaryOp { left, right, .. } => is_pure(left) && is_pure(right),
+        ExpressionKind::Literal(_) | ExpressionKind::Const(_) | ExpressionKind::Var(_) => true,
+    }
+}
+
+/// Removes blocks that can't be reached from the entry and renumbers remaining ones in order.
+fn remove_unreachable(body: &mut Body) {
+    let mut reachable = vec![false; body.blocks.len()];
+    let mut stack = vec![BlockId::ENTRY];
+    while let Some(id) = stack.pop() {
+        if std::mem::replace(&mut reachable[id.0 as usize], true) {
+            continue;
+        }
+        stack.extend(body.block(id).terminator.successors());
+    }
+
+    let mut mapping = vec![None; body.blocks.len()];
+    let mut next = 0;
+    for (old, reachable) in reachable.iter().enumerate() {
+        if *reachable {
+            mapping[old] = Some(BlockId(next));
+            next += 1;
+        }
+    }
+    let blocks = std::mem::take(&mut body.blocks);
+    body.blocks = blocks
+        .into_iter()
+        .zip(reachable)
+        .filter_map(|(block, reachable)| reachable.then_some(block))
+        .collect();
+    for block in body.blocks.iter_mut() {
+        for target in block.terminator.successors_mut() {
+            *target = mapping[target.0 as usize].expect("successor should be reachable");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{hir::Hir, mir::print_mir};
+
+    fn dump(src: &str) -> String {
+        let hir = Hir::new_test(src);
+        let mir = super::lower(&hir);
+        let mut output = Vec::new();
+        print_mir(&mut output, &mir, &hir).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn while_loop() {
+        let mir = dump(
+            "
+            fn count(n: i32) -> i32 {
+                let total: i32 = 0;
+                while total < n { total = total + 1; }
+                total
+            }
+            ",
+        );
+        let expected = "\
+fn crate::count(_1: i32) -> i32 {
+    let _2: i32;
+    let _3: bool;
+    let _4: bool;
+
+    bb0: {
+        _2 = const 0_i32;
+        goto -> bb1;
+    }
+
+    bb1: {
+        _3 = _2 < _1;
+        _4 = !_3;
+        if _4 -> [true: bb3, false: bb4];
+    }
+
+    bb2: {
+        _0 = _2;
+        return;
+    }
+
+    bb3: {
+        goto -> bb2;
+    }
+
+    bb4: {
+        _2 = _2 + const 1_i32;
+        goto -> bb1;
+    }
+}
+";
+        assert_eq!(mir, expected);
+    }
+
+    #[test]
+    fn if_else_expression() {
+        let mir = dump(
+            "
+            fn pick(flag: bool) -> i64 {
+                let x: i64 = if flag { 1 } else { 2 };
+                x
+            }
+            ",
+        );
+        let expected = "\
+fn crate::pick(_1: bool) -> i64 {
+    let _2: i64;
+    let _3: i64;
+
+    bb0: {
+        if _1 -> [true: bb1, false: bb2];
+    }
+
+    bb1: {
+        _3 = const 1_i64;
+        goto -> bb3;
+    }
+
+    bb2: {
+        _3 = const 2_i64;
+        goto -> bb3;
+    }
+
+    bb3: {
+        _2 = _3;
+        _0 = _2;
+        return;
+    }
+}
+";
+        assert_eq!(mir, expected);
+    }
+
+    #[test]
+    fn calls_and_unreachable_blocks() {
+        let mir = dump(
+            "
+            fn abs(x: i32) -> i32 { if x < 0 { -x } else { x } }
+            fn main() { println(to_string(abs(-5) as i64)); }
+            ",
+        );
+        let expected = "\
+fn crate::abs(_1: i32) -> i32 {
+    let _2: bool;
+    let _3: i32;
+
+    bb0: {
+        _2 = _1 < const 0_i32;
+        if _2 -> [true: bb1, false: bb2];
+    }
+
+    bb1: {
+        _3 = -_1;
+        _0 = _3;
+        return;
+    }
+
+    bb2: {
+        _0 = _1;
+        return;
+    }
+}
+
+fn crate::main() -> () {
+    let _1: i32;
+    let _2: i64;
+    let _3: str;
+    let _4: ();
+
+    bb0: {
+        _1 = crate::abs(const -5_i32) -> bb1;
+    }
+
+    bb1: {
+        _2 = _1 as i64;
+        _3 = to_string(_2) -> bb2;
+    }
+
+    bb2: {
+        _4 = println(_3) -> bb3;
+    }
+
+    bb3: {
+        return;
+    }
+}
+";
+        assert_eq!(mir, expected);
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::{Result, Write};
+
+use crate::hir::Hir;
+
+use super::{Body, Callee, Mir, Terminator};
+
+/// Prints control flow graph of every function of the MIR, ordered by path.
+///
+/// `hir` is used to look up names of types and called functions.
+pub fn print_mir(mut w: impl Write, mir: &Mir, hir: &Hir) -> Result<()> {
+    for (n, (_, body)) in mir.bodies().enumerate() {
+        if n != 0 {
+            writeln!(w)?;
+        }
+        print_body(&mut w, body, hir)?;
+    }
+    Ok(())
+}
+
+fn print_body(w: &mut impl Write, body: &Body, hir: &Hir) -> Result<()> {
+    let types = hir.type_table();
+    let params: Vec<_> = body
+        .params()
+        .map(|local| {
+            let type_ = body.locals[local.index() as usize];
+            format!("{local}: {}", types.display(type_))
+        })
+        .collect();
+    writeln!(
+        w,
+        "fn {}({}) -> {} {{",
+        body.path,
+        params.join(", "),
+        types.display(body.locals[0])
+    )?;
+    for (index, type_) in body.locals.iter().enumerate().skip(body.param_count + 1) {
+        writeln!(w, "    let _{index}: {};", types.display(*type_))?;
+    }
+    for (index, block) in body.blocks.iter().enumerate() {
+        writeln!(w)?;
+        writeln!(w, "    bb{index}: {{")?;
+        for stmt in block.statements.iter() {
+            writeln!(w, "        {stmt}")?;
+        }
+        write!(w, "        ")?;
+        match &block.terminator {
+            Terminator::Goto(target) => writeln!(w, "goto -> {target};")?,
+            Terminator::If {
+                condition,
+                then,
+                otherwise,
+            } => writeln!(w, "if {condition} -> [true: {then}, false: {otherwise}];")?,
+            Terminator::Return => writeln!(w, "return;")?,
+            Terminator::Call {
+                callee,
+                args,
+                destination,
+                target,
+            } => {
+                let args: Vec<_> = args.iter().map(ToString::to_string).collect();
+                let callee = match callee {
+                    Callee::Function(id) => hir
+                        .function_path(*id)
+                        .map_or_else(|| String::from("{unknown}"), ToString::to_string),
+                    Callee::Intrinsic(intrinsic) => intrinsic.to_string(),
+                };
+                writeln!(
+                    w,
+                    "{destination} = {callee}({}) -> {target};",
+                    args.join(", ")
+                )?;
+            }
+            Terminator::Unreachable => writeln!(w, "unreachable;")?,
+        }
+        writeln!(w, "    }}")?;
+    }
+    writeln!(w, "}}")
+}
@@ -1,112 +1,342 @@
 //! Parsing stage of the compilation.
 
+mod attribute;
+#[cfg(feature = "serde")]
+mod cache;
 mod expression;
 mod item;
 pub mod operator_expression;
+mod path;
 mod statement;
+mod types;
 
-use std::path::PathBuf;
-
-pub use expression::*;
-pub use item::*;
-pub use statement::*;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    thread,
+    time::Instant,
+};
 
 use crate::{
     ast::item::{Item, Visibility},
     context::Context,
-    error::{CompilerError, ReportProvider},
-    input_stream::InputStream,
+    error::{
+        library::parser::{
+            AmbiguousModuleFile, DuplicateDefinition, ModuleFileNotFound, ModuleFileReused,
+            ModuleWithoutRoot, NestingTooDeep, UnclosedBrace,
+        },
+        CompilerError, Diagnostic, Label, ReportProvider,
+    },
+    input_stream::{InputStream, Location},
     item_table::ItemTable,
-    lexer::Lexer,
+    lexer::{keyword::Keyword, punctuation::Punctuation, Lexer, Token},
     path::AbsolutePath,
     source::{SourceError, SourceId},
+    stats::FileStats,
     util::Span,
 };
 
 /// Interface to compute a [ItemTable] of the whole project.
 pub struct Parser {
     pending: Vec<PendingFile>,
+    /// Modules that were already declared as `mod NAME;`.
+    declared: HashSet<AbsolutePath>,
+    /// Loaded files with paths to modules they were loaded as.
+    loaded: HashMap<SourceId, AbsolutePath>,
+    /// Statistics of parsed files in the order they were loaded in.
+    stats: Vec<FileStats>,
     pub context: Context,
 }
 
 impl Parser {
-    pub fn new(main: PathBuf, context: Context) -> Result<Self, SourceError> {
-        Ok(Parser {
+    /// Creates parser of the crate, starting from the main file of the `context`.
+    pub fn new(context: Context) -> Self {
+        let main = context.source.lock().unwrap().main().to_owned();
+        Parser {
             pending: vec![PendingFile::Specific {
                 scope: AbsolutePath::new(context.metadata.crate_name.clone()),
                 path: main,
             }],
+            declared: HashSet::new(),
+            loaded: HashMap::new(),
+            stats: Vec::new(),
             context,
-        })
+        }
     }
 
     /// Parse the whole package.
-    pub fn parse(&mut self) -> Result<ItemTable, Vec<CompilerError>> {
+    ///
+    /// Warnings reported during parsing are returned alongside the table. Parsing is aborted early
+    /// if the maximal number of errors is reached.
+    pub fn parse(&mut self) -> Result<(ItemTable, Vec<Diagnostic>), Vec<CompilerError>> {
+        let (table, errors) = self.parse_partial();
+        if errors.is_empty() && !self.context.error_reporter.compilation_failed() {
+            Ok((table, self.context.error_reporter.warnings()))
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parse the whole package even if some of its files contain errors.
+    ///
+    /// Table contains every item that was parsed successfully. Files that are known at the same
+    /// time are parsed in parallel by [jobs](crate::context::Metadata::jobs) threads, but the
+    /// result doesn't depend on their scheduling.
+    pub fn parse_partial(&mut self) -> (ItemTable, Vec<CompilerError>) {
         let mut table = ItemTable::new();
         let mut errors = Vec::new();
-        while let Some(file) = self.pending.pop() {
-            let parsed = match file {
-                PendingFile::General(path) => self.parse_file(path.clone()),
-                PendingFile::Specific { scope, path } => self.parse_file_by_path(scope, path),
-            };
-            match parsed {
-                Ok(parsed) => {
-                    self.pending.extend(parsed.pending);
-                    table.extend(parsed.item_table);
-                }
-                Err(err) => {
-                    errors.push(err);
+        while !self.pending.is_empty() {
+            if self.context.error_reporter.limit_reached() {
+                break;
+            }
+            let mut files = Vec::new();
+            for file in std::mem::take(&mut self.pending) {
+                let loaded = match file {
+                    PendingFile::General(path, _) if !self.declared.insert(path.clone()) => {
+                        // Repeated declaration is reported as duplicate definition.
+                        continue;
+                    }
+                    PendingFile::General(path, span) => self.load_module(path, span),
+                    PendingFile::Specific { scope, path } => self.load_file(scope, path),
+                };
+                match loaded {
+                    Ok(file) => files.push(file),
+                    Err(err) => errors.push(err),
                 }
             }
+            for (parsed, stats) in self.parse_files(files) {
+                self.stats.push(stats);
+                self.pending.extend(parsed.pending);
+                table.extend(parsed.item_table);
+                errors.extend(parsed.errors);
+            }
         }
+        errors.extend(self.report_duplicates(&table));
+        (table, errors)
+    }
 
-        if errors.is_empty() {
-            Ok(table)
-        } else {
-            Err(errors)
-        }
+    /// Parses the file with `id` that was already parsed, e.g. after it was
+    /// [refreshed](crate::source::SourceMap::refresh).
+    ///
+    /// Returned table only contains items of that file, so it should replace the stale ones with
+    /// [remove_module](ItemTable::remove_module) and [extend](ItemTable::extend). Modules that are
+    /// newly declared in the file are not loaded. `None` is returned if the file wasn't parsed
+    /// by this parser.
+    pub fn reparse_file(&mut self, id: SourceId) -> Option<(ItemTable, Vec<CompilerError>)> {
+        let scope = self.loaded.get(&id)?.clone();
+        self.context.error_reporter.clear_lint_scopes(id);
+        let file = match self.load_by_id(scope, id) {
+            Ok(file) => file,
+            Err(err) => return Some((ItemTable::new(), vec![err])),
+        };
+        let (parsed, stats) = file.parse(&self.context);
+        self.stats.push(stats);
+        Some((parsed.item_table, parsed.errors))
+    }
+
+    /// Path to the module that file with `id` was parsed as.
+    pub fn loaded_module(&self, id: SourceId) -> Option<&AbsolutePath> {
+        self.loaded.get(&id)
+    }
+
+    /// Duration and size of every file parsed so far.
+    pub fn file_stats(&self) -> &[FileStats] {
+        &self.stats
     }
 
-    /// Parse one file at default location.
-    pub fn parse_file(&mut self, path: AbsolutePath) -> Result<ParsedFile, CompilerError> {
-        let id = self.context.source.lock().unwrap().insert(path.clone())?;
-        self.parse_file_by_id(path, id)
+    /// Reports every item that was defined with already occupied path.
+    pub(crate) fn report_duplicates(&self, table: &ItemTable) -> Vec<CompilerError> {
+        table
+            .duplicates()
+            .map(|(path, original, duplicate)| {
+                let previous = match original.span.source {
+                    Some(id) => format!(
+                        "{}:{}",
+                        self.context.source.lock().unwrap().get_path(id).display(),
+                        original.span.start
+                    ),
+                    None => original.span.start.to_string(),
+                };
+                DuplicateDefinition::report_at(
+                    &self.context.error_reporter,
+                    duplicate.span,
+                    path.clone(),
+                    previous,
+                )
+                .unwrap_err()
+            })
+            .collect()
     }
 
-    /// Parse one file with specified location.
-    pub fn parse_file_by_path(
+    /// Load file of the module at `path`, declared as `mod NAME;` at `span`.
+    ///
+    /// See [SourceMap::insert](crate::source::SourceMap::insert) for the locations of the file.
+    /// File that was already loaded as another module, e.g. due to cyclic declarations, is not
+    /// loaded again.
+    fn load_module(&mut self, path: AbsolutePath, span: Span) -> Result<LoadedFile, CompilerError> {
+        let inserted = self.context.source.lock().unwrap().insert(path.clone());
+        let reporter = &self.context.error_reporter;
+        let name = || path.last().clone();
+        let id = match inserted {
+            Ok(id) => id,
+            Err(SourceError::ModuleNotFound(file, mod_file)) => {
+                return ModuleFileNotFound::report_at(reporter, span, name(), file, mod_file)
+                    .map(|_| unreachable!());
+            }
+            Err(SourceError::AmbiguousModule(file, mod_file)) => {
+                return AmbiguousModuleFile::report_at(reporter, span, name(), file, mod_file)
+                    .map(|_| unreachable!());
+            }
+            Err(SourceError::MissingRoot) => {
+                return ModuleWithoutRoot::report_at(reporter, span, name())
+                    .map(|_| unreachable!());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if let Some(previous) = self.loaded.get(&id) {
+            return ModuleFileReused::report_at(reporter, span, name(), previous.clone())
+                .map(|_| unreachable!());
+        }
+        self.load_by_id(path, id)
+    }
+
+    /// Load file with specified location as the module at `scope`.
+    fn load_file(
         &mut self,
         scope: AbsolutePath,
         path: PathBuf,
-    ) -> Result<ParsedFile, CompilerError> {
+    ) -> Result<LoadedFile, CompilerError> {
         let id = self.context.source.lock().unwrap().insert_path(path)?;
-        self.parse_file_by_id(scope, id)
+        self.load_by_id(scope, id)
     }
 
-    fn parse_file_by_id(
+    fn load_by_id(
         &mut self,
         scope: AbsolutePath,
         id: SourceId,
-    ) -> Result<ParsedFile, CompilerError> {
+    ) -> Result<LoadedFile, CompilerError> {
+        self.loaded.insert(id, scope.clone());
         let mut source_map = self.context.source.lock().unwrap();
-        let file = source_map.get(id).read()?;
-        let stream = InputStream::new(file, Some(id));
-        let lexer = Lexer::new(stream, self.context.clone());
-        let parser = FileParser::new(lexer, scope, self.context.clone());
-
-        parser.parse().map_err(|(err, pending)| {
-            self.pending.extend(pending);
-            err
+        let src = source_map.get(id).read()?.to_owned();
+        let path = source_map.get_path(id).to_owned();
+        Ok(LoadedFile {
+            scope,
+            id,
+            path,
+            src,
+        })
+    }
+
+    /// Parses `files`, distributing them between [jobs](crate::context::Metadata::jobs) threads.
+    ///
+    /// Results are returned in the order of `files`.
+    fn parse_files(&self, files: Vec<LoadedFile>) -> Vec<(ParsedFile, FileStats)> {
+        let jobs = self.context.metadata.jobs.max(1);
+        if jobs == 1 || files.len() < 2 {
+            return files
+                .into_iter()
+                .map(|file| file.parse(&self.context))
+                .collect();
+        }
+        let chunk_size = files.len().div_ceil(jobs);
+        let mut chunks = Vec::new();
+        let mut files = files.into_iter().peekable();
+        while files.peek().is_some() {
+            chunks.push(files.by_ref().take(chunk_size).collect::<Vec<_>>());
+        }
+        thread::scope(|scope| {
+            let workers: Vec<_> = chunks
+                .into_iter()
+                .map(|chunk| {
+                    let context = &self.context;
+                    thread::Builder::new()
+                        .stack_size(context.metadata.stack_size())
+                        .spawn_scoped(scope, move || {
+                            chunk
+                                .into_iter()
+                                .map(|file| file.parse(context))
+                                .collect::<Vec<_>>()
+                        })
+                        .expect("parser thread should be spawned")
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("parser thread should not panic"))
+                .collect()
         })
     }
 }
 
+/// Source code of the file that is ready to be parsed.
+struct LoadedFile {
+    /// Path to the module the file is parsed as.
+    scope: AbsolutePath,
+    id: SourceId,
+    path: PathBuf,
+    src: String,
+}
+
+impl LoadedFile {
+    fn parse(self, context: &Context) -> (ParsedFile, FileStats) {
+        let start = Instant::now();
+        #[cfg(feature = "serde")]
+        let parsed = cache::load(context, &self.scope, self.id, &self.src)
+            .unwrap_or_else(|| self.parse_source(context));
+        #[cfg(not(feature = "serde"))]
+        let parsed = self.parse_source(context);
+        let items = parsed
+            .item_table
+            .iter()
+            .filter(|(path, _)| **path != self.scope)
+            .count();
+        let stats = FileStats {
+            path: self.path,
+            duration: start.elapsed(),
+            tokens: parsed.tokens,
+            items: items + parsed.item_table.impls().len(),
+        };
+        (parsed, stats)
+    }
+
+    /// Lexes and parses the source code, storing the result in the cache if there were no
+    /// diagnostics in the file.
+    fn parse_source(&self, context: &Context) -> ParsedFile {
+        let stream = InputStream::new(&self.src, Some(self.id));
+        let lexer = Lexer::new(stream, context.clone());
+        let parsed = FileParser::new(lexer, self.scope.clone(), context.clone()).parse();
+        #[cfg(feature = "serde")]
+        if parsed.errors.is_empty() && !self.has_diagnostics(context) {
+            cache::store(context, &self.scope, &self.src, &parsed);
+        }
+        parsed
+    }
+
+    /// Checks if any diagnostics were reported in the file.
+    #[cfg(feature = "serde")]
+    fn has_diagnostics(&self, context: &Context) -> bool {
+        context
+            .error_reporter
+            .diagnostics()
+            .iter()
+            .any(|diagnostic| diagnostic.span.and_then(|span| span.source) == Some(self.id))
+    }
+}
+
 /// Interface to parse a single file into [ItemTable].
 pub struct FileParser {
+    /// Items of the file, expressions are allocated in the [arena](ItemTable::exprs) of it.
     pub item_table: ItemTable,
     pub lexer: Lexer,
     scope: AbsolutePath,
     pending: Vec<PendingFile>,
+    errors: Vec<CompilerError>,
+    /// Spans of opening braces that are not closed yet, the innermost one goes last.
+    open_braces: Vec<Span>,
+    /// Number of nested constructs that enclose the current token.
+    nesting: usize,
+    /// Code that expects the value of the expression being parsed, such as `let` or a call.
+    value_site: Option<Label>,
     pub context: Context,
 }
 
@@ -117,10 +347,23 @@ impl FileParser {
             lexer,
             scope,
             pending: Vec::new(),
+            errors: Vec::new(),
+            open_braces: Vec::new(),
+            nesting: 0,
+            value_site: None,
             context,
         }
     }
 
+    /// Creates a span from `start` to the end of the last consumed token.
+    pub(crate) fn span_from(&self, start: Location) -> Span {
+        Span {
+            source: self.source(),
+            start,
+            end: self.lexer.token_end(),
+        }
+    }
+
     #[cfg(test)]
     pub fn new_test(src: &str) -> Self {
         use crate::Identifier;
@@ -131,11 +374,19 @@ impl FileParser {
             lexer: Lexer::new(InputStream::new(src, None), context.clone()),
             scope: AbsolutePath::new(Identifier(String::from("crate"))),
             pending: Vec::new(),
+            errors: Vec::new(),
+            open_braces: Vec::new(),
+            nesting: 0,
+            value_site: None,
             context,
         }
     }
 
-    pub fn parse(mut self) -> Result<ParsedFile, (CompilerError, Vec<PendingFile>)> {
+    /// Parse the file.
+    ///
+    /// Parsing is resumed after syntax errors, so every error of the file is collected alongside
+    /// the items that were parsed successfully.
+    pub fn parse(mut self) -> ParsedFile {
         let start = self.location();
         match self.parse_top_module(self.scope.last().clone()) {
             Ok(module) => {
@@ -149,24 +400,629 @@ impl FileParser {
                     Visibility::Public,
                 );
                 self.item_table.declare_anonymous(self.scope.clone(), item);
-                Ok(ParsedFile {
-                    item_table: self.item_table,
-                    pending: self.pending,
-                })
             }
-            Err(err) => Err((err, self.pending)),
+            Err(err) => self.errors.push(err),
+        }
+        ParsedFile {
+            item_table: self.item_table,
+            pending: self.pending,
+            errors: self.errors,
+            tokens: self.lexer.token_count(),
+        }
+    }
+
+    /// Records that the last consumed token is an opening brace.
+    pub fn open_brace(&mut self) {
+        let span = self.span_from(self.lexer.last_token_start());
+        self.open_braces.push(span);
+    }
+
+    /// Records that the innermost opening brace was closed.
+    pub fn close_brace(&mut self) {
+        self.open_braces.pop();
+    }
+
+    /// Number of opening braces that are not closed yet.
+    pub fn brace_depth(&self) -> usize {
+        self.open_braces.len()
+    }
+
+    /// Records that a nested construct is entered.
+    ///
+    /// # Errors
+    ///
+    /// Error is reported if the construct is nested deeper than
+    /// [max_nesting](crate::context::Metadata::max_nesting) allows.
+    pub fn nest(&mut self) -> Result<(), CompilerError> {
+        self.nesting += 1;
+        let limit = self.context.metadata.max_nesting;
+        if self.nesting > limit {
+            let start = self.lexer.last_token_start();
+            return NestingTooDeep::report(self, start, limit).map(|_| unreachable!());
+        }
+        Ok(())
+    }
+
+    /// Records that `levels` innermost nested constructs were left.
+    pub fn unnest(&mut self, levels: usize) {
+        self.nesting -= levels;
+    }
+
+    /// Runs `func` as a nested construct.
+    ///
+    /// Constructs that `func` [nests](FileParser::nest) are left once it returns, even if it fails.
+    pub fn nested<R>(
+        &mut self,
+        func: impl FnOnce(&mut FileParser) -> Result<R, CompilerError>,
+    ) -> Result<R, CompilerError> {
+        let nesting = self.nesting;
+        let result = self.nest().and_then(|_| func(self));
+        self.nesting = nesting;
+        result
+    }
+
+    /// Runs `func` to parse the value that code at `site` expects, so diagnostics may refer to it.
+    pub fn expecting_value<R>(
+        &mut self,
+        site: Label,
+        func: impl FnOnce(&mut FileParser) -> R,
+    ) -> R {
+        let previous = self.value_site.replace(site);
+        let result = func(self);
+        self.value_site = previous;
+        result
+    }
+
+    /// Records `error` and skips tokens until parsing may be safely resumed.
+    ///
+    /// Tokens are skipped up to and including `;` or `}` that ends the erroneous construct, or up
+    /// to the next `fn`, `struct` or `mod` keyword. Nested braces are skipped entirely, including
+    /// the ones that were opened by the construct after [brace depth](FileParser::brace_depth)
+    /// was `depth`. At least one token is skipped if the construct that started at `start` failed
+    /// without consuming anything.
+    ///
+    /// Error is returned back if the end of file is reached, as nothing can be recovered. In that
+    /// case the innermost brace that is never closed is reported.
+    pub fn recover(
+        &mut self,
+        error: CompilerError,
+        start: Location,
+        depth: usize,
+    ) -> Result<(), CompilerError> {
+        if self.lexer.is_eof() {
+            if let Some(brace) = self.open_braces.last() {
+                let _ = UnclosedBrace::report_at(&self.context.error_reporter, *brace);
+                self.open_braces.clear();
+            }
+            return Err(error);
+        }
+        self.errors.push(error);
+
+        let mut unclosed = self.open_braces.len().saturating_sub(depth);
+        self.open_braces.truncate(depth);
+        loop {
+            let token = match self.lexer.peek() {
+                Ok(Token::Eof) => break,
+                Ok(token) => token,
+                Err(_) => {
+                    // Input that lexer failed to recognize is skipped character by character.
+                    self.lexer.input.next();
+                    continue;
+                }
+            };
+            match token {
+                Token::Kw(Keyword::Fn | Keyword::Struct | Keyword::Mod) if unclosed == 0 => break,
+                Token::Punc(Punctuation::Semicolon) if unclosed == 0 => {
+                    self.lexer.discard();
+                    break;
+                }
+                Token::Punc(Punctuation::LBrace) => unclosed += 1,
+                // Closing brace of the enclosing construct is left for its parser.
+                Token::Punc(Punctuation::RBrace) if unclosed == 0 => break,
+                Token::Punc(Punctuation::RBrace) if unclosed == 1 => {
+                    self.lexer.discard();
+                    break;
+                }
+                Token::Punc(Punctuation::RBrace) => unclosed -= 1,
+                _ => {}
+            }
+            self.lexer.discard();
+        }
+
+        if self.lexer.token_start() == start {
+            self.lexer.discard();
         }
+        Ok(())
     }
 }
 
 /// Result of the file parse.
 pub struct ParsedFile {
+    /// Items of the file along with the [arena](ItemTable::exprs) of their expressions.
     pub item_table: ItemTable,
     pub pending: Vec<PendingFile>,
+    /// Errors that occured in the file.
+    pub errors: Vec<CompilerError>,
+    /// Number of tokens that were read.
+    pub tokens: usize,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum PendingFile {
-    General(AbsolutePath),
+    /// Module declared as `mod NAME;` at the span.
+    General(AbsolutePath, Span),
+    /// File at the path, that is parsed as the module at the scope.
     Specific { scope: AbsolutePath, path: PathBuf },
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{HashMap, HashSet},
+        path::Path,
+        str::FromStr,
+        sync::Arc,
+    };
+
+    use crate::{
+        ast::item::ItemKind,
+        context::Context,
+        error::{
+            library::{
+                lexer::{TokenMismatch, UnexpectedEOF},
+                parser::{
+                    AmbiguousModuleFile, DuplicateDefinition, ElseWithoutIf, ItemInExpression,
+                    ModuleFileNotFound, ModuleFileReused, ModuleWithoutRoot, UnclosedBrace,
+                    UnclosedParenthesis,
+                },
+            },
+            Diagnostic,
+        },
+        item_table::ItemTable,
+        lexer::Lexer,
+        path::AbsolutePath,
+        source::STDIN_PATH,
+    };
+
+    use super::{FileParser, Parser};
+
+    fn parse(scope: &str, src: &str) -> ItemTable {
+        let scope = AbsolutePath::from_str(scope).unwrap();
+        let parser = FileParser::new(Lexer::new_test(src), scope, Context::new_test());
+        let parsed = parser.parse();
+        assert!(parsed.errors.is_empty(), "parsing failed");
+        parsed.item_table
+    }
+
+    fn count_duplicates(table: &ItemTable) -> usize {
+        let parser = Parser {
+            pending: Vec::new(),
+            declared: HashSet::new(),
+            loaded: HashMap::new(),
+            stats: Vec::new(),
+            context: Context::new_test(),
+        };
+        parser.report_duplicates(table).len()
+    }
+
+    #[test]
+    fn duplicate_functions() {
+        let table = parse("crate", "fn run() {} fn run() {}");
+        assert_eq!(count_duplicates(&table), 1);
+    }
+
+    #[test]
+    fn duplicate_structs() {
+        let table = parse("crate", "struct Point { x: i32 } struct Point { y: i32 }");
+        assert_eq!(count_duplicates(&table), 1);
+    }
+
+    #[test]
+    fn module_function_collision() {
+        let table = parse("crate", "mod geometry {} fn geometry() {}");
+        assert_eq!(count_duplicates(&table), 1);
+    }
+
+    #[test]
+    fn merged_files() {
+        let mut table = parse("crate", "mod geometry;");
+        table.extend(parse("crate::geometry", "fn area() {}"));
+        assert_eq!(count_duplicates(&table), 0);
+
+        table.extend(parse("crate::geometry", "fn area() {}"));
+        let path = AbsolutePath::from_str("crate::geometry::area").unwrap();
+        assert!(table
+            .duplicates()
+            .any(|(duplicate, _, _)| duplicate == &path));
+    }
+
+    #[test]
+    fn recovery() {
+        let parser = FileParser::new_test(include_str!("../tests/fixtures/recovery.sun"));
+        let reporter = Arc::clone(&parser.context.error_reporter);
+        let parsed = parser.parse();
+        assert_eq!(parsed.errors.len(), 3);
+        assert_eq!(reporter.diagnostics().len(), 3);
+
+        let mut paths: Vec<_> = parsed
+            .item_table
+            .iter()
+            .map(|(path, _)| path.to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "crate",
+                "crate::Point",
+                "crate::first",
+                "crate::geometry",
+                "crate::geometry::area",
+                "crate::last",
+            ]
+        );
+    }
+
+    #[test]
+    fn item_in_expression() {
+        let src = "fn run() {\n    let x = fn double;\n    take(1, struct Point);\n}";
+        let parser = FileParser::new_test(src);
+        let reporter = Arc::clone(&parser.context.error_reporter);
+        let parsed = parser.parse();
+        assert_eq!(parsed.errors.len(), 2);
+
+        let diagnostics = reporter.diagnostics();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "items are not expressions; declare the function before using it",
+                "items are not expressions; declare the struct before using it",
+            ]
+        );
+        let labels: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                assert_eq!(diagnostic.code, ItemInExpression::CODE);
+                let [label] = diagnostic.secondary_labels.as_slice() else {
+                    panic!("expected a single label: {diagnostic:?}");
+                };
+                let span = diagnostic.span.unwrap();
+                let site = (label.span.start.column, label.span.end.column);
+                (span.start.column, site, label.message.as_str())
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                (12, (4, 11), "value is expected due to this `let`"),
+                (12, (4, 9), "value is expected as an argument of this call"),
+            ]
+        );
+    }
+
+    #[test]
+    fn virtual_files() {
+        let context = Context::builder()
+            .main("/virtual/main.sun")
+            .virtual_file("/virtual/main.sun", "mod utils; mod shapes { mod square; }")
+            .virtual_file("/virtual/utils.sun", "fn helper() {} fn broken() { 1 + }")
+            .virtual_file("/virtual/shapes/square.sun", "struct Square {}")
+            .build()
+            .unwrap();
+        let (table, errors) = Parser::new(context.clone()).parse_partial();
+        assert_eq!(errors.len(), 1);
+        for path in ["main::utils::helper", "main::shapes::square::Square"] {
+            let path = AbsolutePath::from_str(path).unwrap();
+            assert!(table.declared.contains_key(&path), "`{path}` is missing");
+        }
+
+        let diagnostics = context.error_reporter.diagnostics();
+        let source = diagnostics[0].span.unwrap().source.unwrap();
+        assert_eq!(
+            context.source.lock().unwrap().get_path(source),
+            Path::new("/virtual/utils.sun")
+        );
+    }
+
+    /// Parses crate built from virtual `files`, the first of which is the main one.
+    fn parse_virtual(files: &[(&str, &str)]) -> (ItemTable, Vec<Diagnostic>) {
+        let mut builder = Context::builder().main(files[0].0);
+        for (path, contents) in files {
+            builder = builder.virtual_file(*path, *contents);
+        }
+        let context = builder.build().unwrap();
+        let (table, _) = Parser::new(context.clone()).parse_partial();
+        (table, context.error_reporter.diagnostics())
+    }
+
+    #[test]
+    fn directory_module() {
+        let (table, diagnostics) = parse_virtual(&[
+            ("/virtual/main.sun", "mod shapes;"),
+            ("/virtual/shapes/mod.sun", "mod square; fn area() {}"),
+            ("/virtual/shapes/square.sun", "struct Square {}"),
+        ]);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        for path in ["main::shapes::area", "main::shapes::square::Square"] {
+            let path = AbsolutePath::from_str(path).unwrap();
+            assert!(table.declared.contains_key(&path), "`{path}` is missing");
+        }
+    }
+
+    #[test]
+    fn reparse_file() {
+        let context = Context::builder()
+            .main("/virtual/main.sun")
+            .virtual_file("/virtual/main.sun", "mod shapes; fn main() {}")
+            .virtual_file("/virtual/shapes.sun", "fn area() {} fn perimeter() {}")
+            .build()
+            .unwrap();
+        let mut parser = Parser::new(context.clone());
+        let (mut table, _) = parser.parse().unwrap();
+        let original = table.clone();
+
+        let shapes = Path::new("/virtual/shapes.sun");
+        let id = {
+            let mut source = context.source.lock().unwrap();
+            let contents = String::from("fn area() -> i32 { 1 } fn volume() {}");
+            source.insert_virtual(shapes.to_owned(), contents);
+            assert_eq!(source.refresh(Path::new("/virtual/main.sun")), None);
+            source.refresh(shapes).unwrap()
+        };
+        let (fragment, errors) = parser.reparse_file(id).unwrap();
+        assert!(errors.is_empty());
+        let module = parser.loaded_module(id).unwrap().clone();
+        table.remove_module(&module);
+        table.extend(fragment);
+        assert_eq!(table.duplicates().count(), 0);
+
+        let mut changed: Vec<_> = table
+            .iter()
+            .filter(|(path, item)| original.get(path) != Some(*item))
+            .map(|(path, _)| path.to_string())
+            .collect();
+        changed.sort();
+        assert_eq!(changed, ["main::shapes::area", "main::shapes::volume"]);
+        let removed = AbsolutePath::from_str("main::shapes::perimeter").unwrap();
+        assert!(table.get(&removed).is_none());
+    }
+
+    #[test]
+    fn module_file_not_found() {
+        let (_, diagnostics) = parse_virtual(&[("/virtual/main.sun", "mod shapes;")]);
+        let [diagnostic] = diagnostics.as_slice() else {
+            panic!("unexpected diagnostics: {diagnostics:?}");
+        };
+        assert_eq!(diagnostic.code, ModuleFileNotFound::CODE);
+        assert_eq!(
+            diagnostic.message,
+            "file for module `shapes` is not found at `/virtual/shapes.sun` or `/virtual/shapes/mod.sun`"
+        );
+        let span = diagnostic.span.unwrap();
+        assert_eq!((span.start.column, span.end.column), (4, 10));
+    }
+
+    #[test]
+    fn ambiguous_module_file() {
+        let (_, diagnostics) = parse_virtual(&[
+            ("/virtual/main.sun", "mod shapes;"),
+            ("/virtual/shapes.sun", ""),
+            ("/virtual/shapes/mod.sun", ""),
+        ]);
+        let codes: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect();
+        assert_eq!(codes, [AmbiguousModuleFile::CODE]);
+    }
+
+    #[test]
+    fn module_cycle() {
+        let context = Context::builder()
+            .main("/virtual/app/main.sun")
+            .root("/virtual")
+            .virtual_file("/virtual/app/main.sun", "mod app;")
+            .virtual_file("/virtual/app.sun", "mod main;")
+            .build()
+            .unwrap();
+        let (table, errors) = Parser::new(context.clone()).parse_partial();
+        assert_eq!(errors.len(), 1);
+        assert!(table
+            .declared
+            .contains_key(&AbsolutePath::from_str("main::app").unwrap()));
+
+        let diagnostics = context.error_reporter.diagnostics();
+        let [diagnostic] = diagnostics.as_slice() else {
+            panic!("unexpected diagnostics: {diagnostics:?}");
+        };
+        assert_eq!(diagnostic.code, ModuleFileReused::CODE);
+        assert_eq!(
+            diagnostic.message,
+            "file for module `main` is already loaded as module `main`"
+        );
+    }
+
+    #[test]
+    fn dangling_module() {
+        let (table, diagnostics) = parse_virtual(&[
+            ("/virtual/main.sun", "mod missing; mod present;"),
+            ("/virtual/present.sun", "fn run() {}"),
+        ]);
+        let codes: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect();
+        assert_eq!(codes, [ModuleFileNotFound::CODE]);
+        let path = AbsolutePath::from_str("main::present::run").unwrap();
+        assert!(table.declared.contains_key(&path));
+    }
+
+    #[test]
+    fn module_without_root() {
+        let (table, diagnostics) = parse_virtual(&[
+            (STDIN_PATH, "mod shapes; mod inline { fn run() {} }"),
+            ("shapes.sun", "fn area() {}"),
+        ]);
+        let codes: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect();
+        assert_eq!(codes, [ModuleWithoutRoot::CODE]);
+        let path = AbsolutePath::from_str("main::inline::run").unwrap();
+        assert!(table.declared.contains_key(&path));
+    }
+
+    #[test]
+    fn repeated_module_declaration() {
+        let (table, diagnostics) = parse_virtual(&[
+            ("/virtual/main.sun", "mod shapes; mod shapes;"),
+            ("/virtual/shapes.sun", "fn area() {}"),
+        ]);
+        let codes: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect();
+        assert_eq!(codes, [DuplicateDefinition::CODE]);
+        assert_eq!(table.duplicates().count(), 1);
+    }
+
+    /// Crate of 50 modules: main one declares 10 modules, each of which declares 4 more.
+    ///
+    /// Every fifth module contains a syntax error.
+    fn generated_crate() -> Vec<(String, String)> {
+        let module = |index: usize, children: &[String]| {
+            let mut src: String = children
+                .iter()
+                .map(|name| format!("mod {name};\n"))
+                .collect();
+            src.push_str(&format!(
+                "struct Point{index} {{ x: i32, y: i32 }}\nfn run{index}(a: i32) -> i32 {{ a * {index} }}\n"
+            ));
+            if index % 5 == 4 {
+                src.push_str("fn broken() { let x = ; }\n");
+            }
+            src
+        };
+        let outer: Vec<_> = (0..10).map(|i| format!("outer{i}")).collect();
+        let mut files = vec![(String::from("/virtual/main.sun"), module(0, &outer))];
+        for (i, name) in outer.iter().enumerate() {
+            let inner: Vec<_> = (0..4).map(|j| format!("inner{j}")).collect();
+            files.push((format!("/virtual/{name}.sun"), module(i + 1, &inner)));
+            for (j, child) in inner.iter().enumerate() {
+                let index = 11 + i * 4 + j;
+                files.push((format!("/virtual/{name}/{child}.sun"), module(index, &[])));
+            }
+        }
+        files
+    }
+
+    fn parse_with_jobs(files: &[(String, String)], jobs: usize) -> (ItemTable, Vec<Diagnostic>) {
+        let mut builder = Context::builder().main(&files[0].0).jobs(jobs);
+        for (path, contents) in files {
+            builder = builder.virtual_file(path, contents);
+        }
+        let context = builder.build().unwrap();
+        let (table, _) = Parser::new(context.clone()).parse_partial();
+        (table, context.error_reporter.diagnostics())
+    }
+
+    #[test]
+    fn parallel_parsing() {
+        let files = generated_crate();
+        assert_eq!(files.len(), 51);
+        let (table, diagnostics) = parse_with_jobs(&files, 1);
+        assert_eq!(diagnostics.len(), 10);
+        let path = AbsolutePath::from_str("main::outer9::inner3::run50").unwrap();
+        assert!(table.declared.contains_key(&path));
+        for jobs in [2, 4, 8, 64] {
+            for _ in 0..10 {
+                assert_eq!(
+                    parse_with_jobs(&files, jobs),
+                    (table.clone(), diagnostics.clone())
+                );
+            }
+        }
+    }
+
+    /// Parses `src` and returns codes and locations of reported errors.
+    fn errors(src: &str) -> Vec<(&'static str, usize, usize)> {
+        let parser = FileParser::new_test(src);
+        let reporter = Arc::clone(&parser.context.error_reporter);
+        parser.parse();
+        reporter
+            .diagnostics()
+            .into_iter()
+            .map(|diagnostic| {
+                let span = diagnostic.span.unwrap();
+                (diagnostic.code, span.start.line, span.start.column)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unclosed_brace() {
+        let src = "fn main() {\n    if true {\n        let x: i32 = 1;\n    }\n";
+        assert_eq!(
+            errors(src),
+            [(UnclosedBrace::CODE, 0, 10), (UnexpectedEOF::CODE, 4, 0)]
+        );
+    }
+
+    #[test]
+    fn unclosed_parenthesis() {
+        let src = "fn main() -> i32 {\n    (1 + (2 * 3)\n}\nfn other() {}";
+        assert_eq!(errors(src), [(UnclosedParenthesis::CODE, 1, 4)]);
+    }
+
+    #[test]
+    fn else_without_if() {
+        let src = "\
+fn main() {
+    if x < 0 {
+        x = 0;
+    }
+    print(\"Hello world\");
+    else {
+        x -= 1;
+    }
+    else if x > 0 {}
+    let y: i32 = x;
+}
+fn other() {}";
+        assert_eq!(
+            errors(src),
+            [(ElseWithoutIf::CODE, 5, 4), (ElseWithoutIf::CODE, 8, 4)]
+        );
+    }
+
+    #[test]
+    fn missing_semicolons() {
+        let src = "\
+fn main() {
+    let x: i32 = 1
+    let y: i32 = x
+    print(y);
+}";
+        assert_eq!(
+            errors(src),
+            [(TokenMismatch::CODE, 1, 18), (TokenMismatch::CODE, 2, 18)]
+        );
+
+        let parsed = FileParser::new_test(src).parse();
+        assert_eq!(parsed.errors.len(), 2);
+        let path = AbsolutePath::from_str("crate::main").unwrap();
+        let Some(ItemKind::Function(function)) =
+            parsed.item_table.get(&path).map(|item| &item.kind)
+        else {
+            panic!("function should be parsed");
+        };
+        assert_eq!(function.body.statements.len(), 3);
+    }
+
+    #[test]
+    fn recovery_inside_braces() {
+        let src = "struct Point { x: i32 y: i32 }\nfn main() {}";
+        assert_eq!(errors(src), [(TokenMismatch::CODE, 0, 22)]);
+    }
+}
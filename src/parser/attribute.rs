@@ -0,0 +1,367 @@
+use crate::{
+    ast::item::{Attribute, ItemKind},
+    error::{
+        library::{
+            lint,
+            parser::{
+                InvalidAttributeArguments, MisplacedAttribute, UnknownAttribute, UnknownLint,
+            },
+        },
+        CompilerError, LintLevel, ReportProvider,
+    },
+    input_stream::Location,
+    Identifier,
+};
+
+use super::FileParser;
+
+/// Arguments that an attribute accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arguments {
+    /// `#[NAME]`
+    None,
+    /// `#[NAME(ARGUMENT, ...)]`
+    Names,
+}
+
+impl Arguments {
+    fn accepts(&self, arguments: &Option<Vec<Identifier>>) -> bool {
+        match self {
+            Arguments::None => arguments.is_none(),
+            Arguments::Names => arguments.is_some(),
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Arguments::None => "no arguments",
+            Arguments::Names => "a list of names in parentheses",
+        }
+    }
+}
+
+/// Description of an attribute known to the compiler.
+struct AttributeInfo {
+    name: &'static str,
+    arguments: Arguments,
+    /// Items the attribute may be applied to, `None` if it may be applied to any item.
+    items: Option<Items>,
+}
+
+/// Kind of items along with its description used in diagnostics.
+type Items = (&'static str, fn(&ItemKind) -> bool);
+
+const STRUCTS: Items = ("structs", |kind| matches!(kind, ItemKind::Struct(_)));
+const FUNCTIONS: Items = ("functions", |kind| matches!(kind, ItemKind::Function(_)));
+
+/// Registry of known attributes.
+static ATTRIBUTES: [AttributeInfo; 6] = [
+    AttributeInfo {
+        name: "derive",
+        arguments: Arguments::Names,
+        items: Some(STRUCTS),
+    },
+    AttributeInfo {
+        name: "inline",
+        arguments: Arguments::None,
+        items: Some(FUNCTIONS),
+    },
+    AttributeInfo {
+        name: "test",
+        arguments: Arguments::None,
+        items: Some(FUNCTIONS),
+    },
+    AttributeInfo {
+        name: "allow",
+        arguments: Arguments::Names,
+        items: None,
+    },
+    AttributeInfo {
+        name: "warn",
+        arguments: Arguments::Names,
+        items: None,
+    },
+    AttributeInfo {
+        name: "deny",
+        arguments: Arguments::Names,
+        items: None,
+    },
+];
+
+impl AttributeInfo {
+    fn find(attribute: &Attribute) -> Option<&'static AttributeInfo> {
+        let name = attribute.name()?;
+        ATTRIBUTES.iter().find(|info| info.name == name.as_str())
+    }
+}
+
+/// Level set by the lint level attribute with `name`.
+fn level_of(name: &Identifier) -> Option<LintLevel> {
+    match name.as_str() {
+        "allow" => Some(LintLevel::Allow),
+        "warn" => Some(LintLevel::Warn),
+        "deny" => Some(LintLevel::Deny),
+        _ => None,
+    }
+}
+
+/// Lint levels set by `#[allow(..)]`, `#[warn(..)]` and `#[deny(..)]` attributes, by codes of
+/// the lints.
+///
+/// Later attributes go last, so they take precedence. Unknown lints are skipped.
+pub(super) fn lint_levels(attributes: &[Attribute]) -> Vec<(&'static str, LintLevel)> {
+    let mut levels = Vec::new();
+    for attribute in attributes {
+        let Some(level) = attribute.name().and_then(level_of) else {
+            continue;
+        };
+        for name in attribute.arguments.iter().flatten() {
+            if let Some(info) = lint(name.as_str()) {
+                levels.push((info.code, level));
+            }
+        }
+    }
+    levels
+}
+
+/// Parsing of [attributes](Attribute).
+impl FileParser {
+    /// Parse attributes, then the item that follows them with `parse`.
+    ///
+    /// `parse` receives the start of the first attribute. Lint levels set by the attributes
+    /// apply to diagnostics reported while the item is parsed and during later stages.
+    pub(super) fn with_attributes<R>(
+        &mut self,
+        parse: impl FnOnce(&mut Self, Location, Vec<Attribute>) -> Result<R, CompilerError>,
+    ) -> Result<R, CompilerError> {
+        let start = self.lexer.token_start();
+        let attributes = self.parse_attributes()?;
+        let levels = lint_levels(&attributes);
+        let reporter = self.context.error_reporter.clone();
+        let scope =
+            (!levels.is_empty()).then(|| reporter.open_lint_scope(self.source(), start, levels));
+        let result = parse(self, start, attributes);
+        if let Some(scope) = scope {
+            reporter.close_lint_scope(scope, self.lexer.token_end());
+        }
+        result
+    }
+
+    /// Parse attributes `#[PATH]` and `#[PATH(ARGUMENT, ...)]`.
+    ///
+    /// Names and arguments of the attributes are checked against the registry of known ones.
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, CompilerError> {
+        let mut attributes = Vec::new();
+        loop {
+            let start = self.lexer.token_start();
+            if !self.lexer.consume_punctuation("#")? {
+                return Ok(attributes);
+            }
+            self.lexer.expect_punctuation("[")?;
+            let path = self.parse_path()?;
+            let arguments = if self.lexer.consume_punctuation("(")? {
+                let mut arguments = Vec::new();
+                while !self.lexer.consume_punctuation(")")? {
+                    arguments.push(self.lexer.expect_identifier()?);
+                    if !self.lexer.consume_punctuation(",")? {
+                        self.lexer.expect_punctuation(")")?;
+                        break;
+                    }
+                }
+                Some(arguments)
+            } else {
+                None
+            };
+            self.lexer.expect_punctuation("]")?;
+            let attribute = Attribute {
+                path,
+                arguments,
+                span: self.span_from(start),
+            };
+            self.check_attribute(&attribute);
+            attributes.push(attribute);
+        }
+    }
+
+    /// Reports unknown attributes and lints, as well as arguments of the wrong shape.
+    fn check_attribute(&mut self, attribute: &Attribute) {
+        let reporter = &self.context.error_reporter;
+        let Some(info) = AttributeInfo::find(attribute) else {
+            let _ = UnknownAttribute::report_at(reporter, attribute.span, attribute.path.clone());
+            return;
+        };
+        let name = Identifier(String::from(info.name));
+        if !info.arguments.accepts(&attribute.arguments) {
+            let description = info.arguments.description();
+            self.errors.extend(
+                InvalidAttributeArguments::report_at(reporter, attribute.span, name, description)
+                    .err(),
+            );
+        } else if level_of(&name).is_some() {
+            for lint_name in attribute.arguments.iter().flatten() {
+                if lint(lint_name.as_str()).is_none() {
+                    let _ = UnknownLint::report_at(reporter, attribute.span, lint_name.clone());
+                }
+            }
+        }
+    }
+
+    /// Reports attributes that can't be applied to the item of `kind`.
+    pub(super) fn check_attribute_targets(&mut self, attributes: &[Attribute], kind: &ItemKind) {
+        for attribute in attributes {
+            let Some(info) = AttributeInfo::find(attribute) else {
+                continue;
+            };
+            let Some((items, applies_to)) = info.items else {
+                continue;
+            };
+            if !applies_to(kind) {
+                let name = Identifier(String::from(info.name));
+                let reporter = &self.context.error_reporter;
+                self.errors.extend(
+                    MisplacedAttribute::report_at(reporter, attribute.span, name, items).err(),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{
+        compile_str,
+        context::{Context, Emit},
+        error::{
+            library::{
+                hir::UnreachableCode,
+                parser::{
+                    InvalidAttributeArguments, MisplacedAttribute, UnknownAttribute, UnknownLint,
+                },
+            },
+            LintLevel, Severity,
+        },
+        parser::FileParser,
+        path::AbsolutePath,
+        Identifier,
+    };
+
+    use super::lint_levels;
+
+    #[test]
+    fn parsing() {
+        let src = "
+            #[derive(to_string)] #[derive(a, b,)]
+            pub struct Point { x: i32 }
+            fn run() {
+                #[derive()] struct Local {}
+            }
+            impl Point {
+                #[inline] #[allow(E0202, shadowed_variable)]
+                fn len(self) -> i32 { 0 }
+            }
+        ";
+        let parsed = FileParser::new_test(src).parse();
+        assert!(parsed.errors.is_empty());
+        let item = |path: &str| {
+            let path = AbsolutePath::from_str(path).unwrap();
+            parsed.item_table.get(&path).unwrap()
+        };
+        let derives: Vec<_> = item("crate::Point")
+            .derives()
+            .map(Identifier::as_str)
+            .collect();
+        assert_eq!(derives, ["to_string", "a", "b"]);
+        assert_eq!(item("crate::run::Local").derives().count(), 0);
+
+        let method = item("crate::Point::len");
+        let attributes: Vec<_> = method.attributes.iter().map(|a| a.to_string()).collect();
+        assert_eq!(attributes, ["inline", "allow(E0202, shadowed_variable)"]);
+        assert_eq!(
+            lint_levels(&method.attributes),
+            [("E0202", LintLevel::Allow), ("E0203", LintLevel::Allow)]
+        );
+    }
+
+    #[test]
+    fn registry() {
+        let cases = [
+            ("#[inline] #[test] fn run() {}", None),
+            (
+                "#[inline(always)] fn run() {}",
+                Some(InvalidAttributeArguments::CODE),
+            ),
+            (
+                "#[allow] fn run() {}",
+                Some(InvalidAttributeArguments::CODE),
+            ),
+            (
+                "#[derive(to_string)] fn run() {}",
+                Some(MisplacedAttribute::CODE),
+            ),
+            ("#[test] struct Point {}", Some(MisplacedAttribute::CODE)),
+            ("#[unknown] fn run() {}", Some(UnknownAttribute::CODE)),
+            ("#[tool::skip] fn run() {}", Some(UnknownAttribute::CODE)),
+            (
+                "#[allow(unused_variable)] fn run() {}",
+                Some(UnknownLint::CODE),
+            ),
+        ];
+        for (src, code) in cases {
+            let parser = FileParser::new_test(src);
+            let context = parser.context.clone();
+            let parsed = parser.parse();
+            let diagnostics = context.error_reporter.diagnostics();
+            let codes: Vec<_> = diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.code)
+                .collect();
+            assert_eq!(codes, Vec::from_iter(code), "source: {src}");
+            let warned = diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.severity == Severity::Warn);
+            assert_eq!(parsed.errors.is_empty(), warned, "source: {src}");
+        }
+    }
+
+    #[test]
+    fn lint_level_override() {
+        let src = "
+            pub fn loud() -> i32 { return 1; 2 }
+            #[allow(unreachable_code)]
+            pub fn quiet() -> i32 { return 1; 2 }
+            #[deny(E0202)]
+            mod strict {
+                pub fn loud() -> i32 { return 1; 2 }
+                #[warn(unreachable_code)]
+                pub fn lenient() -> i32 { return 1; 2 }
+                #[allow(E0202)] #[deny(E0202)]
+                pub fn last() -> i32 { return 1; 2 }
+            }
+        ";
+        let result = compile_str(src, Context::builder().emit(Emit::Hir));
+        let unreachable: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.code == UnreachableCode::CODE)
+            .map(|diagnostic| (diagnostic.span.unwrap().start.line, diagnostic.severity))
+            .collect();
+        assert_eq!(
+            unreachable,
+            [
+                (1, Severity::Warn),
+                (6, Severity::Deny),
+                (8, Severity::Warn),
+                (10, Severity::Deny),
+            ]
+        );
+
+        // Levels apply to diagnostics that are reported while the item is parsed.
+        let block = "fn run() { if a { 1 } else { 2 } - 3 }";
+        let parser = FileParser::new_test(&format!("#[allow(E0023)] {block} {block}"));
+        let context = parser.context.clone();
+        parser.parse();
+        assert_eq!(context.error_reporter.warnings().len(), 1);
+    }
+}
@@ -0,0 +1,203 @@
+//! On-disk cache of parsed files.
+//!
+//! Items of every file that was parsed without diagnostics are stored as JSON in the
+//! [cache directory](crate::context::ContextBuilder::cache_dir). Entries are named after the hash
+//! of everything that affects the result of parsing: the source code, the module the file is
+//! parsed as and the relevant settings. Spans refer to their file by [SourceId], which may differ
+//! between runs, so they are updated on load.
+//!
+//! Cache is best-effort: entries that can't be read or written are ignored and the file is
+//! parsed as usual.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{
+    context::Context, item_table::ItemTable, path::AbsolutePath, source::SourceId, util::Span,
+};
+
+use super::{attribute::lint_levels, ParsedFile, PendingFile};
+
+/// Items of a parsed file.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    item_table: ItemTable,
+    /// Modules declared as `mod NAME;`, with spans of their names.
+    modules: Vec<(AbsolutePath, Span)>,
+    tokens: usize,
+}
+
+/// Loads items of the file with `id` and content `src`, parsed as the module at `scope`.
+///
+/// `None` is returned if caching is disabled or there is no valid entry for the file.
+pub(super) fn load(
+    context: &Context,
+    scope: &AbsolutePath,
+    id: SourceId,
+    src: &str,
+) -> Option<ParsedFile> {
+    let path = entry_path(context, scope, src)?;
+    let mut value: Value = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+    relocate(&mut value, id);
+    let entry: Entry = serde_json::from_value(value).ok()?;
+    // Lint levels of items are otherwise applied while the file is parsed.
+    let reporter = &context.error_reporter;
+    for (_, item) in entry.item_table.iter() {
+        let levels = lint_levels(&item.attributes);
+        if !levels.is_empty() {
+            let scope = reporter.open_lint_scope(Some(id), item.span.start, levels);
+            reporter.close_lint_scope(scope, item.span.end);
+        }
+    }
+    Some(ParsedFile {
+        item_table: entry.item_table,
+        pending: entry
+            .modules
+            .into_iter()
+            .map(|(path, span)| PendingFile::General(path, span))
+            .collect(),
+        errors: Vec::new(),
+        tokens: entry.tokens,
+    })
+}
+
+/// Stores items of the file with content `src`, parsed as the module at `scope`.
+pub(super) fn store(context: &Context, scope: &AbsolutePath, src: &str, parsed: &ParsedFile) {
+    let Some(path) = entry_path(context, scope, src) else {
+        return;
+    };
+    let entry = Entry {
+        item_table: parsed.item_table.clone(),
+        modules: parsed
+            .pending
+            .iter()
+            .filter_map(|file| match file {
+                PendingFile::General(path, span) => Some((path.clone(), *span)),
+                PendingFile::Specific { .. } => None,
+            })
+            .collect(),
+        tokens: parsed.tokens,
+    };
+    let Ok(json) = serde_json::to_vec(&entry) else {
+        return;
+    };
+    // Entry is renamed into place, so it's never read half-written.
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    let written = fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))
+        .and_then(|_| fs::write(&temporary, json))
+        .and_then(|_| fs::rename(&temporary, &path));
+    if written.is_err() {
+        let _ = fs::remove_file(temporary);
+    }
+}
+
+/// Path to the entry of the file, `None` if caching is disabled.
+fn entry_path(context: &Context, scope: &AbsolutePath, src: &str) -> Option<PathBuf> {
+    let metadata = &context.metadata;
+    let dir = metadata.cache_dir.as_ref()?;
+    let mut lint_levels: Vec<_> = metadata.lint_levels.iter().collect();
+    lint_levels.sort_by_key(|(code, _)| *code);
+
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    scope.to_string().hash(&mut hasher);
+    metadata.max_nesting.hash(&mut hasher);
+    metadata.max_expression_tokens.hash(&mut hasher);
+    lint_levels.hash(&mut hasher);
+    src.hash(&mut hasher);
+    Some(dir.join(format!("{:016x}.json", hasher.finish())))
+}
+
+/// Makes every span of the serialized entry refer to the file with `id`.
+fn relocate(value: &mut Value, id: SourceId) {
+    match value {
+        Value::Object(object) => {
+            let is_span = object.contains_key("start") && object.contains_key("end");
+            match object.get_mut("source") {
+                Some(source) if is_span && !source.is_null() => {
+                    *source = serde_json::to_value(id).expect("id should be serializable");
+                }
+                _ => object.values_mut().for_each(|value| relocate(value, id)),
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(|value| relocate(value, id)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path, str::FromStr};
+
+    use crate::{
+        compile,
+        context::{Context, Emit},
+        path::AbsolutePath,
+        CompileResult,
+    };
+
+    fn compile_in(root: &Path, cache: Option<&Path>) -> CompileResult {
+        let options = Context::builder()
+            .emit(Emit::Hir)
+            .cache_dir(cache.map(Path::to_path_buf));
+        let result = compile(root.join("main.sun"), options).unwrap();
+        assert!(!result.failed());
+        result
+    }
+
+    fn entries(cache: &Path) -> Vec<String> {
+        let mut entries: Vec<_> = fs::read_dir(cache)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn invalidation() {
+        let root = std::env::temp_dir().join(format!("cache-{}", std::process::id()));
+        let cache = root.join("cache");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("main.sun"), "mod shapes; fn main() -> i32 { 1 }").unwrap();
+        fs::write(root.join("shapes.sun"), "fn area() -> i32 { 2 }").unwrap();
+
+        let uncached = compile_in(&root, None);
+        assert!(!cache.exists());
+        let first = compile_in(&root, Some(&cache));
+        assert_eq!(first.item_table, uncached.item_table);
+        assert_eq!(entries(&cache).len(), 2);
+
+        // Unchanged file is loaded from the cache, as proven by a tampered entry.
+        let shapes = entries(&cache)
+            .into_iter()
+            .map(|name| cache.join(name))
+            .find(|path| fs::read_to_string(path).unwrap().contains("area"))
+            .unwrap();
+        let json = fs::read_to_string(&shapes).unwrap();
+        fs::write(&shapes, json.replace("area", "side")).unwrap();
+        let tampered = compile_in(&root, Some(&cache));
+        let side = AbsolutePath::from_str("main::shapes::side").unwrap();
+        assert!(tampered.item_table.get(&side).is_some());
+
+        // Corrupted entry falls back to parsing.
+        fs::write(&shapes, "{ not json").unwrap();
+        let corrupted = compile_in(&root, Some(&cache));
+        assert_eq!(corrupted.item_table, uncached.item_table);
+
+        // Changed file gets a new entry, while the other one is still used.
+        fs::write(root.join("shapes.sun"), "fn area() -> i32 { 3 }").unwrap();
+        let changed = compile_in(&root, Some(&cache));
+        assert_ne!(changed.item_table, uncached.item_table);
+        assert_eq!(entries(&cache).len(), 3);
+        assert_eq!(compile_in(&root, None).item_table, changed.item_table);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
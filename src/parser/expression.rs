@@ -1,20 +1,35 @@
+use std::convert::Infallible;
+
 use crate::{
     ast::{
-        expression::{Block, Expression, Literal},
-        statement::Statement,
+        expression::{Block, ExprId, Expression, ExpressionKind, Literal},
+        statement::{Statement, StatementKind},
     },
     error::{
         library::{
             lexer::{TokenMismatch, UnexpectedEOF},
             parser::{
-                AssignmentInExpressionPosition, InvalidCrateKw, InvalidPunctuation, InvalidSuperKw,
-                KeywordNotAllowedInOperatorExpression,
+                AssignmentInExpressionPosition, ElseWithoutIf, ExpectedExpression,
+                InvalidPunctuation, ItemInExpression, KeywordNotAllowedInOperatorExpression,
+                OperatorAfterBlock, UnclosedParenthesis,
             },
         },
-        CompilerError, ExpectedToken, ReportProvider,
+        Applicability, CompilerError, ExpectedToken, Label, ReportProvider, Suggestion,
+    },
+    input_stream::Location,
+    lexer::{
+        keyword::Keyword,
+        operator::{AssignOp, BinaryOp},
+        punctuation::Punctuation,
+        Token,
+    },
+    parser::{
+        operator_expression::{
+            infix::InfixNotation,
+            postfix::{ConversionError, PostfixNotation},
+        },
+        FileParser,
     },
-    lexer::{keyword::Keyword, punctuation::Punctuation, Token},
-    parser::{operator_expression::postfix::PostfixNotation, FileParser},
     path::{RelativePath, RelativePathStart},
     Identifier,
 };
@@ -26,93 +41,91 @@ use super::operator_expression::Tree;
 /// [Expression]: crate::ast::expression::Expression
 impl FileParser {
     /// Parse expression.
-    pub fn parse_expr(&mut self) -> Result<Expression, CompilerError> {
+    #[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip_all))]
+    pub fn parse_expr(&mut self) -> Result<ExprId, CompilerError> {
         let start = self.location();
         let infix = self.parse_infix()?;
-        let postfix = PostfixNotation::from_infix(infix);
-        match postfix.into_expression() {
-            Ok(tree) => Ok(tree),
-            Err(_) => AssignmentInExpressionPosition::report(self, start).map(|_| unreachable!()),
+        PostfixNotation::from_infix(infix)
+            .and_then(|postfix| postfix.into_expression(self.item_table.exprs_mut()))
+            .or_else(|error| self.report_conversion(error, start).map(|_| unreachable!()))
+    }
+
+    /// Reports that expression that starts at `start` couldn't be converted to a tree.
+    fn report_conversion(
+        &self,
+        error: ConversionError,
+        start: Location,
+    ) -> Result<Infallible, CompilerError> {
+        match error {
+            ConversionError::UnbalancedParenthesis => UnclosedParenthesis::report(self, start),
+            ConversionError::MissingOperand | ConversionError::MissingOperator => {
+                ExpectedExpression::report(self, start)
+            }
+            ConversionError::Assignment => AssignmentInExpressionPosition::report(self, start),
         }
     }
 
+    /// Parse condition of `if` or `while` expression.
+    ///
+    /// Assignment is reported with a suggestion to compare values instead, as `=` is a common
+    /// typo of `==` there.
+    fn parse_condition(&mut self) -> Result<ExprId, CompilerError> {
+        let start = self.lexer.token_start();
+        let (output, assignment) = self.parse_infix_entries()?;
+        let Some((_, operator, operator_span)) = assignment else {
+            return PostfixNotation::from_infix(InfixNotation::Expression(output))
+                .and_then(|postfix| postfix.into_expression(self.item_table.exprs_mut()))
+                .or_else(|error| self.report_conversion(error, start).map(|_| unreachable!()));
+        };
+        let reporter = &self.context.error_reporter;
+        let span = self.span_from(start);
+        match operator {
+            AssignOp::Assign => {
+                let suggestion = Suggestion::new(
+                    "use `==` to compare values",
+                    operator_span,
+                    "==",
+                    Applicability::MaybeIncorrect,
+                );
+                AssignmentInExpressionPosition::report_with(reporter, span, suggestion)
+            }
+            _ => AssignmentInExpressionPosition::report_at(reporter, span),
+        }
+        .map(|_| unreachable!())
+    }
+
     /// Parse a single operand.
     pub(super) fn parse_operand(&mut self) -> Result<Expression, CompilerError> {
         use {Keyword::*, Punctuation::*};
 
-        let start = self.location();
-        let token = match self.lexer.next()? {
-            Token::Punc(LBrace) => Expression::Block(self.parse_block()?),
+        let start = self.lexer.token_start();
+        let kind = match self.lexer.next()? {
+            Token::Punc(LBrace) => ExpressionKind::Block(self.parse_block()?),
 
-            Token::Num(num) => Expression::Literal(Literal::Number(num)),
-            Token::Str(str) => Expression::Literal(Literal::String(str)),
+            Token::Num(num) => ExpressionKind::Literal(Literal::Number(num)),
+            Token::Str(str) => ExpressionKind::Literal(Literal::String(str)),
 
             Token::Kw(If) => self.parse_if()?,
             Token::Kw(While) => self.parse_while()?,
             Token::Kw(For) => self.parse_for()?,
-            Token::Kw(True) => Expression::Literal(Literal::Boolean(true)),
-            Token::Kw(False) => Expression::Literal(Literal::Boolean(false)),
+            Token::Kw(SelfValue) => ExpressionKind::Var(Identifier(String::from("self"))),
+            Token::Kw(True) => ExpressionKind::Literal(Literal::Boolean(true)),
+            Token::Kw(False) => ExpressionKind::Literal(Literal::Boolean(false)),
 
             Token::Ident(ident) => {
-                let path_start = match ident.as_str() {
-                    "super" => RelativePathStart::Super(1),
-                    "crate" => RelativePathStart::Crate,
-                    _ => RelativePathStart::Identifier(Identifier(ident)),
-                };
-                let mut path = RelativePath::new(path_start);
-                while self.lexer.consume_punctuation("::")? {
-                    let ident = self.lexer.expect_identifier()?;
-                    match ident.0.as_str() {
-                        "super" if !path.other.is_empty() => {
-                            return InvalidSuperKw::report(self, start).map(|_| unreachable!());
-                        }
-                        "super" if matches!(path.start, RelativePathStart::Super(_)) => {
-                            let RelativePathStart::Super(ref mut n) = path.start else { unreachable!(); };
-                            *n += 1;
-                        }
-                        "crate" => {
-                            return InvalidCrateKw::report(self, start).map(|_| unreachable!());
-                        }
-                        _ => path.push(ident),
-                    };
-                }
+                let path = self.parse_path_from(start, Identifier(ident))?;
 
                 if self.lexer.consume_punctuation("(")? {
-                    let mut params = Vec::new();
-                    if self.lexer.consume_punctuation(")")? {
-                        return Ok(Expression::FnCall { path, params });
+                    let params = self.parse_args(start)?;
+                    ExpressionKind::FnCall { path, params }
+                } else {
+                    match path {
+                        RelativePath {
+                            start: RelativePathStart::Identifier(ident),
+                            other,
+                        } if other.is_empty() => ExpressionKind::Var(ident),
+                        path => ExpressionKind::Path(path),
                     }
-
-                    return loop {
-                        let start = self.location();
-                        params.push(self.parse_expr()?);
-
-                        if self.lexer.consume_punctuation(")")? {
-                            break Ok(Expression::FnCall { path, params });
-                        }
-
-                        if !self.lexer.consume_punctuation(",")? {
-                            let token = self.lexer.peek()?;
-                            break TokenMismatch::report(
-                                self,
-                                start,
-                                vec![
-                                    ExpectedToken::Punctuation(Punctuation::Comma),
-                                    ExpectedToken::Punctuation(Punctuation::RParent),
-                                ],
-                                token,
-                            )
-                            .map(|_| unreachable!());
-                        }
-                    };
-                }
-
-                match path {
-                    RelativePath {
-                        start: RelativePathStart::Identifier(ident),
-                        other,
-                    } if other.is_empty() => Expression::Var(ident),
-                    _ => todo!(),
                 }
             }
 
@@ -120,6 +133,19 @@ impl FileParser {
                 return UnexpectedEOF::report(self, start).map(|_| unreachable!());
             }
 
+            Token::Kw(kw @ (Fn | Struct | Mod)) => {
+                let item = match kw {
+                    Fn => "function",
+                    Struct => "struct",
+                    _ => "module",
+                };
+                let span = self.span_from(start);
+                let labels = Vec::from_iter(self.value_site.clone());
+                let reporter = &self.context.error_reporter;
+                return ItemInExpression::report_labeled(reporter, span, labels, item)
+                    .map(|_| unreachable!());
+            }
+
             Token::Kw(kw) => {
                 return KeywordNotAllowedInOperatorExpression::report(self, start, kw)
                     .map(|_| unreachable!());
@@ -129,76 +155,255 @@ impl FileParser {
                 return InvalidPunctuation::report(self, start, punc).map(|_| unreachable!());
             }
         };
-        Ok(token)
+        let operand = Expression::new(kind, self.span_from(start));
+        if operand.is_block_expression() {
+            return Ok(operand);
+        }
+        self.parse_projections(operand, start)
+    }
+
+    /// Parse arguments of the call that starts at `call_start`. Opening parenthesis is expected
+    /// to be consumed beforehand.
+    fn parse_args(&mut self, call_start: Location) -> Result<Vec<ExprId>, CompilerError> {
+        let mut params = Vec::new();
+        if self.lexer.consume_punctuation(")")? {
+            return Ok(params);
+        }
+        let site = Label::new(
+            self.span_from(call_start),
+            "value is expected as an argument of this call",
+        );
+        loop {
+            let start = self.location();
+            params.push(self.expecting_value(site.clone(), Self::parse_expr)?);
+
+            if self.lexer.consume_punctuation(")")? {
+                return Ok(params);
+            }
+
+            if !self.lexer.consume_punctuation(",")? {
+                let token = self.lexer.peek()?;
+                return TokenMismatch::report(
+                    self,
+                    start,
+                    vec![
+                        ExpectedToken::Punctuation(Punctuation::Comma),
+                        ExpectedToken::Punctuation(Punctuation::RParent),
+                    ],
+                    token,
+                )
+                .map(|_| unreachable!());
+            }
+        }
+    }
+
+    /// Parse field accesses, method calls and element accesses that follow `operand` starting at `start`.
+    ///
+    /// Every access is [nested](FileParser::nest), as it adds a level to the expression tree.
+    fn parse_projections(
+        &mut self,
+        mut operand: Expression,
+        start: Location,
+    ) -> Result<Expression, CompilerError> {
+        let mut levels = 0;
+        loop {
+            let kind = if self.lexer.consume_punctuation(".")? {
+                let field = self.lexer.expect_identifier()?;
+                let value = self.item_table.exprs_mut().alloc(operand);
+                if self.lexer.consume_punctuation("(")? {
+                    let params = self.parse_args(start)?;
+                    ExpressionKind::MethodCall {
+                        value,
+                        method: field,
+                        params,
+                    }
+                } else {
+                    ExpressionKind::FieldAccess { value, field }
+                }
+            } else if self.lexer.consume_punctuation("[")? {
+                let index = self.parse_expr()?;
+                self.lexer.expect_punctuation("]")?;
+                let value = self.item_table.exprs_mut().alloc(operand);
+                ExpressionKind::Index { value, index }
+            } else {
+                break;
+            };
+            self.nest()?;
+            levels += 1;
+            operand = Expression::new(kind, self.span_from(start));
+        }
+        self.unnest(levels);
+        Ok(operand)
     }
 
     /// Parse block. Opening brace is expected to be consumed beforehand.
+    ///
+    /// Parsing is resumed after erroneous statements, see [recover](FileParser::recover).
+    #[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip_all))]
     pub fn parse_block(&mut self) -> Result<Block, CompilerError> {
+        // Statements of the block don't provide values to the code that encloses it.
+        let site = self.value_site.take();
+        let block = self.parse_block_entries();
+        self.value_site = site;
+        block
+    }
+
+    fn parse_block_entries(&mut self) -> Result<Block, CompilerError> {
+        self.open_brace();
         let mut buffer = Vec::new();
         let expr = loop {
             if self.lexer.consume_punctuation("}")? {
                 break None;
             }
-
-            if self.lexer.consume_keyword(Keyword::Fn)?
-                || self.lexer.consume_keyword(Keyword::Struct)?
-            {
-                self.parse_item()?;
-                continue;
+            let start = self.lexer.token_start();
+            let depth = self.brace_depth();
+            match self.parse_block_entry(start, &mut buffer) {
+                Ok(Some(expr)) => break Some(expr),
+                Ok(None) => {}
+                Err(error) => self.recover(error, start, depth)?,
             }
+        };
+        self.close_brace();
+        Ok(Block {
+            statements: buffer,
+            expression: expr,
+        })
+    }
 
-            if self.lexer.consume_keyword(Keyword::Return)? {
-                buffer.push(Statement::Return(self.parse_expr()?));
-                self.lexer.expect_punctuation(";")?;
-                continue;
-            }
+    /// Parse a single entry of the block that starts at `start`.
+    ///
+    /// Statements are pushed to `buffer`. Trailing expression is returned along with consumed
+    /// closing brace.
+    ///
+    /// Block-like expression at the start of the entry ends it, see
+    /// [parse_block_like_statement](FileParser::parse_block_like_statement).
+    fn parse_block_entry(
+        &mut self,
+        start: Location,
+        buffer: &mut Vec<Statement>,
+    ) -> Result<Option<ExprId>, CompilerError> {
+        if matches!(
+            self.lexer.peek()?,
+            Token::Kw(Keyword::Fn | Keyword::Struct) | Token::Punc(Punctuation::Pound)
+        ) {
+            self.nested(|parser| parser.parse_item())?;
+            return Ok(None);
+        }
 
-            if self.lexer.consume_keyword(Keyword::Let)? {
-                buffer.push(Statement::LetStmt(self.parse_let()?));
-                continue;
+        if self.lexer.consume_keyword(Keyword::Else)? {
+            // Body of the stray `else` is parsed and discarded, so the following statements are
+            // parsed as usual.
+            self.errors.extend(ElseWithoutIf::report(self, start).err());
+            if self.lexer.consume_keyword(Keyword::If)? {
+                self.parse_if()?;
+            } else {
+                self.lexer.expect_punctuation("{")?;
+                self.parse_block()?;
             }
+            return Ok(None);
+        }
 
-            if self.lexer.consume_keyword(Keyword::Break)? {
-                self.lexer.expect_punctuation(";")?;
-                buffer.push(Statement::Break);
-                continue;
-            }
+        if self.lexer.consume_keyword(Keyword::Return)? {
+            let expr = self.parse_expr()?;
+            self.expect_semicolon()?;
+            buffer.push(Statement::new(
+                StatementKind::Return(expr),
+                self.span_from(start),
+            ));
+            return Ok(None);
+        }
 
-            let infix = self.parse_infix()?;
-            let postfix = PostfixNotation::from_infix(infix);
-            let tree = postfix.into_tree();
-            match tree {
-                Tree::Assignment {
-                    assignee,
-                    operator,
-                    expression,
-                } => buffer.push(Statement::Assignment {
+        if self.lexer.consume_keyword(Keyword::Let)? {
+            let statement = StatementKind::LetStmt(self.parse_let()?);
+            buffer.push(Statement::new(statement, self.span_from(start)));
+            return Ok(None);
+        }
+
+        if self.lexer.consume_keyword(Keyword::Break)? {
+            self.expect_semicolon()?;
+            buffer.push(Statement::new(StatementKind::Break, self.span_from(start)));
+            return Ok(None);
+        }
+
+        if self.starts_block_like() {
+            return self.parse_block_like_statement(start, buffer);
+        }
+
+        let infix = self.parse_infix()?;
+        let tree = PostfixNotation::from_infix(infix)
+            .and_then(|postfix| postfix.into_tree(self.item_table.exprs_mut()))
+            .or_else(|error| self.report_conversion(error, start).map(|_| unreachable!()))?;
+        match tree {
+            Tree::Assignment {
+                assignee,
+                operator,
+                expression,
+            } => buffer.push(Statement::new(
+                StatementKind::Assignment {
                     assignee,
                     operator,
                     expression,
-                }),
-                Tree::Expression(expr) => {
-                    if self.lexer.consume_punctuation("}")? {
-                        break Some(expr);
-                    }
-                    if expr.is_block_expression() {
-                        self.lexer.consume_punctuation(";")?;
-                    } else {
-                        self.lexer.expect_punctuation(";")?;
-                    }
-                    buffer.push(Statement::ExprStmt(expr));
+                },
+                self.span_from(start),
+            )),
+            Tree::Expression(expr) => {
+                if self.lexer.consume_punctuation("}")? {
+                    return Ok(Some(expr));
                 }
+                if self.item_table.expr(expr).is_block_expression() {
+                    self.lexer.consume_punctuation(";")?;
+                } else {
+                    self.expect_semicolon()?;
+                }
+                buffer.push(Statement::new(
+                    StatementKind::ExprStmt(expr),
+                    self.span_from(start),
+                ));
             }
-        };
-        Ok(Block {
-            statements: buffer,
-            expression: expr.map(Box::new),
-        })
+        }
+        Ok(None)
+    }
+
+    /// Checks if next token starts block, `if`, `while` or `for` expression.
+    fn starts_block_like(&mut self) -> bool {
+        matches!(
+            self.lexer.peek(),
+            Ok(Token::Punc(Punctuation::LBrace)
+                | Token::Kw(Keyword::If | Keyword::While | Keyword::For))
+        )
+    }
+
+    /// Parse block-like expression in statement position that starts at `start`.
+    ///
+    /// Like in Rust, such expression always ends the statement, and `;` after it is optional.
+    /// Binary operator that follows it starts the next statement instead of using the expression
+    /// as its operand, which is reported as a warning. Parenthesized expression is an operand.
+    fn parse_block_like_statement(
+        &mut self,
+        start: Location,
+        buffer: &mut Vec<Statement>,
+    ) -> Result<Option<ExprId>, CompilerError> {
+        let operand = self.nested(|parser| parser.parse_operand())?;
+        let expr = self.item_table.exprs_mut().alloc(operand);
+        if self.lexer.consume_punctuation("}")? {
+            return Ok(Some(expr));
+        }
+        let span = self.span_from(start);
+        if !self.lexer.consume_punctuation(";")? {
+            if let Ok(Token::Punc(punc)) = self.lexer.peek() {
+                if let Ok(operator) = BinaryOp::try_from(punc) {
+                    let reporter = &self.context.error_reporter;
+                    let _ = OperatorAfterBlock::report_at(reporter, span, operator);
+                }
+            }
+        }
+        buffer.push(Statement::new(StatementKind::ExprStmt(expr), span));
+        Ok(None)
     }
 
     /// Parse if conditional. Keyword [if](Keyword::If) is expected to be consumed beforehand.
-    pub fn parse_if(&mut self) -> Result<Expression, CompilerError> {
-        let condition = Box::new(self.parse_expr()?);
+    pub fn parse_if(&mut self) -> Result<ExpressionKind, CompilerError> {
+        let condition = self.parse_condition()?;
         self.lexer.expect_punctuation("{")?;
         let body = self.parse_block()?;
 
@@ -209,7 +414,7 @@ impl FileParser {
             None
         };
 
-        Ok(Expression::If {
+        Ok(ExpressionKind::If {
             condition,
             body,
             else_body,
@@ -217,20 +422,20 @@ impl FileParser {
     }
 
     /// Parse while loop. Keyword [while](Keyword::While) is expected to be consumed beforehand.
-    pub fn parse_while(&mut self) -> Result<Expression, CompilerError> {
-        let condition = Box::new(self.parse_expr()?);
+    pub fn parse_while(&mut self) -> Result<ExpressionKind, CompilerError> {
+        let condition = self.parse_condition()?;
         self.lexer.expect_punctuation("{")?;
         let body = self.parse_block()?;
-        Ok(Expression::While { condition, body })
+        Ok(ExpressionKind::While { condition, body })
     }
 
     /// Parse for loop. Keyword [for](Keyword::For) is expected to be consumed beforehand.
-    pub fn parse_for(&mut self) -> Result<Expression, CompilerError> {
+    pub fn parse_for(&mut self) -> Result<ExpressionKind, CompilerError> {
         let var = self.lexer.expect_identifier()?;
         self.lexer.expect_keyword(Keyword::In)?;
-        let expr = Box::new(self.parse_expr()?);
+        let expr = self.parse_expr()?;
         self.lexer.expect_punctuation("{")?;
         let body = self.parse_block()?;
-        Ok(Expression::For { var, expr, body })
+        Ok(ExpressionKind::For { var, expr, body })
     }
 }
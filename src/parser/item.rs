@@ -1,11 +1,24 @@
 use crate::{
-    ast::item::{Field, Function, Item, ItemKind, Module, Parameter, Struct, Visibility},
+    ast::{
+        item::{
+            Attribute, Const, Field, Function, Impl, Item, ItemKind, Module, Parameter, Struct,
+            Use, Visibility,
+        },
+        types::TypeExpr,
+    },
     error::{
-        library::{lexer::TokenMismatch, parser::ExpectedItem},
-        CompilerError, ExpectedToken, ReportProvider,
+        library::{
+            lexer::{KeywordAsIdentifier, TokenMismatch},
+            parser::{
+                DuplicateField, DuplicateImport, DuplicateParameter, ExpectedItem,
+                ImportWithoutName,
+            },
+        },
+        Applicability, CompilerError, ExpectedToken, Label, ReportProvider, Suggestion,
     },
+    input_stream::Location,
     lexer::{keyword::Keyword, punctuation::Punctuation, Token},
-    util::Span,
+    util::{edit_distance, Span},
     Identifier,
 };
 
@@ -20,9 +33,20 @@ impl FileParser {
     /// Stores resulting item in parser's [ItemTable].
     ///
     /// [ItemTable]: crate::item_table::ItemTable
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(level = "trace", skip_all, fields(scope = %self.scope))
+    )]
     pub fn parse_item(&mut self) -> Result<(), CompilerError> {
-        let start = self.location();
+        self.with_attributes(Self::parse_attributed_item)
+    }
 
+    /// Parse an item that follows its `attributes`, which start at `start`.
+    fn parse_attributed_item(
+        &mut self,
+        start: Location,
+        attributes: Vec<Attribute>,
+    ) -> Result<(), CompilerError> {
         let visibility = if self.lexer.consume_keyword(Keyword::Pub)? {
             Visibility::Public
         } else {
@@ -35,22 +59,68 @@ impl FileParser {
             self.parse_struct()?.into()
         } else if self.lexer.consume_keyword(Keyword::Mod)? {
             self.parse_module()?.into()
+        } else if self.lexer.consume_keyword(Keyword::Const)? {
+            self.parse_const()?.into()
+        } else if self.lexer.consume_keyword(Keyword::Use)? {
+            self.parse_use()?.into()
+        } else if self.lexer.consume_keyword(Keyword::Impl)? {
+            self.parse_impl()?.into()
         } else {
-            return ExpectedItem::report(self, start).map(|_| unreachable!());
+            return self.expected_item(start);
         };
 
-        let span = Span {
-            source: self.source(),
-            start,
-            end: self.location(),
-        };
+        let span = self.span_from(start);
 
-        let item = Item::new(item_kind, span, visibility);
+        self.check_attribute_targets(&attributes, &item_kind);
+        let mut item = Item::new(item_kind, span, visibility);
+        item.attributes = attributes;
 
         self.item_table.declare(self.scope.clone(), item);
         Ok(())
     }
 
+    /// Reports that item was expected at `start`.
+    ///
+    /// If next token is an identifier that resembles keyword of an item, such as `fnn`, that
+    /// keyword is suggested.
+    fn expected_item(&mut self, start: Location) -> Result<(), CompilerError> {
+        const KEYWORDS: [Keyword; 6] = [
+            Keyword::Fn,
+            Keyword::Struct,
+            Keyword::Mod,
+            Keyword::Const,
+            Keyword::Use,
+            Keyword::Impl,
+        ];
+
+        let ident_start = self.lexer.token_start();
+        let Ok(Token::Ident(ident)) = self.lexer.peek() else {
+            return ExpectedItem::report(self, start).map(|_| unreachable!());
+        };
+        let similar = KEYWORDS
+            .into_iter()
+            .map(|keyword| (edit_distance(&ident, &keyword.to_string()), keyword))
+            .filter(|(distance, keyword)| *distance <= (keyword.to_string().len() / 3).max(1))
+            .min_by_key(|(distance, _)| *distance);
+        let Some((_, keyword)) = similar else {
+            return ExpectedItem::report(self, start).map(|_| unreachable!());
+        };
+
+        self.lexer.discard();
+        let suggestion = Suggestion::new(
+            format!("did you mean `{keyword}`?"),
+            self.span_from(ident_start),
+            keyword.to_string(),
+            Applicability::MaybeIncorrect,
+        );
+        ExpectedItem::report_with(
+            &self.context.error_reporter,
+            self.span_from(start),
+            suggestion,
+        )
+        .map(|_| unreachable!())
+    }
+
     fn subscope<R>(&mut self, ident: Identifier, func: impl Fn(&mut FileParser) -> R) -> R {
         self.scope.push(ident);
         let result = func(self);
@@ -60,14 +130,16 @@ impl FileParser {
 
     /// Parse module. Keyword [mod](Keyword::Mod) is expected to be consumed beforehand.
     pub fn parse_module(&mut self) -> Result<Module, CompilerError> {
+        let name_start = self.lexer.token_start();
         let name = self.lexer.expect_identifier()?;
+        let name_span = self.span_from(name_start);
 
         let start = self.location();
         if self.lexer.consume_punctuation(";")? {
             self.pending.push({
                 let mut path = self.scope.clone();
                 path.push(name.clone());
-                PendingFile::General(path)
+                PendingFile::General(path, name_span)
             });
             return Ok(Module::Loadable(name));
         }
@@ -81,73 +153,209 @@ impl FileParser {
             )
             .map(|_| unreachable!());
         }
-        while !self.lexer.consume_punctuation("}")? {
-            self.subscope(name.clone(), |parser| parser.parse_item())?;
-        }
+        self.open_brace();
+        self.nested(|parser| {
+            while !parser.lexer.consume_punctuation("}")? {
+                let start = parser.lexer.token_start();
+                let depth = parser.brace_depth();
+                if let Err(error) = parser.subscope(name.clone(), |parser| parser.parse_item()) {
+                    parser.recover(error, start, depth)?;
+                }
+            }
+            Ok(())
+        })?;
+        self.close_brace();
         Ok(Module::Inline(name))
     }
 
     /// Parse toplevel module.
     pub fn parse_top_module(&mut self, name: Identifier) -> Result<Module, CompilerError> {
         while !self.lexer.is_eof() {
-            self.parse_item()?;
+            let start = self.lexer.token_start();
+            let depth = self.brace_depth();
+            if let Err(error) = self.parse_item() {
+                self.recover(error, start, depth)?;
+            }
         }
         Ok(Module::Inline(name))
     }
 
     /// Parse structure. Keyword [struct](Keyword::Struct) is expected to be consumed beforehand.
+    ///
+    /// Fields with the names that are already declared are reported and skipped.
     pub fn parse_struct(&mut self) -> Result<Struct, CompilerError> {
         let name = self.lexer.expect_identifier()?;
-        let mut fields = Vec::new();
+        let mut fields: Vec<Field> = Vec::new();
+        // Spans of names of the fields, in the same order.
+        let mut spans: Vec<Span> = Vec::new();
         self.lexer.expect_punctuation("{")?;
+        self.open_brace();
 
-        while let Some(field) = self.parse_field()? {
-            fields.push(field);
+        while let Some((field, span)) = self.parse_field()? {
+            match fields.iter().position(|other| other.name == field.name) {
+                Some(index) => {
+                    let labels = vec![Label::new(spans[index], "first declared here")];
+                    let reporter = &self.context.error_reporter;
+                    self.errors.extend(
+                        DuplicateField::report_labeled(reporter, span, labels, field.name).err(),
+                    );
+                }
+                None => {
+                    fields.push(field);
+                    spans.push(span);
+                }
+            }
             if self.lexer.consume_punctuation("}")? {
                 break;
             } else {
                 self.lexer.expect_punctuation(",")?;
             }
         }
+        self.close_brace();
         Ok(Struct { name, fields })
     }
 
-    /// Parse a single field of struct. Returns `None` if closing brace met instead.
-    fn parse_field(&mut self) -> Result<Option<Field>, CompilerError> {
+    /// Parse a single field of struct along with the span of its name. Returns `None` if closing
+    /// brace met instead.
+    fn parse_field(&mut self) -> Result<Option<(Field, Span)>, CompilerError> {
+        let start = self.lexer.token_start();
         let Some(name) = self.lexer.consume_identifier()? else {
             self.lexer.expect_punctuation("}")?;
             return Ok(None);
         };
+        let span = self.span_from(start);
         self.lexer.expect_punctuation(":")?;
-        let type_ = self.lexer.expect_identifier()?;
+        let type_ = self.parse_type()?;
+        let field = Field {
+            name,
+            type_,
+            span: self.span_from(start),
+        };
 
-        Ok(Some(Field { name, type_ }))
+        Ok(Some((field, span)))
+    }
+
+    /// Parse constant. Keyword [const](Keyword::Const) is expected to be consumed beforehand.
+    pub fn parse_const(&mut self) -> Result<Const, CompilerError> {
+        let name = self.lexer.expect_identifier()?;
+        self.lexer.expect_punctuation(":")?;
+        let type_ = self.parse_type()?;
+        self.lexer.expect_punctuation("=")?;
+        let value = self.parse_expr()?;
+        self.lexer.expect_punctuation(";")?;
+        Ok(Const { name, type_, value })
+    }
+
+    /// Parse import. Keyword [use](Keyword::Use) is expected to be consumed beforehand.
+    pub fn parse_use(&mut self) -> Result<Use, CompilerError> {
+        let start = self.location();
+        let path = self.parse_path()?;
+        let alias = if self.lexer.consume_keyword(Keyword::As)? {
+            Some(self.lexer.expect_identifier()?)
+        } else {
+            None
+        };
+        self.lexer.expect_punctuation(";")?;
+
+        if alias.is_none() && path.last().is_none() {
+            return ImportWithoutName::report(self, start).map(|_| unreachable!());
+        }
+        let import = Use { path, alias };
+        if self
+            .item_table
+            .lookup_import(&self.scope, import.name())
+            .is_some()
+        {
+            let name = import.name().clone();
+            return DuplicateImport::report(self, start, name).map(|_| unreachable!());
+        }
+        Ok(import)
+    }
+
+    /// Parse implementation block. Keyword [impl](Keyword::Impl) is expected to be consumed beforehand.
+    ///
+    /// Functions of the block are declared in the item table with path `TYPE::FUNCTION`.
+    pub fn parse_impl(&mut self) -> Result<Impl, CompilerError> {
+        let type_start = self.lexer.token_start();
+        let type_name = self.lexer.expect_identifier()?;
+        let type_span = self.span_from(type_start);
+        self.lexer.expect_punctuation("{")?;
+        self.open_brace();
+
+        let mut functions = Vec::new();
+        while !self.lexer.consume_punctuation("}")? {
+            let name = self.with_attributes(|parser, start, attributes| {
+                let visibility = if parser.lexer.consume_keyword(Keyword::Pub)? {
+                    Visibility::Public
+                } else {
+                    Visibility::default()
+                };
+                parser.lexer.expect_keyword(Keyword::Fn)?;
+                let function = parser.subscope(type_name.clone(), |parser| parser.parse_fn())?;
+                let span = parser.span_from(start);
+                let name = function.name.clone();
+
+                let mut item = Item::new(function, span, visibility);
+                parser.check_attribute_targets(&attributes, &item.kind);
+                item.attributes = attributes;
+                let mut scope = parser.scope.clone();
+                scope.push(type_name.clone());
+                parser.item_table.declare(scope, item);
+                Ok(name)
+            })?;
+            functions.push(name);
+        }
+        self.close_brace();
+        Ok(Impl {
+            type_name,
+            type_span,
+            functions,
+        })
     }
 
     /// Parse function from token stream. Keyword [fn](Keyword::Fn) is expected to be consumed beforehand.
     pub fn parse_fn(&mut self) -> Result<Function, CompilerError> {
         let name = self.lexer.expect_identifier()?;
         self.lexer.expect_punctuation("(")?;
-        let params = self.parse_params()?;
-        let return_type = self.parse_return_type()?;
+        let (receiver, params) = self.parse_params()?;
+        let (return_type, return_type_span) = self.parse_return_type()?.unzip();
         let body = self.subscope(name.clone(), |parser| parser.parse_block())?;
 
         Ok(Function {
             name,
+            receiver,
             params,
             return_type,
+            return_type_span,
             body,
         })
     }
 
-    /// Parse parameters. Opening parenthesis is expected to be consumed beforehand.
-    fn parse_params(&mut self) -> Result<Vec<Parameter>, CompilerError> {
-        let mut params = Vec::new();
+    /// Parse parameters, along with the span of `self` receiver if it is the first one.
+    ///
+    /// Opening parenthesis is expected to be consumed beforehand. Parameters with names that are
+    /// already declared are reported, but kept, so the function still takes every argument.
+    fn parse_params(&mut self) -> Result<(Option<Span>, Vec<Parameter>), CompilerError> {
+        let mut receiver = None;
+        let mut params: Vec<Parameter> = Vec::new();
         loop {
             let start = self.location();
+            let param_start = self.lexer.token_start();
             let name = match self.lexer.next()? {
                 Token::Ident(ident) => Identifier(ident),
                 Token::Punc(Punctuation::RParent) => break,
+                Token::Kw(Keyword::SelfValue) if receiver.is_none() && params.is_empty() => {
+                    receiver = Some(self.span_from(param_start));
+                    if self.lexer.consume_punctuation(")")? {
+                        break;
+                    }
+                    self.lexer.expect_punctuation(",")?;
+                    continue;
+                }
+                Token::Kw(kw) => {
+                    return KeywordAsIdentifier::report(self, param_start, kw)
+                        .map(|_| unreachable!());
+                }
                 token => {
                     return TokenMismatch::report(
                         self,
@@ -158,9 +366,21 @@ impl FileParser {
                     .map(|_| unreachable!());
                 }
             };
+            if let Some(previous) = params.iter().find(|param| param.name == name) {
+                let labels = vec![Label::new(previous.span, "first declared here")];
+                let span = self.span_from(param_start);
+                let reporter = &self.context.error_reporter;
+                self.errors.extend(
+                    DuplicateParameter::report_labeled(reporter, span, labels, name.clone()).err(),
+                );
+            }
             self.lexer.expect_punctuation(":")?;
-            let type_ = self.lexer.expect_identifier()?;
-            params.push(Parameter { name, type_ });
+            let type_ = self.parse_type()?;
+            params.push(Parameter {
+                name,
+                type_,
+                span: self.span_from(param_start),
+            });
 
             if self.lexer.consume_punctuation(")")? {
                 break;
@@ -168,17 +388,21 @@ impl FileParser {
                 self.lexer.expect_punctuation(",")?;
             }
         }
-        Ok(params)
+        Ok((receiver, params))
     }
 
-    /// Try to parse return type if any. Consumes opening brace `{` which is required for function body.
-    fn parse_return_type(&mut self) -> Result<Option<Identifier>, CompilerError> {
+    /// Try to parse return type if any, along with its span.
+    ///
+    /// Consumes opening brace `{` which is required for function body.
+    fn parse_return_type(&mut self) -> Result<Option<(TypeExpr, Span)>, CompilerError> {
         let start = self.location();
         match self.lexer.next()? {
             Token::Punc(Punctuation::Arrow) => {
-                let return_type = self.lexer.expect_identifier()?;
+                let type_start = self.lexer.token_start();
+                let return_type = self.parse_type()?;
+                let span = self.span_from(type_start);
                 self.lexer.expect_punctuation("{")?;
-                Ok(Some(return_type))
+                Ok(Some((return_type, span)))
             }
             Token::Punc(Punctuation::LBrace) => Ok(None),
             token => TokenMismatch::report(
@@ -194,9 +418,28 @@ impl FileParser {
 
 #[cfg(test)]
 mod test {
-    use crate::{parser::FileParser, Identifier};
+    use std::str::FromStr;
+
+    use crate::{
+        ast::{
+            expression::{Expression, ExpressionKind, Literal},
+            statement::StatementKind,
+        },
+        error::library::{
+            lexer::KeywordAsIdentifier,
+            parser::{DuplicateField, DuplicateParameter},
+        },
+        lexer::{
+            number::{Base, Number},
+            operator::{BinaryOp, UnaryOp},
+        },
+        parser::FileParser,
+        path::{AbsolutePath, RelativePath, RelativePathStart},
+        util::Span,
+        Identifier,
+    };
 
-    use super::{Field, Struct};
+    use super::{Field, ItemKind, Struct, Use};
 
     #[test]
     fn parse_empty_struct() {
@@ -221,11 +464,13 @@ mod test {
             fields: vec![
                 Field {
                     name: Identifier(String::from("field1")),
-                    type_: Identifier(String::from("type1")),
+                    type_: Identifier(String::from("type1")).into(),
+                    span: Span::default(),
                 },
                 Field {
                     name: Identifier(String::from("field2")),
-                    type_: Identifier(String::from("type2")),
+                    type_: Identifier(String::from("type2")).into(),
+                    span: Span::default(),
                 },
             ],
         };
@@ -243,15 +488,253 @@ mod test {
             fields: vec![
                 Field {
                     name: Identifier(String::from("field1")),
-                    type_: Identifier(String::from("type1")),
+                    type_: Identifier(String::from("type1")).into(),
+                    span: Span::default(),
                 },
                 Field {
                     name: Identifier(String::from("field2")),
-                    type_: Identifier(String::from("type2")),
+                    type_: Identifier(String::from("type2")).into(),
+                    span: Span::default(),
                 },
             ],
         };
         let produced = parser.parse_struct().unwrap();
         assert_eq!(expected, produced);
     }
+
+    #[test]
+    fn keywords_as_identifiers() {
+        let cases = [
+            ("fn run() { let true = 5; }", "true", 15),
+            ("fn false() {}", "false", 3),
+            ("struct Flags { visible: bool, if: bool }", "if", 30),
+            ("fn run(x: i32, true: bool) {}", "true", 15),
+            ("fn run(x: i32, self: bool) {}", "self", 15),
+        ];
+        for (src, keyword, column) in cases {
+            let parser = FileParser::new_test(src);
+            let context = parser.context.clone();
+            let parsed = parser.parse();
+            assert!(!parsed.errors.is_empty(), "source: {src}");
+            let diagnostics = context.error_reporter.diagnostics();
+            let [diagnostic] = diagnostics.as_slice() else {
+                panic!("unexpected diagnostics for `{src}`: {diagnostics:?}");
+            };
+            assert_eq!(diagnostic.code, KeywordAsIdentifier::CODE);
+            assert_eq!(
+                diagnostic.message,
+                format!("expected identifier, found keyword `{keyword}`")
+            );
+            assert_eq!(diagnostic.span.unwrap().start.column, column);
+        }
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let src = "
+struct Point { x: i32, y: bool, x: bool }
+fn add(a: i32, b: i32, a: i32) -> i32 { a }";
+        let parser = FileParser::new_test(src);
+        let context = parser.context.clone();
+        let parsed = parser.parse();
+        assert_eq!(parsed.errors.len(), 2);
+        let diagnostics = context.error_reporter.diagnostics();
+        let reported: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let [label] = diagnostic.secondary_labels.as_slice() else {
+                    panic!("first declaration should be labeled: {diagnostic:?}");
+                };
+                let span = diagnostic.span.unwrap();
+                (
+                    diagnostic.code,
+                    diagnostic.message.as_str(),
+                    (span.start.line, span.start.column),
+                    (label.span.start.line, label.span.start.column),
+                )
+            })
+            .collect();
+        assert_eq!(
+            reported,
+            [
+                (
+                    DuplicateField::CODE,
+                    "field `x` is declared multiple times",
+                    (1, 32),
+                    (1, 15),
+                ),
+                (
+                    DuplicateParameter::CODE,
+                    "parameter `a` is declared multiple times",
+                    (2, 23),
+                    (2, 7),
+                ),
+            ]
+        );
+
+        // First field is kept, while every parameter is.
+        let item = |path: &str| {
+            let path = AbsolutePath::from_str(path).unwrap();
+            &parsed.item_table.get(&path).unwrap().kind
+        };
+        let ItemKind::Struct(point) = item("crate::Point") else {
+            panic!("`Point` should be a struct");
+        };
+        let fields: Vec<_> = point
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.type_.to_string()))
+            .collect();
+        assert_eq!(
+            fields,
+            [("x", String::from("i32")), ("y", String::from("bool"))]
+        );
+        let ItemKind::Function(add) = item("crate::add") else {
+            panic!("`add` should be a function");
+        };
+        assert_eq!(add.params.len(), 3);
+    }
+
+    #[test]
+    fn parse_const() {
+        let mut parser = FileParser::new_test("const LIMIT: i32 = 2 * 8;");
+
+        let _ = parser.lexer.next();
+        let produced = parser.parse_const().unwrap();
+        assert_eq!(produced.name, Identifier(String::from("LIMIT")));
+        assert_eq!(produced.type_, Identifier(String::from("i32")).into());
+        let exprs = parser.item_table.exprs();
+        let ExpressionKind::Binary { op, left, right } = &exprs[produced.value].kind else {
+            panic!("constant value should be binary expression");
+        };
+        assert_eq!(*op, BinaryOp::Mul);
+        assert_eq!(exprs[*left], make_num("2"));
+        assert_eq!(exprs[*right], make_num("8"));
+    }
+
+    #[test]
+    fn parse_aliased_use() {
+        let mut parser = FileParser::new_test("use super::thing as alias;");
+
+        let _ = parser.lexer.next();
+        let mut path = RelativePath::new(RelativePathStart::Super(1));
+        path.push(Identifier(String::from("thing")));
+        let expected = Use {
+            path,
+            alias: Some(Identifier(String::from("alias"))),
+        };
+        let produced = parser.parse_use().unwrap();
+        assert_eq!(expected, produced);
+    }
+
+    #[test]
+    fn parse_impl() {
+        let parser = FileParser::new_test(
+            "struct Color {} impl Color { fn new() {} pub fn mix() {} } impl Color { fn new() {} }",
+        );
+        let parsed = parser.parse();
+        assert!(parsed.errors.is_empty(), "parsing failed");
+        let table = parsed.item_table;
+
+        assert_eq!(table.impls().len(), 2);
+        let path = AbsolutePath::from_str("crate::Color::mix").unwrap();
+        assert!(matches!(
+            table.get(&path).map(|item| &item.kind),
+            Some(ItemKind::Function(_))
+        ));
+        let duplicates: Vec<_> = table.duplicates().map(|(path, _, _)| path).collect();
+        assert_eq!(
+            duplicates,
+            vec![&AbsolutePath::from_str("crate::Color::new").unwrap()]
+        );
+    }
+
+    #[test]
+    fn parse_receiver() {
+        let mut parser = FileParser::new_test("fn scale(self, k: f32) {}");
+
+        let _ = parser.lexer.next();
+        let produced = parser.parse_fn().unwrap();
+        assert!(produced.receiver.is_some());
+        assert_eq!(produced.params.len(), 1);
+        assert_eq!(produced.params[0].name, Identifier(String::from("k")));
+    }
+
+    #[test]
+    fn block_like_statements() {
+        let cases = [
+            ("fn run() { if a { 1 } else { 2 } - 3 }", 1, true),
+            ("fn run() { { 1 } + x; }", 2, true),
+            ("fn run() { ({ 1 }) - x }", 0, false),
+            ("fn run() { let y: i32 = { 1 } - x; }", 1, false),
+        ];
+        for (src, statements, warned) in cases {
+            let mut parser = FileParser::new_test(src);
+
+            let _ = parser.lexer.next();
+            let produced = parser.parse_fn().unwrap();
+            let body = produced.body;
+            assert_eq!(body.statements.len(), statements, "source: {src}");
+            let warnings = parser.context.error_reporter.warnings();
+            assert_eq!(!warnings.is_empty(), warned, "source: {src}");
+
+            let exprs = parser.item_table.exprs();
+            if warned {
+                let StatementKind::ExprStmt(block) = body.statements[0].kind else {
+                    panic!("block-like expression should be a statement");
+                };
+                assert!(exprs[block].is_block_expression());
+                let operand = match (body.expression, &body.statements[1..]) {
+                    (Some(expr), []) => expr,
+                    (None, [statement]) => match statement.kind {
+                        StatementKind::ExprStmt(expr) => expr,
+                        _ => panic!("operator should start expression statement"),
+                    },
+                    _ => panic!("operator should start the next statement"),
+                };
+                assert!(matches!(
+                    exprs[operand].kind,
+                    ExpressionKind::Unary {
+                        op: UnaryOp::Add | UnaryOp::Sub,
+                        ..
+                    }
+                ));
+            } else if let Some(expr) = body.expression {
+                assert!(matches!(
+                    exprs[expr].kind,
+                    ExpressionKind::Binary {
+                        op: BinaryOp::Sub,
+                        ..
+                    }
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn receiver_after_parameter() {
+        let parser = FileParser::new_test("fn scale(k: f32, self) {}");
+        assert!(!parser.parse().errors.is_empty());
+    }
+
+    #[test]
+    fn duplicate_use() {
+        let parser = FileParser::new_test("use crate::a::item; use crate::b::item;");
+        assert!(!parser.parse().errors.is_empty());
+    }
+
+    #[test]
+    fn use_without_name() {
+        let parser = FileParser::new_test("use super;");
+        assert!(!parser.parse().errors.is_empty());
+    }
+
+    fn make_num(n: &'static str) -> Expression {
+        let kind = ExpressionKind::Literal(Literal::Number(Number {
+            integer: n.to_string(),
+            fraction: None,
+            base: Base::Decimal,
+        }));
+        Expression::new(kind, Span::default())
+    }
 }
@@ -14,16 +14,19 @@
 pub mod infix;
 pub mod postfix;
 
-use crate::{ast::expression::Expression, lexer::operator::AssignOp, Identifier};
+use crate::{
+    ast::{expression::ExprId, statement::Place},
+    lexer::operator::AssignOp,
+};
 
-/// A tree of expressions that may be preceded by assignment.
-pub type Tree = MaybeAssignment<Expression>;
+/// A tree of expressions that may be preceded by assignment, given by the id of its root.
+pub type Tree = MaybeAssignment<ExprId>;
 
 /// A generic type that may be either expression or assignment of an expression.
 #[derive(Debug, PartialEq, Eq)]
 pub enum MaybeAssignment<Expr> {
     Assignment {
-        assignee: Identifier,
+        assignee: Place,
         operator: AssignOp,
         expression: Expr,
     },
@@ -52,3 +55,21 @@ impl<Expr> MaybeAssignment<Expr> {
         }
     }
 }
+
+impl<Expr, E> MaybeAssignment<Result<Expr, E>> {
+    /// Converts assignment or expression of a result into result of assignment or expression.
+    pub fn transpose(self) -> Result<MaybeAssignment<Expr>, E> {
+        match self {
+            MaybeAssignment::Assignment {
+                assignee,
+                operator,
+                expression,
+            } => Ok(MaybeAssignment::Assignment {
+                assignee,
+                operator,
+                expression: expression?,
+            }),
+            MaybeAssignment::Expression(expr) => expr.map(MaybeAssignment::Expression),
+        }
+    }
+}
@@ -2,16 +2,22 @@
 use std::collections::VecDeque;
 
 use crate::{
-    ast::expression::Expression as AstExpression,
+    ast::{expression::Expression as AstExpression, statement::Place, types::TypeExpr},
     error::{
         library::parser::{
-            ChainedAssignment, ExpectedExpression, InvalidAssignee, UnclosedParenthesis,
+            ChainedAssignment, ChainedComparison, ExpectedExpression, ExpressionTooComplex,
+            InvalidAssignee, TupleExpression, UnclosedParenthesis,
         },
         CompilerError, ReportProvider,
     },
-    lexer::operator::{AssignOp, BinaryOp, UnaryOp},
+    lexer::{
+        keyword::Keyword,
+        operator::{AssignOp, BinaryOp, UnaryOp},
+        punctuation::Punctuation,
+        Token,
+    },
     parser::FileParser,
-    Identifier,
+    util::Span,
 };
 
 use super::MaybeAssignment;
@@ -19,6 +25,9 @@ use super::MaybeAssignment;
 /// A sequence of operands and operators in [infix notation](https://en.wikipedia.org/wiki/Infix_notation).
 pub type InfixNotation = MaybeAssignment<VecDeque<InfixEntry>>;
 
+/// Assignee and operator that precede assigned expression, with the span of the operator.
+pub type AssignmentPrefix = (Place, AssignOp, Span);
+
 impl FileParser {
     /// Parse and validate infix expression.
     ///
@@ -33,89 +42,185 @@ impl FileParser {
     ///
     /// Error will only be produced if parenthesis mismatches or operator without following operand occurs.
     pub fn parse_infix(&mut self) -> Result<InfixNotation, CompilerError> {
-        let start = self.location();
-        let mut depth = 0usize;
+        let (output, assignment) = self.parse_infix_entries()?;
+        Ok(match assignment {
+            Some((assignee, operator, _)) => {
+                self.expect_semicolon()?;
+                InfixNotation::Assignment {
+                    assignee,
+                    operator,
+                    expression: output,
+                }
+            }
+            None => InfixNotation::Expression(output),
+        })
+    }
+
+    /// Parse and validate entries of infix expression.
+    ///
+    /// Assignee and operator of assignment are returned separately along with the span of the
+    /// operator. Unlike [parse_infix](FileParser::parse_infix), `;` is not expected after
+    /// assignment.
+    ///
+    /// Expression itself, its parentheses and unary operators are [nested](FileParser::nest),
+    /// so operands that contain expressions, such as blocks, can't be nested too deeply.
+    ///
+    /// Parsing of pathologically long expressions stops early, as the number of entries is
+    /// limited by [max_expression_tokens](crate::context::Metadata::max_expression_tokens).
+    pub(crate) fn parse_infix_entries(
+        &mut self,
+    ) -> Result<(VecDeque<InfixEntry>, Option<AssignmentPrefix>), CompilerError> {
+        self.nested(Self::parse_nested_infix_entries)
+    }
+
+    fn parse_nested_infix_entries(
+        &mut self,
+    ) -> Result<(VecDeque<InfixEntry>, Option<AssignmentPrefix>), CompilerError> {
+        let expression_start = self.lexer.token_start();
+        // Spans of opening parentheses that are not closed yet.
+        let mut parentheses = Vec::<Span>::new();
+        // Span of the last comparison operator at every level of parentheses, until an operator
+        // of lower priority follows it.
+        let mut comparisons: Vec<Option<Span>> = vec![None];
+        // Number of unary operators that wait for their operand.
+        let mut unary = 0;
         let mut output = VecDeque::<InfixEntry>::new();
-        let mut assignment: Option<(Identifier, AssignOp)> = None;
+        let mut assignment: Option<AssignmentPrefix> = None;
+        let limit = self.context.metadata.max_expression_tokens;
 
         loop {
             use InfixEntry::*;
             let start = self.location();
+            if output.len() > limit {
+                return ExpressionTooComplex::report(self, expression_start, limit)
+                    .map(|_| unreachable!());
+            }
 
+            let operator_start = self.lexer.token_start();
+            let assignee_end = self.lexer.token_end();
             if let Some(operator) = self.lexer.consume_assignment_operator()? {
+                let reporter = &self.context.error_reporter;
                 if assignment.is_some() {
-                    return ChainedAssignment::report(self, start).map(|_| unreachable!());
+                    let span = self.span_from(operator_start);
+                    return ChainedAssignment::report_at(reporter, span).map(|_| unreachable!());
                 }
-                let Some(Operand(AstExpression::Var(assignee))) = output.pop_back() else {
-                    return InvalidAssignee::report(self, start).map(|_| unreachable!());
+                // Everything that precedes the operator is reported as the assignee, or the
+                // operator itself if nothing does.
+                let assignee_span = match output.is_empty() {
+                    true => self.span_from(operator_start),
+                    false => Span {
+                        source: self.source(),
+                        start: expression_start,
+                        end: assignee_end,
+                    },
+                };
+                let invalid_assignee =
+                    || InvalidAssignee::report_at(reporter, assignee_span).map(|_| unreachable!());
+                let Some(Operand(assignee)) = output.pop_back() else {
+                    return invalid_assignee();
+                };
+                let Some(assignee) = Place::from_expr(&assignee, self.item_table.exprs()) else {
+                    return invalid_assignee();
                 };
                 if !output.is_empty() {
-                    return InvalidAssignee::report(self, start).map(|_| unreachable!());
+                    return invalid_assignee();
                 }
-                assignment = Some((assignee, operator));
+                assignment = Some((assignee, operator, self.span_from(operator_start)));
             }
 
             match output.back() {
-                Some(Operand(_) | RightParenthesis) => {
-                    if let Some(op) = self.lexer.consume_binary_operator()? {
+                Some(Operand(_) | RightParenthesis | Cast(..)) => {
+                    if self.lexer.consume_keyword(Keyword::As)? {
+                        let type_start = self.lexer.token_start();
+                        let type_ = self.parse_type()?;
+                        output.push_back(Cast(type_, self.span_from(type_start)));
+                    } else if let Some(op) = self.lexer.consume_binary_operator()? {
+                        let comparison = comparisons.last_mut().expect("outermost level is kept");
+                        if op.is_comparison() {
+                            let span = self.span_from(self.lexer.last_token_start());
+                            if let Some(previous) = comparison.replace(span) {
+                                let reporter = &self.context.error_reporter;
+                                return ChainedComparison::report_at(reporter, previous.join(span))
+                                    .map(|_| unreachable!());
+                            }
+                        } else if op.priority() < BinaryOp::Eq.priority() {
+                            *comparison = None;
+                        }
                         output.push_back(BinaryOperator(op));
                     } else if self.lexer.peek_punctuation(")") {
-                        if depth > 0 {
+                        if parentheses.pop().is_some() {
+                            comparisons.pop();
                             self.lexer.discard();
-                            depth -= 1;
+                            self.unnest(1);
                             output.push_back(RightParenthesis);
                         } else {
                             break;
                         }
+                    } else if !parentheses.is_empty() && self.lexer.peek_punctuation(",") {
+                        let comma_start = self.lexer.token_start();
+                        self.lexer.discard();
+                        let span = self.span_from(comma_start);
+                        return TupleExpression::report_at(&self.context.error_reporter, span)
+                            .map(|_| unreachable!());
                     } else {
                         break;
                     }
                 }
                 None | Some(UnaryOperator(_) | BinaryOperator(_) | LeftParenthesis) => {
                     if let Some(op) = self.lexer.consume_unary_operator()? {
+                        self.nest()?;
+                        unary += 1;
                         output.push_back(UnaryOperator(op));
                     } else if self.lexer.consume_punctuation("(")? {
-                        depth += 1;
+                        self.nest()?;
+                        parentheses.push(self.span_from(self.lexer.last_token_start()));
+                        comparisons.push(None);
                         output.push_back(LeftParenthesis);
+                    } else if self.ends_expression() {
+                        return ExpectedExpression::report(self, start).map(|_| unreachable!());
                     } else {
                         let operand = self.parse_operand()?;
+                        self.unnest(unary);
+                        unary = 0;
                         output.push_back(Operand(operand));
                     }
                 }
             }
         }
 
-        if depth != 0 {
-            return UnclosedParenthesis::report(self, start).map(|_| unreachable!());
+        if let Some(parenthesis) = parentheses.last() {
+            return UnclosedParenthesis::report_at(&self.context.error_reporter, *parenthesis)
+                .map(|_| unreachable!());
         }
 
         match output.front() {
             Some(InfixEntry::BinaryOperator(_)) | None => {
-                return ExpectedExpression::report(self, start).map(|_| unreachable!());
+                return ExpectedExpression::report(self, expression_start).map(|_| unreachable!());
             }
             _ => {}
         }
 
-        Ok(match assignment {
-            Some((assignee, operator)) => {
-                self.lexer.expect_punctuation(";")?;
-                InfixNotation::Assignment {
-                    assignee,
-                    operator,
-                    expression: output,
-                }
-            }
-            None => InfixNotation::Expression(output),
-        })
+        Ok((output, assignment))
+    }
+
+    /// Checks if next token may only follow an expression, so the operand is missing.
+    fn ends_expression(&mut self) -> bool {
+        use Punctuation::*;
+        matches!(
+            self.lexer.peek(),
+            Ok(Token::Punc(Semicolon | Comma | RParent | RBrace))
+        )
     }
 }
 
-/// An entry of infix expression: operand, operator (unary or binary) or parenthesis.
+/// An entry of infix expression: operand, operator (unary, binary or cast) or parenthesis.
 #[derive(Debug, PartialEq, Eq)]
 pub enum InfixEntry {
     Operand(AstExpression),
     UnaryOperator(UnaryOp),
     BinaryOperator(BinaryOp),
+    /// `as TYPE` that follows an operand, with the span of the type.
+    Cast(TypeExpr, Span),
     LeftParenthesis,
     RightParenthesis,
 }
@@ -123,12 +228,17 @@ pub enum InfixEntry {
 #[cfg(test)]
 mod tests {
     use crate::{
-        ast::expression::{Expression, Literal},
+        ast::expression::{Expression, ExpressionKind, Literal},
+        error::library::parser::{
+            ChainedAssignment, ChainedComparison, ExpectedExpression, InvalidAssignee,
+            TupleExpression, UnclosedParenthesis,
+        },
         lexer::{
             number::{Base, Number},
             operator::{BinaryOp, UnaryOp},
         },
         parser::FileParser,
+        util::Span,
         Identifier,
     };
 
@@ -141,11 +251,7 @@ mod tests {
         let mut parser = FileParser::new_test("-x");
         let parsed = parser.parse_infix().expect("parsing failed");
         let expected = InfixNotation::Expression(
-            vec![
-                UnaryOperator(UnaryOp::Sub),
-                Operand(Expression::Var(Identifier(String::from("x")))),
-            ]
-            .into(),
+            vec![UnaryOperator(UnaryOp::Sub), Operand(make_var("x"))].into(),
         );
         assert!(
             expected == parsed,
@@ -165,7 +271,7 @@ mod tests {
             vec![
                 Operand(make_num("4")),
                 BinaryOperator(BinaryOp::MoreEq),
-                Operand(Expression::Var(Identifier(String::from("x")))),
+                Operand(make_var("x")),
             ]
             .into(),
         );
@@ -233,10 +339,91 @@ mod tests {
     }
 
     fn make_num(n: &'static str) -> Expression {
-        Expression::Literal(Literal::Number(Number {
+        let kind = ExpressionKind::Literal(Literal::Number(Number {
             integer: n.to_string(),
             fraction: None,
             base: Base::Decimal,
-        }))
+        }));
+        Expression::new(kind, Span::default())
+    }
+
+    fn make_var(name: &'static str) -> Expression {
+        let kind = ExpressionKind::Var(Identifier(String::from(name)));
+        Expression::new(kind, Span::default())
+    }
+
+    /// Parses single-line `src` as a block and returns codes and columns of reported errors.
+    fn errors(src: &str) -> Vec<(&'static str, usize, usize)> {
+        let mut parser = FileParser::new_test(src);
+        let _ = parser.parse_block();
+        parser
+            .context
+            .error_reporter
+            .diagnostics()
+            .into_iter()
+            .map(|diagnostic| {
+                let span = diagnostic.span.unwrap();
+                (diagnostic.code, span.start.column, span.end.column)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expected_expression() {
+        assert_eq!(errors("let x = ; }"), [(ExpectedExpression::CODE, 8, 9)]);
+    }
+
+    #[test]
+    fn chained_assignment() {
+        assert_eq!(errors("x = y = 5; }"), [(ChainedAssignment::CODE, 6, 7)]);
+        assert_eq!(errors("x += y -= 1; }"), [(ChainedAssignment::CODE, 7, 9)]);
+        assert_eq!(
+            errors("p.x = 1 + y = 5; }"),
+            [(ChainedAssignment::CODE, 12, 13)]
+        );
+    }
+
+    #[test]
+    fn chained_comparison() {
+        assert_eq!(errors("a < b < c; }"), [(ChainedComparison::CODE, 2, 7)]);
+        assert_eq!(
+            errors("a == b + 1 != c; }"),
+            [(ChainedComparison::CODE, 2, 13)]
+        );
+        assert_eq!(
+            errors("(a < b) < c == d; }"),
+            [(ChainedComparison::CODE, 8, 14)]
+        );
+        assert_eq!(errors("a < b && b < c; }"), []);
+        assert_eq!(errors("(a < b) == c; }"), []);
+        assert_eq!(errors("a == (b < c); }"), []);
+        assert_eq!(errors("f(a < b, b < c) || a..b < c; }"), []);
+    }
+
+    #[test]
+    fn invalid_assignee() {
+        assert_eq!(errors("5 = 6; }"), [(InvalidAssignee::CODE, 0, 1)]);
+        assert_eq!(errors("f() = 5; }"), [(InvalidAssignee::CODE, 0, 3)]);
+        assert_eq!(errors("p.x + 1 = 5; }"), [(InvalidAssignee::CODE, 0, 7)]);
+        assert_eq!(errors("(x) = 5; }"), [(InvalidAssignee::CODE, 0, 3)]);
+        assert_eq!(errors("= 5; }"), [(InvalidAssignee::CODE, 0, 1)]);
+        assert_eq!(errors("p.x[0].y = 5; }"), []);
+    }
+
+    #[test]
+    fn unclosed_parenthesis() {
+        assert_eq!(
+            errors("let y = (1 + 2; }"),
+            [(UnclosedParenthesis::CODE, 8, 9)]
+        );
+    }
+
+    #[test]
+    fn tuple_expression() {
+        assert_eq!(
+            errors("let t: (i32, bool) = (1, true); }"),
+            [(TupleExpression::CODE, 23, 24)]
+        );
+        assert_eq!(errors("let x: i32 = f((1), 2); }"), []);
     }
 }
@@ -2,9 +2,12 @@
 use std::collections::VecDeque;
 
 use crate::{
-    ast::expression::Expression,
-    error::CompilerError,
+    ast::{
+        expression::{ExprArena, ExprId, Expression, ExpressionKind},
+        types::TypeExpr,
+    },
     lexer::operator::{BinaryOp, UnaryOp},
+    util::Span,
 };
 
 use super::{
@@ -17,126 +20,387 @@ pub type PostfixNotation = MaybeAssignment<VecDeque<PostfixEntry>>;
 
 impl PostfixNotation {
     /// Converts from infix to postfix notation.
-    pub fn from_infix(infix: InfixNotation) -> Self {
-        infix.map_expr(|entries| {
-            let mut output = VecDeque::<PostfixEntry>::with_capacity(entries.capacity());
-            let mut op_stack = Vec::<Operator>::with_capacity(4);
-
-            for entry in entries {
-                match entry {
-                    InfixEntry::Operand(operand) => {
-                        output.push_back(PostfixEntry::Operand(operand));
-                    }
-                    InfixEntry::UnaryOperator(op) => op_stack.push(Operator::Unary(op)),
-                    InfixEntry::BinaryOperator(op) => {
-                        while let Some(top_op) = op_stack.last() {
-                            let top_priority = match top_op {
-                                Operator::Unary(_) => 0,
-                                Operator::Binary(op) => op.priority(),
-                                Operator::LeftParenthesis => break,
-                            };
-                            if top_priority < op.priority() {
-                                break;
-                            }
-                            output.push_back(op_stack.pop().unwrap().try_into().unwrap());
-                        }
-                        op_stack.push(Operator::Binary(op));
-                    }
-                    InfixEntry::LeftParenthesis => op_stack.push(Operator::LeftParenthesis),
-                    InfixEntry::RightParenthesis => {
-                        while let Some(top_op) = op_stack.last() {
-                            if top_op == &Operator::LeftParenthesis {
-                                break;
-                            }
-                            output.push_back(op_stack.pop().unwrap().try_into().unwrap());
-                        }
+    ///
+    /// # Errors
+    ///
+    /// Error is returned if parentheses of `infix` are unbalanced.
+    pub fn from_infix(infix: InfixNotation) -> Result<Self, ConversionError> {
+        infix.map_expr(Self::shunting_yard).transpose()
+    }
+
+    /// Reorders `entries` with [shunting yard algorithm](https://en.wikipedia.org/wiki/Shunting_yard_algorithm).
+    fn shunting_yard(
+        entries: VecDeque<InfixEntry>,
+    ) -> Result<VecDeque<PostfixEntry>, ConversionError> {
+        let mut output = VecDeque::<PostfixEntry>::with_capacity(entries.len());
+        let mut op_stack = Vec::<Operator>::with_capacity(4);
 
-                        // Either `op_stack` is empty or left parenthesis is on the top at that point.
-                        if op_stack.pop().is_none() {
-                            panic!("Operator stack should be empty");
+        for entry in entries {
+            match entry {
+                InfixEntry::Operand(operand) => {
+                    output.push_back(PostfixEntry::Operand(operand));
+                }
+                InfixEntry::UnaryOperator(op) => op_stack.push(Operator::Unary(op)),
+                // Cast binds tighter than any binary operator, but unary ones bind tighter still,
+                // so `-x as f32` is `(-x) as f32`.
+                InfixEntry::Cast(type_, span) => {
+                    while let Some(&Operator::Unary(op)) = op_stack.last() {
+                        op_stack.pop();
+                        output.push_back(PostfixEntry::UnaryOperator(op));
+                    }
+                    output.push_back(PostfixEntry::Cast(type_, span));
+                }
+                InfixEntry::BinaryOperator(op) => {
+                    while let Some(&top_op) = op_stack.last() {
+                        let top_priority = match top_op {
+                            Operator::Unary(op) => op.priority(),
+                            Operator::Binary(op) => op.priority(),
+                            Operator::LeftParenthesis => break,
+                        };
+                        if top_priority < op.priority() {
+                            break;
                         }
+                        op_stack.pop();
+                        output.push_back(top_op.into_entry()?);
                     }
+                    op_stack.push(Operator::Binary(op));
                 }
+                InfixEntry::LeftParenthesis => op_stack.push(Operator::LeftParenthesis),
+                InfixEntry::RightParenthesis => loop {
+                    match op_stack.pop() {
+                        Some(Operator::LeftParenthesis) => break,
+                        Some(op) => output.push_back(op.into_entry()?),
+                        None => return Err(ConversionError::UnbalancedParenthesis),
+                    }
+                },
             }
+        }
 
-            while let Some(op) = op_stack.pop() {
-                output.push_back(op.try_into().unwrap());
-            }
+        while let Some(op) = op_stack.pop() {
+            output.push_back(op.into_entry()?);
+        }
 
-            output
-        })
+        Ok(output)
     }
 
-    /// Converts from postfix notation to tree.
-    pub fn into_tree(self) -> Tree {
-        match self {
-            PostfixNotation::Expression(mut expression) => {
-                MaybeAssignment::Expression(Self::get_node(&mut expression))
-            }
-            PostfixNotation::Assignment {
-                assignee,
-                operator,
-                mut expression,
-            } => MaybeAssignment::Assignment {
-                assignee,
-                operator,
-                expression: Self::get_node(&mut expression),
-            },
-        }
+    /// Converts from postfix notation to tree, allocating its nodes in the `arena`.
+    ///
+    /// # Errors
+    ///
+    /// Error is returned if operators don't match operands. Nodes that were allocated before
+    /// the error is found are left in the `arena`.
+    pub fn into_tree(self, arena: &mut ExprArena) -> Result<Tree, ConversionError> {
+        self.map_expr(|entries| Self::build(entries, arena))
+            .transpose()
     }
 
-    /// Converts from postfix notation to expression tree, issuing a error if it is not possible.
-    pub fn into_expression(self) -> Result<Expression, CompilerError> {
-        if let PostfixNotation::Expression(mut expression) = self {
-            Ok(Self::get_node(&mut expression))
-        } else {
-            Err(CompilerError)
+    /// Converts from postfix notation to expression tree.
+    ///
+    /// # Errors
+    ///
+    /// Error is returned if the notation is an assignment, or if operators don't match operands.
+    pub fn into_expression(self, arena: &mut ExprArena) -> Result<ExprId, ConversionError> {
+        match self {
+            PostfixNotation::Expression(entries) => Self::build(entries, arena),
+            PostfixNotation::Assignment { .. } => Err(ConversionError::Assignment),
         }
     }
 
-    fn get_node(buf: &mut VecDeque<PostfixEntry>) -> Expression {
-        match buf.pop_back().unwrap() {
-            PostfixEntry::Operand(expr) => expr,
-            PostfixEntry::UnaryOperator(punc) => {
-                let value = Box::new(Self::get_node(buf));
-                Expression::Unary { op: punc, value }
-            }
-            PostfixEntry::BinaryOperator(punc) => {
-                let right = Box::new(Self::get_node(buf));
-                let left = Box::new(Self::get_node(buf));
-                Expression::Binary {
-                    op: punc,
-                    left,
-                    right,
+    /// Builds the tree bottom-up, so its depth isn't limited by the stack.
+    fn build(
+        entries: VecDeque<PostfixEntry>,
+        arena: &mut ExprArena,
+    ) -> Result<ExprId, ConversionError> {
+        let mut operands = Vec::<ExprId>::new();
+        for entry in entries {
+            let expr = match entry {
+                PostfixEntry::Operand(expr) => expr,
+                PostfixEntry::UnaryOperator(op) => {
+                    let value = operands.pop().ok_or(ConversionError::MissingOperand)?;
+                    let span = arena[value].span;
+                    Expression::new(ExpressionKind::Unary { op, value }, span)
                 }
-            }
+                PostfixEntry::Cast(type_, span) => {
+                    let value = operands.pop().ok_or(ConversionError::MissingOperand)?;
+                    let span = arena[value].span.join(span);
+                    Expression::new(ExpressionKind::Cast { value, type_ }, span)
+                }
+                PostfixEntry::BinaryOperator(op) => {
+                    let right = operands.pop().ok_or(ConversionError::MissingOperand)?;
+                    let left = operands.pop().ok_or(ConversionError::MissingOperand)?;
+                    let span = arena[left].span.join(arena[right].span);
+                    Expression::new(ExpressionKind::Binary { op, left, right }, span)
+                }
+            };
+            operands.push(arena.alloc(expr));
+        }
+        match operands[..] {
+            [root] => Ok(root),
+            [] => Err(ConversionError::MissingOperand),
+            _ => Err(ConversionError::MissingOperator),
         }
     }
 }
 
-/// An entry of postfix expression: operand or operator (unary or binary).
+/// Reason why infix notation can't be converted to a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    /// Parenthesis is closed without being opened or is never closed.
+    UnbalancedParenthesis,
+    /// Operator lacks an operand or the expression is empty.
+    MissingOperand,
+    /// Operands are not separated by an operator.
+    MissingOperator,
+    /// Assignment is found where an expression is expected.
+    Assignment,
+}
+
+/// An entry of postfix expression: operand or operator (unary, binary or cast).
 #[derive(Debug, PartialEq, Eq)]
 pub enum PostfixEntry {
     Operand(Expression),
     UnaryOperator(UnaryOp),
     BinaryOperator(BinaryOp),
+    Cast(TypeExpr, Span),
 }
 
-impl TryFrom<Operator> for PostfixEntry {
-    type Error = ();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    LeftParenthesis,
+}
 
-    fn try_from(value: Operator) -> Result<Self, Self::Error> {
-        match value {
+impl Operator {
+    /// Converts operator popped from the stack to postfix entry.
+    ///
+    /// Left parenthesis is never converted, as it must be removed by matching right one.
+    fn into_entry(self) -> Result<PostfixEntry, ConversionError> {
+        match self {
             Operator::Unary(op) => Ok(PostfixEntry::UnaryOperator(op)),
             Operator::Binary(op) => Ok(PostfixEntry::BinaryOperator(op)),
-            Operator::LeftParenthesis => Err(()),
+            Operator::LeftParenthesis => Err(ConversionError::UnbalancedParenthesis),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum Operator {
-    Unary(UnaryOp),
-    Binary(BinaryOp),
-    LeftParenthesis,
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use crate::{
+        ast::{
+            expression::{ExprArena, ExprId, Expression, ExpressionKind, Literal},
+            statement::Place,
+        },
+        lexer::operator::{AssignOp, BinaryOp, UnaryOp},
+        parser::{
+            operator_expression::infix::{InfixEntry, InfixNotation},
+            FileParser,
+        },
+        util::Span,
+        Identifier,
+    };
+
+    use super::{ConversionError, PostfixNotation};
+
+    fn var() -> InfixEntry {
+        let kind = ExpressionKind::Var(Identifier(String::from("x")));
+        InfixEntry::Operand(Expression::new(kind, Span::default()))
+    }
+
+    fn convert(entries: Vec<InfixEntry>) -> Result<(), ConversionError> {
+        let infix = InfixNotation::Expression(VecDeque::from(entries));
+        let postfix = PostfixNotation::from_infix(infix)?;
+        postfix.into_expression(&mut ExprArena::new()).map(|_| ())
+    }
+
+    /// Generates pseudo-random numbers with xorshift, so failures are reproducible.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn tree() {
+        let mut parser = FileParser::new_test("1 - 2 * 3 - -x");
+        let root = parser.parse_expr().expect("parsing failed");
+        let exprs = parser.item_table.exprs();
+        assert_eq!(exprs.len(), 8);
+
+        let ExpressionKind::Binary { op: BinaryOp::Sub, left, right } = &exprs[root].kind else {
+            panic!("root should be subtraction");
+        };
+        assert!(matches!(
+            exprs[*right].kind,
+            ExpressionKind::Unary {
+                op: UnaryOp::Sub,
+                ..
+            }
+        ));
+        let ExpressionKind::Binary { op: BinaryOp::Sub, right, .. } = &exprs[*left].kind else {
+            panic!("subtraction should be left-associative");
+        };
+        assert!(matches!(
+            exprs[*right].kind,
+            ExpressionKind::Binary {
+                op: BinaryOp::Mul,
+                ..
+            }
+        ));
+    }
+
+    /// Parses `src` and renders its tree with every operator parenthesized.
+    fn grouping(src: &str) -> String {
+        fn render(exprs: &ExprArena, expr: ExprId) -> String {
+            match &exprs[expr].kind {
+                ExpressionKind::Unary { op, value } => format!("({op}{})", render(exprs, *value)),
+                ExpressionKind::Binary { op, left, right } => {
+                    format!("({} {op} {})", render(exprs, *left), render(exprs, *right))
+                }
+                ExpressionKind::Cast { value, type_ } => {
+                    format!("({} as {type_})", render(exprs, *value))
+                }
+                ExpressionKind::Var(var) => var.to_string(),
+                ExpressionKind::Literal(Literal::Number(num)) => num.to_string(),
+                kind => panic!("unexpected expression {kind:?}"),
+            }
+        }
+        let mut parser = FileParser::new_test(src);
+        let root = parser.parse_expr().expect("parsing failed");
+        render(parser.item_table.exprs(), root)
+    }
+
+    #[test]
+    fn unary_priority() {
+        assert_eq!(grouping("-x * y"), "((-x) * y)");
+        assert_eq!(grouping("-x + y"), "((-x) + y)");
+        assert_eq!(grouping("!a == b"), "((!a) == b)");
+        assert_eq!(grouping("-(x + y)"), "(-(x + y))");
+        assert_eq!(grouping("2 * - -x / 3"), "((2 * (-(-x))) / 3)");
+    }
+
+    #[test]
+    fn cast_priority() {
+        assert_eq!(grouping("a + b as f32"), "(a + (b as f32))");
+        assert_eq!(grouping("-x as f32 * y"), "(((-x) as f32) * y)");
+        assert_eq!(grouping("x as i64 as f64"), "((x as i64) as f64)");
+        assert_eq!(grouping("(a + b) as u8 < c"), "(((a + b) as u8) < c)");
+    }
+
+    #[test]
+    fn logical_priority() {
+        assert_eq!(
+            grouping("a < b && b < c || d"),
+            "(((a < b) && (b < c)) || d)"
+        );
+        assert_eq!(grouping("a || b && c == d"), "(a || (b && (c == d)))");
+    }
+
+    #[test]
+    fn deep_expression() {
+        const TERMS: usize = 30_000;
+        let src = vec!["1"; TERMS].join(" + ");
+
+        let mut parser = FileParser::new_test(&src);
+        let mut node = parser.parse_expr().expect("parsing failed");
+        let exprs = parser.item_table.exprs();
+        assert_eq!(exprs.len(), 2 * TERMS - 1);
+        let mut depth = 0;
+        while let ExpressionKind::Binary { left, .. } = exprs[node].kind {
+            node = left;
+            depth += 1;
+        }
+        assert_eq!(depth, TERMS - 1);
+        assert!(matches!(
+            exprs[node].kind,
+            ExpressionKind::Literal(Literal::Number(_))
+        ));
+
+        let parsed = FileParser::new_test(&format!("fn main() -> i32 {{ {src} }}")).parse();
+        assert!(parsed.errors.is_empty(), "parsing failed");
+    }
+
+    #[test]
+    fn malformed() {
+        use super::InfixEntry::*;
+        use ConversionError::*;
+
+        let cases = [
+            (vec![RightParenthesis], UnbalancedParenthesis),
+            (vec![var(), RightParenthesis], UnbalancedParenthesis),
+            (vec![LeftParenthesis, var()], UnbalancedParenthesis),
+            (vec![], MissingOperand),
+            (vec![UnaryOperator(UnaryOp::Not)], MissingOperand),
+            (vec![var(), BinaryOperator(BinaryOp::Add)], MissingOperand),
+            (vec![var(), var()], MissingOperator),
+        ];
+        for (entries, error) in cases {
+            assert_eq!(convert(entries), Err(error));
+        }
+
+        let assignment = InfixNotation::Assignment {
+            assignee: Place {
+                var: Identifier(String::from("x")),
+                projections: Vec::new(),
+            },
+            operator: AssignOp::Assign,
+            expression: VecDeque::from([var()]),
+        };
+        let postfix = PostfixNotation::from_infix(assignment).unwrap();
+        assert_eq!(
+            postfix.into_expression(&mut ExprArena::new()),
+            Err(Assignment)
+        );
+    }
+
+    /// Random sequences of entries are either converted or rejected, but never panic.
+    #[test]
+    fn fuzz_entries() {
+        use super::InfixEntry::*;
+
+        let mut random = Random(0x5EED);
+        for _ in 0..10_000 {
+            let len = random.below(12);
+            let entries = (0..len)
+                .map(|_| match random.below(5) {
+                    0 => var(),
+                    1 => UnaryOperator(UnaryOp::Sub),
+                    2 => BinaryOperator([BinaryOp::Add, BinaryOp::Mul][random.below(2)]),
+                    3 => LeftParenthesis,
+                    _ => RightParenthesis,
+                })
+                .collect();
+            let _ = convert(entries);
+        }
+    }
+
+    /// Random sequences of tokens are either parsed or reported, but never panic.
+    ///
+    /// Errors in blocks are recovered from, so an expression may be parsed despite them.
+    #[test]
+    fn fuzz_tokens() {
+        const TOKENS: [&str; 16] = [
+            "1", "x", "true", "+", "-", "*", "!", "==", "=", "+=", "(", ")", "{", "}", ";", "f(",
+        ];
+
+        let mut random = Random(0xF00D);
+        for _ in 0..10_000 {
+            let len = random.below(12);
+            let src = (0..len)
+                .map(|_| TOKENS[random.below(TOKENS.len())])
+                .collect::<Vec<_>>()
+                .join(" ");
+            let mut parser = FileParser::new_test(&src);
+            if parser.parse_expr().is_err() {
+                assert!(parser.context.error_reporter.compilation_failed(), "{src}");
+            }
+        }
+    }
 }
@@ -0,0 +1,77 @@
+use crate::{
+    error::{
+        library::parser::{InvalidCrateKw, InvalidSuperKw},
+        CompilerError, ReportProvider,
+    },
+    input_stream::Location,
+    parser::FileParser,
+    path::{RelativePath, RelativePathStart},
+    Identifier,
+};
+
+/// [RelativePath]'s parsing.
+impl FileParser {
+    /// Parse path.
+    pub fn parse_path(&mut self) -> Result<RelativePath, CompilerError> {
+        let start = self.location();
+        let first = self.lexer.expect_identifier()?;
+        self.parse_path_from(start, first)
+    }
+
+    /// Parse path. Its first segment is expected to be consumed beforehand.
+    pub(super) fn parse_path_from(
+        &mut self,
+        start: Location,
+        first: Identifier,
+    ) -> Result<RelativePath, CompilerError> {
+        let path_start = match first.as_str() {
+            "super" => RelativePathStart::Super(1),
+            "crate" => RelativePathStart::Crate,
+            _ => RelativePathStart::Identifier(first),
+        };
+        let mut path = RelativePath::new(path_start);
+        while self.lexer.consume_punctuation("::")? {
+            let ident = self.lexer.expect_identifier()?;
+            match ident.as_str() {
+                "super" if path.other.is_empty() => {
+                    let RelativePathStart::Super(ref mut n) = path.start else {
+                        return InvalidSuperKw::report(self, start).map(|_| unreachable!());
+                    };
+                    *n += 1;
+                }
+                "super" => {
+                    return InvalidSuperKw::report(self, start).map(|_| unreachable!());
+                }
+                "crate" => {
+                    return InvalidCrateKw::report(self, start).map(|_| unreachable!());
+                }
+                _ => path.push(ident),
+            };
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        parser::FileParser,
+        path::{RelativePath, RelativePathStart},
+        Identifier,
+    };
+
+    #[test]
+    fn parse_super_path() {
+        let mut parser = FileParser::new_test("super::super::module::item");
+        let mut expected = RelativePath::new(RelativePathStart::Super(2));
+        expected.push(Identifier(String::from("module")));
+        expected.push(Identifier(String::from("item")));
+        assert_eq!(expected, parser.parse_path().unwrap());
+    }
+
+    #[test]
+    fn misplaced_super() {
+        let mut parser = FileParser::new_test("module::super::item");
+        assert!(parser.parse_path().is_err());
+    }
+}
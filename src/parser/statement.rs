@@ -1,4 +1,13 @@
-use crate::{ast::statement::LetStatement, error::CompilerError, parser::FileParser};
+use crate::{
+    ast::statement::LetStatement,
+    error::{
+        library::lexer::TokenMismatch, Applicability, CompilerError, Label, ReportProvider,
+        Suggestion,
+    },
+    lexer::{keyword::Keyword, punctuation::Punctuation, Token},
+    parser::FileParser,
+    util::Span,
+};
 
 /// [Statement]'s parsing.
 ///
@@ -8,19 +17,71 @@ impl FileParser {
     ///
     /// [let]: crate::lexer::keyword::Keyword::Let
     pub fn parse_let(&mut self) -> Result<LetStatement, CompilerError> {
+        let start = self.lexer.last_token_start();
         let name = self.lexer.expect_identifier()?;
         let mut statement = LetStatement {
             name,
             type_: None,
+            type_span: None,
             value: None,
         };
         if self.lexer.consume_punctuation(":")? {
-            statement.type_ = Some(self.lexer.expect_identifier()?);
+            let start = self.lexer.token_start();
+            statement.type_ = Some(self.parse_type()?);
+            statement.type_span = Some(self.span_from(start));
         }
         if self.lexer.consume_punctuation("=")? {
-            statement.value = Some(Box::new(self.parse_expr()?));
+            let site = Label::new(self.span_from(start), "value is expected due to this `let`");
+            statement.value = Some(self.expecting_value(site, Self::parse_expr)?);
         }
-        self.lexer.expect_punctuation(";")?;
+        self.expect_semicolon()?;
         Ok(statement)
     }
+
+    /// Check if next token is `;` or error otherwise.
+    ///
+    /// Error suggests to insert `;` right after the last consumed token. Unexpected token is not
+    /// consumed.
+    ///
+    /// If the next token starts a statement, closes the block or is on the next line, `;` is
+    /// assumed to be forgotten, so error is only recorded and parsing continues with the next
+    /// statement.
+    pub fn expect_semicolon(&mut self) -> Result<(), CompilerError> {
+        let end = self.lexer.token_end();
+        if self.lexer.consume_punctuation(";")? {
+            return Ok(());
+        }
+        let found = self.lexer.peek()?;
+        let span = Span {
+            source: self.source(),
+            start: end,
+            end,
+        };
+        let suggestion =
+            Suggestion::new("add `;` here", span, ";", Applicability::MachineApplicable);
+        let is_boundary = matches!(
+            found,
+            Token::Kw(
+                Keyword::Let
+                    | Keyword::Return
+                    | Keyword::Break
+                    | Keyword::If
+                    | Keyword::While
+                    | Keyword::For
+            ) | Token::Punc(Punctuation::RBrace)
+        ) || self.lexer.token_start().line > end.line;
+        let report = TokenMismatch::report_with(
+            &self.context.error_reporter,
+            span,
+            suggestion,
+            vec![Punctuation::Semicolon.into()],
+            found,
+        )
+        .map(|_| unreachable!());
+        if is_boundary {
+            self.errors.extend(report.err());
+            return Ok(());
+        }
+        report
+    }
 }
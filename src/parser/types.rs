@@ -0,0 +1,134 @@
+use crate::{
+    ast::types::TypeExpr,
+    error::{
+        library::{lexer::TokenMismatch, parser::InvalidArrayLength},
+        CompilerError, ExpectedToken, ReportProvider,
+    },
+    lexer::{number::Number, punctuation::Punctuation, Token},
+    parser::FileParser,
+    Identifier,
+};
+
+/// [TypeExpr]'s parsing.
+impl FileParser {
+    /// Parse type expression.
+    pub fn parse_type(&mut self) -> Result<TypeExpr, CompilerError> {
+        let start = self.location();
+        match self.lexer.next()? {
+            Token::Ident(ident) => Ok(TypeExpr::Path(
+                self.parse_path_from(start, Identifier(ident))?,
+            )),
+            Token::Punc(Punctuation::LParent) => self.parse_tuple_type(),
+            Token::Punc(Punctuation::LBracket) => self.parse_array_type(),
+            token => TokenMismatch::report(
+                self,
+                start,
+                vec![
+                    ExpectedToken::Type,
+                    Punctuation::LParent.into(),
+                    Punctuation::LBracket.into(),
+                ],
+                token,
+            )
+            .map(|_| unreachable!()),
+        }
+    }
+
+    /// Parse tuple type. Opening parenthesis is expected to be consumed beforehand.
+    ///
+    /// A single type in parenthesis without trailing comma is not a tuple, so the type itself is returned.
+    fn parse_tuple_type(&mut self) -> Result<TypeExpr, CompilerError> {
+        if self.lexer.consume_punctuation(")")? {
+            return Ok(TypeExpr::Unit);
+        }
+        let mut types = Vec::new();
+        loop {
+            types.push(self.parse_type()?);
+            if self.lexer.consume_punctuation(")")? {
+                if types.len() == 1 {
+                    return Ok(types.pop().unwrap());
+                }
+                break;
+            }
+            self.lexer.expect_punctuation(",")?;
+            if self.lexer.consume_punctuation(")")? {
+                break;
+            }
+        }
+        Ok(TypeExpr::Tuple(types))
+    }
+
+    /// Parse array type. Opening bracket is expected to be consumed beforehand.
+    fn parse_array_type(&mut self) -> Result<TypeExpr, CompilerError> {
+        let element = Box::new(self.parse_type()?);
+        self.lexer.expect_punctuation(";")?;
+        let start = self.location();
+        let length = match self.lexer.next()? {
+            Token::Num(Number {
+                integer,
+                fraction: None,
+                base,
+            }) => usize::from_str_radix(&integer, base.radix()).ok(),
+            _ => None,
+        };
+        let Some(length) = length else {
+            return InvalidArrayLength::report(self, start).map(|_| unreachable!());
+        };
+        self.lexer.expect_punctuation("]")?;
+        Ok(TypeExpr::Array { element, length })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        ast::types::TypeExpr,
+        parser::FileParser,
+        path::{RelativePath, RelativePathStart},
+        Identifier,
+    };
+
+    fn parse(src: &str) -> TypeExpr {
+        FileParser::new_test(src)
+            .parse_type()
+            .expect("parsing failed")
+    }
+
+    fn ident(name: &str) -> TypeExpr {
+        Identifier(String::from(name)).into()
+    }
+
+    #[test]
+    fn path() {
+        let mut path = RelativePath::new(RelativePathStart::Crate);
+        path.push(Identifier(String::from("geometry")));
+        path.push(Identifier(String::from("Point")));
+        assert_eq!(TypeExpr::Path(path), parse("crate::geometry::Point"));
+    }
+
+    #[test]
+    fn tuple() {
+        assert_eq!(
+            TypeExpr::Tuple(vec![ident("i32"), ident("bool")]),
+            parse("(i32, bool)")
+        );
+        assert_eq!(TypeExpr::Tuple(vec![ident("i32")]), parse("(i32,)"));
+        assert_eq!(ident("i32"), parse("(i32)"));
+        assert_eq!(TypeExpr::Unit, parse("()"));
+    }
+
+    #[test]
+    fn array() {
+        let expected = TypeExpr::Array {
+            element: Box::new(TypeExpr::Tuple(vec![ident("u8"), ident("u8")])),
+            length: 16,
+        };
+        assert_eq!(expected, parse("[(u8, u8); 0x10]"));
+    }
+
+    #[test]
+    fn invalid_array_length() {
+        assert!(FileParser::new_test("[i32; 1.5]").parse_type().is_err());
+        assert!(FileParser::new_test("[i32; N]").parse_type().is_err());
+    }
+}
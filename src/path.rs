@@ -3,9 +3,11 @@ use thiserror::Error;
 
 mod absolute;
 mod relative;
+mod resolve;
 
 pub use absolute::AbsolutePath;
 pub use relative::{RelativePath, RelativePathStart};
+pub use resolve::resolve_path;
 
 #[derive(Debug, PartialEq, Eq, Error)]
 pub enum PathParsingError {
@@ -34,10 +34,24 @@ impl AbsolutePath {
         self.other.last().unwrap_or(&self.krate)
     }
 
-    pub fn iter(&self) -> slice::Iter<Identifier> {
+    pub fn iter(&self) -> slice::Iter<'_, Identifier> {
         self.other.iter()
     }
 
+    /// Checks if the path points to an item declared directly in the `module`.
+    pub fn is_child_of(&self, module: &AbsolutePath) -> bool {
+        self.krate == module.krate
+            && self.other.len() == module.other.len() + 1
+            && self.other.starts_with(&module.other)
+    }
+
+    /// Checks if the path points to an item declared in the `module` or in its nested modules.
+    pub fn is_descendant_of(&self, module: &AbsolutePath) -> bool {
+        self.krate == module.krate
+            && self.other.len() > module.other.len()
+            && self.other.starts_with(&module.other)
+    }
+
     /// Maps [AbsolutePath] into relative [PathBuf].
     ///
     /// # Example
@@ -102,6 +116,22 @@ impl FromStr for AbsolutePath {
     }
 }
 
+/// Path is serialized as a string, so it may be used as a key of JSON object.
+#[cfg(feature = "serde")]
+impl serde::Serialize for AbsolutePath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AbsolutePath {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        AbsolutePath::from_str(&path).map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
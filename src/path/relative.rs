@@ -5,6 +5,7 @@ use crate::Identifier;
 
 /// A relative path that is interpreted differently depending on context.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RelativePath {
     pub(crate) start: RelativePathStart,
     pub(crate) other: Vec<Identifier>,
@@ -12,6 +13,7 @@ pub struct RelativePath {
 
 /// First segment of the relative path
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RelativePathStart {
     Crate,
     Super(usize),
@@ -49,6 +51,15 @@ impl RelativePath {
         self.other.pop()
     }
 
+    /// Returns the last segment of the path if it is an identifier.
+    pub fn last(&self) -> Option<&Identifier> {
+        match (self.other.last(), &self.start) {
+            (Some(ident), _) => Some(ident),
+            (None, RelativePathStart::Identifier(ident)) => Some(ident),
+            (None, _) => None,
+        }
+    }
+
     /// Try to map relative path to absolute based on context.
     ///
     /// Returns `None` if the resulting path is invalid (e. g. `super` used on root level).
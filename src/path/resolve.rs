@@ -0,0 +1,39 @@
+use crate::Identifier;
+
+use super::{AbsolutePath, RelativePath, RelativePathStart};
+
+/// Maps relative `path` met in the `module` to absolute, taking imports of the module into
+/// account.
+///
+/// Items declared in `bodies` of functions that enclose the path, innermost first, shadow items
+/// of the module. Path imported into the module under a name is looked up by `import`, and
+/// `is_declared` checks if an item that may be referenced by path is declared at a path.
+pub fn resolve_path<'a>(
+    path: &RelativePath,
+    module: &AbsolutePath,
+    bodies: &[AbsolutePath],
+    import: impl FnOnce(&Identifier) -> Option<&'a RelativePath>,
+    is_declared: impl Fn(&AbsolutePath) -> bool,
+) -> Option<AbsolutePath> {
+    if let RelativePathStart::Identifier(first) = &path.start {
+        let nested = bodies.iter().find_map(|body| {
+            let mut item = body.clone();
+            item.push(first.clone());
+            is_declared(&item).then_some(item)
+        });
+        if let Some(mut resolved) = nested {
+            for ident in path.other.iter() {
+                resolved.push(ident.clone());
+            }
+            return Some(resolved);
+        }
+        if let Some(import) = import(first) {
+            let mut resolved = import.to_absolute(module)?;
+            for ident in path.other.iter() {
+                resolved.push(ident.clone());
+            }
+            return Some(resolved);
+        }
+    }
+    path.to_absolute(module)
+}
@@ -0,0 +1,181 @@
+//! Queries over the result of compilation by position in the source code, as needed by editors.
+
+use crate::{
+    ast::item::{Item, ItemKind},
+    context::Context,
+    input_stream::{InputStream, Location},
+    lexer::{Lexer, Token},
+    path::{resolve_path, AbsolutePath, RelativePath},
+    source::SourceId,
+    util::Span,
+    CompileResult,
+};
+
+impl CompileResult {
+    /// Finds the innermost item which span covers byte `offset` of the `source`.
+    ///
+    /// Root module of the crate spans its whole file, but it's not declared there, so it's never
+    /// found.
+    pub fn item_at(&self, source: SourceId, offset: usize) -> Option<(&AbsolutePath, &Item)> {
+        self.item_table
+            .iter()
+            .filter(|(path, _)| path.iter().len() > 0)
+            .filter(|(_, item)| item.span.source == Some(source) && item.span.contains(offset))
+            .min_by_key(|(_, item)| item.span.len_bytes())
+    }
+
+    /// Finds the token that covers byte `offset` of the `source`.
+    ///
+    /// Only the line that contains the offset is lexed again, so tokens that span multiple
+    /// lines aren't found.
+    pub fn token_at(&self, source: SourceId, offset: usize) -> Option<(Token, Span)> {
+        let (text, number, start) = {
+            let mut source_map = self.context.source.lock().unwrap();
+            let index = source_map.get(source).line_index().ok()?;
+            let number = index.location(offset)?.line;
+            let start = index.offset(number, 0)?;
+            (String::from(index.line(number)?), number, start)
+        };
+        // Errors are already reported by the compilation, so they go to a scratch context.
+        let mut lexer = Lexer::new(
+            InputStream::new(&text, Some(source)),
+            Context::builder().build_str(""),
+        );
+        lexer.tokenize().into_iter().find_map(|(token, span)| {
+            let span = Span {
+                start: shift(span.start, start, number),
+                end: shift(span.end, start, number),
+                ..span
+            };
+            (token != Token::Eof && span.contains(offset)).then_some((token, span))
+        })
+    }
+
+    /// Finds the span of the item that `path` refers to when used in `from_module`.
+    ///
+    /// Paths are resolved by the same rules as during translation: `from_module` may be a
+    /// function, whose nested items shadow items of the enclosing module.
+    pub fn definition_of(&self, path: &RelativePath, from_module: &AbsolutePath) -> Option<Span> {
+        let (module, bodies) = self.item_scope(from_module.clone());
+        let resolved = resolve_path(
+            path,
+            &module,
+            &bodies,
+            |name| {
+                self.item_table
+                    .lookup_import(&module, name)
+                    .map(|import| &import.path)
+            },
+            |item| {
+                self.item_table
+                    .get(item)
+                    .is_some_and(|item| !matches!(item.kind, ItemKind::Use(_)))
+            },
+        )?;
+        Some(self.item_table.get(&resolved)?.span)
+    }
+
+    /// Finds the module that `parent` belongs to, along with bodies of functions that enclose
+    /// it, innermost first.
+    fn item_scope(&self, mut parent: AbsolutePath) -> (AbsolutePath, Vec<AbsolutePath>) {
+        let mut bodies = Vec::new();
+        loop {
+            match self.item_table.get(&parent).map(|item| &item.kind) {
+                Some(ItemKind::Function(_)) => bodies.push(parent.clone()),
+                Some(ItemKind::Struct(_)) => {}
+                _ => return (parent, bodies),
+            }
+            parent.pop();
+        }
+    }
+}
+
+/// Moves `location` within the line to the line `number` that starts at byte `start`.
+fn shift(location: Location, start: usize, number: usize) -> Location {
+    Location {
+        pos: location.pos + start,
+        line: number,
+        column: location.column,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{
+        context::{Context, Emit},
+        lexer::Token,
+        path::{AbsolutePath, RelativePath, RelativePathStart},
+        source::SourceId,
+        CompileResult, Identifier,
+    };
+
+    const SRC: &str = include_str!("../tests/fixtures/query.sun");
+
+    fn compile() -> (CompileResult, SourceId) {
+        let result = crate::compile_str(SRC, Context::builder().emit(Emit::Hir));
+        assert!(!result.failed());
+        let item = result.item_table.items().next().unwrap();
+        let source = item.span.source.unwrap();
+        (result, source)
+    }
+
+    fn path(segments: &[&str]) -> RelativePath {
+        let mut segments = segments.iter().map(|s| Identifier::from_str(s).unwrap());
+        let mut path = RelativePath::new(RelativePathStart::Identifier(segments.next().unwrap()));
+        segments.for_each(|ident| path.push(ident));
+        path
+    }
+
+    #[test]
+    fn item_at() {
+        let (result, source) = compile();
+        let item_path = |offset| {
+            let (path, _) = result.item_at(source, offset)?;
+            Some(path.to_string())
+        };
+        let x = SRC.find("x: i32").unwrap();
+        assert_eq!(item_path(x).as_deref(), Some("main::geometry::Point"));
+        let side = SRC.find("side * side").unwrap();
+        assert_eq!(item_path(side).as_deref(), Some("main::geometry::area"));
+        let two = SRC.find("2\n").unwrap();
+        assert_eq!(item_path(two).as_deref(), Some("main::main::helper"));
+        let call = SRC.find("area(helper())").unwrap();
+        assert_eq!(item_path(call).as_deref(), Some("main::main"));
+        assert_eq!(item_path(SRC.find("\n\nuse").unwrap() + 1), None);
+    }
+
+    #[test]
+    fn token_at() {
+        let (result, source) = compile();
+        let call = SRC.find("area(helper())").unwrap();
+        let (token, span) = result.token_at(source, call + 7).unwrap();
+        assert_eq!(token, Token::Ident(String::from("helper")));
+        assert_eq!(span.start.pos, call + 5);
+        assert_eq!(span.end.pos, call + 11);
+        assert_eq!(span.start.line, 16);
+        assert_eq!(span.start.column, 9);
+        assert!(result.token_at(source, call - 1).is_none());
+    }
+
+    #[test]
+    fn definition_of() {
+        let (result, _) = compile();
+        let main = AbsolutePath::from_str("main::main").unwrap();
+        let definition = |relative, module| {
+            let span = result.definition_of(&relative, module)?;
+            Some(&SRC[span.start.pos..span.end.pos])
+        };
+        let helper = definition(path(&["helper"]), &main).unwrap();
+        assert!(helper.starts_with("fn helper()"));
+        let area = definition(path(&["area"]), &main).unwrap();
+        assert!(area.starts_with("pub fn area("));
+        let point = definition(path(&["geometry", "Point"]), &main).unwrap();
+        assert!(point.starts_with("pub struct Point"));
+
+        let root = AbsolutePath::from_str("main").unwrap();
+        assert_eq!(definition(path(&["helper"]), &root), None);
+        assert_eq!(definition(path(&["missing"]), &main), None);
+    }
+}
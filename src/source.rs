@@ -1,22 +1,37 @@
 //! Source code and file hierarchy management.
 
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet},
     fs,
     io::{self, Read},
-    ops::IndexMut,
+    ops::{IndexMut, Range},
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 use thiserror::Error;
 
-use crate::{path::AbsolutePath, util::MonotonicVec};
+use crate::{
+    path::AbsolutePath,
+    util::{LineIndex, MonotonicVec, Span},
+};
+
+/// Path that the main file read from the standard input is referred to with.
+///
+/// Crate with such main file has no root directory, so its modules can't be loaded from files.
+pub const STDIN_PATH: &str = "<stdin>";
 
 /// The structure that holds the whole source code of the compiled program.
 #[derive(Debug)]
 pub struct SourceMap {
-    root: PathBuf,
+    /// Directory that modules are looked up in, if any.
+    root: Option<PathBuf>,
+    main: PathBuf,
     mapping: HashMap<PathBuf, SourceId>,
     files: MonotonicVec<SourceFile>,
+    /// Modification time of files read from the disk at the moment they were opened.
+    modified: HashMap<SourceId, SystemTime>,
+    /// In-memory files whose contents were replaced since the last [refresh](SourceMap::refresh).
+    replaced: HashSet<SourceId>,
 }
 
 impl SourceMap {
@@ -26,14 +41,51 @@ impl SourceMap {
     ///
     /// Error is only returned if `root` is not found or couldn't be opened.
     pub fn new(main: PathBuf) -> Result<Self, SourceError> {
+        let mut root = main.clone();
+        root.pop();
+        Self::create(root, main)
+    }
+
+    /// Creates new [SourceMap] with path to the main file and the directory modules are looked
+    /// up in.
+    ///
+    /// # Errors
+    ///
+    /// Error is returned if `root` is not a directory or `main` couldn't be opened.
+    pub fn with_root(root: PathBuf, main: PathBuf) -> Result<Self, SourceError> {
+        match fs::metadata(&root) {
+            Ok(meta) if meta.is_dir() => Self::create(root, main),
+            Ok(_) => Err(SourceError::NotADirectory(root)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Err(SourceError::NotFound(root)),
+            Err(err) => Err(SourceError::IoErrorWithSource(root, err)),
+        }
+    }
+
+    /// Creates new [SourceMap] with the main file given by its content.
+    ///
+    /// `main` is only used to refer to the file, it isn't read from the disk. If `root` is `None`,
+    /// modules can't be loaded from files.
+    pub fn in_memory(root: Option<PathBuf>, main: PathBuf, src: String) -> Self {
+        let mut map = Self {
+            mapping: HashMap::new(),
+            files: MonotonicVec::new(),
+            modified: HashMap::new(),
+            replaced: HashSet::new(),
+            root,
+            main: main.clone(),
+        };
+        map.insert_virtual(main, src);
+        map
+    }
+
+    fn create(root: PathBuf, main: PathBuf) -> Result<Self, SourceError> {
         let mut map = Self {
             mapping: HashMap::new(),
             files: MonotonicVec::new(),
-            root: {
-                let mut root = main.clone();
-                root.pop();
-                root
-            },
+            modified: HashMap::new(),
+            replaced: HashSet::new(),
+            root: Some(root),
+            main: main.clone(),
         };
         map.insert_path(main)?;
         Ok(map)
@@ -46,15 +98,47 @@ impl SourceMap {
         Ok(Self {
             mapping: HashMap::new(),
             files: MonotonicVec::new(),
-            root: PathBuf::from_str("/dev/null").unwrap(),
+            modified: HashMap::new(),
+            replaced: HashSet::new(),
+            root: Some(PathBuf::from_str("/dev/null").unwrap()),
+            main: PathBuf::from_str("/dev/null").unwrap(),
         })
     }
 
-    /// Inserts new source file to the map and returns its id.
+    /// Path to the main file of the crate.
+    pub fn main(&self) -> &Path {
+        &self.main
+    }
+
+    /// Inserts source file of the module at `path` to the map and returns its id.
+    ///
+    /// Module `crate::a::b` is looked up at `a/b.sun` and `a/b/mod.sun` relative to the root.
+    ///
+    /// # Errors
+    ///
+    /// Error is returned if neither or both of the files exist, or if there is no root directory.
     pub fn insert(&mut self, path: AbsolutePath) -> Result<SourceId, SourceError> {
-        let mut source_path = self.root.clone();
-        source_path.extend(path.into_path_buf().iter());
-        self.insert_path(source_path)
+        let (file, mod_file) = self.module_paths(path)?;
+        match (self.exists(&file), self.exists(&mod_file)) {
+            (true, false) => self.insert_path(file),
+            (false, true) => self.insert_path(mod_file),
+            (true, true) => Err(SourceError::AmbiguousModule(file, mod_file)),
+            (false, false) => Err(SourceError::ModuleNotFound(file, mod_file)),
+        }
+    }
+
+    /// Paths that the file of the module at `path` may be located at.
+    fn module_paths(&self, path: AbsolutePath) -> Result<(PathBuf, PathBuf), SourceError> {
+        let mut file = self.root.clone().ok_or(SourceError::MissingRoot)?;
+        file.extend(path.into_path_buf().iter());
+        let mut mod_file = file.with_extension("");
+        mod_file.push("mod.sun");
+        Ok((file, mod_file))
+    }
+
+    /// Check if file at `path` is in the map or on the disk.
+    fn exists(&self, path: &Path) -> bool {
+        self.mapping.contains_key(path) || path.is_file()
     }
 
     /// Inserts new source file to the map and returns its id.
@@ -62,20 +146,75 @@ impl SourceMap {
         let id = self.generate_id();
         Ok(match self.mapping.entry(path.clone()) {
             Entry::Vacant(entry) => {
-                let file = SourceFile::new(path)?;
+                let file = SourceFile::new(&path)?;
                 entry.insert(id);
                 self.files.push(file);
+                if let Some(modified) = modification_time(&path) {
+                    self.modified.insert(id, modified);
+                }
                 id
             }
             Entry::Occupied(entry) => *entry.get(),
         })
     }
 
+    /// Inserts in-memory source file to the map and returns its id.
+    ///
+    /// Filesystem is never accessed for such files, even if a file at `path` exists. If `path` is
+    /// already in the map, contents of the file are replaced.
+    pub fn insert_virtual(&mut self, path: PathBuf, contents: String) -> SourceId {
+        let id = self.generate_id();
+        let file = SourceFile::Loaded(LineIndex::new(contents));
+        match self.mapping.entry(path) {
+            Entry::Vacant(entry) => {
+                entry.insert(id);
+                self.files.push(file);
+                id
+            }
+            Entry::Occupied(entry) => {
+                let id = *entry.get();
+                *self.files.index_mut(id.0 as usize) = file;
+                self.modified.remove(&id);
+                self.replaced.insert(id);
+                id
+            }
+        }
+    }
+
+    /// Reloads the file at `path` if it was changed since it was loaded and returns its id.
+    ///
+    /// File read from the disk is opened again if its modification time differs. In-memory file
+    /// is changed if it was [replaced](SourceMap::insert_virtual). `None` is returned if the file
+    /// is unchanged, isn't in the map or couldn't be opened.
+    pub fn refresh(&mut self, path: &Path) -> Option<SourceId> {
+        let id = *self.mapping.get(path)?;
+        if self.replaced.remove(&id) {
+            return Some(id);
+        }
+        let recorded = *self.modified.get(&id)?;
+        let modified = modification_time(path)?;
+        if modified == recorded {
+            return None;
+        }
+        *self.files.index_mut(id.0 as usize) = SourceFile::new(path).ok()?;
+        self.modified.insert(id, modified);
+        Some(id)
+    }
+
     /// Gets file by id.
     pub fn get(&mut self, id: SourceId) -> &mut SourceFile {
         self.files.index_mut(id.0 as usize)
     }
 
+    /// Gets the code covered by `span`.
+    ///
+    /// `None` is returned if the span has no source or it is out of the file's bounds.
+    pub fn snippet(&mut self, span: Span) -> Option<String> {
+        let index = self.get(span.source?).line_index().ok()?;
+        let snippet = index.src().get(span.start.pos..span.end.pos)?;
+        Some(snippet.to_owned())
+    }
+
     /// Gets path of the file.
     ///
     /// That function may be slow as it traverses internal HashMap to find the value.
@@ -92,18 +231,24 @@ impl SourceMap {
     }
 }
 
+/// Modification time of the file at `path`, if it is supported by the platform.
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
 /// A sequential id of the file.
 ///
 /// It is guaranteed that every SourceId maps to a file.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SourceId(u32);
 
 /// A single file of the source code.
 ///
-/// File's content is buffered.
+/// File's content is buffered along with the [LineIndex] of it.
 #[derive(Debug)]
 pub enum SourceFile {
-    Loaded(String),
+    Loaded(LineIndex),
     Opened(fs::File),
 }
 
@@ -130,16 +275,34 @@ impl SourceFile {
 
     /// Read file to string slice.
     pub fn read(&mut self) -> Result<&str, SourceError> {
+        self.line_index().map(LineIndex::src)
+    }
+
+    /// Read file and get [LineIndex] of it.
+    pub fn line_index(&mut self) -> Result<&LineIndex, SourceError> {
         match self {
             SourceFile::Opened(file) => {
                 let mut buf = String::new();
                 file.read_to_string(&mut buf)?;
-                *self = SourceFile::Loaded(buf);
-                self.read()
+                *self = SourceFile::Loaded(LineIndex::new(buf));
+                self.line_index()
             }
-            SourceFile::Loaded(string) => Ok(string.as_str()),
+            SourceFile::Loaded(index) => Ok(index),
         }
     }
+
+    /// Zero-based line `n` of the file without line break.
+    ///
+    /// `None` is returned if there is no such line or the file couldn't be read.
+    pub fn line(&mut self, n: usize) -> Option<&str> {
+        self.line_index().ok()?.line(n)
+    }
+
+    /// Zero-based lines of the file within `range`, up to the last line of the file.
+    pub fn lines_in(&mut self, range: Range<usize>) -> impl Iterator<Item = &str> {
+        let index = self.line_index().ok();
+        range.map_while(move |n| index?.line(n))
+    }
 }
 
 /// Error caused by filesystem interaction.
@@ -147,6 +310,16 @@ impl SourceFile {
 pub enum SourceError {
     #[error("provided path `{0}` is expected to be a file")]
     NotAFile(PathBuf),
+    #[error("provided path `{0}` is expected to be a directory")]
+    NotADirectory(PathBuf),
+    #[error("main file of the crate is not specified")]
+    MissingMain,
+    #[error("crate has no root directory to look up modules in")]
+    MissingRoot,
+    #[error("file of the module is not found at `{0}` or `{1}`")]
+    ModuleNotFound(PathBuf, PathBuf),
+    #[error("file of the module is found at both `{0}` and `{1}`")]
+    AmbiguousModule(PathBuf, PathBuf),
     #[error("provided path `{0}` is expected to be relative")]
     NotRelative(PathBuf),
     #[error("permission to access `{0}` was denied")]
@@ -158,3 +331,38 @@ pub enum SourceError {
     #[error("{0}")]
     IoError(#[from] io::Error),
 }
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::util::Span;
+
+    use super::SourceMap;
+
+    #[test]
+    fn lines() {
+        let mut map = SourceMap::new_test().unwrap();
+        let src = "fn main() {\r\n    let ñ: i32 = 1;\r\n}";
+        let id = map.insert_virtual(PathBuf::from("main.sun"), String::from(src));
+        let file = map.get(id);
+        assert_eq!(file.line(0), Some("fn main() {"));
+        assert_eq!(file.line(2), Some("}"));
+        assert_eq!(file.line(3), None);
+        let lines: Vec<_> = file.lines_in(1..5).collect();
+        assert_eq!(lines, ["    let ñ: i32 = 1;", "}"]);
+
+        let index = file.line_index().unwrap();
+        let span = Span {
+            source: Some(id),
+            start: index.location(17).unwrap(),
+            end: index.location(32).unwrap(),
+        };
+        assert_eq!(span.start.line, 1);
+        assert_eq!(map.snippet(span).as_deref(), Some("let ñ: i32 = 1"));
+
+        let id = map.insert_virtual(PathBuf::from("other.sun"), String::from("a\nb\n"));
+        let lines: Vec<_> = map.get(id).lines_in(0..usize::MAX).collect();
+        assert_eq!(lines, ["a", "b"]);
+    }
+}
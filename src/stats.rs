@@ -0,0 +1,179 @@
+//! Duration and size of compilation phases.
+//!
+//! Statistics are only collected if [timings](crate::context::ContextBuilder::timings) are
+//! enabled, see [CompileResult::stats](crate::CompileResult::stats).
+
+use std::{
+    io::{Result, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::error::json::string;
+
+/// Statistics of the whole compilation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileStats {
+    /// Parsed files in the order they were loaded in.
+    pub files: Vec<FileStats>,
+    /// Translation to HIR, `None` if it wasn't performed.
+    pub hir: Option<HirStats>,
+    /// Duration of the whole compilation.
+    pub total: Duration,
+}
+
+/// Lexing and parsing of a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStats {
+    pub path: PathBuf,
+    pub duration: Duration,
+    /// Number of tokens that were read, not including end of file.
+    pub tokens: usize,
+    /// Number of items declared in the file, including ones of its inline modules.
+    pub items: usize,
+}
+
+/// Translation to HIR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HirStats {
+    pub duration: Duration,
+    /// Number of functions that were translated, including ones that failed to translate.
+    pub functions: usize,
+    /// Number of errors reported during translation.
+    pub errors: usize,
+}
+
+impl CompileStats {
+    /// Writes statistics as a human-readable table.
+    ///
+    /// ```text
+    /// phase         time  details
+    /// parse      1.214ms  src/main.sun: 250 tokens, 12 items
+    /// hir      830.000µs  8 functions, 0 errors
+    /// total      2.101ms
+    /// ```
+    pub fn write_table(&self, mut w: impl Write) -> Result<()> {
+        writeln!(w, "phase  {:>11}  details", "time")?;
+        for file in self.files.iter() {
+            writeln!(
+                w,
+                "parse  {:>11}  {}: {} tokens, {} items",
+                format!("{:.3?}", file.duration),
+                file.path.display(),
+                file.tokens,
+                file.items,
+            )?;
+        }
+        if let Some(hir) = &self.hir {
+            writeln!(
+                w,
+                "hir    {:>11}  {} functions, {} errors",
+                format!("{:.3?}", hir.duration),
+                hir.functions,
+                hir.errors,
+            )?;
+        }
+        writeln!(w, "total  {:>11}", format!("{:.3?}", self.total))
+    }
+
+    /// Writes statistics as a single line of JSON of the following structure:
+    ///
+    /// ```json
+    /// {
+    ///     "files": [
+    ///         { "path": "src/main.sun", "time_ms": 1.214, "tokens": 250, "items": 12 }
+    ///     ],
+    ///     "hir": { "time_ms": 0.830, "functions": 8, "errors": 0 },
+    ///     "total_ms": 2.101
+    /// }
+    /// ```
+    ///
+    /// `hir` is `null` if translation to HIR wasn't performed.
+    pub fn write_json(&self, mut w: impl Write) -> Result<()> {
+        let files: Vec<_> = self
+            .files
+            .iter()
+            .map(|file| {
+                format!(
+                    r#"{{"path":{},"time_ms":{},"tokens":{},"items":{}}}"#,
+                    string(&file.path.to_string_lossy()),
+                    millis(file.duration),
+                    file.tokens,
+                    file.items,
+                )
+            })
+            .collect();
+        let hir = match &self.hir {
+            Some(hir) => format!(
+                r#"{{"time_ms":{},"functions":{},"errors":{}}}"#,
+                millis(hir.duration),
+                hir.functions,
+                hir.errors,
+            ),
+            None => String::from("null"),
+        };
+        writeln!(
+            w,
+            r#"{{"files":[{}],"hir":{hir},"total_ms":{}}}"#,
+            files.join(","),
+            millis(self.total),
+        )
+    }
+}
+
+/// Formats `duration` as a number of milliseconds.
+fn millis(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::{CompileStats, FileStats, HirStats};
+
+    fn stats() -> CompileStats {
+        CompileStats {
+            files: vec![FileStats {
+                path: PathBuf::from("src/main.sun"),
+                duration: Duration::from_micros(1214),
+                tokens: 250,
+                items: 12,
+            }],
+            hir: Some(HirStats {
+                duration: Duration::from_micros(830),
+                functions: 8,
+                errors: 0,
+            }),
+            total: Duration::from_micros(2101),
+        }
+    }
+
+    #[test]
+    fn table() {
+        let mut output = Vec::new();
+        stats().write_table(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "phase         time  details
+parse      1.214ms  src/main.sun: 250 tokens, 12 items
+hir      830.000µs  8 functions, 0 errors
+total      2.101ms
+"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let mut output = Vec::new();
+        stats().write_json(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"{"files":[{"path":"src/main.sun","time_ms":1.214,"tokens":250,"items":12}],"#,
+                r#""hir":{"time_ms":0.830,"functions":8,"errors":0},"total_ms":2.101}"#,
+                "\n"
+            )
+        );
+    }
+}
@@ -1,8 +1,12 @@
 //! Various utility functions and types.
 
+mod edit_distance;
+mod line_index;
 mod monotonic;
 mod span;
 
+pub use edit_distance::edit_distance;
+pub use line_index::LineIndex;
 pub use monotonic::MonotonicVec;
 pub use span::Span;
 
@@ -0,0 +1,30 @@
+/// Computes Levenshtein distance between two strings.
+///
+/// That's the minimal number of single-character insertions, deletions and substitutions
+/// required to turn one string into another.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_ch) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_ch) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_ch != *b_ch);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::edit_distance;
+
+    #[test]
+    fn distance() {
+        assert_eq!(edit_distance("area", "area"), 0);
+        assert_eq!(edit_distance("area", "aera"), 2);
+        assert_eq!(edit_distance("area", "areas"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
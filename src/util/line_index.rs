@@ -0,0 +1,133 @@
+use std::ops::Range;
+
+use crate::input_stream::Location;
+
+/// Source code along with the table of starts of its lines.
+///
+/// Converts byte offsets to [locations](Location) and back without scanning the whole code, as
+/// the line is found by binary search. Columns are counted in characters.
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    src: String,
+    /// Byte offsets of the first character of every line.
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(src: String) -> Self {
+        let starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(pos, _)| pos + 1))
+            .collect();
+        LineIndex { src, starts }
+    }
+
+    /// The whole source code.
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    /// Number of lines of the code.
+    ///
+    /// Like in [str::lines], empty line after the final line break is not counted.
+    pub fn line_count(&self) -> usize {
+        if self.src.ends_with('\n') {
+            self.starts.len() - 1
+        } else {
+            self.starts.len()
+        }
+    }
+
+    /// Text of zero-based line `line` without line break.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        if line >= self.line_count() {
+            return None;
+        }
+        let text = &self.src[self.range(line)?];
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// Location of the character that starts at byte offset `pos`.
+    ///
+    /// Offset equal to the length of the code is the location of the end of file.
+    pub fn location(&self, pos: usize) -> Option<Location> {
+        if !self.src.is_char_boundary(pos) {
+            return None;
+        }
+        let line = self.starts.partition_point(|start| *start <= pos) - 1;
+        let column = self.src[self.starts[line]..pos].chars().count();
+        Some(Location { pos, line, column })
+    }
+
+    /// Byte offset of the character at zero-based `line` and `column`.
+    ///
+    /// Column right after the last character of the line is the offset of its line break.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let range = self.range(line)?;
+        let (start, text) = (range.start, &self.src[range]);
+        text.char_indices()
+            .map(|(pos, _)| pos)
+            .chain(std::iter::once(text.len()))
+            .nth(column)
+            .map(|pos| start + pos)
+    }
+
+    /// Column of `location` in UTF-16 code units, as used by the language server protocol.
+    pub fn utf16_column(&self, location: Location) -> Option<usize> {
+        let start = *self.starts.get(location.line)?;
+        let text = self.src.get(start..location.pos)?;
+        Some(text.encode_utf16().count())
+    }
+
+    /// Byte range of zero-based line `line` up to its `\n`.
+    fn range(&self, line: usize) -> Option<Range<usize>> {
+        let start = *self.starts.get(line)?;
+        let end = self
+            .starts
+            .get(line + 1)
+            .map_or(self.src.len(), |next| next - 1);
+        Some(start..end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::input_stream::Location;
+
+    use super::LineIndex;
+
+    #[test]
+    fn conversion() {
+        let src = "let ñ = 1;\r\n// 日本\nx𝕊y\n";
+        let index = LineIndex::new(String::from(src));
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line(0), Some("let ñ = 1;"));
+        assert_eq!(index.line(2), Some("x𝕊y"));
+        assert_eq!(index.line(3), None);
+
+        let cases = [
+            (0, 0, 0),
+            (4, 0, 4),
+            (6, 0, 5),
+            (16, 1, 3),
+            (19, 1, 4),
+            (22, 1, 5),
+            (23, 2, 0),
+            (24, 2, 1),
+            (28, 2, 2),
+            (30, 3, 0),
+        ];
+        for (pos, line, column) in cases {
+            let location = Location { pos, line, column };
+            assert_eq!(index.location(pos), Some(location), "offset {pos}");
+            assert_eq!(index.offset(line, column), Some(pos), "offset {pos}");
+        }
+        assert_eq!(index.location(5), None);
+        assert_eq!(index.location(25), None);
+        assert_eq!(index.location(31), None);
+        assert_eq!(index.offset(2, 4), None);
+        assert_eq!(index.offset(4, 0), None);
+
+        let location = index.location(28).unwrap();
+        assert_eq!(index.utf16_column(location), Some(3));
+    }
+}
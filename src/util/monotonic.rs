@@ -5,7 +5,7 @@ use std::ops::{Index, IndexMut};
 /// Once inserted, an element can never be removed or swapped, guaranteeing that any indices into a `MonotonicVec` are stable.
 ///
 /// Inspired by [rustc](https://doc.rust-lang.org/beta/nightly-rustc/src/rustc_span/source_map.rs.html#52)'s internal data structure.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MonotonicVec<T>(Vec<T>);
 
 impl<T> MonotonicVec<T> {
@@ -34,6 +34,22 @@ impl<T> MonotonicVec<T> {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Returns a reference to an element, or `None` if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.0.get(index)
+    }
+
+    /// Returns an iterator over the elements.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+}
+
+impl<T> Default for MonotonicVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> From<Vec<T>> for MonotonicVec<T> {
@@ -1,9 +1,31 @@
 use crate::{input_stream::Location, source::SourceId};
 
 /// Location in code.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     pub source: Option<SourceId>,
     pub start: Location,
     pub end: Location,
 }
+
+impl Span {
+    /// Creates a span that covers both `self` and following `other` span.
+    pub fn join(self, other: Span) -> Span {
+        Span {
+            source: self.source,
+            start: self.start,
+            end: other.end,
+        }
+    }
+
+    /// Length of the span in bytes.
+    pub fn len_bytes(&self) -> usize {
+        self.end.pos.saturating_sub(self.start.pos)
+    }
+
+    /// Checks if byte offset `pos` is covered by the span. End of the span is not included.
+    pub fn contains(&self, pos: usize) -> bool {
+        (self.start.pos..self.end.pos).contains(&pos)
+    }
+}
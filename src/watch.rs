@@ -0,0 +1,411 @@
+//! Compilation of a crate that is repeated when its files change.
+//!
+//! [Watcher] polls modification times of the source files, and [WatchSession] keeps the parsed
+//! crate between compilations, so only the modified files are parsed again when possible.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    ast::item::{ItemKind, Module},
+    compile::{on_large_stack, translate, CompileOptions},
+    context::Context,
+    error::{library::parser::DuplicateDefinition, Diagnostic, Severity},
+    item_table::ItemTable,
+    parser::Parser,
+    path::AbsolutePath,
+    source::SourceError,
+};
+
+/// Change of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Created(PathBuf),
+    Modified(PathBuf),
+    Deleted(PathBuf),
+}
+
+impl Change {
+    /// Path to the changed file.
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Created(path) | Change::Modified(path) | Change::Deleted(path) => path,
+        }
+    }
+}
+
+/// Modification times of the source files found in a directory and its subdirectories.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot(HashMap<PathBuf, SystemTime>);
+
+impl Snapshot {
+    /// Collects modification times of files with `.sun` extension under `root`.
+    ///
+    /// Entries that can't be read, e.g. because they are deleted during the scan, are skipped.
+    pub fn scan(root: &Path) -> Snapshot {
+        let mut files = HashMap::new();
+        let mut directories = vec![root.to_owned()];
+        while let Some(directory) = directories.pop() {
+            let Ok(entries) = fs::read_dir(&directory) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                if meta.is_dir() {
+                    directories.push(path);
+                } else if path.extension().is_some_and(|extension| extension == "sun") {
+                    if let Ok(modified) = meta.modified() {
+                        files.insert(path, modified);
+                    }
+                }
+            }
+        }
+        Snapshot(files)
+    }
+
+    /// Changes of the files made between `self` and `newer` snapshot, ordered by path.
+    pub fn changes(&self, newer: &Snapshot) -> Vec<Change> {
+        let mut changes: Vec<_> = newer
+            .0
+            .iter()
+            .filter_map(|(path, modified)| match self.0.get(path) {
+                None => Some(Change::Created(path.clone())),
+                Some(previous) if previous != modified => Some(Change::Modified(path.clone())),
+                Some(_) => None,
+            })
+            .chain(
+                self.0
+                    .keys()
+                    .filter(|path| !newer.0.contains_key(*path))
+                    .map(|path| Change::Deleted(path.clone())),
+            )
+            .collect();
+        changes.sort_by(|a, b| a.path().cmp(b.path()));
+        changes
+    }
+}
+
+/// Polls the source files under a directory for changes.
+#[derive(Debug)]
+pub struct Watcher {
+    root: PathBuf,
+    snapshot: Snapshot,
+    /// Delay between the polls.
+    interval: Duration,
+    /// Time during which files must stay unchanged for the changes to be reported.
+    debounce: Duration,
+}
+
+impl Watcher {
+    /// Starts watching files under `root`, their current state is the starting point.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Watcher {
+            snapshot: Snapshot::scan(&root),
+            root,
+            interval: Duration::from_millis(200),
+            debounce: Duration::from_millis(100),
+        }
+    }
+
+    /// Blocks until the files are changed and returns the changes.
+    ///
+    /// Changes are only returned after the files stay unchanged for a while, so a burst of
+    /// changes, e.g. saving of several files at once, is reported together. Files that were
+    /// created and deleted in the meantime are not reported.
+    pub fn wait(&mut self) -> Vec<Change> {
+        loop {
+            thread::sleep(self.interval);
+            let previous = self.snapshot.clone();
+            if !self.poll() {
+                continue;
+            }
+            loop {
+                thread::sleep(self.debounce);
+                if !self.poll() {
+                    break;
+                }
+            }
+            let changes = previous.changes(&self.snapshot);
+            if !changes.is_empty() {
+                return changes;
+            }
+        }
+    }
+
+    /// Scans the files again and checks if anything changed since the previous scan.
+    fn poll(&mut self) -> bool {
+        let snapshot = Snapshot::scan(&self.root);
+        let changed = snapshot != self.snapshot;
+        self.snapshot = snapshot;
+        changed
+    }
+}
+
+/// Compilation that is repeated every time the files of the crate change.
+pub struct WatchSession {
+    main: PathBuf,
+    options: CompileOptions,
+    /// Crate parsed by the last compilation, `None` if its main file couldn't be opened.
+    parsed: Option<ParsedCrate>,
+}
+
+impl WatchSession {
+    /// Creates session for the crate with the main file at `main`.
+    ///
+    /// Nothing is compiled until the first [update](WatchSession::update). Crate is compiled from
+    /// scratch with `options`, so in-memory files that were replaced are reset then.
+    pub fn new(main: impl Into<PathBuf>, options: CompileOptions) -> Self {
+        WatchSession {
+            main: main.into(),
+            options,
+            parsed: None,
+        }
+    }
+
+    /// Compiles the crate again after `changes` of its files.
+    ///
+    /// Only modified files are parsed again if they still declare the same modules. Otherwise,
+    /// including the first update, the whole crate is compiled from scratch.
+    ///
+    /// # Errors
+    ///
+    /// Error is returned if the main file couldn't be opened, e.g. if it was deleted.
+    pub fn update(&mut self, changes: &[Change]) -> Result<WatchReport, SourceError> {
+        let start = Instant::now();
+        let modified_only = changes
+            .iter()
+            .all(|change| matches!(change, Change::Modified(_)));
+        let mut incremental = false;
+        if let Some(parsed) = self.parsed.as_mut().filter(|_| modified_only) {
+            let stack_size = parsed.context.metadata.stack_size();
+            incremental = on_large_stack(stack_size, || parsed.reparse(changes));
+        }
+        if !incremental {
+            self.parsed = None;
+            let context = self.options.clone().main(&self.main).build()?;
+            let stack_size = context.metadata.stack_size();
+            self.parsed = Some(on_large_stack(stack_size, || ParsedCrate::parse(context)));
+        }
+
+        let parsed = self.parsed.as_ref().expect("crate should be parsed");
+        let reporter = &parsed.context.error_reporter;
+        if !reporter.compilation_failed() {
+            let stack_size = parsed.context.metadata.stack_size();
+            on_large_stack(stack_size, || {
+                translate(&parsed.context, &parsed.item_table);
+            });
+        }
+        let diagnostics = reporter.diagnostics();
+        let errors = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Deny)
+            .count();
+        Ok(WatchReport {
+            errors,
+            warnings: diagnostics.len() - errors,
+            duration: start.elapsed(),
+            incremental,
+        })
+    }
+
+    /// Context of the last compilation, that may be used to render its diagnostics.
+    pub fn context(&self) -> Option<&Context> {
+        self.parsed.as_ref().map(|parsed| &parsed.context)
+    }
+}
+
+/// Outcome of a single compilation of [WatchSession].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchReport {
+    pub errors: usize,
+    pub warnings: usize,
+    pub duration: Duration,
+    /// Whether only modified files were parsed.
+    pub incremental: bool,
+}
+
+impl Display for WatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} error{}, {} warning{} in {} ms",
+            self.errors,
+            if self.errors == 1 { "" } else { "s" },
+            self.warnings,
+            if self.warnings == 1 { "" } else { "s" },
+            self.duration.as_millis()
+        )
+    }
+}
+
+/// Items of the crate along with the state required to parse its files again.
+struct ParsedCrate {
+    context: Context,
+    parser: Parser,
+    item_table: ItemTable,
+    /// Diagnostics reported while parsing, that are kept for the files that are not modified.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ParsedCrate {
+    fn parse(context: Context) -> Self {
+        let mut parser = Parser::new(context.clone());
+        let (item_table, _) = parser.parse_partial();
+        let diagnostics = context.error_reporter.take();
+        context.error_reporter.restore(diagnostics.clone());
+        ParsedCrate {
+            context,
+            parser,
+            item_table,
+            diagnostics,
+        }
+    }
+
+    /// Parses files modified by `changes` again, replacing their items.
+    ///
+    /// Diagnostics of the previous compilation are discarded, except the ones reported while
+    /// parsing files that are not modified. `false` is returned if modules declared by the files
+    /// have changed, so the crate should be parsed from scratch.
+    fn reparse(&mut self, changes: &[Change]) -> bool {
+        let reporter = &self.context.error_reporter;
+        reporter.take();
+        for change in changes {
+            let refreshed = self.context.source.lock().unwrap().refresh(change.path());
+            // Files that are not a part of the crate are never loaded.
+            let Some(id) = refreshed else {
+                continue;
+            };
+            let Some(module) = self.parser.loaded_module(id).cloned() else {
+                continue;
+            };
+            let Some((fragment, _)) = self.parser.reparse_file(id) else {
+                continue;
+            };
+            let declared = loadable_modules(&self.item_table);
+            self.item_table.remove_module(&module);
+            self.item_table.extend(fragment);
+            if loadable_modules(&self.item_table) != declared {
+                return false;
+            }
+            self.diagnostics
+                .retain(|diagnostic| diagnostic.span.is_none_or(|span| span.source != Some(id)));
+        }
+        // Duplicates are reported for the whole table, as the modified files may collide with
+        // the other ones.
+        self.diagnostics
+            .retain(|diagnostic| diagnostic.code != DuplicateDefinition::CODE);
+        self.parser.report_duplicates(&self.item_table);
+        self.diagnostics.extend(reporter.take());
+        reporter.restore(self.diagnostics.clone());
+        true
+    }
+}
+
+/// Paths of the modules declared as `mod NAME;`.
+fn loadable_modules(item_table: &ItemTable) -> HashSet<AbsolutePath> {
+    item_table
+        .iter()
+        .filter(|(_, item)| matches!(item.kind, ItemKind::Module(Module::Loadable(_))))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime},
+    };
+
+    use crate::context::{Context, Emit};
+
+    use super::{Change, Snapshot, WatchSession};
+
+    const MAIN: &str = "use crate::shapes::area; mod shapes; fn main() -> i32 { area() }";
+
+    #[test]
+    fn incremental_update() {
+        let options = Context::builder()
+            .emit(Emit::Hir)
+            .virtual_file("/virtual/main.sun", MAIN)
+            .virtual_file("/virtual/shapes.sun", "fn area() -> i32 { 1 }");
+        let mut session = WatchSession::new("/virtual/main.sun", options);
+        let report = session.update(&[]).unwrap();
+        assert_eq!((report.errors, report.incremental), (0, false));
+
+        let shapes = PathBuf::from("/virtual/shapes.sun");
+        let mut edit = |contents: &str| {
+            let context = session.context().unwrap();
+            let mut source = context.source.lock().unwrap();
+            source.insert_virtual(shapes.clone(), String::from(contents));
+            drop(source);
+            session.update(&[Change::Modified(shapes.clone())]).unwrap()
+        };
+        let report = edit("fn area() -> i32 { true }");
+        assert_eq!((report.errors, report.incremental), (1, true));
+        let report = edit("fn area() -> i32 { 2 } fn area() -> i32 { 3 }");
+        assert_eq!((report.errors, report.incremental), (1, true));
+        let report = edit("fn area() -> i32 { 2 }");
+        assert_eq!((report.errors, report.incremental), (0, true));
+    }
+
+    /// Writes `contents` to the file at `path`, setting its modification time to `seconds` since
+    /// the epoch, so changes are noticed regardless of the precision of the filesystem.
+    fn write(path: &Path, contents: &str, seconds: u64) {
+        fs::write(path, contents).unwrap();
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    #[test]
+    fn deleted_files() {
+        let root = std::env::temp_dir().join(format!("watch-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let main = root.join("main.sun");
+        let shapes = root.join("shapes.sun");
+        write(&main, MAIN, 1);
+        write(&shapes, "fn area() -> i32 { 1 }", 1);
+
+        let mut session = WatchSession::new(&main, Context::builder().emit(Emit::Hir));
+        assert_eq!(session.update(&[]).unwrap().errors, 0);
+        let mut snapshot = Snapshot::scan(&root);
+        let mut changes = || {
+            let newer = Snapshot::scan(&root);
+            std::mem::replace(&mut snapshot, newer.clone()).changes(&newer)
+        };
+
+        write(&shapes, "fn area() -> i32 { true }", 2);
+        let modified = changes();
+        assert_eq!(modified, [Change::Modified(shapes.clone())]);
+        let report = session.update(&modified).unwrap();
+        assert_eq!((report.errors, report.incremental), (1, true));
+
+        // New module can only be loaded by compiling the crate from scratch.
+        write(&shapes, "fn area() -> i32 { 1 } mod circle;", 3);
+        let report = session.update(&changes()).unwrap();
+        assert_eq!((report.errors, report.incremental), (1, false));
+
+        fs::remove_file(&shapes).unwrap();
+        let deleted = changes();
+        assert_eq!(deleted, [Change::Deleted(shapes)]);
+        let report = session.update(&deleted).unwrap();
+        assert_eq!((report.errors, report.incremental), (1, false));
+
+        fs::remove_file(&main).unwrap();
+        assert!(session.update(&[Change::Deleted(main)]).is_err());
+        assert!(session.context().is_none());
+        fs::remove_dir(&root).unwrap();
+    }
+}
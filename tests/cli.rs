@@ -0,0 +1,203 @@
+//! Exit codes and output of the command line interface.
+
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn run(args: &[&str], file: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_compiler_frontend"))
+        .args(args)
+        .arg(fixture(file))
+        .output()
+        .expect("compiler should start")
+}
+
+fn run_stdin(args: &[&str], src: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_compiler_frontend"))
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("compiler should start");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(src.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn parse() {
+    let output = run(&["parse"], "valid.sun");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("valid::sum"));
+}
+
+#[test]
+fn check() {
+    let output = run(&["check"], "valid.sun");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn hir() {
+    let output = run(&["hir"], "valid.sun");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("valid::main"));
+}
+
+#[test]
+fn emit_c() {
+    let output = run(&["emit-c"], "valid.sun");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("_S5valid3sum("));
+}
+
+#[test]
+#[cfg(feature = "wasm")]
+fn emit_wat() {
+    let output = run(&["emit-wat"], "valid.sun");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("(module\n"));
+    assert!(stdout(&output).contains("call $valid::sum"));
+
+    let output = run(&["emit-wat"], "struct_param.sun");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not yet supported by wasm backend"));
+}
+
+#[test]
+fn lex() {
+    let output = run(&["lex"], "valid.sun");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("1:1..1:7  Kw(struct)\n"));
+}
+
+#[test]
+fn compile_errors() {
+    for (command, file) in [
+        ("parse", "recovery.sun"),
+        ("check", "two_errors.sun"),
+        ("hir", "two_errors.sun"),
+    ] {
+        let output = run(&[command], file);
+        assert_eq!(output.status.code(), Some(1), "`{command}` should fail");
+        assert!(!output.stderr.is_empty());
+    }
+}
+
+#[test]
+fn warnings_only() {
+    let output = run(
+        &["check", "--warn", "E0205", "--warn", "E0210"],
+        "diagnostics.sun",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn missing_file() {
+    for command in ["parse", "check", "hir", "lex"] {
+        let output = run(&[command], "missing.sun");
+        assert_eq!(output.status.code(), Some(2), "`{command}` should fail");
+    }
+}
+
+#[test]
+fn crate_directory() {
+    let output = run(&["hir"], "crates/app");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("main::geometry::area"));
+
+    let output = run(&["check"], "crates/tool");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("variable `missing` is not declared"));
+    assert!(stderr.contains("bin.sun:2:5"));
+
+    let output = run(&["check"], "crates");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn usage_error() {
+    let output = run(&["check", "--unknown-flag"], "valid.sun");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn stdin() {
+    let output = run_stdin(&["parse"], "fn main() -> i32 { 1 }");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("main::main"));
+
+    let output = run_stdin(&["lex"], "fn");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "1:1..1:3  Kw(fn)\n1:3..1:3  Eof\n");
+}
+
+#[test]
+fn stdin_errors() {
+    let output = run_stdin(&["check"], "fn main() -> i32 { true }");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("<stdin>:1:"));
+
+    let output = run_stdin(&["check"], "mod shapes;");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("E0020"));
+}
+
+#[test]
+fn timings() {
+    let output = run(&["check", "--timings=json"], "valid.sun");
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with(r#"{"files":[{"path":"#), "{stderr}");
+}
+
+#[test]
+fn no_debug_output() {
+    let operands: Vec<_> = (0..100).map(|i| i.to_string()).collect();
+    let src = format!(
+        "fn main() -> i32 {{ let x: i32 = {}; {{ {{ x * (1 + 2) }} }} }}",
+        operands.join(" + ")
+    );
+    let output = run_stdin(&["check"], &src);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test() {
+    let output = run(&["test"], "unit_tests.sun");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "test unit_tests::sum_of_three ... FAILED: returned `false`\n\
+         test unit_tests::sum_of_two ... ok\n\
+         test result: FAILED. 1 passed; 1 failed; 0 filtered out\n"
+    );
+
+    let output = run(&["test", "--filter", "two"], "unit_tests.sun");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).ends_with("test result: ok. 1 passed; 0 failed; 1 filtered out\n"));
+}
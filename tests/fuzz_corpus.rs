@@ -0,0 +1,33 @@
+//! Replays the corpus of fuzz targets, so inputs they found are checked without `cargo fuzz`.
+
+use std::{fs, panic, path::PathBuf};
+
+#[path = "../fuzz/fuzz_targets/check.rs"]
+mod check;
+
+/// Runs `check` on every input in the corpus of fuzz `target`.
+fn replay(target: &str, check: fn(&[u8])) {
+    let corpus = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fuzz/corpus")
+        .join(target);
+    let mut inputs = 0;
+    for entry in fs::read_dir(&corpus).expect("corpus should exist") {
+        let path = entry.unwrap().path();
+        let data = fs::read(&path).unwrap();
+        if panic::catch_unwind(|| check(&data)).is_err() {
+            panic!("input {} isn't handled correctly", path.display());
+        }
+        inputs += 1;
+    }
+    assert!(inputs > 0, "corpus of `{target}` is empty");
+}
+
+#[test]
+fn lexer() {
+    replay("lexer", check::lex);
+}
+
+#[test]
+fn parser() {
+    replay("parser", check::parse);
+}